@@ -0,0 +1,161 @@
+/// Blocks above the top of the world the player has to be before the wind picks up.
+pub const WIND_ALTITUDE: f32 = 8.0;
+
+/// Seconds for one ambient loop to fade fully out while the next fades in.
+const CROSSFADE: f32 = 2.0;
+
+/// Background loops played depending on where the player is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Ambience {
+    /// Echoing drips, for underground with no sky overhead.
+    Cave,
+    /// High above the ground.
+    Wind,
+    /// Water lapping, for near water at the surface.
+    Water,
+}
+
+impl Ambience {
+    pub const ALL: [Ambience; 3] = [Ambience::Cave, Ambience::Wind, Ambience::Water];
+
+    /// Which loop suits the player's surroundings, if any. Being enclosed wins over being high
+    /// up, which wins over being near water.
+    pub fn pick(surroundings: &Surroundings) -> Option<Self> {
+        if !surroundings.sky_access {
+            Some(Ambience::Cave)
+        } else if surroundings.altitude >= WIND_ALTITUDE {
+            Some(Ambience::Wind)
+        } else if surroundings.near_water {
+            Some(Ambience::Water)
+        } else {
+            None
+        }
+    }
+}
+
+impl<'a> From<Ambience> for &'a str {
+    fn from(ambience: Ambience) -> &'a str {
+        match ambience {
+            Ambience::Cave => "cave",
+            Ambience::Wind => "wind",
+            Ambience::Water => "water",
+        }
+    }
+}
+
+/// What the world looks like around the player, as far as ambient sound cares.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Surroundings {
+    /// Nothing solid between the player and the sky.
+    pub sky_access: bool,
+    /// Height above the top of the world, negative below it.
+    pub altitude: f32,
+    pub near_water: bool,
+}
+
+/// The volume of each ambient loop, fading between them as the surroundings change so the
+/// player never hears a hard cut. An audio backend plays each loop at its volume here.
+#[derive(Default)]
+pub struct Soundscape {
+    volumes: [f32; Ambience::ALL.len()],
+    /// Every loop is silent while muted, carrying on fading underneath.
+    pub muted: bool,
+}
+
+impl Soundscape {
+    /// Fades towards `ambience` over `dt` seconds, and every other loop out.
+    pub fn update(&mut self, dt: f32, ambience: Option<Ambience>) {
+        let step = dt / CROSSFADE;
+        for (loop_ambience, volume) in Ambience::ALL.into_iter().zip(&mut self.volumes) {
+            let target = if ambience == Some(loop_ambience) {
+                1.0
+            } else {
+                0.0
+            };
+            *volume = if *volume < target {
+                (*volume + step).min(target)
+            } else {
+                (*volume - step).max(target)
+            };
+        }
+    }
+
+    pub fn volume(&self, ambience: Ambience) -> f32 {
+        if self.muted {
+            return 0.0;
+        }
+        self.volumes[ambience as usize]
+    }
+
+    /// The loops that can be heard at the moment, for the debug overlay.
+    pub fn describe(&self) -> String {
+        let playing: Vec<String> = Ambience::ALL
+            .into_iter()
+            .filter(|ambience| self.volume(*ambience) > 0.0)
+            .map(|ambience| {
+                format!(
+                    "{} {:.0}%",
+                    <&str>::from(ambience),
+                    self.volume(ambience) * 100.0
+                )
+            })
+            .collect();
+        if self.muted {
+            "ambience muted".into()
+        } else if playing.is_empty() {
+            "ambience none".into()
+        } else {
+            format!("ambience {}", playing.join(", "))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Ambience, Soundscape, Surroundings, CROSSFADE, WIND_ALTITUDE};
+
+    #[test]
+    fn surroundings_pick_a_loop() {
+        let open = Surroundings {
+            sky_access: true,
+            altitude: 0.0,
+            near_water: false,
+        };
+        assert_eq!(Ambience::pick(&open), None);
+        let shore = Surroundings {
+            near_water: true,
+            ..open
+        };
+        assert_eq!(Ambience::pick(&shore), Some(Ambience::Water));
+        let cliff = Surroundings {
+            altitude: WIND_ALTITUDE,
+            ..shore
+        };
+        assert_eq!(Ambience::pick(&cliff), Some(Ambience::Wind));
+        let cave = Surroundings {
+            sky_access: false,
+            ..cliff
+        };
+        assert_eq!(Ambience::pick(&cave), Some(Ambience::Cave));
+    }
+
+    #[test]
+    fn loops_crossfade() {
+        let mut soundscape = Soundscape::default();
+        soundscape.update(CROSSFADE, Some(Ambience::Wind));
+        assert_eq!(soundscape.volume(Ambience::Wind), 1.0);
+
+        // halfway through the fade both can be heard
+        soundscape.update(CROSSFADE / 2.0, Some(Ambience::Cave));
+        assert_eq!(soundscape.volume(Ambience::Wind), 0.5);
+        assert_eq!(soundscape.volume(Ambience::Cave), 0.5);
+        assert_eq!(soundscape.describe(), "ambience cave 50%, wind 50%");
+        soundscape.muted = true;
+        assert_eq!(soundscape.volume(Ambience::Wind), 0.0);
+        assert_eq!(soundscape.describe(), "ambience muted");
+        soundscape.muted = false;
+
+        soundscape.update(CROSSFADE, None);
+        assert_eq!(soundscape.describe(), "ambience none");
+    }
+}
@@ -0,0 +1,350 @@
+use std::{cell::RefCell, io::Read, path::PathBuf};
+
+use flate2::read::{GzDecoder, ZlibDecoder};
+use fxhash::FxHashMap;
+use glam::{ivec2, ivec3, IVec2, IVec3};
+
+use crate::{
+    chunk::CHUNK_SIZE,
+    generator::{ChunkData, WorldGenerator},
+    nbt::{self, Tag},
+    world::{BlockType, WORLD_TOP},
+};
+
+/// The minecraft y shown at the top of the world, at `WORLD_TOP`. Sea level is at 63, so this
+/// leaves room for hills above it and caves below.
+const TOP_Y: i32 = 100;
+/// Chunk columns along each side of a region file.
+const REGION_COLUMNS: i32 = 32;
+/// Region files are laid out in sectors this many bytes long.
+const SECTOR: usize = 4096;
+/// Blocks in each 16 block cube of a chunk column.
+const SECTION_BLOCKS: usize = 16 * 16 * 16;
+/// The data version from which block states are padded to whole longs rather than running
+/// across from one long into the next.
+const PADDED_STATES: i32 = 2527;
+
+/// The blocks of one chunk column, a section of 16 blocks tall per y, indexed by y then z then
+/// x within the section.
+type Column = FxHashMap<i32, Vec<Option<BlockType>>>;
+
+/// Reads the blocks of a Minecraft Java world saved in the Anvil format, from 1.13 on, rather
+/// than generating them. Blocks with a type here are given it, plants and other small things
+/// are left out, and everything else becomes a placeholder. Region files are only read once
+/// a chunk in them is wanted.
+pub struct AnvilGenerator {
+    /// The world's `region` directory.
+    dir: PathBuf,
+    /// The contents of every region file read so far, or None where there isn't one.
+    regions: RefCell<FxHashMap<IVec2, Option<Vec<u8>>>>,
+    columns: RefCell<FxHashMap<IVec2, Column>>,
+}
+
+impl AnvilGenerator {
+    /// The generator for the world saved in `dir`, the folder with `level.dat` in it.
+    pub fn open(dir: &str) -> Result<Self, String> {
+        let dir = PathBuf::from(dir).join("region");
+        if !dir.is_dir() {
+            return Err(format!("{} isn't a minecraft world", dir.display()));
+        }
+        Ok(Self {
+            dir,
+            regions: RefCell::default(),
+            columns: RefCell::default(),
+        })
+    }
+
+    /// Reads the column at `column` out of its region file, leaving it empty if it's missing
+    /// or can't be read.
+    fn read_column(&self, column: IVec2) -> Column {
+        let region = ivec2(
+            column.x.div_euclid(REGION_COLUMNS),
+            column.y.div_euclid(REGION_COLUMNS),
+        );
+        let mut regions = self.regions.borrow_mut();
+        let bytes = regions.entry(region).or_insert_with(|| {
+            let path = self.dir.join(format!("r.{}.{}.mca", region.x, region.y));
+            std::fs::read(path).ok()
+        });
+        let Some(bytes) = bytes else {
+            return Column::default();
+        };
+        let local = ivec2(
+            column.x.rem_euclid(REGION_COLUMNS),
+            column.y.rem_euclid(REGION_COLUMNS),
+        );
+        let blocks = chunk_nbt(bytes, local).and_then(|nbt| match nbt {
+            Some(nbt) => parse_column(&nbt),
+            None => Ok(Column::default()),
+        });
+        match blocks {
+            Ok(blocks) => blocks,
+            Err(err) => {
+                eprintln!("chunk {} {} of the world: {err}", column.x, column.y);
+                Column::default()
+            }
+        }
+    }
+}
+
+impl WorldGenerator for AnvilGenerator {
+    fn generate_chunk(&self, _seed: u64, coord: IVec3) -> ChunkData {
+        let column = ivec2(coord.x, coord.z);
+        let mut columns = self.columns.borrow_mut();
+        let blocks = columns
+            .entry(column)
+            .or_insert_with(|| self.read_column(column));
+        let mut chunk = ChunkData::default();
+        for z in 0..CHUNK_SIZE {
+            for y in 0..CHUNK_SIZE {
+                for x in 0..CHUNK_SIZE {
+                    let local = ivec3(x, y, z);
+                    let minecraft_y = coord.y * CHUNK_SIZE + y + TOP_Y - WORLD_TOP;
+                    let Some(section) = blocks.get(&minecraft_y.div_euclid(16)) else {
+                        continue;
+                    };
+                    let index = (minecraft_y.rem_euclid(16) * 256 + z * 16 + x) as usize;
+                    chunk.set(local, section[index]);
+                }
+            }
+        }
+        chunk
+    }
+}
+
+/// The block type a minecraft block name stands for, None for air and blocks too small to be
+/// worth a whole block, or a placeholder if there's nothing like it here. Any block states
+/// after the name are ignored.
+fn block_type(name: &str) -> Option<BlockType> {
+    let name = name.split_once('[').map_or(name, |(name, _)| name);
+    let name = name.strip_prefix("minecraft:").unwrap_or(name);
+    let block_type = match name {
+        "air" | "cave_air" | "void_air" | "grass" | "short_grass" | "tall_grass" | "fern"
+        | "large_fern" | "dead_bush" | "vine" | "snow" | "torch" | "wall_torch" | "sugar_cane"
+        | "lily_pad" | "dandelion" | "poppy" | "cornflower" | "azure_bluet" | "oxeye_daisy"
+        | "brown_mushroom" | "red_mushroom" | "glow_lichen" | "hanging_roots" => return None,
+        "grass_block" | "dirt" | "coarse_dirt" | "rooted_dirt" | "podzol" | "mycelium"
+        | "dirt_path" | "farmland" | "mud" | "clay" => BlockType::Dirt,
+        "cobblestone" | "mossy_cobblestone" | "cobbled_deepslate" | "gravel" => BlockType::Cobble,
+        "stone" | "granite" | "diorite" | "andesite" | "deepslate" | "tuff" | "calcite"
+        | "bedrock" | "smooth_stone" | "stone_bricks" => BlockType::Stone,
+        name if name.ends_with("_ore") => BlockType::Stone,
+        "sand" | "red_sand" | "sandstone" | "red_sandstone" => BlockType::Sand,
+        "water" | "bubble_column" | "seagrass" | "tall_seagrass" | "kelp" | "kelp_plant" => {
+            BlockType::Water
+        }
+        "glass" | "tinted_glass" => BlockType::Glass,
+        name if name.ends_with("_stained_glass") => BlockType::Glass,
+        "nether_portal" => BlockType::Portal,
+        _ => BlockType::Placeholder,
+    };
+    Some(block_type)
+}
+
+/// The uncompressed nbt of the chunk column at `local` within a region file, or None if the
+/// column was never saved.
+fn chunk_nbt(region: &[u8], local: IVec2) -> Result<Option<Tag>, String> {
+    let entry = 4 * (local.x + local.y * REGION_COLUMNS) as usize;
+    let location = region
+        .get(entry..entry + 4)
+        .ok_or("region file ends in its header")?;
+    let sector = u32::from_be_bytes([0, location[0], location[1], location[2]]) as usize;
+    if sector == 0 {
+        return Ok(None);
+    }
+    let start = sector * SECTOR;
+    let header = region
+        .get(start..start + 5)
+        .ok_or("region file ends before the chunk")?;
+    let len = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
+    let compressed = region
+        .get(start + 5..(start + 4 + len).max(start + 5))
+        .ok_or("region file ends partway through the chunk")?;
+    let mut bytes = vec![];
+    let read = match header[4] {
+        1 => GzDecoder::new(compressed).read_to_end(&mut bytes),
+        2 => ZlibDecoder::new(compressed).read_to_end(&mut bytes),
+        3 => {
+            bytes = compressed.to_vec();
+            Ok(bytes.len())
+        }
+        compression => return Err(format!("unsupported chunk compression {compression}")),
+    };
+    read.map_err(|e| format!("couldn't decompress chunk: {e}"))?;
+    nbt::read(&bytes).map(|(_, tag)| Some(tag))
+}
+
+/// The blocks of a chunk column from its nbt, which has moved around between versions.
+fn parse_column(nbt: &Tag) -> Result<Column, String> {
+    let data_version = match nbt.get("DataVersion") {
+        Some(Tag::Int(version)) => *version,
+        _ => 0,
+    };
+    // 1.18 moved everything out of a level compound and renamed the block fields
+    let (sections, palette_key, states_key) = match nbt.get("Level") {
+        Some(level) => (level.get("Sections"), "Palette", "BlockStates"),
+        None => (nbt.get("sections"), "palette", "data"),
+    };
+    let Some(Tag::List(sections)) = sections else {
+        return Err("chunk has no sections".into());
+    };
+    let mut column = Column::default();
+    for section in sections {
+        let Some(Tag::Byte(y)) = section.get("Y") else {
+            continue;
+        };
+        let states = section.get("block_states").unwrap_or(section);
+        // sections before 1.13 had numeric ids instead, and empty ones have no palette
+        let Some(Tag::List(palette)) = states.get(palette_key) else {
+            continue;
+        };
+        let palette: Vec<Option<BlockType>> = palette
+            .iter()
+            .map(|entry| match entry.get("Name") {
+                Some(Tag::String(name)) => block_type(name),
+                _ => Some(BlockType::Placeholder),
+            })
+            .collect();
+        let indices = match states.get(states_key) {
+            Some(Tag::LongArray(longs)) => {
+                unpack(longs, palette.len(), data_version >= PADDED_STATES)
+            }
+            // a single block type throughout leaves the data out
+            _ => vec![0; SECTION_BLOCKS],
+        };
+        let blocks = indices
+            .into_iter()
+            .map(|index| palette.get(index).copied().flatten())
+            .collect();
+        column.insert(*y as i32, blocks);
+    }
+    Ok(column)
+}
+
+/// Palette indices packed into longs, lowest bits first, using as few bits as the palette
+/// needs but no fewer than 4. Padded data starts each long afresh rather than splitting an
+/// index across two.
+fn unpack(longs: &[i64], palette_len: usize, padded: bool) -> Vec<usize> {
+    let bits = (usize::BITS - palette_len.saturating_sub(1).leading_zeros()).max(4) as usize;
+    let mask = (1u64 << bits) - 1;
+    let per_long = 64 / bits;
+    (0..SECTION_BLOCKS)
+        .map(|i| {
+            let (long, shift) = if padded {
+                (i / per_long, i % per_long * bits)
+            } else {
+                (i * bits / 64, i * bits % 64)
+            };
+            let low = longs.get(long).copied().unwrap_or(0) as u64 >> shift;
+            let index = if shift + bits > 64 {
+                let high = longs.get(long + 1).copied().unwrap_or(0) as u64;
+                low | high << (64 - shift)
+            } else {
+                low
+            };
+            (index & mask) as usize
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::{write::ZlibEncoder, Compression};
+    use glam::ivec2;
+
+    use crate::{nbt::Tag, world::BlockType};
+
+    use super::{block_type, chunk_nbt, parse_column, unpack, SECTION_BLOCKS, SECTOR};
+
+    /// Packs indices the way `unpack` reads them.
+    fn pack(indices: &[u64], bits: usize, padded: bool) -> Vec<i64> {
+        let per_long = 64 / bits;
+        let mut longs = vec![0u64; indices.len().div_ceil(per_long) + 1];
+        for (i, index) in indices.iter().enumerate() {
+            let (long, shift) = if padded {
+                (i / per_long, i % per_long * bits)
+            } else {
+                (i * bits / 64, i * bits % 64)
+            };
+            longs[long] |= index << shift;
+            if shift + bits > 64 {
+                longs[long + 1] |= index >> (64 - shift);
+            }
+        }
+        longs.into_iter().map(|long| long as i64).collect()
+    }
+
+    #[test]
+    fn block_states_unpack_both_ways() {
+        // 5 bits, so tightly packed indices run across longs
+        let indices: Vec<u64> = (0..SECTION_BLOCKS as u64).map(|i| i % 17).collect();
+        let expected: Vec<usize> = indices.iter().map(|i| *i as usize).collect();
+        for padded in [false, true] {
+            let longs = pack(&indices, 5, padded);
+            assert_eq!(unpack(&longs, 17, padded), expected);
+        }
+    }
+
+    #[test]
+    fn minecraft_blocks_map_onto_ours() {
+        assert_eq!(
+            block_type("minecraft:grass_block[snowy=false]"),
+            Some(BlockType::Dirt)
+        );
+        assert_eq!(
+            block_type("minecraft:deepslate_iron_ore"),
+            Some(BlockType::Stone)
+        );
+        assert_eq!(block_type("minecraft:cave_air"), None);
+        assert_eq!(block_type("minecraft:poppy"), None);
+        assert_eq!(
+            block_type("minecraft:oak_log[axis=y]"),
+            Some(BlockType::Placeholder)
+        );
+    }
+
+    #[test]
+    fn chunks_are_read_from_region_files() {
+        // one section at y = 4, stone with a block of glass in its corner
+        let mut indices = vec![0; SECTION_BLOCKS];
+        indices[0] = 1;
+        let palette = ["minecraft:stone", "minecraft:glass"]
+            .map(|name| Tag::Compound(vec![("Name".into(), Tag::String(name.into()))]));
+        let section = Tag::Compound(vec![
+            ("Y".into(), Tag::Byte(4)),
+            (
+                "block_states".into(),
+                Tag::Compound(vec![
+                    ("palette".into(), Tag::List(palette.to_vec())),
+                    ("data".into(), Tag::LongArray(pack(&indices, 4, true))),
+                ]),
+            ),
+        ]);
+        let chunk = Tag::Compound(vec![
+            ("DataVersion".into(), Tag::Int(3120)),
+            ("sections".into(), Tag::List(vec![section])),
+        ]);
+        let mut nbt = vec![];
+        chunk.write_named("", &mut nbt);
+        let mut encoder = ZlibEncoder::new(vec![], Compression::default());
+        encoder.write_all(&nbt).unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        // the column at 1, 2 in the region, in the sector after the two header sectors
+        let mut region = vec![0; 2 * SECTOR];
+        let entry = 4 * (1 + 2 * 32);
+        region[entry..entry + 4].copy_from_slice(&[0, 0, 2, 1]);
+        region.extend((compressed.len() as u32 + 1).to_be_bytes());
+        region.push(2);
+        region.extend(compressed);
+
+        assert_eq!(chunk_nbt(&region, ivec2(0, 0)), Ok(None));
+        let nbt = chunk_nbt(&region, ivec2(1, 2)).unwrap().unwrap();
+        let column = parse_column(&nbt).unwrap();
+        let section = &column[&4];
+        assert_eq!(section[0], Some(BlockType::Glass));
+        assert_eq!(section[SECTION_BLOCKS - 1], Some(BlockType::Stone));
+    }
+}
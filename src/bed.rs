@@ -0,0 +1,50 @@
+use glam::{vec3, IVec3, Quat, Vec3};
+
+use crate::{
+    chunk::Face,
+    instance::Instance,
+    renderer::{Drawable, Renderer, Vertex},
+    world::{cube_indices, cube_vertices, World},
+};
+
+/// The renderer object beds are drawn with.
+const BED_OBJECT: u32 = 2;
+
+/// Height of the mattress, resting on the block below.
+const BED_HEIGHT: f32 = 0.5;
+
+/// A bed as it's drawn: one low box over both of its blocks.
+pub struct Bed {
+    /// The foot of the bed.
+    position: IVec3,
+    /// The face of the foot block the head is against.
+    towards: Face,
+}
+
+impl Bed {
+    pub fn new(position: IVec3, towards: Face) -> Self {
+        Self { position, towards }
+    }
+}
+
+impl Drawable for Bed {
+    fn draw(&self, renderer: &mut Renderer, world: &World) {
+        renderer.queue_draw(BED_OBJECT, self, world);
+    }
+
+    fn vertices(&self) -> Vec<Vertex> {
+        cube_vertices()
+    }
+
+    fn indices(&self) -> Vec<u16> {
+        cube_indices()
+    }
+
+    fn instance(&self, world: &World) -> Instance {
+        let normal = self.towards.normal().as_vec3();
+        // halfway between the two blocks, sitting on the floor
+        let centre = self.position.as_vec3() + normal * 0.5 - Vec3::Y * (0.5 - BED_HEIGHT / 2.0);
+        let scale = vec3(1.0, BED_HEIGHT, 1.0) + normal.abs();
+        Instance::new(centre, Quat::IDENTITY, world.get_texture("bed")).with_scale(scale)
+    }
+}
@@ -0,0 +1,275 @@
+use std::{f32::consts::TAU, fmt::Write, path::Path, process::Command, time::Duration};
+
+use glam::{vec3, Vec3};
+
+use crate::{camera::Camera, world::MeshingStats};
+
+/// The world seed benchmark runs use, so every run renders the same scene.
+pub const SEED: u64 = 0x5eed;
+
+/// How long a benchmark runs when no duration is given, in seconds.
+const DEFAULT_DURATION: f32 = 30.0;
+
+/// Radius of the camera's orbit around the world centre.
+const ORBIT_RADIUS: f32 = 80.0;
+/// Height of the orbit above the top of the world.
+const ORBIT_HEIGHT: f32 = 20.0;
+
+/// Reads `--benchmark [seconds]` from the command line, returning the duration to run for.
+pub fn from_args(args: impl Iterator<Item = String>) -> Option<f32> {
+    let mut args = args.skip_while(|arg| arg != "--benchmark");
+    args.next()?;
+    let duration = args.next().and_then(|arg| arg.parse().ok());
+    Some(duration.unwrap_or(DEFAULT_DURATION))
+}
+
+/// Flies the camera along a fixed path and records how each frame went.
+pub struct Benchmark {
+    duration: f32,
+    /// What the camera orbits.
+    centre: Vec3,
+    elapsed: f32,
+    /// Seconds between consecutive frames.
+    frame_times: Vec<f32>,
+    draw_calls: u64,
+    meshing: MeshingStats,
+    /// The most meshing time spent in a single frame.
+    worst_meshing: Duration,
+    /// What the world was made with, how long it took, and how much of that was erosion.
+    generator: String,
+    generation: Duration,
+    erosion: Duration,
+}
+
+impl Benchmark {
+    pub fn new(duration: f32, centre: Vec3) -> Self {
+        Self {
+            duration,
+            centre,
+            elapsed: 0.0,
+            frame_times: vec![],
+            draw_calls: 0,
+            meshing: MeshingStats::default(),
+            worst_meshing: Duration::ZERO,
+            generator: String::new(),
+            generation: Duration::ZERO,
+            erosion: Duration::ZERO,
+        }
+    }
+
+    /// Records that the world was made by the generator called `generator` in `time`, `erosion`
+    /// of which was spent eroding the terrain.
+    pub fn with_generation(mut self, generator: &str, time: Duration, erosion: Duration) -> Self {
+        self.generator = generator.into();
+        self.generation = time;
+        self.erosion = erosion;
+        self
+    }
+
+    /// Moves the camera to where the path is at the current time: one full orbit around the
+    /// world over the length of the run, looking at its centre.
+    pub fn place_camera(&self, camera: &mut Camera) {
+        let angle = self.elapsed / self.duration * TAU;
+        let position = self.centre
+            + vec3(
+                angle.cos() * ORBIT_RADIUS,
+                ORBIT_HEIGHT,
+                angle.sin() * ORBIT_RADIUS,
+            );
+        camera.set_position(position);
+        camera.look_at(self.centre - position);
+    }
+
+    /// Records a frame. Returns true once the run is over.
+    pub fn record(&mut self, frame_time: f32, draw_calls: u32, meshing: &MeshingStats) -> bool {
+        self.elapsed += frame_time;
+        self.frame_times.push(frame_time);
+        self.draw_calls += draw_calls as u64;
+        self.meshing.add(meshing);
+        self.worst_meshing = self.worst_meshing.max(meshing.time);
+        self.elapsed >= self.duration
+    }
+
+    pub fn report(&self) -> Report {
+        let frames = self.frame_times.len().max(1);
+        let total: f32 = self.frame_times.iter().sum();
+        let mut sorted = self.frame_times.clone();
+        sorted.sort_by(|a, b| b.total_cmp(a));
+        // the average of the slowest 1% of frames, at least one of them
+        let slowest = &sorted[..(sorted.len() / 100).max(1).min(sorted.len())];
+        let slowest_average = slowest.iter().sum::<f32>() / slowest.len().max(1) as f32;
+        Report {
+            commit: commit(),
+            seed: SEED,
+            generator: self.generator.clone(),
+            generation_ms: self.generation.as_secs_f32() * 1000.0,
+            erosion_ms: self.erosion.as_secs_f32() * 1000.0,
+            seconds: total,
+            frames: self.frame_times.len(),
+            average_fps: frames as f32 / total.max(f32::EPSILON),
+            one_percent_low_fps: 1.0 / slowest_average.max(f32::EPSILON),
+            average_frame_ms: total / frames as f32 * 1000.0,
+            worst_frame_ms: sorted.first().copied().unwrap_or(0.0) * 1000.0,
+            average_draw_calls: self.draw_calls as f32 / frames as f32,
+            chunks_meshed: self.meshing.chunks,
+            quads_meshed: self.meshing.quads,
+            mesh_bytes: self.meshing.bytes,
+            meshing_ms: self.meshing.time.as_secs_f32() * 1000.0,
+            average_chunk_mesh_ms: self.meshing.time.as_secs_f32() * 1000.0
+                / self.meshing.chunks.max(1) as f32,
+            worst_frame_meshing_ms: self.worst_meshing.as_secs_f32() * 1000.0,
+        }
+    }
+}
+
+/// The short hash of the checked out commit, if there is one.
+fn commit() -> Option<String> {
+    let output = Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+pub struct Report {
+    commit: Option<String>,
+    seed: u64,
+    generator: String,
+    generation_ms: f32,
+    erosion_ms: f32,
+    seconds: f32,
+    frames: usize,
+    average_fps: f32,
+    one_percent_low_fps: f32,
+    average_frame_ms: f32,
+    worst_frame_ms: f32,
+    average_draw_calls: f32,
+    chunks_meshed: usize,
+    quads_meshed: usize,
+    mesh_bytes: usize,
+    meshing_ms: f32,
+    average_chunk_mesh_ms: f32,
+    worst_frame_meshing_ms: f32,
+}
+
+impl Report {
+    pub fn to_json(&self) -> String {
+        let commit = match &self.commit {
+            Some(commit) => format!("\"{commit}\""),
+            None => "null".into(),
+        };
+        let mut json = String::from("{\n");
+        let fields: [(&str, String); 18] = [
+            ("commit", commit),
+            ("seed", self.seed.to_string()),
+            ("generator", format!("{:?}", self.generator)),
+            ("generation_ms", format!("{:.3}", self.generation_ms)),
+            ("erosion_ms", format!("{:.3}", self.erosion_ms)),
+            ("seconds", format!("{:.3}", self.seconds)),
+            ("frames", self.frames.to_string()),
+            ("average_fps", format!("{:.2}", self.average_fps)),
+            (
+                "one_percent_low_fps",
+                format!("{:.2}", self.one_percent_low_fps),
+            ),
+            ("average_frame_ms", format!("{:.3}", self.average_frame_ms)),
+            ("worst_frame_ms", format!("{:.3}", self.worst_frame_ms)),
+            (
+                "average_draw_calls",
+                format!("{:.1}", self.average_draw_calls),
+            ),
+            ("chunks_meshed", self.chunks_meshed.to_string()),
+            ("quads_meshed", self.quads_meshed.to_string()),
+            ("mesh_bytes", self.mesh_bytes.to_string()),
+            ("meshing_ms", format!("{:.3}", self.meshing_ms)),
+            (
+                "average_chunk_mesh_ms",
+                format!("{:.3}", self.average_chunk_mesh_ms),
+            ),
+            (
+                "worst_frame_meshing_ms",
+                format!("{:.3}", self.worst_frame_meshing_ms),
+            ),
+        ];
+        for (i, (key, value)) in fields.iter().enumerate() {
+            let comma = if i + 1 < fields.len() { "," } else { "" };
+            writeln!(json, "  \"{key}\": {value}{comma}").unwrap();
+        }
+        json.push('}');
+        json.push('\n');
+        json
+    }
+
+    pub fn write<P: AsRef<Path>>(&self, path: P) -> std::io::Result<()> {
+        std::fs::write(path, self.to_json())
+    }
+
+    pub fn summary(&self) -> String {
+        format!(
+            "{} frames in {:.1} s: {:.1} fps average, {:.1} fps 1% low",
+            self.frames, self.seconds, self.average_fps, self.one_percent_low_fps
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use glam::Vec3;
+
+    use crate::world::MeshingStats;
+
+    use super::{from_args, Benchmark, DEFAULT_DURATION};
+
+    fn args(args: &[&str]) -> impl Iterator<Item = String> {
+        let mut all = vec!["normalcraft".to_string()];
+        all.extend(args.iter().map(|arg| arg.to_string()));
+        all.into_iter()
+    }
+
+    #[test]
+    fn benchmark_flag_takes_an_optional_duration() {
+        assert_eq!(from_args(args(&[])), None);
+        assert_eq!(from_args(args(&["--benchmark"])), Some(DEFAULT_DURATION));
+        assert_eq!(from_args(args(&["--benchmark", "5"])), Some(5.0));
+    }
+
+    #[test]
+    fn report_finds_the_slow_frames() {
+        let mut benchmark = Benchmark::new(10.0, Vec3::ZERO).with_generation(
+            "hills:iterations=50",
+            Duration::from_millis(120),
+            Duration::from_millis(80),
+        );
+        let meshing = MeshingStats {
+            chunks: 2,
+            time: Duration::from_millis(4),
+            ..MeshingStats::default()
+        };
+        for i in 0..200 {
+            // two 50ms hitches among 10ms frames
+            let frame_time = if i % 100 == 0 { 0.05 } else { 0.01 };
+            let done = benchmark.record(frame_time, 3, &meshing);
+            assert_eq!(done, benchmark.elapsed >= 10.0);
+        }
+        let report = benchmark.report();
+        assert_eq!(report.frames, 200);
+        assert!((report.one_percent_low_fps - 20.0).abs() < 0.01);
+        assert!((report.worst_frame_ms - 50.0).abs() < 0.01);
+        assert!((report.average_draw_calls - 3.0).abs() < 1e-5);
+        assert_eq!(report.chunks_meshed, 400);
+        assert!((report.average_chunk_mesh_ms - 2.0).abs() < 1e-3);
+
+        let json = report.to_json();
+        assert!(json.starts_with("{\n") && json.ends_with("}\n"));
+        assert!(json.contains("\"one_percent_low_fps\": 20.00,"));
+        assert!(json.contains("\"generator\": \"hills:iterations=50\","));
+        assert!(json.contains("\"erosion_ms\": 80.000,"));
+        assert!(json.contains("\"generation_ms\": 120.000,"));
+        assert!(json.contains("\"worst_frame_meshing_ms\": 4.000\n"));
+    }
+}
@@ -0,0 +1,131 @@
+use glam::{ivec2, IVec2, Vec3};
+
+/// How many columns to either side of a corner its tint is averaged over, so colours fade from
+/// one biome into the next instead of changing at a hard edge.
+pub const BLEND_RADIUS: i32 = 3;
+
+/// The kind of land a column of the world is, which decides the colour of its grass and water.
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub enum Biome {
+    /// Leaves textures as they are.
+    #[default]
+    Plains,
+    Forest,
+    Desert,
+    Tundra,
+}
+
+/// What a face is tinted as.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Tint {
+    Grass,
+    Water,
+}
+
+impl Biome {
+    /// The biome for a climate, both measures roughly from -1 to 1.
+    pub fn from_climate(temperature: f64, humidity: f64) -> Self {
+        if temperature < -0.2 {
+            Biome::Tundra
+        } else if temperature > 0.2 && humidity < 0.0 {
+            Biome::Desert
+        } else if humidity > 0.15 {
+            Biome::Forest
+        } else {
+            Biome::Plains
+        }
+    }
+
+    /// The colour textures of `tint` are multiplied by here.
+    pub fn color(self, tint: Tint) -> [u8; 3] {
+        match (self, tint) {
+            (Biome::Plains, _) => [255, 255, 255],
+            (Biome::Forest, Tint::Grass) => [190, 230, 160],
+            (Biome::Forest, Tint::Water) => [190, 220, 230],
+            (Biome::Desert, Tint::Grass) => [235, 215, 140],
+            (Biome::Desert, Tint::Water) => [180, 240, 225],
+            (Biome::Tundra, Tint::Grass) => [205, 225, 230],
+            (Biome::Tundra, Tint::Water) => [165, 185, 235],
+        }
+    }
+}
+
+/// The biome of every column of a world, x along its width and z along its height.
+pub struct BiomeMap {
+    width: i32,
+    height: i32,
+    biomes: Vec<Biome>,
+}
+
+impl BiomeMap {
+    pub fn new(width: u32, height: u32, biome: impl Fn(IVec2) -> Biome) -> Self {
+        let (width, height) = (width as i32, height as i32);
+        let biomes = (0..height)
+            .flat_map(|z| (0..width).map(move |x| ivec2(x, z)))
+            .map(biome)
+            .collect();
+        Self {
+            width,
+            height,
+            biomes,
+        }
+    }
+
+    /// The biome of a column, with columns off the edge of the world taking the nearest one's.
+    pub fn get(&self, column: IVec2) -> Biome {
+        if self.biomes.is_empty() {
+            return Biome::default();
+        }
+        let x = column.x.clamp(0, self.width - 1);
+        let z = column.y.clamp(0, self.height - 1);
+        self.biomes[(x + z * self.width) as usize]
+    }
+
+    /// The `tint` colour at the corner between columns `corner - 1` and `corner`, averaged over
+    /// the `BLEND_RADIUS` columns to each side of it.
+    pub fn blend(&self, tint: Tint, corner: IVec2) -> [u8; 3] {
+        let mut total = Vec3::ZERO;
+        for z in corner.y - BLEND_RADIUS..corner.y + BLEND_RADIUS {
+            for x in corner.x - BLEND_RADIUS..corner.x + BLEND_RADIUS {
+                total += Vec3::from(self.get(ivec2(x, z)).color(tint).map(f32::from));
+            }
+        }
+        let average = total / (2 * BLEND_RADIUS).pow(2) as f32;
+        average.round().to_array().map(|channel| channel as u8)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::ivec2;
+
+    use super::{Biome, BiomeMap, Tint, BLEND_RADIUS};
+
+    #[test]
+    fn colours_fade_across_biome_borders() {
+        // desert on the left half, forest on the right
+        let map = BiomeMap::new(32, 4, |column| {
+            if column.x < 16 {
+                Biome::Desert
+            } else {
+                Biome::Forest
+            }
+        });
+        let desert = Biome::Desert.color(Tint::Grass);
+        let forest = Biome::Forest.color(Tint::Grass);
+        assert_eq!(map.blend(Tint::Grass, ivec2(16 - BLEND_RADIUS, 0)), desert);
+        assert_eq!(map.blend(Tint::Grass, ivec2(16 + BLEND_RADIUS, 0)), forest);
+        // the border itself is halfway between
+        let middle = map.blend(Tint::Grass, ivec2(16, 2));
+        for channel in 0..3 {
+            let average = (desert[channel] as f32 + forest[channel] as f32) / 2.0;
+            assert!((middle[channel] as f32 - average).abs() <= 0.5);
+        }
+        // and every step towards the forest gets closer to it
+        let steps: Vec<_> = (12..=20)
+            .map(|x| map.blend(Tint::Grass, ivec2(x, 0))[0])
+            .collect();
+        assert!(steps.windows(2).all(|pair| pair[0] >= pair[1]));
+        assert_eq!(map.get(ivec2(-5, 100)), Biome::Desert);
+    }
+}
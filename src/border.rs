@@ -0,0 +1,89 @@
+use glam::{vec2, IVec3, Vec2, Vec3, Vec3Swizzles};
+
+/// A wall around the playable area, lined up with the x and z axes and running the full height
+/// of the world. Nothing beyond it is meshed and the player can't walk through it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WorldBorder {
+    /// On the x and z plane.
+    pub centre: Vec2,
+    /// Along x and z, in blocks.
+    pub size: Vec2,
+}
+
+impl WorldBorder {
+    pub fn new(centre: Vec2, size: Vec2) -> Self {
+        Self {
+            centre,
+            size: size.max(Vec2::ZERO),
+        }
+    }
+
+    /// Fits a `width` by `depth` block area whose first block is centred on the origin, as the
+    /// world lays its blocks out.
+    pub fn around_blocks(width: u32, depth: u32) -> Self {
+        let size = vec2(width as f32, depth as f32);
+        Self::new(size / 2.0 - 0.5, size)
+    }
+
+    pub fn min(&self) -> Vec2 {
+        self.centre - self.size / 2.0
+    }
+
+    pub fn max(&self) -> Vec2 {
+        self.centre + self.size / 2.0
+    }
+
+    /// Whether the block centred on `position` is inside.
+    pub fn contains_block(&self, position: IVec3) -> bool {
+        let position = position.xz().as_vec2();
+        position.cmpge(self.min()).all() && position.cmplt(self.max()).all()
+    }
+
+    /// Pushes a box standing at `position` with the given horizontal half extents back inside.
+    /// A border smaller than the box pins it to the centre.
+    pub fn confine(&self, position: Vec3, half_extents: Vec2) -> Vec3 {
+        let slack = (self.size / 2.0 - half_extents).max(Vec2::ZERO);
+        let xz = position
+            .xz()
+            .clamp(self.centre - slack, self.centre + slack);
+        Vec3::new(xz.x, position.y, xz.y)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{ivec3, vec2, vec3};
+
+    use super::WorldBorder;
+
+    #[test]
+    fn world_border_covers_every_block() {
+        let border = WorldBorder::around_blocks(16, 8);
+        assert_eq!(border.min(), vec2(-0.5, -0.5));
+        assert_eq!(border.max(), vec2(15.5, 7.5));
+        assert!(border.contains_block(ivec3(0, -100, 0)));
+        assert!(border.contains_block(ivec3(15, 3, 7)));
+        assert!(!border.contains_block(ivec3(16, 0, 0)));
+        assert!(!border.contains_block(ivec3(0, 0, -1)));
+    }
+
+    #[test]
+    fn confine_keeps_the_box_inside() {
+        let border = WorldBorder::new(vec2(0.0, 0.0), vec2(10.0, 10.0));
+        let half_extents = vec2(0.3, 0.3);
+        // inside nothing moves, only the horizontal position is touched
+        let position = vec3(1.0, -7.0, 2.0);
+        assert_eq!(border.confine(position, half_extents), position);
+        let confined = border.confine(vec3(20.0, 3.0, -5.5), half_extents);
+        assert!(
+            confined.abs_diff_eq(vec3(4.7, 3.0, -4.7), 1e-6),
+            "{confined}"
+        );
+
+        let tiny = WorldBorder::new(vec2(1.0, 1.0), vec2(0.2, 0.2));
+        assert_eq!(
+            tiny.confine(vec3(5.0, 0.0, 5.0), half_extents),
+            vec3(1.0, 0.0, 1.0)
+        );
+    }
+}
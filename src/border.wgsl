@@ -0,0 +1,79 @@
+struct Frame {
+    view_proj: mat4x4<f32>,
+    view: mat4x4<f32>,
+    camera_position: vec3<f32>,
+    time: f32,
+    sun_direction: vec3<f32>,
+    fog_start: f32,
+    fog_color: vec3<f32>,
+    fog_end: f32,
+    screen_size: vec2<f32>,
+    // how bright the sky lights the world, from 0 to 1
+    sky_light: f32,
+    // world border corners on the x z plane, min in xy and max in zw
+    border: vec4<f32>,
+}
+
+@group(0) @binding(0)
+var<uniform> frame: Frame;
+
+// the walls reach this far above and below the camera, so they never seem to end
+let HALF_HEIGHT: f32 = 64.0;
+// the wall fades in as the camera gets within this many blocks of it
+let FADE_DISTANCE: f32 = 24.0;
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) world_position: vec3<f32>,
+    // distance along the wall, for the stripes
+    @location(1) along: f32,
+}
+
+// four walls of two triangles each, no vertex buffers needed
+@vertex
+fn vertex(@builtin(vertex_index) index: u32) -> VertexOutput {
+    let wall = index / 6u;
+    let vertex = index % 6u;
+    // corners 0 1 2 and 0 2 3 of the quad, one bit per vertex as in chunk.wgsl
+    let along = f32((0x16u >> vertex) & 1u);
+    let up = f32((0x34u >> vertex) & 1u);
+
+    let min = frame.border.xy;
+    let max = frame.border.zw;
+    var xz: vec2<f32>;
+    if (wall == 0u) {
+        xz = vec2<f32>(min.x, mix(min.y, max.y, along));
+    } else if (wall == 1u) {
+        xz = vec2<f32>(max.x, mix(min.y, max.y, along));
+    } else if (wall == 2u) {
+        xz = vec2<f32>(mix(min.x, max.x, along), min.y);
+    } else {
+        xz = vec2<f32>(mix(min.x, max.x, along), max.y);
+    }
+    let y = frame.camera_position.y + (up * 2.0 - 1.0) * HALF_HEIGHT;
+    let world_position = vec3<f32>(xz.x, y, xz.y);
+
+    var out: VertexOutput;
+    out.position = frame.view_proj * vec4<f32>(world_position, 1.0);
+    out.world_position = world_position;
+    out.along = xz.x + xz.y;
+    return out;
+}
+
+struct FragmentInput {
+    @location(0) world_position: vec3<f32>,
+    @location(1) along: f32,
+}
+
+@fragment
+fn fragment(in: FragmentInput) -> @location(0) vec4<f32> {
+    // diagonal stripes drifting up the wall
+    let stripe = fract((in.along + in.world_position.y) / 4.0 - frame.time * 0.25);
+    var alpha = 0.15;
+    if (stripe < 0.5) {
+        alpha = 0.4;
+    }
+    let distance = length(in.world_position.xz - frame.camera_position.xz);
+    alpha = alpha * (1.0 - smoothstep(0.0, FADE_DISTANCE, distance));
+    return vec4<f32>(0.3, 0.6, 1.0, alpha);
+}
@@ -3,18 +3,30 @@ use std::f32::consts::PI;
 use glam::{Mat4, Vec2, Vec3};
 use winit::dpi::PhysicalSize;
 
+/// glam's right-handed projections already map depth onto wgpu's [0, 1] range. This flips it so
+/// the near plane lands on 1 and the far plane on 0 (reverse-Z), which keeps float depth precise
+/// far away instead of spending it all next to the camera. Pipelines test with `Greater` and the
+/// depth buffer is cleared to 0.
 #[rustfmt::skip]
-const OPENGL_TO_WGPU: Mat4 = Mat4::from_cols_array(&[
+const REVERSE_Z: Mat4 = Mat4::from_cols_array(&[
     1.0, 0.0, 0.0, 0.0,
     0.0, 1.0, 0.0, 0.0,
-    0.0, 0.0, 0.5, 0.0,
-    0.0, 0.0, 0.5, 1.0,
+    0.0, 0.0, -1.0, 0.0,
+    0.0, 0.0, 1.0, 1.0,
 ]);
 
-/// Only applies to orthographic projections.
+/// How a camera adapts its projection when the window changes shape. Extents are always derived
+/// from the projection the camera was created with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum ResizeStrategy {
+    /// Keep the vertical extent (or vertical fov) and widen or narrow horizontally.
     KeepY,
+    /// Keep the horizontal extent (or horizontal fov) and grow or shrink vertically.
     KeepX,
+    /// Keep the whole original view visible, adding space along whichever axis the window has
+    /// grown relative to it.
+    Letterbox,
+    /// Keep the original projection and let the image stretch with the window.
     Stretch,
 }
 
@@ -42,45 +54,55 @@ impl Projection {
         size: PhysicalSize<u32>,
         resize_strategy: ResizeStrategy,
     ) -> Self {
+        let new_aspect_ratio = size.width as f32 / size.height as f32;
         match self {
             Projection::Perspective {
                 fov_y,
+                aspect_ratio,
                 z_near,
                 z_far,
-                ..
-            } => Projection::Perspective {
-                fov_y,
-                aspect_ratio: (size.width / size.height) as f32,
-                z_near,
-                z_far,
-            },
+            } => {
+                let keep_x = match resize_strategy {
+                    ResizeStrategy::KeepX => true,
+                    ResizeStrategy::KeepY => false,
+                    ResizeStrategy::Letterbox => new_aspect_ratio < aspect_ratio,
+                    ResizeStrategy::Stretch => return self,
+                };
+                let fov_y = if keep_x {
+                    // hold the horizontal fov the camera was created with
+                    let half_fov_x = (fov_y.to_radians() / 2.0).tan() * aspect_ratio;
+                    (2.0 * (half_fov_x / new_aspect_ratio).atan()).to_degrees()
+                } else {
+                    fov_y
+                };
+                Projection::Perspective {
+                    fov_y,
+                    aspect_ratio: new_aspect_ratio,
+                    z_near,
+                    z_far,
+                }
+            }
             Projection::Orthographic {
-                mut left,
+                left,
                 mut right,
-                mut bottom,
+                bottom,
                 mut top,
                 near,
                 far,
             } => {
-                match resize_strategy {
-                    ResizeStrategy::KeepX => {
-                        let inverse_aspect_ratio = size.height as f32 / size.width as f32;
-                        top *= inverse_aspect_ratio;
-                        bottom *= inverse_aspect_ratio;
-                    }
-                    ResizeStrategy::KeepY => {
-                        let aspect_ratio = size.width as f32 / size.height as f32;
-                        right *= aspect_ratio;
-                        left *= aspect_ratio;
-                    }
-                    ResizeStrategy::Stretch => {
-                        // let aspect_ratio = size.width as f32 / size.height as f32;
-                        // right *= aspect_ratio;
-                        // left *= aspect_ratio;
-                        // let inverse_aspect_ratio = size.height as f32 / size.width as f32;
-                        // top *= inverse_aspect_ratio;
-                        // bottom *= inverse_aspect_ratio;
-                    }
+                // the bottom left corner stays put so ui anchored to the origin doesn't move
+                let width = right - left;
+                let height = top - bottom;
+                let keep_x = match resize_strategy {
+                    ResizeStrategy::KeepX => true,
+                    ResizeStrategy::KeepY => false,
+                    ResizeStrategy::Letterbox => new_aspect_ratio < width / height,
+                    ResizeStrategy::Stretch => return self,
+                };
+                if keep_x {
+                    top = bottom + width / new_aspect_ratio;
+                } else {
+                    right = left + height * new_aspect_ratio;
                 }
                 Projection::Orthographic {
                     left,
@@ -104,6 +126,8 @@ pub struct Camera {
     // look_dir: Vec3,
     pitch: f32, // up and down
     yaw: f32,   // left and right
+    /// How much bigger an orthographic camera draws everything, see `set_scale`.
+    scale: f32,
 }
 
 impl Camera {
@@ -126,6 +150,7 @@ impl Camera {
             position,
             pitch: 0.0,
             yaw: PI, // look_dir: DEFAULT_LOOK_DIR,
+            scale: 1.0,
         }
     }
 
@@ -153,6 +178,7 @@ impl Camera {
             pitch: 0.0,
             yaw: PI,
             // look_dir: DEFAULT_LOOK_DIR,
+            scale: 1.0,
         }
     }
 
@@ -180,18 +206,24 @@ impl Camera {
         self.forward().cross(UP)
     }
 
+    pub fn position(&self) -> Vec3 {
+        self.position
+    }
+
     pub fn set_position(&mut self, position: Vec3) {
         self.position = position;
     }
 
-    pub fn translate(&mut self, translation: Vec3) {
-        self.position += translation;
+    /// Points the camera along `direction`, which needn't be normalised.
+    pub fn look_at(&mut self, direction: Vec3) {
+        let direction = direction.normalize();
+        self.yaw = direction.x.atan2(direction.z);
+        self.pitch = direction
+            .y
+            .asin()
+            .clamp(-89.0_f32.to_radians(), 89.0_f32.to_radians());
     }
 
-    // pub fn look_at(&mut self, direction: Vec3) {
-    //     self.look_dir = direction;
-    // }
-
     pub fn look_add(&mut self, other: Vec2) {
         self.pitch += other.y;
         self.pitch = self
@@ -208,11 +240,17 @@ impl Camera {
         // we calculate the current projection from our original projection (const) to avoid cumulative float errors
     }
 
+    /// Draws everything an orthographic camera sees `scale` times bigger, seeing less of it, with
+    /// the bottom left corner kept in place like resizing does. Perspective cameras ignore it.
+    pub fn set_scale(&mut self, scale: f32) {
+        self.scale = scale;
+    }
+
     pub fn compute(&self) -> Mat4 {
         // let pitch be the angle on the z-plane, 0 if front facing, positive looking up
         // let yaw be the angle on the x-plane, 0 if front facing, positive looking right
 
-        OPENGL_TO_WGPU
+        REVERSE_Z
             * match self.projection {
                 Projection::Perspective {
                     fov_y,
@@ -227,8 +265,103 @@ impl Camera {
                     top,
                     near,
                     far,
-                } => Mat4::orthographic_rh(left, right, bottom, top, near, far),
+                } => {
+                    let right = left + (right - left) / self.scale;
+                    let top = bottom + (top - bottom) / self.scale;
+                    Mat4::orthographic_rh(left, right, bottom, top, near, far)
+                }
             }
-            * Mat4::look_to_rh(self.position, self.look_dir(), UP)
+            * self.view()
+    }
+
+    /// The world to view space transform on its own, without the projection.
+    pub fn view(&self) -> Mat4 {
+        Mat4::look_to_rh(self.position, self.look_dir(), UP)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{vec3, Vec3};
+    use winit::dpi::PhysicalSize;
+
+    use super::{Camera, ResizeStrategy};
+
+    fn depth(camera: &Camera, point: Vec3) -> f32 {
+        let clip = camera.compute() * point.extend(1.0);
+        clip.z / clip.w
+    }
+
+    #[test]
+    fn perspective_depth_is_reversed() {
+        // the default camera looks down -z
+        let camera = Camera::new_projection(Vec3::ZERO, 75.0, 1.0, 0.1, 1000.0);
+        assert!((depth(&camera, vec3(0.0, 0.0, -0.1)) - 1.0).abs() < 1e-4);
+        assert!(depth(&camera, vec3(0.0, 0.0, -1000.0)).abs() < 1e-4);
+        assert!(depth(&camera, vec3(0.0, 0.0, -10.0)) > depth(&camera, vec3(0.0, 0.0, -20.0)));
+    }
+
+    #[test]
+    fn look_at_points_the_camera() {
+        let mut camera = Camera::new_projection(Vec3::ZERO, 75.0, 1.0, 0.1, 1000.0);
+        let direction = vec3(1.0, -0.5, 2.0).normalize();
+        camera.look_at(direction * 3.0);
+        assert!(camera.look_dir().abs_diff_eq(direction, 1e-5));
+    }
+
+    #[test]
+    fn orthographic_depth_is_reversed() {
+        let camera = Camera::new_orthographic(Vec3::ZERO, 0.0, 800.0, 0.0, 600.0, 0.0, 100.0);
+        assert!((depth(&camera, vec3(0.0, 0.0, 0.0)) - 1.0).abs() < 1e-4);
+        assert!(depth(&camera, vec3(0.0, 0.0, -100.0)).abs() < 1e-4);
+    }
+
+    fn ortho_corner(camera: &Camera) -> (f32, f32) {
+        // project the point that lands on the top right corner of a 0..800 x 0..600 view
+        let clip = camera.compute() * vec3(800.0, 600.0, 0.0).extend(1.0);
+        (clip.x / clip.w, clip.y / clip.w)
+    }
+
+    #[test]
+    fn orthographic_resize_strategies() {
+        let ui = || Camera::new_orthographic(Vec3::ZERO, 0.0, 800.0, 0.0, 600.0, 0.0, 100.0);
+        let wide = PhysicalSize::new(1600, 600);
+        let tall = PhysicalSize::new(800, 1200);
+
+        let mut camera = ui();
+        camera.resize(wide, ResizeStrategy::KeepY);
+        let (x, y) = ortho_corner(&camera);
+        assert!((x - 0.0).abs() < 1e-4 && (y - 1.0).abs() < 1e-4, "{x}, {y}");
+
+        let mut camera = ui();
+        camera.resize(tall, ResizeStrategy::KeepX);
+        let (x, y) = ortho_corner(&camera);
+        assert!((x - 1.0).abs() < 1e-4 && (y - 0.0).abs() < 1e-4, "{x}, {y}");
+
+        // letterboxing keeps the original view on screen whichever way the window grows
+        for size in [wide, tall] {
+            let mut camera = ui();
+            camera.resize(size, ResizeStrategy::Letterbox);
+            let (x, y) = ortho_corner(&camera);
+            assert!(x <= 1.0 + 1e-4 && y <= 1.0 + 1e-4, "{x}, {y}");
+        }
+
+        let mut camera = ui();
+        camera.resize(wide, ResizeStrategy::Stretch);
+        let (x, y) = ortho_corner(&camera);
+        assert!((x - 1.0).abs() < 1e-4 && (y - 1.0).abs() < 1e-4, "{x}, {y}");
+    }
+
+    #[test]
+    fn scaled_orthographic_cameras_grow_from_the_corner() {
+        let mut camera = Camera::new_orthographic(Vec3::ZERO, 0.0, 800.0, 0.0, 600.0, 0.0, 100.0);
+        camera.set_scale(2.0);
+        let project = |point| {
+            let clip = camera.compute() * vec3(point, point * 0.75, 0.0).extend(1.0);
+            (clip.x / clip.w, clip.y / clip.w)
+        };
+        assert_eq!(project(0.0), (-1.0, -1.0));
+        let (x, y) = project(400.0);
+        assert!((x - 1.0).abs() < 1e-4 && (y - 1.0).abs() < 1e-4, "{x}, {y}");
     }
 }
@@ -0,0 +1,665 @@
+use glam::{IVec3, Vec3};
+
+use crate::texture::TextureHandle;
+
+/// Edge length of a chunk in blocks.
+pub const CHUNK_SIZE: i32 = 16;
+
+/// How many texture handles chunk meshes can refer to, packed quads keep 8 bits of texture.
+pub const MAX_TEXTURES: u32 = 256;
+
+/// The direction a quad faces. The discriminants are what the vertex pulling shader sees.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Face {
+    PosX = 0,
+    NegX = 1,
+    PosY = 2,
+    NegY = 3,
+    PosZ = 4,
+    NegZ = 5,
+}
+
+impl Face {
+    pub const ALL: [Face; 6] = [
+        Face::PosX,
+        Face::NegX,
+        Face::PosY,
+        Face::NegY,
+        Face::PosZ,
+        Face::NegZ,
+    ];
+
+    /// The axis the face points along, followed by the two axes its quads span. Each triple is
+    /// right handed (u × v = normal) so positive faces wind counter-clockwise in (u, v).
+    fn axes(self) -> (usize, usize, usize) {
+        let normal = self as usize / 2;
+        (normal, (normal + 1) % 3, (normal + 2) % 3)
+    }
+
+    fn is_positive(self) -> bool {
+        matches!(self, Face::PosX | Face::PosY | Face::PosZ)
+    }
+
+    pub fn opposite(self) -> Face {
+        Face::ALL[self as usize ^ 1]
+    }
+
+    pub fn normal(self) -> IVec3 {
+        let mut normal = IVec3::ZERO;
+        normal[self.axes().0] = if self.is_positive() { 1 } else { -1 };
+        normal
+    }
+}
+
+/// How many variants a connected texture has, one for each combination of the four blocks
+/// beside a face being joined to it or not.
+pub const CONNECTED_VARIANTS: u32 = 16;
+
+/// The texture of a block's faces in a chunk mesh.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FaceTexture {
+    /// The same texture on every face.
+    Single(TextureHandle),
+    /// The first of `CONNECTED_VARIANTS` consecutive handles. A face takes the variant whose
+    /// bits say which of the blocks to its left, right, top and bottom, as the texture is laid
+    /// on it, have the same texture, so walls of them show no borders between blocks.
+    Connected(TextureHandle),
+}
+
+/// The directions the right and the bottom of a texture run along on a face. Must match
+/// `chunk_vertex` in chunk.wgsl.
+fn texture_axes(face: Face) -> (IVec3, IVec3) {
+    match face.axes().0 {
+        0 => (IVec3::Z, IVec3::NEG_Y),
+        1 => (IVec3::X, IVec3::Z),
+        _ => (IVec3::X, IVec3::NEG_Y),
+    }
+}
+
+/// The variant of a connected texture for `face` of the block at `local`, given `joined`, which
+/// says whether a neighbour joins up with it. Bit 0 is set for a join on the texture's left, 1
+/// its right, 2 its top and 3 its bottom.
+fn connected_variant(local: IVec3, face: Face, joined: impl Fn(IVec3) -> bool) -> u32 {
+    let (right, down) = texture_axes(face);
+    [-right, right, -down, down]
+        .into_iter()
+        .enumerate()
+        .filter(|(_, side)| joined(local + *side))
+        .fold(0, |variant, (bit, _)| variant | 1 << bit)
+}
+
+/// Brightest light level a block can give off or a face can receive.
+pub const MAX_LIGHT: u8 = 15;
+
+/// The tint that leaves a texture as it is.
+pub const WHITE: [u8; 3] = [255; 3];
+
+/// A rectangle of identical block faces. Corners are in block space relative to the chunk
+/// origin, where the block at (x, y, z) spans (x, y, z) to (x + 1, y + 1, z + 1).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Quad {
+    /// The block whose face is the quad's lowest corner.
+    pub position: IVec3,
+    pub face: Face,
+    /// Extent along the face's u axis, in blocks.
+    pub width: u32,
+    /// Extent along the face's v axis, in blocks.
+    pub height: u32,
+    pub texture: TextureHandle,
+    /// Ambient occlusion at each corner, indexed by u step + 2 * v step, from 0 (fully occluded)
+    /// to 3 (open).
+    pub ao: [u8; 4],
+    /// Block light reaching the face, from 0 to `MAX_LIGHT`.
+    pub light: u8,
+    /// Sky light reaching the face, from 0 to `MAX_LIGHT`, dimmed further as the sky darkens.
+    pub sky_light: u8,
+    /// The colour the texture is multiplied by at each corner, indexed like `ao`. Blended across
+    /// the quad, so it can fade from one biome's colour into another's.
+    pub tint: [[u8; 3]; 4],
+}
+
+impl Quad {
+    /// The four corners in counter-clockwise order seen from the front, with their index into
+    /// `ao` and `tint`. Must match `pulled_vertex` in chunk.wgsl.
+    pub fn corners(&self) -> [(IVec3, usize); 4] {
+        corners(self.position, self.face, self.width, self.height)
+    }
+
+    /// Packs each corner into two u32s for the `vertex` shader. In the first x, y and z take 5
+    /// bits each, followed by 3 bits of face, 2 of ambient occlusion, 4 of light and 8 of
+    /// texture. The second holds the tint's red, green and blue in 8 bits each, then 4 bits of
+    /// sky light. Texture coordinates aren't stored, the shader derives them from the position
+    /// and face.
+    #[cfg_attr(feature = "vertex-pulling", allow(dead_code))]
+    pub fn vertices(&self) -> [[u32; 2]; 4] {
+        debug_assert!(self.texture < MAX_TEXTURES);
+        self.corners().map(|(corner, index)| {
+            let [r, g, b] = self.tint[index].map(u32::from);
+            [
+                corner.x as u32
+                    | (corner.y as u32) << 5
+                    | (corner.z as u32) << 10
+                    | (self.face as u32) << 15
+                    | (self.ao[index] as u32) << 18
+                    | (self.light as u32) << 20
+                    | self.texture << 24,
+                r | g << 8 | b << 16 | (self.sky_light as u32) << 24,
+            ]
+        })
+    }
+
+    /// Packs the quad into four u32s for the vertex pulling shader. The first holds x, y, z,
+    /// width - 1 and height - 1 in 4 bits each, then 3 bits of face and 8 of texture. The
+    /// second holds the 2 bit ambient occlusion of each corner followed by 4 bits of light and 4
+    /// of sky light. The last two hold the tint of two corners each, in 5 bits of red, 6 of
+    /// green and 5 of blue.
+    #[cfg_attr(not(feature = "vertex-pulling"), allow(dead_code))]
+    pub fn pack(&self) -> [u32; 4] {
+        debug_assert!(self.texture < MAX_TEXTURES);
+        let ao = self
+            .ao
+            .iter()
+            .enumerate()
+            .fold(0, |packed, (i, ao)| packed | (*ao as u32) << (2 * i));
+        let [a, b, c, d] = self
+            .tint
+            .map(|[r, g, b]| (r as u32 >> 3) << 11 | (g as u32 >> 2) << 5 | b as u32 >> 3);
+        [
+            self.position.x as u32
+                | (self.position.y as u32) << 4
+                | (self.position.z as u32) << 8
+                | (self.width - 1) << 12
+                | (self.height - 1) << 16
+                | (self.face as u32) << 20
+                | self.texture << 23,
+            ao | (self.light as u32) << 8 | (self.sky_light as u32) << 12,
+            a | b << 16,
+            c | d << 16,
+        ]
+    }
+}
+
+/// The corners of a rectangle of faces, as in `Quad::corners`.
+fn corners(position: IVec3, face: Face, width: u32, height: u32) -> [(IVec3, usize); 4] {
+    let (normal, u, v) = face.axes();
+    let mut base = position;
+    if face.is_positive() {
+        base[normal] += 1;
+    }
+    // negative faces swap u and v to flip the winding
+    let steps = if face.is_positive() {
+        [(0, 0), (1, 0), (1, 1), (0, 1)]
+    } else {
+        [(0, 0), (0, 1), (1, 1), (1, 0)]
+    };
+    steps.map(|(step_u, step_v)| {
+        let mut corner = base;
+        corner[u] += step_u * width as i32;
+        corner[v] += step_v * height as i32;
+        (corner, (step_u + 2 * step_v) as usize)
+    })
+}
+
+/// Index of a block in a chunk padded by one block on every side, so faces on the chunk border
+/// can see their neighbours in the next chunk.
+fn padded_index(local: IVec3) -> usize {
+    let padded = CHUNK_SIZE + 2;
+    let p = local + IVec3::ONE;
+    (p.x + padded * (p.y + padded * p.z)) as usize
+}
+
+/// Ambient occlusion of each corner of a block's face, from the three blocks in front of the
+/// face that touch the corner. Indexed like `Quad::ao`.
+fn face_ao(covered: &[bool], local: IVec3, face: Face) -> [u8; 4] {
+    let (_, u, v) = face.axes();
+    let front = local + face.normal();
+    let solid = |step_u: i32, step_v: i32| {
+        let mut position = front;
+        position[u] += step_u;
+        position[v] += step_v;
+        covered[padded_index(position)] as u8
+    };
+    [(-1, -1), (1, -1), (-1, 1), (1, 1)].map(|(step_u, step_v)| {
+        let (side_u, side_v) = (solid(step_u, 0), solid(0, step_v));
+        if side_u == 1 && side_v == 1 {
+            0
+        } else {
+            3 - side_u - side_v - solid(step_u, step_v)
+        }
+    })
+}
+
+/// Builds the quads for the chunk at `coord`, merging adjacent faces with the same texture,
+/// light and tint into as few rectangles as it greedily can. `block` gives the texture of the
+/// solid block at a world position, or None for air, `light` the block and sky light levels of a
+/// world position, and `tint` the colour of a block's face at one of its corners, all in world
+/// space. Faces between two solid blocks are never emitted, and faces whose corners are unevenly
+/// occluded are left unmerged so their shading stays per block.
+pub fn greedy_mesh(
+    coord: IVec3,
+    block: impl Fn(IVec3) -> Option<FaceTexture>,
+    light: impl Fn(IVec3) -> (u8, u8),
+    tint: impl Fn(IVec3, Face, IVec3) -> [u8; 3],
+) -> Vec<Quad> {
+    mesh_faces(
+        coord,
+        &block,
+        |position| block(position).is_some(),
+        light,
+        tint,
+    )
+}
+
+/// Like `greedy_mesh`, for translucent blocks such as water that are drawn separately from the
+/// solid ones. `block` gives the texture of the translucent blocks only, and `covers` whether a
+/// position is opaque and so hides the faces next to it. Faces between two translucent blocks
+/// of the same texture are hidden too, but water still shows through glass and glass through
+/// water.
+pub fn translucent_mesh(
+    coord: IVec3,
+    block: impl Fn(IVec3) -> Option<FaceTexture>,
+    covers: impl Fn(IVec3) -> bool,
+    light: impl Fn(IVec3) -> (u8, u8),
+    tint: impl Fn(IVec3, Face, IVec3) -> [u8; 3],
+) -> Vec<Quad> {
+    mesh_faces(coord, block, covers, light, tint)
+}
+
+/// Orders translucent quads furthest first from `eye`, given in the chunk's block space, so
+/// blending them in order shows the nearer ones over the further ones.
+pub fn sort_back_to_front(quads: &mut [Quad], eye: Vec3) {
+    let distance = |quad: &Quad| {
+        let [(a, _), _, (c, _), _] = quad.corners();
+        ((a + c).as_vec3() / 2.0).distance_squared(eye)
+    };
+    quads.sort_by(|a, b| distance(b).total_cmp(&distance(a)));
+}
+
+/// A face's texture, corner occlusion, block and sky light, and corner tints. Neighbouring faces
+/// with the same key merge into one quad, so a quad is only ever one colour where tints blend
+/// from one to another.
+type FaceKey = (TextureHandle, [u8; 4], (u8, u8), [[u8; 3]; 4]);
+
+fn mesh_faces(
+    coord: IVec3,
+    block: impl Fn(IVec3) -> Option<FaceTexture>,
+    covers: impl Fn(IVec3) -> bool,
+    light: impl Fn(IVec3) -> (u8, u8),
+    tint: impl Fn(IVec3, Face, IVec3) -> [u8; 3],
+) -> Vec<Quad> {
+    let origin = coord * CHUNK_SIZE;
+    let padded = (CHUNK_SIZE + 2) as usize;
+    let mut blocks = vec![None; padded * padded * padded];
+    let mut covered = vec![false; padded * padded * padded];
+    for z in -1..=CHUNK_SIZE {
+        for y in -1..=CHUNK_SIZE {
+            for x in -1..=CHUNK_SIZE {
+                let local = IVec3::new(x, y, z);
+                blocks[padded_index(local)] = block(origin + local);
+                covered[padded_index(local)] = covers(origin + local);
+            }
+        }
+    }
+
+    let size = CHUNK_SIZE as usize;
+    let mut quads = vec![];
+    let mut mask: Vec<Option<FaceKey>> = vec![None; size * size];
+    for face in Face::ALL {
+        let (normal, u, v) = face.axes();
+        for depth in 0..CHUNK_SIZE {
+            let local = |i: usize, j: usize| {
+                let mut local = IVec3::ZERO;
+                local[normal] = depth;
+                local[u] = i as i32;
+                local[v] = j as i32;
+                local
+            };
+
+            for j in 0..size {
+                for i in 0..size {
+                    let local = local(i, j);
+                    let front = padded_index(local + face.normal());
+                    let hidden = covered[front] || blocks[front] == blocks[padded_index(local)];
+                    let texture = blocks[padded_index(local)].filter(|_| !hidden);
+                    mask[i + j * size] = texture.map(|texture| {
+                        let texture = match texture {
+                            FaceTexture::Single(texture) => texture,
+                            FaceTexture::Connected(first) => {
+                                // joined to neighbours of the same block whose matching face
+                                // shows too
+                                let joined = |side: IVec3| {
+                                    blocks[padded_index(side)] == Some(texture)
+                                        && !covered[padded_index(side + face.normal())]
+                                };
+                                first + connected_variant(local, face, joined)
+                            }
+                        };
+                        // faces are lit by the open cell in front of them
+                        let light = light(origin + local + face.normal());
+                        let mut tints = [WHITE; 4];
+                        for (corner, index) in corners(local, face, 1, 1) {
+                            tints[index] = tint(origin + local, face, origin + corner);
+                        }
+                        (texture, face_ao(&covered, local, face), light, tints)
+                    });
+                }
+            }
+
+            for j in 0..size {
+                let mut i = 0;
+                while i < size {
+                    let Some(key @ (texture, ao, (light, sky_light), tint)) = mask[i + j * size]
+                    else {
+                        i += 1;
+                        continue;
+                    };
+                    let (width, height) = if ao.iter().all(|&corner| corner == ao[0]) {
+                        let width = (i..size)
+                            .take_while(|&i| mask[i + j * size] == Some(key))
+                            .count();
+                        let height = (j..size)
+                            .take_while(|&j| {
+                                (i..i + width).all(|i| mask[i + j * size] == Some(key))
+                            })
+                            .count();
+                        (width, height)
+                    } else {
+                        (1, 1)
+                    };
+                    for j in j..j + height {
+                        mask[i + j * size..i + width + j * size].fill(None);
+                    }
+                    quads.push(Quad {
+                        position: local(i, j),
+                        face,
+                        width: width as u32,
+                        height: height as u32,
+                        texture,
+                        ao,
+                        light,
+                        sky_light,
+                        tint,
+                    });
+                    i += width;
+                }
+            }
+        }
+    }
+    quads
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{ivec3, IVec3, Vec3};
+
+    use super::{
+        greedy_mesh, sort_back_to_front, translucent_mesh, Face,
+        FaceTexture::{Connected, Single},
+        Quad, MAX_LIGHT, WHITE,
+    };
+
+    fn quad(position: IVec3, face: Face, width: u32, height: u32) -> Quad {
+        Quad {
+            position,
+            face,
+            width,
+            height,
+            texture: 0,
+            ao: [3; 4],
+            light: MAX_LIGHT,
+            sky_light: MAX_LIGHT,
+            tint: [WHITE; 4],
+        }
+    }
+
+    fn untinted(_: IVec3, _: Face, _: IVec3) -> [u8; 3] {
+        WHITE
+    }
+
+    #[test]
+    fn solid_chunk_merges_into_one_quad_per_face() {
+        let quads = greedy_mesh(
+            IVec3::ZERO,
+            |p| {
+                (p.cmpge(IVec3::ZERO).all() && p.cmplt(IVec3::splat(16)).all()).then_some(Single(3))
+            },
+            |_| (0, MAX_LIGHT),
+            untinted,
+        );
+        assert_eq!(quads.len(), 6);
+        assert!(quads
+            .iter()
+            .all(|quad| quad.width == 16 && quad.height == 16 && quad.texture == 3));
+    }
+
+    #[test]
+    fn faces_against_neighbouring_chunks_are_hidden() {
+        // a floor one block thick that runs through every chunk
+        let quads = greedy_mesh(
+            IVec3::ZERO,
+            |p| (p.y == 0).then_some(Single(0)),
+            |_| (0, MAX_LIGHT),
+            untinted,
+        );
+        let faces: Vec<Face> = quads.iter().map(|quad| quad.face).collect();
+        assert_eq!(faces, [Face::PosY, Face::NegY]);
+    }
+
+    #[test]
+    fn different_textures_are_not_merged() {
+        let quads = greedy_mesh(
+            IVec3::ZERO,
+            |p| (p == ivec3(0, 0, 0) || p == ivec3(1, 0, 0)).then_some(Single(p.x as u32)),
+            |_| (0, MAX_LIGHT),
+            untinted,
+        );
+        // the shared face is hidden, the top faces differ in texture
+        assert_eq!(quads.len(), 10);
+    }
+
+    #[test]
+    fn faces_are_not_merged_across_a_change_of_tint() {
+        // a floor that turns from red to blue at x = 4
+        let tint = |_, _, corner: IVec3| {
+            if corner.x < 4 {
+                [255, 0, 0]
+            } else {
+                [0, 0, 255]
+            }
+        };
+        let quads = greedy_mesh(
+            IVec3::ZERO,
+            |p| (p.y == 0).then_some(Single(0)),
+            |_| (0, MAX_LIGHT),
+            tint,
+        );
+        let top: Vec<&Quad> = quads.iter().filter(|q| q.face == Face::PosY).collect();
+        // all red, the column that fades from red to blue, and all blue
+        assert_eq!(top.len(), 3);
+        for quad in top {
+            for (corner, index) in quad.corners() {
+                assert_eq!(quad.tint[index], tint(IVec3::ZERO, quad.face, corner));
+            }
+        }
+    }
+
+    #[test]
+    fn connected_faces_join_their_neighbours() {
+        // a wall of glass three blocks wide and two tall, facing z
+        let glass = |p: IVec3| (0..3).contains(&p.x) && (0..2).contains(&p.y) && p.z == 0;
+        let quads = translucent_mesh(
+            IVec3::ZERO,
+            |p| glass(p).then_some(Connected(16)),
+            glass,
+            |_| (0, MAX_LIGHT),
+            untinted,
+        );
+        let front: Vec<&Quad> = quads.iter().filter(|q| q.face == Face::NegZ).collect();
+        // every block has a different set of neighbours, so none merge
+        assert_eq!(front.len(), 6);
+        let variant = |x: i32, y: i32| {
+            let quad = front.iter().find(|q| q.position == ivec3(x, y, 0)).unwrap();
+            quad.texture - 16
+        };
+        // the texture's bottom is towards -y: left, right, top and bottom bits
+        assert_eq!(variant(0, 0), 0b0110);
+        assert_eq!(variant(1, 1), 0b1011);
+        // the top of the wall only joins along it
+        let top = quads.iter().find(|q| q.face == Face::PosY).unwrap();
+        assert_eq!(top.texture - 16, 0b0010);
+    }
+
+    #[test]
+    fn faces_take_the_light_in_front_of_them() {
+        // a floor lit brighter towards +x
+        let quads = greedy_mesh(
+            IVec3::ZERO,
+            |p| (p.y == 0).then_some(Single(0)),
+            |p| {
+                if p.y == 1 {
+                    ((p.x.clamp(0, 8) / 4) as u8, MAX_LIGHT)
+                } else {
+                    (0, 0)
+                }
+            },
+            untinted,
+        );
+        let top: Vec<&Quad> = quads.iter().filter(|q| q.face == Face::PosY).collect();
+        // three bands of light, each merged into one quad
+        assert_eq!(top.len(), 3);
+        let lights: Vec<u8> = top.iter().map(|quad| quad.light).collect();
+        assert!(lights.contains(&0) && lights.contains(&1) && lights.contains(&2));
+        let bottom = quads.iter().find(|q| q.face == Face::NegY).unwrap();
+        assert_eq!((bottom.light, bottom.width, bottom.height), (0, 16, 16));
+        assert_eq!((top[0].sky_light, bottom.sky_light), (MAX_LIGHT, 0));
+    }
+
+    #[test]
+    fn translucent_faces_only_show_against_open_cells() {
+        // a pool of water two blocks wide sunk into a stone floor
+        let water = |p: IVec3| p.y == 0 && (0..2).contains(&p.x) && p.z == 0;
+        let quads = translucent_mesh(
+            IVec3::ZERO,
+            |p| water(p).then_some(Single(1)),
+            |p| p.y <= 0,
+            |_| (0, MAX_LIGHT),
+            untinted,
+        );
+        // just the surface, merged across both blocks
+        assert_eq!(quads.len(), 1);
+        assert_eq!(
+            (quads[0].face, quads[0].width, quads[0].height),
+            (Face::PosY, 1, 2)
+        );
+    }
+
+    #[test]
+    fn water_shows_through_glass() {
+        // water with a pane of glass beside it, in a stone floor
+        let block = |p: IVec3| match (p.x, p.y, p.z) {
+            (0, 0, 0) => Some(Single(1)),
+            (1, 0, 0) => Some(Single(2)),
+            _ => None,
+        };
+        let quads = translucent_mesh(
+            IVec3::ZERO,
+            block,
+            |p| p.y < 0,
+            |_| (0, MAX_LIGHT),
+            untinted,
+        );
+        let between = |quad: &&Quad| {
+            (quad.texture, quad.face) == (1, Face::PosX)
+                || (quad.texture, quad.face) == (2, Face::NegX)
+        };
+        assert_eq!(quads.iter().filter(between).count(), 2);
+    }
+
+    #[test]
+    fn translucent_quads_sort_furthest_first() {
+        let mut quads = [0, 4, 2].map(|x| quad(ivec3(x, 0, 0), Face::PosY, 1, 1));
+        sort_back_to_front(&mut quads, Vec3::new(0.5, 2.0, 0.5));
+        let order = quads.map(|quad| quad.position.x);
+        assert_eq!(order, [4, 2, 0]);
+        sort_back_to_front(&mut quads, Vec3::new(4.5, 2.0, 0.5));
+        let order = quads.map(|quad| quad.position.x);
+        assert_eq!(order, [0, 2, 4]);
+    }
+
+    #[test]
+    fn corners_wind_counter_clockwise_from_outside() {
+        for face in Face::ALL {
+            let quad = quad(IVec3::ZERO, face, 2, 3);
+            let [a, b, c, _] = quad.corners().map(|(corner, _)| corner.as_vec3());
+            let normal = (b - a).cross(c - a).normalize();
+            assert_eq!(normal, face.normal().as_vec3(), "{face:?}");
+            // the quad sits on the outside of the block
+            let centre = Vec3::splat(0.5);
+            assert!((a - centre).dot(normal) > 0.0, "{face:?}");
+        }
+    }
+
+    #[test]
+    fn corners_next_to_a_wall_are_occluded() {
+        // a block with another one diagonally above it in +x
+        let quads = greedy_mesh(
+            IVec3::ZERO,
+            |p| (p == IVec3::ZERO || p == ivec3(1, 1, 0)).then_some(Single(0)),
+            |_| (0, MAX_LIGHT),
+            untinted,
+        );
+        let top = quads
+            .iter()
+            .find(|quad| quad.position == IVec3::ZERO && quad.face == Face::PosY)
+            .unwrap();
+        // +y faces span z then x, so the corners at x + 1 are the v steps
+        assert_eq!(top.ao, [3, 3, 2, 2]);
+    }
+
+    #[test]
+    fn vertex_fields_land_in_their_bits() {
+        let mut quad = quad(ivec3(15, 1, 2), Face::PosX, 1, 3);
+        quad.texture = 255;
+        quad.ao = [0, 1, 2, 3];
+        quad.light = 7;
+        quad.sky_light = 9;
+        quad.tint[3] = [10, 20, 30];
+        let [[first, _], [second, _], [third, third_tint], _] = quad.vertices();
+        // the first corner is on the +x side of the block
+        assert_eq!(first & 0x1f, 16);
+        assert_eq!(first >> 5 & 0x1f, 1);
+        assert_eq!(first >> 10 & 0x1f, 2);
+        assert_eq!(first >> 15 & 0x7, Face::PosX as u32);
+        assert_eq!(first >> 18 & 0x3, 0);
+        assert_eq!(first >> 20 & 0xf, 7);
+        assert_eq!(first >> 24, 255);
+        // then one step along u (y) and one along v (z)
+        assert_eq!(second >> 5 & 0x1f, 2);
+        assert_eq!(second >> 18 & 0x3, 1);
+        assert_eq!(third >> 10 & 0x1f, 5);
+        assert_eq!(third >> 18 & 0x3, 3);
+        assert_eq!(third_tint, 10 | 20 << 8 | 30 << 16 | 9 << 24);
+    }
+
+    #[test]
+    fn pack_fields_land_in_their_bits() {
+        let mut quad = quad(ivec3(15, 1, 2), Face::NegZ, 16, 1);
+        quad.texture = 255;
+        quad.ao = [0, 1, 2, 3];
+        quad.light = 7;
+        quad.sky_light = 9;
+        quad.tint[1] = [0, 255, 0];
+        quad.tint[2] = [255, 0, 255];
+        let [packed, shading, tints, more_tints] = quad.pack();
+        assert_eq!(packed & 0xf, 15);
+        assert_eq!(packed >> 4 & 0xf, 1);
+        assert_eq!(packed >> 8 & 0xf, 2);
+        assert_eq!(packed >> 12 & 0xf, 15);
+        assert_eq!(packed >> 16 & 0xf, 0);
+        assert_eq!(packed >> 20 & 0x7, Face::NegZ as u32);
+        assert_eq!(packed >> 23, 255);
+        assert_eq!(shading, 0b11_10_01_00 | 7 << 8 | 9 << 12);
+        assert_eq!(tints, 0xffff | 0x07e0 << 16);
+        assert_eq!(more_tints, 0xf81f | 0xffff << 16);
+    }
+}
@@ -0,0 +1,216 @@
+struct Frame {
+    view_proj: mat4x4<f32>,
+    view: mat4x4<f32>,
+    camera_position: vec3<f32>,
+    time: f32,
+    sun_direction: vec3<f32>,
+    fog_start: f32,
+    fog_color: vec3<f32>,
+    fog_end: f32,
+    screen_size: vec2<f32>,
+    // how bright the sky lights the world, from 0 to 1
+    sky_light: f32,
+    // world border corners on the x z plane, min in xy and max in zw
+    border: vec4<f32>,
+}
+
+// opacity of translucent blocks such as water
+let TRANSLUCENT_ALPHA: f32 = 0.7;
+
+struct Chunk {
+    // world position of the chunk's first block, w is unused
+    origin: vec4<f32>,
+}
+
+@group(0) @binding(0)
+var<uniform> frame: Frame;
+@group(1) @binding(0)
+var texture: texture_2d<f32>;
+@group(1) @binding(1)
+var samp: sampler;
+struct TextureInfo {
+    // where the texture is in the atlas, offset in xy and size in zw, in texels
+    rect: vec4<f32>,
+    // how many variants blocks pick between, this handle and the ones after it
+    variants: u32,
+    // whether blocks turn the texture a random number of quarter turns
+    rotate: u32,
+}
+
+// indexed by texture handle, as written by Renderer::write_texture_info
+@group(2) @binding(0)
+var<storage, read> textures: array<TextureInfo>;
+@group(2) @binding(1)
+var<uniform> chunk: Chunk;
+// four words per quad as packed by Quad::pack, only bound when vertex pulling is enabled
+@group(2) @binding(2)
+var<storage, read> quads: array<vec4<u32>>;
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) tex: vec2<f32>,
+    @location(1) @interpolate(flat) texture: u32,
+    @location(2) normal: vec3<f32>,
+    @location(3) world_position: vec3<f32>,
+    @location(4) shade: f32,
+    @location(5) tint: vec3<f32>,
+}
+
+// local is in block space relative to the chunk origin, where blocks span whole units; in the
+// world blocks are centred on whole units
+fn chunk_vertex(
+    local: vec3<f32>,
+    face: u32,
+    ao: u32,
+    light: u32,
+    sky_light: u32,
+    texture: u32,
+    tint: vec3<f32>,
+) -> VertexOutput {
+    // side faces keep the texture upright, top and bottom faces map x and z straight across;
+    // connected textures pick their variants to match, see texture_axes in chunk.rs
+    let normal = face / 2u;
+    var tex: vec2<f32>;
+    if (normal == 0u) {
+        tex = vec2<f32>(local.z, -local.y);
+    } else if (normal == 1u) {
+        tex = vec2<f32>(local.x, local.z);
+    } else {
+        tex = vec2<f32>(local.x, -local.y);
+    }
+
+    let world_position = chunk.origin.xyz + local - vec3<f32>(0.5);
+    var normal_vector = vec3<f32>(0.0);
+    normal_vector[normal] = select(-1.0, 1.0, face % 2u == 0u);
+
+    var out: VertexOutput;
+    out.position = frame.view_proj * vec4<f32>(world_position, 1.0);
+    out.world_position = world_position;
+    out.tex = tex;
+    out.texture = texture;
+    out.normal = normal_vector;
+    // lit by whichever is brighter of the sky light reaching the face and nearby light sources
+    let sky = frame.sky_light * f32(sky_light) / 15.0;
+    out.shade = (0.4 + 0.2 * f32(ao)) * max(sky, f32(light) / 15.0);
+    out.tint = tint;
+    return out;
+}
+
+// unpacks the vertices built by Quad::vertices
+@vertex
+fn vertex(@location(0) packed: u32, @location(1) tint: u32) -> VertexOutput {
+    let local = vec3<f32>(f32(packed & 31u), f32((packed >> 5u) & 31u), f32((packed >> 10u) & 31u));
+    return chunk_vertex(
+        local,
+        (packed >> 15u) & 7u,
+        (packed >> 18u) & 3u,
+        (packed >> 20u) & 15u,
+        tint >> 24u,
+        packed >> 24u,
+        vec3<f32>(f32(tint & 255u), f32((tint >> 8u) & 255u), f32((tint >> 16u) & 255u)) / 255.0,
+    );
+}
+
+// expands the quads described by Quad::pack, six vertices per quad; must match Quad::corners
+@vertex
+fn pulled_vertex(@builtin(vertex_index) index: u32) -> VertexOutput {
+    let quad = quads[index / 6u];
+    let packed = quad.x;
+    let vertex = index % 6u;
+    // the two triangles use corners 0 1 2 and 0 2 3, these masks hold the u and v step of the
+    // corner each vertex lands on, one bit per vertex
+    var step = vec2<u32>((0x16u >> vertex) & 1u, (0x34u >> vertex) & 1u);
+
+    let face = (packed >> 20u) & 7u;
+    let normal = face / 2u;
+    let u = (normal + 1u) % 3u;
+    let v = (normal + 2u) % 3u;
+    var local = vec3<f32>(f32(packed & 15u), f32((packed >> 4u) & 15u), f32((packed >> 8u) & 15u));
+    let size = vec2<f32>(f32(((packed >> 12u) & 15u) + 1u), f32(((packed >> 16u) & 15u) + 1u));
+    if (face % 2u == 0u) {
+        local[normal] = local[normal] + 1.0;
+    } else {
+        // negative faces wind the other way round
+        step = step.yx;
+    }
+    local[u] = local[u] + f32(step.x) * size.x;
+    local[v] = local[v] + f32(step.y) * size.y;
+
+    let corner = step.x + 2u * step.y;
+    let ao = (quad.y >> (2u * corner)) & 3u;
+    // two 5:6:5 tints to a word
+    let tint = (quad[2u + corner / 2u] >> (16u * (corner % 2u))) & 0xffffu;
+    let color = vec3<f32>(
+        f32(tint >> 11u) / 31.0,
+        f32((tint >> 5u) & 63u) / 63.0,
+        f32(tint & 31u) / 31.0,
+    );
+    let light = (quad.y >> 8u) & 15u;
+    let sky_light = (quad.y >> 12u) & 15u;
+    return chunk_vertex(local, face, ao, light, sky_light, packed >> 23u, color);
+}
+
+struct FragmentInput {
+    @location(0) tex: vec2<f32>,
+    @location(1) @interpolate(flat) texture: u32,
+    @location(2) normal: vec3<f32>,
+    @location(3) world_position: vec3<f32>,
+    @location(4) shade: f32,
+    @location(5) tint: vec3<f32>,
+}
+
+// a number that looks random but is always the same for the block whose face this is
+fn block_hash(world_position: vec3<f32>, normal: vec3<f32>) -> u32 {
+    let block = vec3<i32>(floor(world_position - normal * 0.5 + 0.5));
+    var hash = (u32(block.x) * 73856093u) ^ (u32(block.y) * 19349663u) ^ (u32(block.z) * 83492791u);
+    hash = (hash ^ (hash >> 13u)) * 0x5bd1e995u;
+    return hash ^ (hash >> 15u);
+}
+
+// turns a position in a block's texture by `turns` quarter turns about its centre
+fn turn(uv: vec2<f32>, turns: u32) -> vec2<f32> {
+    switch (turns) {
+        case 1u: {
+            return vec2<f32>(1.0 - uv.y, uv.x);
+        }
+        case 2u: {
+            return 1.0 - uv;
+        }
+        case 3u: {
+            return vec2<f32>(uv.y, 1.0 - uv.x);
+        }
+        default: {
+            return uv;
+        }
+    }
+}
+
+fn shade(in: FragmentInput) -> vec4<f32> {
+    // merged quads span several blocks, so the texture repeats once per block, each block
+    // picking its own variant and turn so wide areas don't show the same tile over and over
+    let info = textures[in.texture];
+    let hash = block_hash(in.world_position, in.normal);
+    var local_uv = fract(in.tex);
+    if (info.rotate != 0u) {
+        local_uv = turn(local_uv, hash & 3u);
+    }
+    let rect = textures[in.texture + (hash >> 2u) % max(info.variants, 1u)].rect;
+    let dimensions = vec2<f32>(textureDimensions(texture));
+    let uv = (rect.xy + local_uv * rect.zw) / dimensions;
+    let color = textureSample(texture, samp, uv) * vec4<f32>(in.tint * in.shade, 1.0);
+    let distance = length(in.world_position - frame.camera_position);
+    let fog = clamp((distance - frame.fog_start) / (frame.fog_end - frame.fog_start), 0.0, 1.0);
+    return vec4<f32>(mix(color.rgb, frame.fog_color, fog), color.a);
+}
+
+@fragment
+fn fragment(in: FragmentInput) -> @location(0) vec4<f32> {
+    return shade(in);
+}
+
+// for the sorted translucent meshes, blended over what's already drawn
+@fragment
+fn translucent_fragment(in: FragmentInput) -> @location(0) vec4<f32> {
+    let color = shade(in);
+    return vec4<f32>(color.rgb, color.a * TRANSLUCENT_ALPHA);
+}
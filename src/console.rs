@@ -0,0 +1,781 @@
+use std::str::FromStr;
+
+use glam::{ivec3, vec2, vec3, IVec3, Vec2, Vec3};
+
+use crate::{entity::EntityKind, structure::Rotation, text::CHARS, world::BlockType};
+
+/// How typing into a line finished.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LineEnd {
+    /// Enter was pressed.
+    Submitted,
+    /// Escape was pressed.
+    Cancelled,
+}
+
+/// Applies a typed character to a line of text input: backspace deletes, characters the font
+/// can't draw are dropped, and enter or escape end the line.
+pub fn edit_line(line: &mut String, char: char) -> Option<LineEnd> {
+    match char {
+        '\r' | '\n' => return Some(LineEnd::Submitted),
+        // backspace
+        '\u{8}' => {
+            line.pop();
+        }
+        // escape
+        '\u{1b}' => return Some(LineEnd::Cancelled),
+        char if CHARS.contains(&char) => line.push(char),
+        _ => (),
+    }
+    None
+}
+
+/// How many submitted lines the console remembers.
+const HISTORY_LENGTH: usize = 50;
+
+/// Seconds `effect` makes entities invisible for when it isn't told.
+const DEFAULT_INVISIBILITY: f32 = 30.0;
+
+/// A single line command prompt. Typing `/` opens it, enter runs the line and escape throws it
+/// away. Tab completes the word being typed, and up and down step through earlier lines.
+#[derive(Default)]
+pub struct Console {
+    /// The line being typed, while open.
+    line: Option<String>,
+    /// Submitted lines, oldest first.
+    history: Vec<String>,
+    /// Which line of the history is being shown, and the line that was being typed before
+    /// stepping back into it.
+    browsing: Option<(usize, String)>,
+    /// What the word being typed could complete to, after tab found more than one.
+    options: Option<String>,
+}
+
+impl Console {
+    pub fn is_open(&self) -> bool {
+        self.line.is_some()
+    }
+
+    /// Feeds in a typed character, returning the line once it's submitted. Characters the font
+    /// can't draw are dropped.
+    pub fn input_char(&mut self, char: char) -> Option<String> {
+        let Some(line) = &mut self.line else {
+            if char == '/' {
+                self.line = Some(String::new());
+            }
+            return None;
+        };
+        self.options = None;
+        if char == '\t' {
+            self.options = complete(line);
+            return None;
+        }
+        let end = edit_line(line, char);
+        if end.is_some() {
+            self.browsing = None;
+        }
+        match end {
+            Some(LineEnd::Submitted) => {
+                let line = self.line.take()?;
+                let repeated = self.history.last() == Some(&line);
+                if !line.trim().is_empty() && !repeated {
+                    if self.history.len() == HISTORY_LENGTH {
+                        self.history.remove(0);
+                    }
+                    self.history.push(line.clone());
+                }
+                Some(line)
+            }
+            Some(LineEnd::Cancelled) => {
+                self.line = None;
+                None
+            }
+            None => None,
+        }
+    }
+
+    /// Swaps the line for the one submitted before it.
+    pub fn history_back(&mut self) {
+        let Some(line) = &mut self.line else {
+            return;
+        };
+        let index = match &self.browsing {
+            Some((index, _)) => index.checked_sub(1),
+            None => self.history.len().checked_sub(1),
+        };
+        let Some(index) = index else {
+            return;
+        };
+        let draft = match self.browsing.take() {
+            Some((_, draft)) => draft,
+            None => line.clone(),
+        };
+        self.browsing = Some((index, draft));
+        *line = self.history[index].clone();
+        self.options = None;
+    }
+
+    /// Swaps the line for the one submitted after it, or back to what was being typed.
+    pub fn history_forward(&mut self) {
+        let (Some(line), Some((index, draft))) = (&mut self.line, &mut self.browsing) else {
+            return;
+        };
+        if *index + 1 < self.history.len() {
+            *index += 1;
+            *line = self.history[*index].clone();
+        } else {
+            *line = std::mem::take(draft);
+            self.browsing = None;
+        }
+        self.options = None;
+    }
+
+    /// What to show while the console is open: the line, followed by what's wrong with it so
+    /// far or what tab could complete it to.
+    pub fn prompt(&self) -> Option<String> {
+        let line = self.line.as_ref()?;
+        Some(match check(line).or_else(|| self.options.clone()) {
+            Some(note) => format!("/{line}   {note}"),
+            None => format!("/{line}"),
+        })
+    }
+}
+
+/// What an argument to a command can be, to complete and check it while it's typed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Arg {
+    /// One of these words.
+    Word(&'static [&'static str]),
+    Integer,
+    Number,
+    /// A block type's name, or air.
+    Block,
+    Entity,
+    Rotation,
+    /// Anything at all, like a structure's name.
+    Name,
+}
+
+impl Arg {
+    /// Every value there is for arguments with a fixed set of them.
+    fn options(self) -> Vec<&'static str> {
+        match self {
+            Arg::Word(words) => words.to_vec(),
+            Arg::Block => BlockType::ALL
+                .map(<&str>::from)
+                .into_iter()
+                .chain(["air"])
+                .collect(),
+            Arg::Entity => EntityKind::ALL.map(EntityKind::name).into(),
+            Arg::Rotation => vec!["0", "90", "180", "270"],
+            Arg::Integer | Arg::Number | Arg::Name => vec![],
+        }
+    }
+
+    fn check(self, word: &str) -> Result<(), String> {
+        match self {
+            Arg::Word(words) if words.contains(&word) => Ok(()),
+            Arg::Word(words) => Err(format!("expected {}", words.join(" or "))),
+            Arg::Integer => integer(word).map(drop),
+            Arg::Number => number(word).map(drop),
+            Arg::Block if word == "air" || BlockType::from_name(word).is_some() => Ok(()),
+            Arg::Block => Err(format!("unknown block {word}")),
+            Arg::Entity if EntityKind::from_name(word).is_some() => Ok(()),
+            Arg::Entity => Err(format!("unknown entity {word}")),
+            Arg::Rotation => word.parse::<Rotation>().map(drop),
+            Arg::Name => Ok(()),
+        }
+    }
+}
+
+/// A command the console knows, with every form its arguments can take.
+struct CommandInfo {
+    name: &'static str,
+    usage: &'static str,
+    forms: &'static [&'static [Arg]],
+}
+
+/// Every command, for completing and checking lines as they're typed. Running them is up to
+/// `Command`'s parsing.
+const COMMANDS: [CommandInfo; 7] = [
+    CommandInfo {
+        name: "border",
+        usage: "border [size | x z | centre x z | reset]",
+        forms: &[
+            &[],
+            &[Arg::Word(&["reset"])],
+            &[Arg::Word(&["centre", "center"]), Arg::Number, Arg::Number],
+            &[Arg::Number],
+            &[Arg::Number, Arg::Number],
+        ],
+    },
+    CommandInfo {
+        name: "effect",
+        usage: "effect entity invisibility [seconds] | effect entity clear",
+        forms: &[
+            &[Arg::Entity, Arg::Word(&["invisibility"]), Arg::Number],
+            &[Arg::Entity, Arg::Word(&["clear"])],
+        ],
+    },
+    CommandInfo {
+        name: "kill",
+        usage: "kill",
+        forms: &[&[]],
+    },
+    CommandInfo {
+        name: "setblock",
+        usage: "setblock x y z block",
+        forms: &[&[Arg::Integer, Arg::Integer, Arg::Integer, Arg::Block]],
+    },
+    CommandInfo {
+        name: "structure",
+        usage: "structure save|export name x1 y1 z1 x2 y2 z2 | structure place|import name x y z \
+                [rotation]",
+        forms: &[
+            &[
+                Arg::Word(&["save", "export"]),
+                Arg::Name,
+                Arg::Integer,
+                Arg::Integer,
+                Arg::Integer,
+                Arg::Integer,
+                Arg::Integer,
+                Arg::Integer,
+            ],
+            &[
+                Arg::Word(&["place", "import"]),
+                Arg::Name,
+                Arg::Integer,
+                Arg::Integer,
+                Arg::Integer,
+                Arg::Rotation,
+            ],
+        ],
+    },
+    CommandInfo {
+        name: "summon",
+        usage: "summon entity [x y z] [name]",
+        forms: &[
+            &[Arg::Entity, Arg::Name],
+            &[
+                Arg::Entity,
+                Arg::Number,
+                Arg::Number,
+                Arg::Number,
+                Arg::Name,
+            ],
+        ],
+    },
+    CommandInfo {
+        name: "time",
+        usage: "time [day | night | fraction of the day]",
+        forms: &[&[Arg::Word(&["day", "night"])], &[Arg::Number]],
+    },
+];
+
+/// How to type a command, for when it's typed wrong.
+fn usage(name: &str) -> String {
+    let info = COMMANDS.iter().find(|info| info.name == name);
+    format!("usage: {}", info.map_or(name, |info| info.usage))
+}
+
+/// Splits a line into the words finished so far and the one being typed, which is empty after
+/// a space.
+fn split_line(line: &str) -> (Vec<&str>, &str) {
+    let mut words: Vec<_> = line.split_whitespace().collect();
+    let typing = match line.ends_with(char::is_whitespace) {
+        true => "",
+        false => words.pop().unwrap_or(""),
+    };
+    (words, typing)
+}
+
+/// The forms of a command that the arguments finished so far fit, or what's wrong with them if
+/// none do. Optional arguments are trailing ones, so the forms here list every argument and
+/// shorter lines fit them too.
+fn fitting_forms(info: &CommandInfo, args: &[&str]) -> Result<Vec<&'static [Arg]>, String> {
+    let mut forms = info.forms.to_vec();
+    for (i, word) in args.iter().enumerate() {
+        let errors: Vec<_> = forms
+            .iter()
+            .filter_map(|form| form.get(i))
+            .filter_map(|arg| arg.check(word).err())
+            .collect();
+        forms.retain(|form| form.get(i).is_some_and(|arg| arg.check(word).is_ok()));
+        if forms.is_empty() {
+            // a message that fits every way it could have gone wrong, or else how to type it
+            return Err(match errors.as_slice() {
+                [error, rest @ ..] if rest.iter().all(|other| other == error) => error.clone(),
+                _ => usage(info.name),
+            });
+        }
+    }
+    Ok(forms)
+}
+
+/// What's wrong with the words of a line finished so far, if anything.
+fn check(line: &str) -> Option<String> {
+    let (words, _) = split_line(line);
+    let (name, args) = words.split_first()?;
+    let Some(info) = COMMANDS.iter().find(|info| info.name == *name) else {
+        return Some(format!("unknown command {name}"));
+    };
+    fitting_forms(info, args).err()
+}
+
+/// Completes the word being typed as far as it can, adding a space after it once it's whole.
+/// Returns the options to choose between when there's more than one.
+fn complete(line: &mut String) -> Option<String> {
+    let (words, typing) = split_line(line);
+    let options: Vec<&str> = match words.split_first() {
+        None => COMMANDS.iter().map(|info| info.name).collect(),
+        Some((name, args)) => {
+            let info = COMMANDS.iter().find(|info| info.name == *name)?;
+            let mut options = vec![];
+            for form in fitting_forms(info, args).ok()? {
+                for option in form.get(args.len()).map_or(vec![], |arg| arg.options()) {
+                    if !options.contains(&option) {
+                        options.push(option);
+                    }
+                }
+            }
+            options
+        }
+    };
+    let options: Vec<_> = options
+        .into_iter()
+        .filter(|option| option.starts_with(typing))
+        .collect();
+    let (first, rest) = options.split_first()?;
+    // as much as every option starts with
+    let common = rest.iter().fold(first.len(), |common, option| {
+        first
+            .chars()
+            .zip(option.chars())
+            .take_while(|(a, b)| a == b)
+            .count()
+            .min(common)
+    });
+    let start = line.len() - typing.len();
+    line.replace_range(start.., &first[..common]);
+    if rest.is_empty() {
+        line.push(' ');
+        None
+    } else {
+        Some(options.join(" "))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command {
+    Border(BorderCommand),
+    /// Places a block at a world position, or clears it with None.
+    SetBlock(IVec3, Option<BlockType>),
+    /// Sets the time of day as a fraction from sunrise, or reports it with None.
+    Time(Option<f32>),
+    /// Kills the player.
+    Kill,
+    Structure(StructureCommand),
+    /// Creates an entity at a position, or beside the player with None, with a name shown
+    /// above it if it's given one.
+    Summon(EntityKind, Option<Vec3>, Option<String>),
+    /// Makes every entity of a kind invisible for a number of seconds, or visible again with 0.
+    Invisibility(EntityKind, f32),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BorderCommand {
+    /// Reports where the border is.
+    Show,
+    /// Resizes the border around its centre, in blocks along x and z.
+    Size(Vec2),
+    Centre(Vec2),
+    /// Fits the border back around the whole world.
+    Reset,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum StructureCommand {
+    /// Saves the full blocks between two corners under a name.
+    Save(String, IVec3, IVec3),
+    /// Places a saved structure with its origin at a world position.
+    Place(String, IVec3, Rotation),
+    /// Like `Save`, writing a schematic other voxel tools can read.
+    Export(String, IVec3, IVec3),
+    /// Like `Place`, reading a schematic from another voxel tool.
+    Import(String, IVec3, Rotation),
+}
+
+impl FromStr for Command {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut words = s.split_whitespace();
+        match words.next() {
+            Some("border") => parse_border(words.collect()).map(Command::Border),
+            Some("setblock") => parse_set_block(words.collect()),
+            Some("time") => parse_time(words.collect()).map(Command::Time),
+            Some("kill") if words.next().is_none() => Ok(Command::Kill),
+            Some("kill") => Err(usage("kill")),
+            Some("structure") => parse_structure(words.collect()).map(Command::Structure),
+            Some("summon") => parse_summon(words.collect()),
+            Some("effect") => parse_effect(words.collect()),
+            Some(command) => Err(format!("unknown command {command}")),
+            None => Err("no command".into()),
+        }
+    }
+}
+
+fn parse_border(args: Vec<&str>) -> Result<BorderCommand, String> {
+    match args.as_slice() {
+        [] => Ok(BorderCommand::Show),
+        ["reset"] => Ok(BorderCommand::Reset),
+        ["centre" | "center", x, z] => Ok(BorderCommand::Centre(vec2(number(x)?, number(z)?))),
+        [size] => Ok(BorderCommand::Size(Vec2::splat(number(size)?))),
+        [x, z] => Ok(BorderCommand::Size(vec2(number(x)?, number(z)?))),
+        _ => Err(usage("border")),
+    }
+}
+
+fn parse_set_block(args: Vec<&str>) -> Result<Command, String> {
+    let [x, y, z, name] = args.as_slice() else {
+        return Err(usage("setblock"));
+    };
+    let position = ivec3(integer(x)?, integer(y)?, integer(z)?);
+    if *name == "air" {
+        return Ok(Command::SetBlock(position, None));
+    }
+    BlockType::from_name(name)
+        .map(|block_type| Command::SetBlock(position, Some(block_type)))
+        .ok_or_else(|| format!("unknown block {name}"))
+}
+
+fn parse_structure(args: Vec<&str>) -> Result<StructureCommand, String> {
+    let position =
+        |x, y, z| -> Result<IVec3, String> { Ok(ivec3(integer(x)?, integer(y)?, integer(z)?)) };
+    // schematics are saved and placed with the same arguments as structures
+    let (save, place): (fn(_, _, _) -> _, fn(_, _, _) -> _) = match args.first() {
+        Some(&"export" | &"import") => (StructureCommand::Export, StructureCommand::Import),
+        _ => (StructureCommand::Save, StructureCommand::Place),
+    };
+    match args.as_slice() {
+        ["save" | "export", name, x1, y1, z1, x2, y2, z2] => Ok(save(
+            name.to_string(),
+            position(x1, y1, z1)?,
+            position(x2, y2, z2)?,
+        )),
+        ["place" | "import", name, x, y, z] => {
+            Ok(place(name.to_string(), position(x, y, z)?, Rotation::None))
+        }
+        ["place" | "import", name, x, y, z, rotation] => Ok(place(
+            name.to_string(),
+            position(x, y, z)?,
+            rotation.parse()?,
+        )),
+        _ => Err(usage("structure")),
+    }
+}
+
+fn entity_kind(name: &str) -> Result<EntityKind, String> {
+    EntityKind::from_name(name).ok_or_else(|| {
+        let names: Vec<_> = EntityKind::ALL.map(EntityKind::name).into();
+        format!(
+            "unknown entity {name}, expected one of {}",
+            names.join(", ")
+        )
+    })
+}
+
+fn parse_summon(args: Vec<&str>) -> Result<Command, String> {
+    let (kind, position, name) = match args.as_slice() {
+        [kind] => (kind, None, None),
+        [kind, name] => (kind, None, Some(name)),
+        [kind, x, y, z] => (kind, Some((x, y, z)), None),
+        [kind, x, y, z, name] => (kind, Some((x, y, z)), Some(name)),
+        _ => return Err(usage("summon")),
+    };
+    let position = match position {
+        Some((x, y, z)) => Some(vec3(number(x)?, number(y)?, number(z)?)),
+        None => None,
+    };
+    Ok(Command::Summon(
+        entity_kind(kind)?,
+        position,
+        name.map(|name| name.to_string()),
+    ))
+}
+
+fn parse_effect(args: Vec<&str>) -> Result<Command, String> {
+    match args.as_slice() {
+        [name, "invisibility"] => Ok(Command::Invisibility(
+            entity_kind(name)?,
+            DEFAULT_INVISIBILITY,
+        )),
+        [name, "invisibility", seconds] => {
+            Ok(Command::Invisibility(entity_kind(name)?, number(seconds)?))
+        }
+        [name, "clear"] => Ok(Command::Invisibility(entity_kind(name)?, 0.0)),
+        _ => Err(usage("effect")),
+    }
+}
+
+fn parse_time(args: Vec<&str>) -> Result<Option<f32>, String> {
+    match args.as_slice() {
+        [] => Ok(None),
+        ["day"] => Ok(Some(0.25)),
+        ["night"] => Ok(Some(0.75)),
+        [time] => number(time).map(Some),
+        _ => Err(usage("time")),
+    }
+}
+
+fn integer(arg: &str) -> Result<i32, String> {
+    arg.parse()
+        .map_err(|_| format!("{arg} isn't a whole number"))
+}
+
+fn number(arg: &str) -> Result<f32, String> {
+    arg.parse().map_err(|_| format!("{arg} isn't a number"))
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{ivec3, vec2, vec3};
+
+    use crate::{entity::EntityKind, structure::Rotation, world::BlockType};
+
+    use super::{BorderCommand, Command, Console, StructureCommand};
+
+    fn type_line(console: &mut Console, text: &str) -> Option<String> {
+        text.chars().find_map(|char| console.input_char(char))
+    }
+
+    #[test]
+    fn slash_opens_and_enter_submits() {
+        let mut console = Console::default();
+        // typing while closed does nothing
+        assert_eq!(type_line(&mut console, "abc\r"), None);
+        assert!(!console.is_open());
+
+        assert_eq!(type_line(&mut console, "/bordx\u{8}er 5"), None);
+        assert_eq!(console.prompt().as_deref(), Some("/border 5"));
+        assert_eq!(type_line(&mut console, "\r").as_deref(), Some("border 5"));
+        assert!(!console.is_open());
+
+        assert_eq!(type_line(&mut console, "/border\u{1b}"), None);
+        assert!(!console.is_open());
+    }
+
+    #[test]
+    fn tab_completes_commands_and_arguments() {
+        let mut console = Console::default();
+        type_line(&mut console, "/se\t");
+        assert_eq!(console.prompt().as_deref(), Some("/setblock "));
+        type_line(&mut console, "1 -5 2 s\t");
+        // stone, sand and sign all fit, so it stops there and lists them
+        assert_eq!(
+            console.prompt().as_deref(),
+            Some("/setblock 1 -5 2 s   stone sand sign")
+        );
+        type_line(&mut console, "a\t");
+        assert_eq!(console.prompt().as_deref(), Some("/setblock 1 -5 2 sand "));
+
+        let mut console = Console::default();
+        type_line(&mut console, "/structure pl\thut 0 0 0 2\t");
+        assert_eq!(
+            console.prompt().as_deref(),
+            Some("/structure place hut 0 0 0 270 ")
+        );
+        let mut console = Console::default();
+        type_line(&mut console, "/summon \t");
+        assert_eq!(
+            console.prompt().as_deref(),
+            Some("/summon    item mob orb boat")
+        );
+    }
+
+    #[test]
+    fn bad_arguments_show_as_theyre_typed() {
+        let mut console = Console::default();
+        type_line(&mut console, "/setblock 1 x");
+        // the word being typed isn't checked until it's finished
+        assert_eq!(console.prompt().as_deref(), Some("/setblock 1 x"));
+        type_line(&mut console, " ");
+        assert_eq!(
+            console.prompt().as_deref(),
+            Some("/setblock 1 x    x isn't a whole number")
+        );
+
+        let mut console = Console::default();
+        type_line(&mut console, "/time later ");
+        assert_eq!(
+            console.prompt().as_deref(),
+            Some("/time later    usage: time [day | night | fraction of the day]")
+        );
+        let mut console = Console::default();
+        type_line(&mut console, "/fly ");
+        assert_eq!(
+            console.prompt().as_deref(),
+            Some("/fly    unknown command fly")
+        );
+    }
+
+    #[test]
+    fn up_and_down_step_through_history() {
+        let mut console = Console::default();
+        type_line(&mut console, "/kill\r");
+        type_line(&mut console, "/time day\r");
+        type_line(&mut console, "/time day\r");
+        type_line(&mut console, "/bor");
+
+        console.history_back();
+        assert_eq!(console.prompt().as_deref(), Some("/time day"));
+        console.history_back();
+        console.history_back();
+        assert_eq!(console.prompt().as_deref(), Some("/kill"));
+        console.history_forward();
+        assert_eq!(console.prompt().as_deref(), Some("/time day"));
+        // past the newest is what was being typed
+        console.history_forward();
+        assert_eq!(console.prompt().as_deref(), Some("/bor"));
+
+        console.history_back();
+        assert_eq!(
+            type_line(&mut console, " 5\r").as_deref(),
+            Some("time day 5")
+        );
+    }
+
+    #[test]
+    fn structure_commands_parse() {
+        let parse = |s: &str| s.parse::<Command>();
+        assert_eq!(
+            parse("structure save hut 0 -5 0 4 -9 4"),
+            Ok(Command::Structure(StructureCommand::Save(
+                "hut".into(),
+                ivec3(0, -5, 0),
+                ivec3(4, -9, 4)
+            )))
+        );
+        assert_eq!(
+            parse("structure place hut 10 -5 3 90"),
+            Ok(Command::Structure(StructureCommand::Place(
+                "hut".into(),
+                ivec3(10, -5, 3),
+                Rotation::Quarter
+            )))
+        );
+        assert_eq!(
+            parse("structure import castle 0 -5 0"),
+            Ok(Command::Structure(StructureCommand::Import(
+                "castle".into(),
+                ivec3(0, -5, 0),
+                Rotation::None
+            )))
+        );
+        assert!(parse("structure place hut 10 -5 3 45").is_err());
+        assert!(parse("structure save hut 0 0 0").is_err());
+    }
+
+    #[test]
+    fn summon_commands_parse() {
+        let parse = |s: &str| s.parse::<Command>();
+        assert_eq!(
+            parse("summon mob"),
+            Ok(Command::Summon(EntityKind::Mob, None, None))
+        );
+        assert_eq!(
+            parse("summon mob Steve"),
+            Ok(Command::Summon(EntityKind::Mob, None, Some("Steve".into())))
+        );
+        assert_eq!(
+            parse("summon orb 1 -5.5 2"),
+            Ok(Command::Summon(
+                EntityKind::Orb,
+                Some(vec3(1.0, -5.5, 2.0)),
+                None
+            ))
+        );
+        assert_eq!(
+            parse("summon mob 0 -4 0 Alex"),
+            Ok(Command::Summon(
+                EntityKind::Mob,
+                Some(vec3(0.0, -4.0, 0.0)),
+                Some("Alex".into())
+            ))
+        );
+        assert!(parse("summon creeper").is_err());
+        assert!(parse("summon mob 1 2").is_err());
+    }
+
+    #[test]
+    fn effect_commands_parse() {
+        let parse = |s: &str| s.parse::<Command>();
+        assert_eq!(
+            parse("effect mob invisibility 5"),
+            Ok(Command::Invisibility(EntityKind::Mob, 5.0))
+        );
+        assert_eq!(
+            parse("effect boat clear"),
+            Ok(Command::Invisibility(EntityKind::Boat, 0.0))
+        );
+        assert!(matches!(
+            parse("effect orb invisibility"),
+            Ok(Command::Invisibility(EntityKind::Orb, _))
+        ));
+        assert!(parse("effect mob speed").is_err());
+        assert!(parse("effect creeper clear").is_err());
+    }
+
+    #[test]
+    fn border_commands_parse() {
+        let parse = |s: &str| s.parse::<Command>();
+        assert_eq!(parse("border"), Ok(Command::Border(BorderCommand::Show)));
+        assert_eq!(
+            parse("border 64"),
+            Ok(Command::Border(BorderCommand::Size(vec2(64.0, 64.0))))
+        );
+        assert_eq!(
+            parse(" border  32 48 "),
+            Ok(Command::Border(BorderCommand::Size(vec2(32.0, 48.0))))
+        );
+        assert_eq!(
+            parse("border centre 1 -2.5"),
+            Ok(Command::Border(BorderCommand::Centre(vec2(1.0, -2.5))))
+        );
+        assert_eq!(
+            parse("border reset"),
+            Ok(Command::Border(BorderCommand::Reset))
+        );
+        assert!(parse("border big").is_err());
+        assert!(parse("teleport").is_err());
+        assert!(parse("").is_err());
+    }
+
+    #[test]
+    fn set_block_commands_parse() {
+        let parse = |s: &str| s.parse::<Command>();
+        assert_eq!(
+            parse("setblock 1 -20 3 sand"),
+            Ok(Command::SetBlock(ivec3(1, -20, 3), Some(BlockType::Sand)))
+        );
+        assert_eq!(
+            parse("setblock 0 0 0 air"),
+            Ok(Command::SetBlock(ivec3(0, 0, 0), None))
+        );
+        assert!(parse("setblock 0 0 0 lava").is_err());
+        assert!(parse("setblock 0 0.5 0 sand").is_err());
+        assert!(parse("setblock 0 0 sand").is_err());
+    }
+
+    #[test]
+    fn time_commands_parse() {
+        let parse = |s: &str| s.parse::<Command>();
+        assert_eq!(parse("time"), Ok(Command::Time(None)));
+        assert_eq!(parse("time night"), Ok(Command::Time(Some(0.75))));
+        assert_eq!(parse("time 0.1"), Ok(Command::Time(Some(0.1))));
+        assert!(parse("time later").is_err());
+        assert_eq!(parse("kill"), Ok(Command::Kill));
+        assert!(parse("kill steve").is_err());
+    }
+}
@@ -0,0 +1,46 @@
+use glam::{vec2, Vec2};
+
+use crate::{
+    renderer::{FontHandle, Renderer, TextHandle},
+    world::World,
+};
+
+/// The respawn button, its bottom left corner and size on the ui.
+const BUTTON_MIN: Vec2 = vec2(300.0, 240.0);
+const BUTTON_SIZE: Vec2 = vec2(200.0, 50.0);
+
+/// Shown over the world while the player is dead, with a button to respawn.
+pub struct DeathScreen {
+    title: TextHandle,
+    button_label: TextHandle,
+}
+
+impl DeathScreen {
+    pub fn new(renderer: &mut Renderer, font_handle: FontHandle) -> Self {
+        Self {
+            title: renderer.create_text("You died", font_handle, 300.0, 360.0, 0.4),
+            button_label: renderer.create_text("Respawn", font_handle, 350.0, 257.0, 0.2),
+        }
+    }
+
+    pub fn draw(&self, renderer: &mut Renderer, world: &World) {
+        // well past the edges, however the ui has been stretched to fit the window
+        renderer.queue_ui_quad(
+            vec2(-800.0, -600.0),
+            vec2(1600.0, 1200.0),
+            world.get_texture("shade"),
+        );
+        renderer.queue_ui_quad(
+            BUTTON_MIN,
+            BUTTON_MIN + BUTTON_SIZE,
+            world.get_texture("slot"),
+        );
+        renderer.queue_draw_text(self.title);
+        renderer.queue_draw_text(self.button_label);
+    }
+
+    /// Whether a point on the ui is over the respawn button.
+    pub fn on_button(point: Vec2) -> bool {
+        point.cmpge(BUTTON_MIN).all() && point.cmplt(BUTTON_MIN + BUTTON_SIZE).all()
+    }
+}
@@ -0,0 +1,119 @@
+use glam::{vec3, Vec3};
+
+use crate::{generator::NetherGenerator, world::World};
+
+/// Blocks along x and z of the nether, far smaller than the overworld since every nether block
+/// spans several overworld ones.
+const NETHER_SIZE: u32 = 64;
+const NETHER_DEPTH: u32 = 64;
+
+/// The worlds the player can travel between through portals.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Dimension {
+    Overworld,
+    Nether,
+}
+
+impl Dimension {
+    /// Where a portal in this dimension leads.
+    pub fn other(self) -> Self {
+        match self {
+            Dimension::Overworld => Dimension::Nether,
+            Dimension::Nether => Dimension::Overworld,
+        }
+    }
+
+    /// The name its part of a saved world is kept under.
+    pub fn name(self) -> &'static str {
+        match self {
+            Dimension::Overworld => "overworld",
+            Dimension::Nether => "nether",
+        }
+    }
+
+    /// How many overworld blocks along x and z one block of this dimension stands for.
+    pub fn scale(self) -> f32 {
+        match self {
+            Dimension::Overworld => 1.0,
+            Dimension::Nether => 8.0,
+        }
+    }
+
+    /// Where a position in this dimension ends up in `to`. Only x and z are scaled, the height
+    /// is kept.
+    pub fn translate(self, position: Vec3, to: Dimension) -> Vec3 {
+        let scale = self.scale() / to.scale();
+        vec3(position.x * scale, position.y, position.z * scale)
+    }
+
+    /// Generates the world of a dimension other than the overworld, which is made at startup
+    /// with the generator from the settings.
+    pub fn generate(self, seed: u64) -> Option<World> {
+        match self {
+            Dimension::Overworld => None,
+            Dimension::Nether => Some(
+                World::new(
+                    NETHER_SIZE,
+                    NETHER_SIZE,
+                    NETHER_DEPTH,
+                    &NetherGenerator,
+                    seed,
+                )
+                // the glow under the roof
+                .without_open_sky(),
+            ),
+        }
+    }
+
+    pub fn atmosphere(self) -> Atmosphere {
+        match self {
+            Dimension::Overworld => Atmosphere {
+                day_cycle: true,
+                sky_light: 1.0,
+                fog_colour: vec3(0.1, 0.1, 0.5),
+                fog_distance: 1.0,
+            },
+            Dimension::Nether => Atmosphere {
+                day_cycle: false,
+                sky_light: 0.3,
+                fog_colour: vec3(0.25, 0.04, 0.02),
+                fog_distance: 0.5,
+            },
+        }
+    }
+}
+
+/// How a dimension's sky looks and lights the world.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Atmosphere {
+    /// The sun, moon and stars show and the day cycle sets the light and fog colour. Without it
+    /// they stay at the values below.
+    pub day_cycle: bool,
+    pub sky_light: f32,
+    pub fog_colour: Vec3,
+    /// How far the fog reaches, as a fraction of the render distance.
+    pub fog_distance: f32,
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::vec3;
+
+    use super::Dimension;
+
+    #[test]
+    fn nether_distances_are_scaled() {
+        let position = vec3(80.0, -20.0, -16.0);
+        let nether = Dimension::Overworld.translate(position, Dimension::Nether);
+        assert_eq!(nether, vec3(10.0, -20.0, -2.0));
+        assert_eq!(
+            nether,
+            Dimension::Nether.translate(nether, Dimension::Nether)
+        );
+        assert_eq!(
+            Dimension::Nether.translate(nether, Dimension::Overworld),
+            position
+        );
+        assert_eq!(Dimension::Nether.other().other(), Dimension::Nether);
+    }
+}
@@ -0,0 +1,650 @@
+use fxhash::{FxHashMap, FxHashSet};
+use glam::{vec3, IVec3, Vec3};
+
+use crate::{
+    instance::Instance,
+    interpolation::{Interpolated, Transform},
+    inventory::ItemStack,
+    player::Player,
+    renderer::{Drawable, FontHandle, Renderer, Vertex, WorldTextHandle},
+    util::{block_position, Aabb},
+    world::{cube_indices, cube_vertices, BlockType, World},
+};
+
+/// Edge length of the cells entities are bucketed into for the broad phase. Matches the chunk
+/// size so a cell is never smaller than the largest entity.
+const CELL_SIZE: f32 = 16.0;
+
+/// Fraction of velocity kept after each second, so pushed entities glide to a stop.
+const DAMPING: f32 = 0.05;
+
+/// Height of nameplate text in world units.
+const NAMEPLATE_HEIGHT: f32 = 0.25;
+/// Gap between the top of an entity and its nameplate.
+const NAMEPLATE_GAP: f32 = 0.2;
+
+/// Seconds after being hit that an entity can't be hurt again, and flashes red.
+const INVULNERABILITY: f32 = 0.5;
+
+/// Seconds a new entity takes to fade in, and an invisible one to fade out or back.
+const FADE: f32 = 0.5;
+/// How opaque an invisible entity is, just enough to make out...
+const INVISIBLE_ALPHA: f32 = 0.15;
+/// ...as a pale blue shimmer.
+const INVISIBLE_TINT: Vec3 = vec3(0.7, 0.8, 1.0);
+
+/// Speed an attack knocks its victim back at, in blocks per second.
+const KNOCKBACK: f32 = 6.0;
+
+/// Speed a boat is driven at, in blocks per second.
+const BOAT_SPEED: f32 = 5.0;
+
+/// Orbs closer than this to the player fly towards them...
+const ORB_ATTRACTION: f32 = 6.0;
+/// ...at this speed, in blocks per second...
+const ORB_SPEED: f32 = 8.0;
+/// ...and are collected once this close to the player's box.
+const ORB_PICKUP: f32 = 0.3;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EntityKind {
+    Item,
+    Mob,
+    /// Experience, collected by flying into the player.
+    Orb,
+    /// Can be ridden across water.
+    Boat,
+}
+
+impl EntityKind {
+    pub const ALL: [EntityKind; 4] = [
+        EntityKind::Item,
+        EntityKind::Mob,
+        EntityKind::Orb,
+        EntityKind::Boat,
+    ];
+
+    /// The name an entity kind is summoned by.
+    pub fn name(self) -> &'static str {
+        match self {
+            EntityKind::Item => "item",
+            EntityKind::Mob => "mob",
+            EntityKind::Orb => "orb",
+            EntityKind::Boat => "boat",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.name() == name)
+    }
+
+    /// Health a new entity starts with, or None if it can't be hurt.
+    fn max_health(self) -> Option<f32> {
+        match self {
+            EntityKind::Item | EntityKind::Orb | EntityKind::Boat => None,
+            EntityKind::Mob => Some(10.0),
+        }
+    }
+
+    fn half_extents(self) -> Vec3 {
+        match self {
+            EntityKind::Item => Vec3::splat(0.125),
+            EntityKind::Mob => Vec3::new(0.3, 0.9, 0.3),
+            EntityKind::Orb => Vec3::splat(0.1),
+            EntityKind::Boat => Vec3::new(0.7, 0.3, 0.7),
+        }
+    }
+
+    /// How hard the entity is to push. Overlapping bodies are separated in inverse proportion.
+    fn mass(self) -> f32 {
+        match self {
+            EntityKind::Item => 0.25,
+            EntityKind::Mob => 1.0,
+            EntityKind::Orb => 0.05,
+            EntityKind::Boat => 2.0,
+        }
+    }
+
+    fn texture(self) -> &'static str {
+        match self {
+            EntityKind::Item => "sand",
+            EntityKind::Mob => "cobble",
+            EntityKind::Orb => "orb",
+            EntityKind::Boat => "sign",
+        }
+    }
+
+    fn rideable(self) -> bool {
+        self == EntityKind::Boat
+    }
+}
+
+pub type EntityId = u32;
+
+pub struct Entity {
+    pub kind: EntityKind,
+    /// The centre of the entity's bounding box.
+    pub position: Vec3,
+    pub velocity: Vec3,
+    /// Shown above the entity when set.
+    pub name: Option<String>,
+    /// What can be picked up from an item.
+    pub item: Option<ItemStack>,
+    /// For entities that can be hurt, dead at 0.
+    pub health: Option<f32>,
+    /// Points of experience an orb is worth.
+    pub experience: u32,
+    /// Seconds left until it can be hurt again.
+    invulnerable: f32,
+    /// Seconds since it was spawned.
+    age: f32,
+    /// Seconds left until it can be seen again.
+    invisible: f32,
+    /// Ticks in a row it's been far from the player, for mobs, which despawn after long enough.
+    idle: u32,
+    /// Where it's drawn, following its position a tick behind.
+    motion: Interpolated,
+    /// The name's text, created the first time the entity is drawn.
+    nameplate: Option<WorldTextHandle>,
+}
+
+impl Entity {
+    pub fn new(kind: EntityKind, position: Vec3) -> Self {
+        Self {
+            kind,
+            position,
+            velocity: Vec3::ZERO,
+            name: None,
+            item: None,
+            health: kind.max_health(),
+            experience: 0,
+            invulnerable: 0.0,
+            age: 0.0,
+            invisible: 0.0,
+            idle: 0,
+            motion: Interpolated::new(Transform::at(position)),
+            nameplate: None,
+        }
+    }
+
+    /// An item entity holding `stack`, drawn as its block.
+    pub fn dropped(stack: ItemStack, position: Vec3, velocity: Vec3) -> Self {
+        Self {
+            velocity,
+            item: Some(stack),
+            ..Self::new(EntityKind::Item, position)
+        }
+    }
+
+    /// An orb worth `experience` points.
+    pub fn orb(position: Vec3, experience: u32) -> Self {
+        Self {
+            experience,
+            ..Self::new(EntityKind::Orb, position)
+        }
+    }
+
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    pub fn aabb(&self) -> Aabb {
+        Aabb::from_center(self.position, self.kind.half_extents())
+    }
+
+    /// How opaque to draw it, fading in once it's spawned and out while it's invisible.
+    fn alpha(&self) -> f32 {
+        let spawned = (self.age / FADE).min(1.0);
+        let invisible = (self.invisible / FADE).min(1.0);
+        spawned * (INVISIBLE_ALPHA + (1.0 - INVISIBLE_ALPHA) * (1.0 - invisible))
+    }
+}
+
+impl Drawable for Entity {
+    fn draw(&self, renderer: &mut Renderer, world: &World) {
+        renderer.queue_draw(0, self, world);
+    }
+
+    fn vertices(&self) -> Vec<Vertex> {
+        cube_vertices()
+    }
+
+    fn indices(&self) -> Vec<u16> {
+        cube_indices()
+    }
+
+    fn instance(&self, world: &World) -> Instance {
+        Instance::moving(
+            self.motion,
+            world.get_texture(match self.item {
+                Some(stack) => stack.block_type.into(),
+                None => self.kind.texture(),
+            }),
+        )
+        .with_scale(self.kind.half_extents() * 2.0)
+        .with_flash(self.invulnerable / INVULNERABILITY)
+        .with_tint(Vec3::ONE.lerp(INVISIBLE_TINT, (self.invisible / FADE).min(1.0)))
+        .with_alpha(self.alpha())
+    }
+}
+
+/// What an attack did.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Hit {
+    Hurt,
+    /// The victim died and is gone, this is where it was.
+    Killed(Vec3),
+}
+
+#[derive(Default)]
+pub struct Entities {
+    next_id: EntityId,
+    entities: FxHashMap<EntityId, Entity>,
+    /// What the player is riding, which carries them along and doesn't push them away.
+    ridden: Option<EntityId>,
+}
+
+impl Entities {
+    pub fn spawn(&mut self, entity: Entity) -> EntityId {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.entities.insert(id, entity);
+        id
+    }
+
+    /// Where every entity of `kind` is.
+    pub fn positions(&self, kind: EntityKind) -> impl Iterator<Item = Vec3> + '_ {
+        self.entities
+            .values()
+            .filter(move |entity| entity.kind == kind)
+            .map(|entity| entity.position)
+    }
+
+    /// Moves every entity by its velocity and then pushes apart anything overlapping, including
+    /// the player.
+    pub fn update(&mut self, dt: f32, player: &mut Player) {
+        let damping = DAMPING.powf(dt);
+        for entity in self.entities.values_mut() {
+            entity.position += entity.velocity * dt;
+            entity.velocity *= damping;
+            entity.invulnerable = (entity.invulnerable - dt).max(0.0);
+            entity.age += dt;
+            entity.invisible = (entity.invisible - dt).max(0.0);
+        }
+        self.resolve_collisions(player);
+        if let Some(seat) = self.seat() {
+            player.position = seat;
+        }
+        for entity in self.entities.values_mut() {
+            entity.motion.tick(Transform::at(entity.position));
+        }
+    }
+
+    /// The closest entity that can be hurt along a ray within `reach`, and how far along it is.
+    pub fn raycast(&self, origin: Vec3, direction: Vec3, reach: f32) -> Option<(EntityId, f32)> {
+        self.raycast_where(origin, direction, reach, |entity| entity.health.is_some())
+    }
+
+    /// Like `raycast`, for entities that can be ridden.
+    pub fn raycast_rideable(
+        &self,
+        origin: Vec3,
+        direction: Vec3,
+        reach: f32,
+    ) -> Option<(EntityId, f32)> {
+        self.raycast_where(origin, direction, reach, |entity| entity.kind.rideable())
+    }
+
+    fn raycast_where(
+        &self,
+        origin: Vec3,
+        direction: Vec3,
+        reach: f32,
+        filter: impl Fn(&Entity) -> bool,
+    ) -> Option<(EntityId, f32)> {
+        let direction = direction.normalize_or_zero();
+        self.entities
+            .iter()
+            .filter(|(_, entity)| filter(entity))
+            .filter_map(|(id, entity)| {
+                let distance = entity.aabb().ray_distance(origin, direction)?;
+                (distance <= reach).then_some((*id, distance))
+            })
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+    }
+
+    /// Deals `damage` to an entity and knocks it away along `direction`, unless it was hurt too
+    /// recently or can't be hurt at all.
+    pub fn attack(&mut self, id: EntityId, damage: f32, direction: Vec3) -> Option<Hit> {
+        let entity = self.entities.get_mut(&id)?;
+        let health = entity.health.as_mut()?;
+        if entity.invulnerable > 0.0 {
+            return None;
+        }
+        *health -= damage;
+        if *health <= 0.0 {
+            let position = entity.position;
+            self.entities.remove(&id);
+            return Some(Hit::Killed(position));
+        }
+        entity.invulnerable = INVULNERABILITY;
+        // along the ground, with no gravity to bring it back down
+        let away = (direction * vec3(1.0, 0.0, 1.0)).normalize_or_zero();
+        entity.velocity += away * KNOCKBACK;
+        Some(Hit::Hurt)
+    }
+
+    /// Makes every entity of `kind` invisible for `seconds`, or visible again with 0. Returns
+    /// how many there were.
+    pub fn make_invisible(&mut self, kind: EntityKind, seconds: f32) -> usize {
+        let mut count = 0;
+        for entity in self
+            .entities
+            .values_mut()
+            .filter(|entity| entity.kind == kind)
+        {
+            entity.invisible = seconds;
+            count += 1;
+        }
+        count
+    }
+
+    /// Despawns mobs `far` or further from `player`, and those that have been `idle` or further
+    /// for `ticks` ticks in a row. Named mobs stay. Called once a tick.
+    pub fn despawn_mobs(&mut self, player: Vec3, far: f32, idle: f32, ticks: u32) {
+        self.entities.retain(|_, entity| {
+            if entity.kind != EntityKind::Mob || entity.name.is_some() {
+                return true;
+            }
+            let distance = entity.position.distance(player);
+            entity.idle = if distance >= idle { entity.idle + 1 } else { 0 };
+            distance < far && entity.idle < ticks
+        });
+    }
+
+    /// Puts the player in `id` if it can be ridden, returning whether it could.
+    pub fn mount(&mut self, id: EntityId) -> bool {
+        let rideable = self
+            .entities
+            .get(&id)
+            .is_some_and(|entity| entity.kind.rideable());
+        if rideable {
+            self.ridden = Some(id);
+        }
+        rideable
+    }
+
+    /// Takes the player out of whatever they're riding, returning where they should stand, on
+    /// top of it.
+    pub fn dismount(&mut self) -> Option<Vec3> {
+        let entity = self.entities.get(&self.ridden.take()?)?;
+        let aabb = entity.aabb();
+        Some(vec3(entity.position.x, aabb.max.y, entity.position.z))
+    }
+
+    pub fn riding(&self) -> bool {
+        self.ridden.is_some()
+    }
+
+    /// Where the player's feet go while riding, down in the middle of the ride.
+    fn seat(&self) -> Option<Vec3> {
+        let entity = self.entities.get(&self.ridden?)?;
+        Some(vec3(
+            entity.position.x,
+            entity.aabb().min.y,
+            entity.position.z,
+        ))
+    }
+
+    /// Drives what the player is riding along `direction`, kept level, for the coming tick of
+    /// `dt` seconds. Boats only go where there's water under them, stopping at the shore.
+    pub fn drive(&mut self, direction: Vec3, dt: f32, world: &World) {
+        let Some(entity) = self.ridden.and_then(|id| self.entities.get_mut(&id)) else {
+            return;
+        };
+        let direction = (direction * vec3(1.0, 0.0, 1.0)).normalize_or_zero();
+        let velocity = direction * BOAT_SPEED;
+        // just under the bottom of the hull, in the water it floats on
+        let hull = entity.aabb().min.y - 0.25;
+        let next = vec3(entity.position.x, hull, entity.position.z) + velocity * dt;
+        let afloat = world.block_type(block_position(next)) == Some(BlockType::Water);
+        entity.velocity = if afloat { velocity } else { Vec3::ZERO };
+    }
+
+    /// Steers nearby orbs towards the player and collects the ones that reach them, returning
+    /// how much experience they were worth.
+    pub fn collect_orbs(&mut self, player: &Player) -> u32 {
+        let aabb = player.aabb();
+        let centre = (aabb.min + aabb.max) / 2.0;
+        let mut collected = vec![];
+        for (id, entity) in self.entities.iter_mut() {
+            if entity.kind != EntityKind::Orb {
+                continue;
+            }
+            let closest = entity.position.clamp(aabb.min, aabb.max);
+            if entity.position.distance(closest) <= ORB_PICKUP {
+                collected.push(*id);
+            } else if entity.position.distance(centre) < ORB_ATTRACTION {
+                entity.velocity = (centre - entity.position).normalize() * ORB_SPEED;
+            }
+        }
+        collected
+            .into_iter()
+            .filter_map(|id| self.entities.remove(&id))
+            .map(|orb| orb.experience)
+            .sum()
+    }
+
+    /// Pairs of entities whose boxes share a broad phase cell, each pair once with the lower id
+    /// first.
+    fn candidate_pairs(&self) -> FxHashSet<(EntityId, EntityId)> {
+        let mut cells: FxHashMap<IVec3, Vec<EntityId>> = FxHashMap::default();
+        for (id, entity) in self.entities.iter() {
+            for cell in entity.aabb().cells(CELL_SIZE) {
+                cells.entry(cell).or_default().push(*id);
+            }
+        }
+        let mut pairs = FxHashSet::default();
+        for ids in cells.values() {
+            for (i, a) in ids.iter().enumerate() {
+                for b in &ids[i + 1..] {
+                    pairs.insert((*a.min(b), *a.max(b)));
+                }
+            }
+        }
+        pairs
+    }
+
+    fn resolve_collisions(&mut self, player: &mut Player) {
+        for (a, b) in self.candidate_pairs() {
+            let (entity_a, entity_b) = (&self.entities[&a], &self.entities[&b]);
+            if let Some(push) = entity_a.aabb().penetration(&entity_b.aabb()) {
+                let (mass_a, mass_b) = (entity_a.kind.mass(), entity_b.kind.mass());
+                let total = mass_a + mass_b;
+                self.entities.get_mut(&a).unwrap().position += push * mass_b / total;
+                self.entities.get_mut(&b).unwrap().position -= push * mass_a / total;
+            }
+        }
+
+        // the player only ever overlaps a handful of entities so it skips the broad phase
+        let ridden = self.ridden;
+        for (_, entity) in self
+            .entities
+            .iter_mut()
+            .filter(|(id, _)| Some(**id) != ridden)
+        {
+            if let Some(push) = entity.aabb().penetration(&player.aabb()) {
+                let total = entity.kind.mass() + Player::MASS;
+                entity.position += push * Player::MASS / total;
+                player.position -= push * entity.kind.mass() / total;
+            }
+        }
+    }
+
+    pub fn draw(&mut self, renderer: &mut Renderer, world: &World, font: FontHandle) {
+        for entity in self.entities.values_mut() {
+            entity.draw(renderer, world);
+            if let Some(name) = &entity.name {
+                let nameplate = *entity.nameplate.get_or_insert_with(|| {
+                    renderer.create_world_text(name, font, NAMEPLATE_HEIGHT)
+                });
+                let position = entity.motion.at(renderer.alpha()).position;
+                let top = position.y + entity.kind.half_extents().y + NAMEPLATE_GAP;
+                renderer.queue_draw_world_text(nameplate, Vec3::new(position.x, top, position.z));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{vec3, Vec3};
+
+    use crate::{generator::FlatGenerator, player::Player, world::World};
+
+    use super::{Entities, Entity, EntityKind, Hit, FADE, INVISIBLE_ALPHA, INVULNERABILITY};
+
+    fn player_far_away() -> Player {
+        Player::new(Vec3::splat(1000.0))
+    }
+
+    #[test]
+    fn overlapping_entities_are_separated() {
+        let mut entities = Entities::default();
+        let mut player = player_far_away();
+        let a = entities.spawn(Entity::new(EntityKind::Mob, vec3(0.0, 0.0, 0.0)));
+        let b = entities.spawn(Entity::new(EntityKind::Mob, vec3(0.4, 0.0, 0.0)));
+        entities.update(0.0, &mut player);
+        let (a, b) = (&entities.entities[&a], &entities.entities[&b]);
+        assert!(a.aabb().penetration(&b.aabb()).is_none());
+        // equal masses split the push evenly
+        assert!((a.position.x + 0.1).abs() < 1e-5 && (b.position.x - 0.5).abs() < 1e-5);
+    }
+
+    #[test]
+    fn entities_across_cell_borders_collide() {
+        let mut entities = Entities::default();
+        let mut player = player_far_away();
+        let a = entities.spawn(Entity::new(EntityKind::Item, vec3(15.9, 0.0, 0.0)));
+        let b = entities.spawn(Entity::new(EntityKind::Item, vec3(16.1, 0.0, 0.0)));
+        entities.update(0.0, &mut player);
+        let (a, b) = (&entities.entities[&a], &entities.entities[&b]);
+        assert!(a.aabb().penetration(&b.aabb()).is_none());
+    }
+
+    #[test]
+    fn rays_find_the_nearest_mob() {
+        let mut entities = Entities::default();
+        let near = entities.spawn(Entity::new(EntityKind::Mob, vec3(0.0, 0.0, -3.0)));
+        entities.spawn(Entity::new(EntityKind::Mob, vec3(0.0, 0.0, -6.0)));
+        // items can't be hit
+        entities.spawn(Entity::new(EntityKind::Item, vec3(0.0, 0.0, -1.0)));
+        let (hit, distance) = entities.raycast(Vec3::ZERO, Vec3::NEG_Z, 5.0).unwrap();
+        assert_eq!(hit, near);
+        assert!((distance - 2.7).abs() < 1e-5);
+        assert_eq!(entities.raycast(Vec3::ZERO, Vec3::NEG_Z, 2.0), None);
+        assert_eq!(entities.raycast(Vec3::ZERO, Vec3::X, 5.0), None);
+    }
+
+    #[test]
+    fn boats_carry_their_rider_across_water() {
+        // a pond 4 blocks across, its surface at the top of the world
+        let world = World::new(4, 4, 8, &FlatGenerator::parse("water").unwrap(), 0);
+        let mut entities = Entities::default();
+        let mut player = player_far_away();
+        let mob = entities.spawn(Entity::new(EntityKind::Mob, vec3(1.0, -3.6, 3.0)));
+        assert!(!entities.mount(mob));
+        let boat = entities.spawn(Entity::new(EntityKind::Boat, vec3(1.0, -4.2, 1.0)));
+        assert!(entities.mount(boat));
+
+        for _ in 0..60 {
+            entities.drive(Vec3::X, 0.05, &world);
+            entities.update(0.05, &mut player);
+        }
+        // it runs aground at the far edge of the pond, with the player still in it
+        let position = entities.entities[&boat].position;
+        assert!((3.0..3.6).contains(&position.x), "{position}");
+        assert_eq!(player.position, vec3(position.x, -4.5, 1.0));
+
+        let standing = entities.dismount().unwrap();
+        assert!(standing.distance(vec3(position.x, -3.9, 1.0)) < 1e-5);
+        assert!(!entities.riding());
+    }
+
+    #[test]
+    fn entities_fade_in_and_out_of_invisibility() {
+        let mut entities = Entities::default();
+        let mut player = player_far_away();
+        let mob = entities.spawn(Entity::new(EntityKind::Mob, Vec3::ZERO));
+        let item = entities.spawn(Entity::new(EntityKind::Item, vec3(5.0, 0.0, 0.0)));
+        let alpha = |entities: &Entities, id| entities.entities[&id].alpha();
+        assert_eq!(alpha(&entities, mob), 0.0);
+        entities.update(FADE / 2.0, &mut player);
+        assert_eq!(alpha(&entities, mob), 0.5);
+        entities.update(FADE, &mut player);
+        assert_eq!(alpha(&entities, mob), 1.0);
+
+        assert_eq!(entities.make_invisible(EntityKind::Mob, 2.0), 1);
+        assert_eq!(alpha(&entities, mob), INVISIBLE_ALPHA);
+        assert_eq!(alpha(&entities, item), 1.0);
+        // fading back in over its last moments
+        entities.update(2.0 - FADE / 2.0, &mut player);
+        assert!((alpha(&entities, mob) - (1.0 + INVISIBLE_ALPHA) / 2.0).abs() < 1e-5);
+        entities.update(FADE, &mut player);
+        assert_eq!(alpha(&entities, mob), 1.0);
+    }
+
+    #[test]
+    fn attacks_hurt_knock_back_and_kill() {
+        let mut entities = Entities::default();
+        let mut player = player_far_away();
+        let mob = entities.spawn(Entity::new(EntityKind::Mob, Vec3::ZERO));
+        assert_eq!(entities.attack(mob, 4.0, Vec3::NEG_Z), Some(Hit::Hurt));
+        assert!(entities.entities[&mob].velocity.z < 0.0);
+        // still flashing from the last hit
+        assert_eq!(entities.attack(mob, 4.0, Vec3::NEG_Z), None);
+        assert_eq!(entities.entities[&mob].health, Some(6.0));
+
+        entities.update(INVULNERABILITY, &mut player);
+        assert_eq!(entities.attack(mob, 4.0, Vec3::NEG_Z), Some(Hit::Hurt));
+        entities.update(INVULNERABILITY, &mut player);
+        let position = entities.entities[&mob].position;
+        assert_eq!(
+            entities.attack(mob, 4.0, Vec3::NEG_Z),
+            Some(Hit::Killed(position))
+        );
+        assert!(entities.entities.is_empty());
+    }
+
+    #[test]
+    fn orbs_fly_to_the_player() {
+        let mut entities = Entities::default();
+        let mut player = Player::new(Vec3::ZERO);
+        entities.spawn(Entity::orb(vec3(4.0, 1.0, 0.0), 3));
+        entities.spawn(Entity::orb(vec3(2.0, 1.0, 1.0), 2));
+        // too far to notice the player
+        let far = entities.spawn(Entity::orb(vec3(20.0, 1.0, 0.0), 5));
+        let mut collected = 0;
+        for _ in 0..60 {
+            collected += entities.collect_orbs(&player);
+            entities.update(1.0 / 60.0, &mut player);
+        }
+        assert_eq!(collected, 5);
+        assert_eq!(entities.entities.len(), 1);
+        assert!(entities.entities.contains_key(&far));
+    }
+
+    #[test]
+    fn items_make_way_for_the_player() {
+        let mut entities = Entities::default();
+        let mut player = Player::new(Vec3::ZERO);
+        let start = player.position;
+        let item = entities.spawn(Entity::new(EntityKind::Item, player.aabb().max - 0.1));
+        entities.update(0.0, &mut player);
+        let item = &entities.entities[&item];
+        assert!(item.aabb().penetration(&player.aabb()).is_none());
+        // the much heavier player barely moves
+        assert!(player.position.distance(start) < 0.05);
+    }
+}
@@ -0,0 +1,216 @@
+use std::str::FromStr;
+
+/// Height difference between neighbouring columns, in blocks, past which thermal erosion
+/// crumbles the higher one onto the lower.
+const TALUS: f32 = 1.5;
+/// Water rained onto every column each iteration, in blocks.
+const RAIN: f32 = 0.1;
+/// Fraction of its water a column loses to evaporation each iteration.
+const EVAPORATION: f32 = 0.5;
+/// Sediment each block of water can carry.
+const CAPACITY: f32 = 0.5;
+
+/// How hard the weather wears down a heightmap before blocks are placed on it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Erosion {
+    /// Rounds of rain and crumbling, none at all leaves the heightmap as it is.
+    pub iterations: u32,
+    /// From 0 to 1, how much each round moves.
+    pub strength: f32,
+}
+
+impl Default for Erosion {
+    fn default() -> Self {
+        Self {
+            iterations: 0,
+            strength: 0.5,
+        }
+    }
+}
+
+/// Parses `iterations=50,strength=0.5`, either of them left out keeping its default.
+impl FromStr for Erosion {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut erosion = Erosion::default();
+        for option in s
+            .split(',')
+            .map(str::trim)
+            .filter(|option| !option.is_empty())
+        {
+            match option.split_once('=') {
+                Some(("iterations", value)) => {
+                    erosion.iterations = value
+                        .parse()
+                        .map_err(|_| format!("{value} isn't a whole number"))?;
+                }
+                Some(("strength", value)) => {
+                    erosion.strength = value
+                        .parse()
+                        .ok()
+                        .filter(|strength| (0.0..=1.0).contains(strength))
+                        .ok_or_else(|| format!("{value} isn't a number from 0 to 1"))?;
+                }
+                _ => {
+                    return Err(format!(
+                        "unknown erosion option {option}, expected iterations=n or strength=n"
+                    ))
+                }
+            }
+        }
+        Ok(erosion)
+    }
+}
+
+impl Erosion {
+    /// Wears down `heights`, `width` columns to a row, with rain carrying soil down into the
+    /// valleys and slopes too steep to stand crumbling until they can. Material is only ever
+    /// moved, so the total height stays the same.
+    pub fn erode(&self, heights: &mut [f32], width: usize) {
+        if self.iterations == 0 || width == 0 {
+            return;
+        }
+        let mut water = vec![0.0; heights.len()];
+        let mut sediment = vec![0.0; heights.len()];
+        for _ in 0..self.iterations {
+            self.crumble(heights, width);
+            self.rain(heights, &mut water, &mut sediment, width);
+        }
+        // whatever the water still carries settles where it is
+        for (height, sediment) in heights.iter_mut().zip(sediment) {
+            *height += sediment;
+        }
+    }
+
+    /// Thermal erosion: moves part of whatever sticks out past `TALUS` above each lower
+    /// neighbour onto it.
+    fn crumble(&self, heights: &mut [f32], width: usize) {
+        let rate = self.strength * 0.5;
+        let mut moved = vec![0.0; heights.len()];
+        for i in 0..heights.len() {
+            let drops = || {
+                neighbours(i, width, heights.len())
+                    .map(|j| (j, heights[i] - heights[j]))
+                    .filter(|(_, drop)| *drop > TALUS)
+            };
+            let total: f32 = drops().map(|(_, drop)| drop).sum();
+            let Some(steepest) = drops().map(|(_, drop)| drop).reduce(f32::max) else {
+                continue;
+            };
+            let amount = rate * (steepest - TALUS);
+            for (j, drop) in drops() {
+                moved[j] += amount * drop / total;
+            }
+            moved[i] -= amount;
+        }
+        for (height, moved) in heights.iter_mut().zip(moved) {
+            *height += moved;
+        }
+    }
+
+    /// Hydraulic erosion: rain dissolves soil, flows downhill with it and drops it again as
+    /// it evaporates.
+    fn rain(&self, heights: &mut [f32], water: &mut [f32], sediment: &mut [f32], width: usize) {
+        let solubility = self.strength * 0.3;
+        for i in 0..heights.len() {
+            water[i] += RAIN;
+            let dissolved = solubility * water[i];
+            heights[i] -= dissolved;
+            sediment[i] += dissolved;
+        }
+
+        // water runs off to lower neighbours in proportion to how much lower they are, taking
+        // its share of the sediment along
+        let mut flow = vec![(0.0, 0.0); heights.len()];
+        for i in 0..heights.len() {
+            let level = heights[i] + water[i];
+            let drops = || {
+                neighbours(i, width, heights.len())
+                    .map(|j| (j, level - heights[j] - water[j]))
+                    .filter(|(_, drop)| *drop > 0.0)
+            };
+            let (count, total) = drops().fold((0, 0.0), |(count, total), (_, drop)| {
+                (count + 1, total + drop)
+            });
+            if count == 0 {
+                continue;
+            }
+            let average = total / (count + 1) as f32;
+            let fraction = water[i].min(average) / water[i];
+            for (j, drop) in drops() {
+                let share = fraction * drop / total;
+                flow[j].0 += water[i] * share;
+                flow[j].1 += sediment[i] * share;
+            }
+            flow[i].0 -= water[i] * fraction;
+            flow[i].1 -= sediment[i] * fraction;
+        }
+
+        for i in 0..heights.len() {
+            water[i] = (water[i] + flow[i].0) * (1.0 - EVAPORATION);
+            sediment[i] += flow[i].1;
+            let deposited = (sediment[i] - CAPACITY * water[i]).max(0.0);
+            sediment[i] -= deposited;
+            heights[i] += deposited;
+        }
+    }
+}
+
+/// The columns beside column `i` of a heightmap `width` columns to a row and `len` long.
+fn neighbours(i: usize, width: usize, len: usize) -> impl Iterator<Item = usize> {
+    let (x, row) = (i % width, i / width);
+    let rows = len / width;
+    [
+        (x > 0).then(|| i - 1),
+        (x + 1 < width).then_some(i + 1),
+        (row > 0).then(|| i - width),
+        (row + 1 < rows).then_some(i + width),
+    ]
+    .into_iter()
+    .flatten()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Erosion, TALUS};
+
+    #[test]
+    fn erosion_wears_down_peaks_without_losing_material() {
+        // a lone spike on flat ground
+        let width = 9;
+        let mut heights = vec![0.0; width * width];
+        heights[4 * width + 4] = 20.0;
+        let erosion = Erosion {
+            iterations: 100,
+            strength: 1.0,
+        };
+        erosion.erode(&mut heights, width);
+
+        let peak = heights.iter().copied().fold(f32::MIN, f32::max);
+        assert!(peak < 10.0, "{peak}");
+        let total: f32 = heights.iter().sum();
+        assert!((total - 20.0).abs() < 1e-3, "{total}");
+        // what's left stands no steeper than the talus, give or take what's still settling
+        for row in heights.chunks(width) {
+            for pair in row.windows(2) {
+                assert!((pair[0] - pair[1]).abs() < TALUS + 1.0, "{row:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn erosion_options_parse() {
+        assert_eq!("".parse(), Ok(Erosion::default()));
+        assert_eq!(
+            "iterations=40, strength=0.25".parse(),
+            Ok(Erosion {
+                iterations: 40,
+                strength: 0.25
+            })
+        );
+        assert!("iterations=-1".parse::<Erosion>().is_err());
+        assert!("strength=2".parse::<Erosion>().is_err());
+        assert!("rain=5".parse::<Erosion>().is_err());
+    }
+}
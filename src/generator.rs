@@ -0,0 +1,595 @@
+use std::{
+    cell::{Cell, RefCell},
+    time::{Duration, Instant},
+};
+
+use fxhash::FxHashMap;
+use glam::{ivec2, ivec3, IVec2, IVec3};
+use noise::{NoiseFn, Perlin};
+use rand::{rngs::StdRng, SeedableRng};
+
+use crate::{
+    anvil::AnvilGenerator,
+    biome::Biome,
+    chunk::{Face, CHUNK_SIZE},
+    erosion::Erosion,
+    structure::{Rotation, Structure},
+    util::split,
+    village,
+    world::{BlockType, World, WORLD_DEPTH, WORLD_TOP},
+};
+
+/// The blocks of one chunk as a generator produces them, indexed by position relative to the
+/// chunk origin.
+pub struct ChunkData {
+    blocks: Vec<Option<BlockType>>,
+}
+
+impl Default for ChunkData {
+    fn default() -> Self {
+        Self {
+            blocks: vec![None; (CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE) as usize],
+        }
+    }
+}
+
+impl ChunkData {
+    fn index(local: IVec3) -> usize {
+        debug_assert!(
+            local.cmpge(IVec3::ZERO).all() && local.cmplt(IVec3::splat(CHUNK_SIZE)).all()
+        );
+        (local.x + CHUNK_SIZE * (local.y + CHUNK_SIZE * local.z)) as usize
+    }
+
+    pub fn get(&self, local: IVec3) -> Option<BlockType> {
+        self.blocks[Self::index(local)]
+    }
+
+    pub fn set(&mut self, local: IVec3, block_type: Option<BlockType>) {
+        self.blocks[Self::index(local)] = block_type;
+    }
+}
+
+/// Decides what a new world is made of. Chunks are generated independently of each other, and
+/// once all of them are in place the decorate pass can add anything that reaches across chunk
+/// borders.
+pub trait WorldGenerator {
+    /// The blocks of the chunk at `coord`. The same seed and coord must always give the same
+    /// blocks.
+    fn generate_chunk(&self, seed: u64, coord: IVec3) -> ChunkData;
+
+    /// The biome of the column of blocks at x and z, plains everywhere by default.
+    fn biome(&self, _seed: u64, _column: IVec2) -> Biome {
+        Biome::Plains
+    }
+
+    /// Runs once every chunk has been generated. Does nothing by default.
+    fn decorate(&self, _seed: u64, _world: &mut World) {}
+
+    /// How long has been spent eroding terrain so far, none for generators that don't.
+    fn erosion_time(&self) -> Duration {
+        Duration::ZERO
+    }
+}
+
+/// The names generators can be picked by, in settings and on the command line. A flat world
+/// can also be given its layers, as in `flat:dirt,3*stone`, hills how hard they're eroded, as
+/// in `hills:iterations=50,strength=0.5`, and `anvil:path` reads the minecraft world saved at
+/// path.
+pub const GENERATORS: &[&str] = &["perlin", "flat", "hills", "debug", "nether"];
+
+/// The generator called `name`, or why there isn't one.
+pub fn named(name: &str) -> Result<Box<dyn WorldGenerator>, String> {
+    match name.split_once(':') {
+        None if name == "perlin" => Ok(Box::new(PerlinGenerator::new(0.0))),
+        None if name == "flat" => Ok(Box::new(FlatGenerator::default())),
+        None if name == "hills" => Ok(Box::new(HillsGenerator::new(Erosion::default()))),
+        None if name == "debug" => Ok(Box::new(DebugGenerator)),
+        None if name == "nether" => Ok(Box::new(NetherGenerator)),
+        Some(("flat", layers)) => Ok(Box::new(FlatGenerator::parse(layers)?)),
+        Some(("hills", erosion)) => Ok(Box::new(HillsGenerator::new(erosion.parse()?))),
+        Some(("anvil", dir)) => Ok(Box::new(AnvilGenerator::open(dir)?)),
+        _ => Err(format!(
+            "unknown generator {name}, expected one of {}",
+            GENERATORS.join(", ")
+        )),
+    }
+}
+
+/// Reads `--generator name` from the command line.
+pub fn from_args(args: impl Iterator<Item = String>) -> Option<String> {
+    let mut args = args.skip_while(|arg| arg != "--generator");
+    args.next()?;
+    args.next()
+}
+
+/// Solid wherever 3d perlin noise is above a threshold, each block a random type. Raising the
+/// threshold hollows the world out, and thresholds outside -1 to 1 make it entirely empty or
+/// entirely solid. Villages go on whatever flat ground is left open to the sky.
+pub struct PerlinGenerator {
+    threshold: f32,
+}
+
+impl PerlinGenerator {
+    pub const fn new(threshold: f32) -> Self {
+        Self { threshold }
+    }
+}
+
+impl WorldGenerator for PerlinGenerator {
+    fn generate_chunk(&self, seed: u64, coord: IVec3) -> ChunkData {
+        let perlin = Perlin::new(seed as u32);
+        // every chunk gets its own stream of block types, so generating them in any order gives
+        // the same world
+        let chunk_seed = seed
+            ^ (coord.x as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15)
+            ^ (coord.y as u64).wrapping_mul(0xc2b2_ae3d_27d4_eb4f)
+            ^ (coord.z as u64).wrapping_mul(0x1656_67b1_9e37_79f9);
+        let mut rng = StdRng::seed_from_u64(chunk_seed);
+        let mut chunk = ChunkData::default();
+        for z in 0..CHUNK_SIZE {
+            for y in 0..CHUNK_SIZE {
+                for x in 0..CHUNK_SIZE {
+                    let local = IVec3::new(x, y, z);
+                    let p = (coord * CHUNK_SIZE + local).as_dvec3() / 16.0;
+                    if perlin.get([p.x, p.y, p.z]) > self.threshold as f64 {
+                        chunk.set(local, Some(BlockType::random(&mut rng)));
+                    }
+                }
+            }
+        }
+        chunk
+    }
+
+    /// Warmth and wetness follow two slow noise fields of their own, so biomes come in wide
+    /// patches.
+    fn biome(&self, seed: u64, column: IVec2) -> Biome {
+        climate_biome(seed, column)
+    }
+
+    fn decorate(&self, seed: u64, world: &mut World) {
+        village::place_villages(seed, world);
+    }
+}
+
+/// Warmth and wetness following two slow noise fields of their own, so biomes come in wide
+/// patches.
+fn climate_biome(seed: u64, column: IVec2) -> Biome {
+    let perlin = Perlin::new(seed as u32 ^ 0x5eed_b10e);
+    let p = column.as_dvec2() / 48.0;
+    let temperature = perlin.get([p.x, p.y]);
+    let humidity = perlin.get([p.x + 1000.5, p.y - 1000.5]);
+    Biome::from_climate(temperature * 2.0, humidity * 2.0)
+}
+
+/// World y the hills' surface averages out at...
+const HILLS_SURFACE: f64 = -40.0;
+/// ...rising and falling this far either side of it.
+const HILLS_AMPLITUDE: f64 = 28.0;
+/// Below this the valleys are flooded.
+const SEA_LEVEL: i32 = -48;
+/// Depth of the dirt over the stone.
+const SOIL_DEPTH: i32 = 3;
+/// Columns along each side of the squares the heightmap is eroded in...
+const EROSION_TILE: i32 = 128;
+/// ...together with this many more around them, so where one square meets the next the two
+/// have been worn down alike.
+const EROSION_MARGIN: i32 = 32;
+
+/// Rolling hills on a heightmap of layered noise, dirt over stone with lakes and beaches in
+/// the valleys. The heightmap can be eroded before blocks are placed on it, which carves out
+/// valleys and softens the slopes.
+pub struct HillsGenerator {
+    erosion: Erosion,
+    /// The surface y of every column of each eroded square, by the square's position.
+    tiles: RefCell<FxHashMap<(u64, IVec2), Vec<i32>>>,
+    /// The time spent in erosion over every square so far.
+    eroding: Cell<Duration>,
+}
+
+impl HillsGenerator {
+    pub fn new(erosion: Erosion) -> Self {
+        Self {
+            erosion,
+            tiles: RefCell::default(),
+            eroding: Cell::default(),
+        }
+    }
+
+    /// The surface height at a column before erosion, octaves of noise each half the size of
+    /// the last.
+    fn noise_height(perlin: &Perlin, column: IVec2) -> f32 {
+        let mut height = 0.0;
+        let (mut scale, mut amplitude) = (1.0 / 96.0, 1.0);
+        for _ in 0..4 {
+            let p = column.as_dvec2() * scale;
+            height += perlin.get([p.x, p.y]) * amplitude;
+            scale *= 2.0;
+            amplitude *= 0.5;
+        }
+        (HILLS_SURFACE + height * HILLS_AMPLITUDE) as f32
+    }
+
+    /// The surface y of every column in the square at `tile`, a row of `EROSION_TILE` at a
+    /// time.
+    fn erode_tile(&self, seed: u64, tile: IVec2) -> Vec<i32> {
+        let perlin = Perlin::new(seed as u32);
+        let origin = tile * EROSION_TILE - EROSION_MARGIN;
+        let size = EROSION_TILE + 2 * EROSION_MARGIN;
+        let mut heights: Vec<f32> = (0..size)
+            .flat_map(|z| (0..size).map(move |x| ivec2(x, z)))
+            .map(|local| Self::noise_height(&perlin, origin + local))
+            .collect();
+        let start = Instant::now();
+        self.erosion.erode(&mut heights, size as usize);
+        self.eroding.set(self.eroding.get() + start.elapsed());
+        heights
+            .chunks(size as usize)
+            .skip(EROSION_MARGIN as usize)
+            .take(EROSION_TILE as usize)
+            .flat_map(|row| &row[EROSION_MARGIN as usize..(EROSION_MARGIN + EROSION_TILE) as usize])
+            .map(|height| height.round() as i32)
+            .collect()
+    }
+}
+
+impl WorldGenerator for HillsGenerator {
+    fn generate_chunk(&self, seed: u64, coord: IVec3) -> ChunkData {
+        let mut tiles = self.tiles.borrow_mut();
+        let mut chunk = ChunkData::default();
+        for z in 0..CHUNK_SIZE {
+            for x in 0..CHUNK_SIZE {
+                let column = ivec2(coord.x, coord.z) * CHUNK_SIZE + ivec2(x, z);
+                let tile = ivec2(
+                    column.x.div_euclid(EROSION_TILE),
+                    column.y.div_euclid(EROSION_TILE),
+                );
+                let surfaces = tiles
+                    .entry((seed, tile))
+                    .or_insert_with(|| self.erode_tile(seed, tile));
+                let local = column - tile * EROSION_TILE;
+                let surface = surfaces[(local.x + local.y * EROSION_TILE) as usize];
+                for y in 0..CHUNK_SIZE {
+                    let world_y = coord.y * CHUNK_SIZE + y;
+                    let block_type = if world_y > surface {
+                        (world_y <= SEA_LEVEL).then_some(BlockType::Water)
+                    } else if world_y <= surface - SOIL_DEPTH {
+                        Some(BlockType::Stone)
+                    } else if surface <= SEA_LEVEL + 1 {
+                        Some(BlockType::Sand)
+                    } else {
+                        Some(BlockType::Dirt)
+                    };
+                    chunk.set(ivec3(x, y, z), block_type);
+                }
+            }
+        }
+        chunk
+    }
+
+    fn biome(&self, seed: u64, column: IVec2) -> Biome {
+        climate_biome(seed, column)
+    }
+
+    fn erosion_time(&self) -> Duration {
+        self.eroding.get()
+    }
+}
+
+/// World y of the top layer of a flat world, the top of the world itself.
+const FLAT_SURFACE: i32 = WORLD_TOP;
+
+/// Layers of blocks stacked down from the top of the world, with nothing below them.
+pub struct FlatGenerator {
+    /// One entry per layer, top first.
+    layers: Vec<BlockType>,
+}
+
+impl Default for FlatGenerator {
+    fn default() -> Self {
+        Self::parse("dirt,3*stone").unwrap()
+    }
+}
+
+impl FlatGenerator {
+    /// Reads a comma separated list of layers, top first, each a block name optionally
+    /// preceded by how many layers thick it is, as in `dirt,3*stone`. Layers deeper than any
+    /// world are dropped.
+    pub fn parse(layers: &str) -> Result<Self, String> {
+        let mut parsed = vec![];
+        for layer in layers.split(',').map(str::trim) {
+            let (count, name) = match layer.split_once('*') {
+                Some((count, name)) => (
+                    count
+                        .trim()
+                        .parse()
+                        .map_err(|_| format!("{count} isn't a number of layers"))?,
+                    name.trim(),
+                ),
+                None => (1, layer),
+            };
+            let block_type =
+                BlockType::from_name(name).ok_or_else(|| format!("unknown block {name}"))?;
+            if !block_type.is_cube() {
+                return Err(format!("{name} can't be a layer"));
+            }
+            let depth = parsed.len().saturating_add(count).min(WORLD_DEPTH as usize);
+            parsed.resize(depth, block_type);
+        }
+        Ok(Self { layers: parsed })
+    }
+}
+
+impl WorldGenerator for FlatGenerator {
+    fn generate_chunk(&self, _seed: u64, coord: IVec3) -> ChunkData {
+        let mut chunk = ChunkData::default();
+        for y in 0..CHUNK_SIZE {
+            let depth = FLAT_SURFACE - (coord.y * CHUNK_SIZE + y);
+            let Some(&layer) = usize::try_from(depth)
+                .ok()
+                .and_then(|depth| self.layers.get(depth))
+            else {
+                continue;
+            };
+            for z in 0..CHUNK_SIZE {
+                for x in 0..CHUNK_SIZE {
+                    chunk.set(IVec3::new(x, y, z), Some(layer));
+                }
+            }
+        }
+        chunk
+    }
+}
+
+/// World y of the row of blocks in the debug world, a couple of blocks under the top so
+/// torches fit on top.
+const DEBUG_ROW: i32 = -7;
+
+/// The faces a torch can be attached to.
+const TORCH_FACES: [Face; 5] = [Face::PosY, Face::PosX, Face::NegX, Face::PosZ, Face::NegZ];
+
+/// The sides a bed's head can be towards.
+const BED_FACES: [Face; 4] = [Face::PosX, Face::NegX, Face::PosZ, Face::NegZ];
+
+/// Every block laid out in a row along x, one block apart, and behind them a stone block for
+/// each way a torch can be attached, with the torch on it. Further back, a small structure
+/// placed in each rotation, then a bed facing each way and a sign, on stone. Nothing else, so
+/// each one can be seen from every side.
+pub struct DebugGenerator;
+
+impl DebugGenerator {
+    /// Where each full block goes.
+    fn blocks() -> impl Iterator<Item = (IVec3, BlockType)> {
+        BlockType::ALL
+            .into_iter()
+            .filter(|block_type| block_type.is_cube())
+            .enumerate()
+            .map(|(i, block_type)| (ivec3(1 + 2 * i as i32, DEBUG_ROW, 1), block_type))
+    }
+
+    /// The stone each torch hangs off, spaced so no two torches touch.
+    fn torch_supports() -> impl Iterator<Item = (IVec3, Face)> {
+        TORCH_FACES
+            .into_iter()
+            .enumerate()
+            .map(|(i, face)| (ivec3(2 + 4 * i as i32, DEBUG_ROW, 5), face))
+    }
+
+    /// The foot of each bed and the way it points, one for each side a bed can face.
+    fn beds() -> impl Iterator<Item = (IVec3, Face)> {
+        BED_FACES
+            .into_iter()
+            .enumerate()
+            .map(|(i, face)| (ivec3(3 + 5 * i as i32, DEBUG_ROW + 1, 16), face))
+    }
+
+    /// Where the sign stands, after the beds.
+    const SIGN: IVec3 = ivec3(23, DEBUG_ROW + 1, 16);
+
+    /// The stone under the beds and the sign.
+    fn floor() -> impl Iterator<Item = IVec3> {
+        Self::beds()
+            .flat_map(|(foot, face)| [foot, foot + face.normal()])
+            .chain([Self::SIGN])
+            .map(|position| position - IVec3::Y)
+    }
+}
+
+impl WorldGenerator for DebugGenerator {
+    fn generate_chunk(&self, _seed: u64, coord: IVec3) -> ChunkData {
+        let mut chunk = ChunkData::default();
+        let supports = Self::torch_supports()
+            .map(|(position, _)| position)
+            .chain(Self::floor())
+            .map(|position| (position, BlockType::Stone));
+        for (position, block_type) in Self::blocks().chain(supports) {
+            let (block_coord, local) = split(position);
+            if block_coord == coord {
+                chunk.set(local, Some(block_type));
+            }
+        }
+        chunk
+    }
+
+    fn decorate(&self, _seed: u64, world: &mut World) {
+        for (support, face) in Self::torch_supports() {
+            world.place_torch(support, face);
+        }
+        // an L with a post on its corner, so which way it's turned is plain to see
+        let structure = Structure::from_blocks([
+            (IVec3::ZERO, BlockType::Stone),
+            (IVec3::Y, BlockType::Stone),
+            (IVec3::X, BlockType::Cobble),
+            (ivec3(2, 0, 0), BlockType::Cobble),
+            (IVec3::Z, BlockType::Sand),
+        ]);
+        let rotations = [
+            Rotation::None,
+            Rotation::Quarter,
+            Rotation::Half,
+            Rotation::ThreeQuarters,
+        ];
+        for (i, rotation) in rotations.into_iter().enumerate() {
+            let origin = ivec3(4 + 6 * i as i32, DEBUG_ROW, 10);
+            world.place_structure(&structure, origin, rotation);
+        }
+        for (foot, face) in Self::beds() {
+            world.place_bed(foot, face);
+        }
+        world.place_sign(Self::SIGN);
+        let lines = ["debug", "world", "", ""].map(String::from);
+        world.write_sign(Self::SIGN, lines);
+    }
+}
+
+/// Layers of solid roof at the top of a nether world.
+const NETHER_ROOF: i32 = 3;
+
+/// Winding caverns through solid cobble under an unbroken roof, with patches of sand.
+pub struct NetherGenerator;
+
+impl WorldGenerator for NetherGenerator {
+    fn generate_chunk(&self, seed: u64, coord: IVec3) -> ChunkData {
+        let perlin = Perlin::new(seed as u32);
+        let mut chunk = ChunkData::default();
+        for z in 0..CHUNK_SIZE {
+            for y in 0..CHUNK_SIZE {
+                for x in 0..CHUNK_SIZE {
+                    let local = IVec3::new(x, y, z);
+                    let position = coord * CHUNK_SIZE + local;
+                    let roof = position.y > WORLD_TOP - NETHER_ROOF;
+                    let p = position.as_dvec3() / 24.0;
+                    if roof || perlin.get([p.x, p.y, p.z]) > -0.1 {
+                        // offset so the sand doesn't follow the caverns
+                        let q = p * 3.0 + 100.0;
+                        let sand = !roof && perlin.get([q.x, q.y, q.z]) > 0.3;
+                        let block_type = if sand {
+                            BlockType::Sand
+                        } else {
+                            BlockType::Cobble
+                        };
+                        chunk.set(local, Some(block_type));
+                    }
+                }
+            }
+        }
+        chunk
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use glam::{ivec3, IVec3};
+
+    use crate::{
+        erosion::Erosion,
+        world::{BlockType, World, WORLD_DEPTH},
+    };
+
+    use super::{
+        named, DebugGenerator, FlatGenerator, HillsGenerator, PerlinGenerator, WorldGenerator,
+        CHUNK_SIZE, GENERATORS,
+    };
+
+    #[test]
+    fn perlin_chunks_depend_only_on_seed_and_coord() {
+        let generator = PerlinGenerator::new(0.0);
+        let a = generator.generate_chunk(7, ivec3(1, -2, 3));
+        generator.generate_chunk(7, IVec3::ZERO);
+        let b = generator.generate_chunk(7, ivec3(1, -2, 3));
+        assert_eq!(a.blocks, b.blocks);
+        assert!(a.blocks.iter().any(Option::is_some) && a.blocks.iter().any(Option::is_none));
+
+        let c = generator.generate_chunk(8, ivec3(1, -2, 3));
+        assert_ne!(a.blocks, c.blocks);
+    }
+
+    #[test]
+    fn every_listed_generator_exists() {
+        for name in GENERATORS {
+            assert!(named(name).is_ok(), "{name}");
+        }
+        assert!(named("flat:sand,2*stone").is_ok());
+        assert!(named("hills:iterations=20,strength=0.3").is_ok());
+        assert!(named("hills:strength=3").is_err());
+        assert!(named("moon").is_err());
+    }
+
+    #[test]
+    fn flat_layers_stack_down_from_the_top() {
+        let generator = FlatGenerator::parse("sand, 2*stone").unwrap();
+        // the chunk holding y = -16 to -1
+        let chunk = generator.generate_chunk(0, ivec3(0, -1, 0));
+        let layer = |y: i32| chunk.get(ivec3(3, y + CHUNK_SIZE, 7));
+        assert_eq!(layer(-4), None);
+        assert_eq!(layer(-5), Some(BlockType::Sand));
+        assert_eq!(layer(-6), Some(BlockType::Stone));
+        assert_eq!(layer(-7), Some(BlockType::Stone));
+        assert_eq!(layer(-8), None);
+
+        assert!(FlatGenerator::parse("2*lava").is_err());
+        assert!(FlatGenerator::parse("torch").is_err());
+        assert!(FlatGenerator::parse("x*dirt").is_err());
+
+        let deep = FlatGenerator::parse("dirt,18446744073709551615*stone").unwrap();
+        assert_eq!(deep.layers.len(), WORLD_DEPTH as usize);
+    }
+
+    #[test]
+    fn eroded_hills_are_smoother() {
+        // how far each column's surface is from the next along x, over one chunk column
+        let roughness = |generator: &HillsGenerator| {
+            let chunks: Vec<_> = (-5..0)
+                .map(|y| (y, generator.generate_chunk(3, ivec3(2, y, 1))))
+                .collect();
+            let surface = |x, z| {
+                chunks
+                    .iter()
+                    .flat_map(|(chunk_y, chunk)| {
+                        (0..CHUNK_SIZE)
+                            .filter(move |y| chunk.get(ivec3(x, *y, z)).is_some())
+                            .map(move |y| chunk_y * CHUNK_SIZE + y)
+                    })
+                    .max()
+                    .unwrap()
+            };
+            (0..CHUNK_SIZE)
+                .flat_map(|z| (1..CHUNK_SIZE).map(move |x| (x, z)))
+                .map(|(x, z)| (surface(x, z) - surface(x - 1, z)).abs())
+                .sum::<i32>()
+        };
+        let rough = roughness(&HillsGenerator::new(Erosion::default()));
+        let eroded = HillsGenerator::new(Erosion {
+            iterations: 10,
+            strength: 1.0,
+        });
+        assert_eq!(eroded.erosion_time(), Duration::ZERO);
+        let smooth = roughness(&eroded);
+        assert!(smooth < rough, "{smooth} {rough}");
+        assert!(eroded.erosion_time() > Duration::ZERO);
+    }
+
+    #[test]
+    fn debug_world_shows_every_block() {
+        let chunk = DebugGenerator.generate_chunk(0, ivec3(0, -1, 0));
+        for block_type in BlockType::ALL.into_iter().filter(|b| b.is_cube()) {
+            assert!(chunk.blocks.contains(&Some(block_type)), "{block_type:?}");
+        }
+    }
+
+    #[test]
+    fn debug_world_has_a_bed_facing_each_way_and_a_sign() {
+        let world = World::new(32, 32, 16, &DebugGenerator, 0);
+        for (foot, face) in DebugGenerator::beds() {
+            for half in [foot, foot + face.normal()] {
+                assert_eq!(world.block_type(half), Some(BlockType::Bed), "{face:?}");
+            }
+        }
+        assert_eq!(
+            world.block_type(DebugGenerator::SIGN),
+            Some(BlockType::Sign)
+        );
+    }
+}
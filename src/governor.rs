@@ -0,0 +1,220 @@
+/// How much each new frame time moves the running average.
+const SMOOTHING: f32 = 0.1;
+
+/// Quality drops once the average frame time is this far over the target...
+const DOWNGRADE_ABOVE: f32 = 1.1;
+/// ...and only climbs back once it's this far under, so it doesn't flip between two levels.
+const UPGRADE_BELOW: f32 = 0.7;
+
+/// Seconds to wait after a change before judging the new level, in real time.
+const COOLDOWN: f32 = 2.0;
+
+/// Everything the governor trades for frame time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Levels {
+    /// Chunks meshed per frame.
+    pub meshing_budget: usize,
+    /// In chunks.
+    pub render_distance: i32,
+    /// For the particle system to respect.
+    pub max_particles: usize,
+}
+
+/// From cheapest to best looking.
+const LEVELS: [Levels; 4] = [
+    Levels {
+        meshing_budget: 1,
+        render_distance: 4,
+        max_particles: 64,
+    },
+    Levels {
+        meshing_budget: 2,
+        render_distance: 6,
+        max_particles: 256,
+    },
+    Levels {
+        meshing_budget: 4,
+        render_distance: 8,
+        max_particles: 1024,
+    },
+    Levels {
+        meshing_budget: 8,
+        render_distance: 12,
+        max_particles: 4096,
+    },
+];
+
+/// Steps quality up and down to hold a target frame time. Starts at the best quality and
+/// backs off while frames run long.
+pub struct Governor {
+    /// Seconds.
+    target: f32,
+    /// Smoothed frame time in seconds.
+    average: f32,
+    level: usize,
+    cooldown: f32,
+    /// When disabled quality stays at the top level.
+    enabled: bool,
+}
+
+impl Governor {
+    pub fn new(target_frame_time: f32, enabled: bool) -> Self {
+        Self {
+            target: target_frame_time,
+            average: target_frame_time,
+            level: LEVELS.len() - 1,
+            cooldown: COOLDOWN,
+            enabled,
+        }
+    }
+
+    /// Feeds in how long the last frame took to produce and how long it's been since the one
+    /// before, in seconds. Returns true when the levels changed.
+    pub fn update(&mut self, frame_time: f32, interval: f32) -> bool {
+        self.average += (frame_time - self.average) * SMOOTHING;
+        self.cooldown -= interval;
+        if !self.enabled || self.cooldown > 0.0 {
+            return false;
+        }
+
+        let level = if self.average > self.target * DOWNGRADE_ABOVE {
+            self.level.saturating_sub(1)
+        } else if self.average < self.target * UPGRADE_BELOW {
+            (self.level + 1).min(LEVELS.len() - 1)
+        } else {
+            self.level
+        };
+        if level == self.level {
+            return false;
+        }
+        self.level = level;
+        self.cooldown = COOLDOWN;
+        true
+    }
+
+    pub fn levels(&self) -> Levels {
+        LEVELS[self.level]
+    }
+
+    pub fn overlay_text(&self) -> String {
+        let levels = self.levels();
+        format!(
+            "frame {:.1} ms / {:.1} ms  quality {}/{}  meshing {}  view {}  particles {}",
+            self.average * 1000.0,
+            self.target * 1000.0,
+            self.level + 1,
+            LEVELS.len(),
+            levels.meshing_budget,
+            levels.render_distance,
+            levels.max_particles,
+        )
+    }
+}
+
+/// Whether the window has focus and can be seen, which decides how often frames come.
+#[derive(Clone, Copy, Debug)]
+pub struct WindowActivity {
+    pub focused: bool,
+    /// Minimised, or covered up entirely by other windows.
+    pub hidden: bool,
+}
+
+impl Default for WindowActivity {
+    fn default() -> Self {
+        Self {
+            focused: true,
+            hidden: false,
+        }
+    }
+}
+
+impl WindowActivity {
+    /// In the background, with the world running at a low rate until the player comes back.
+    pub fn throttled(self) -> bool {
+        !self.focused || self.hidden
+    }
+
+    /// Seconds between frames, `frame_time` normally and `background_frame_time` while
+    /// throttled.
+    pub fn frame_time(self, frame_time: f32, background_frame_time: f32) -> f32 {
+        if self.throttled() {
+            background_frame_time.max(frame_time)
+        } else {
+            frame_time
+        }
+    }
+
+    /// Whether there's any point drawing frames, which there isn't while nothing shows them.
+    pub fn draws(self) -> bool {
+        !self.hidden
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Governor, WindowActivity, COOLDOWN, LEVELS};
+
+    const TARGET: f32 = 1.0 / 60.0;
+
+    fn run(governor: &mut Governor, frame_time: f32, seconds: f32) {
+        for _ in 0..(seconds / frame_time) as usize {
+            governor.update(frame_time, frame_time);
+        }
+    }
+
+    #[test]
+    fn slow_frames_lower_quality_one_step_at_a_time() {
+        let mut governor = Governor::new(TARGET, true);
+        run(&mut governor, TARGET * 2.0, 3.0);
+        assert_eq!(governor.levels(), LEVELS[2]);
+        run(&mut governor, TARGET * 2.0, 60.0);
+        assert_eq!(governor.levels(), LEVELS[0]);
+    }
+
+    #[test]
+    fn frames_near_the_target_hold_the_level() {
+        let mut governor = Governor::new(TARGET, true);
+        run(&mut governor, TARGET * 2.0, 3.0);
+        // between the two thresholds nothing changes either way
+        run(&mut governor, TARGET * 0.9, 30.0);
+        assert_eq!(governor.levels(), LEVELS[2]);
+        run(&mut governor, TARGET * 0.5, 3.0);
+        assert_eq!(governor.levels(), LEVELS[3]);
+    }
+
+    #[test]
+    fn changes_wait_out_the_cooldown_in_real_time() {
+        let mut governor = Governor::new(TARGET, true);
+        // slow frames, though the frame rate is capped well above what they take between them
+        for _ in 0..(COOLDOWN * 0.75 / TARGET) as usize {
+            governor.update(TARGET * 2.0, TARGET);
+        }
+        assert_eq!(governor.levels(), LEVELS[3]);
+        for _ in 0..(COOLDOWN * 0.5 / TARGET) as usize {
+            governor.update(TARGET * 2.0, TARGET);
+        }
+        assert_eq!(governor.levels(), LEVELS[2]);
+    }
+
+    #[test]
+    fn disabled_governor_keeps_the_best_quality() {
+        let mut governor = Governor::new(TARGET, false);
+        run(&mut governor, TARGET * 4.0, 30.0);
+        assert_eq!(governor.levels(), LEVELS[3]);
+    }
+
+    #[test]
+    fn background_windows_slow_down_and_hidden_ones_stop_drawing() {
+        let mut activity = WindowActivity::default();
+        assert_eq!(activity.frame_time(TARGET, 0.1), TARGET);
+        activity.focused = false;
+        assert_eq!(activity.frame_time(TARGET, 0.1), 0.1);
+        assert!(activity.draws());
+        // a background rate faster than the normal one isn't
+        assert_eq!(activity.frame_time(TARGET, 0.001), TARGET);
+        activity.hidden = true;
+        assert!(!activity.draws());
+        activity = WindowActivity::default();
+        assert!(!activity.throttled() && activity.draws());
+    }
+}
@@ -0,0 +1,182 @@
+use std::str::FromStr;
+
+use wgpu::{Adapter, AdapterInfo, Backends, Instance, PowerPreference, Surface};
+
+/// The graphics API to draw with.
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub enum Backend {
+    /// Whichever works best on this platform.
+    #[default]
+    Auto,
+    Vulkan,
+    Metal,
+    Dx12,
+    Gl,
+}
+
+impl Backend {
+    pub fn backends(self) -> Backends {
+        match self {
+            Backend::Auto => Backends::all(),
+            Backend::Vulkan => Backends::VULKAN,
+            Backend::Metal => Backends::METAL,
+            Backend::Dx12 => Backends::DX12,
+            Backend::Gl => Backends::GL,
+        }
+    }
+}
+
+impl FromStr for Backend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "auto" => Ok(Backend::Auto),
+            "vulkan" => Ok(Backend::Vulkan),
+            "metal" => Ok(Backend::Metal),
+            "dx12" => Ok(Backend::Dx12),
+            "gl" | "opengl" => Ok(Backend::Gl),
+            _ => Err(format!("unknown graphics backend {s}")),
+        }
+    }
+}
+
+/// Which GPU to draw with, for machines with more than one.
+#[derive(Clone, Default, Debug, PartialEq, Eq)]
+pub enum AdapterChoice {
+    /// Whichever the backend offers first.
+    #[default]
+    Default,
+    /// The faster one, usually a discrete GPU.
+    HighPerformance,
+    /// The one that uses less power, usually the integrated GPU.
+    LowPower,
+    /// The first whose name contains this, ignoring case.
+    Named(String),
+}
+
+impl AdapterChoice {
+    fn power_preference(&self) -> PowerPreference {
+        match self {
+            AdapterChoice::HighPerformance => PowerPreference::HighPerformance,
+            AdapterChoice::LowPower => PowerPreference::LowPower,
+            _ => PowerPreference::default(),
+        }
+    }
+
+    /// Whether an adapter is the one named, for `Named` choices.
+    fn matches(&self, info: &AdapterInfo) -> bool {
+        match self {
+            AdapterChoice::Named(name) => info.name.to_lowercase().contains(&name.to_lowercase()),
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for AdapterChoice {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "" => Err("expected an adapter".into()),
+            "default" => Ok(AdapterChoice::Default),
+            "high-performance" => Ok(AdapterChoice::HighPerformance),
+            "low-power" => Ok(AdapterChoice::LowPower),
+            name => Ok(AdapterChoice::Named(name.into())),
+        }
+    }
+}
+
+/// Reads `--backend name` from the command line.
+pub fn backend_from_args(mut args: impl Iterator<Item = String>) -> Option<Backend> {
+    args.find(|arg| arg == "--backend")?;
+    let name = args.next()?;
+    name.parse().map_err(|err| eprintln!("{err}")).ok()
+}
+
+/// Reads `--adapter choice` from the command line.
+pub fn adapter_from_args(mut args: impl Iterator<Item = String>) -> Option<AdapterChoice> {
+    args.find(|arg| arg == "--adapter")?;
+    let choice = args.next()?;
+    choice.parse().map_err(|err| eprintln!("{err}")).ok()
+}
+
+/// A line describing an adapter, for the log.
+fn describe(info: &AdapterInfo) -> String {
+    format!(
+        "{} ({:?}, {:?}, driver {} {})",
+        info.name, info.device_type, info.backend, info.driver, info.driver_info
+    )
+}
+
+/// The adapter to draw to `surface` with, or None if `backend` has none that can. Named
+/// adapters that can't be found fall back to the default one, listing the ones there are.
+pub async fn request_adapter(
+    instance: &Instance,
+    surface: &Surface,
+    backend: Backend,
+    choice: &AdapterChoice,
+) -> Option<Adapter> {
+    if let AdapterChoice::Named(name) = choice {
+        let found = instance
+            .enumerate_adapters(backend.backends())
+            .filter(|adapter| adapter.is_surface_supported(surface))
+            .find(|adapter| choice.matches(&adapter.get_info()));
+        if let Some(adapter) = found {
+            println!("graphics adapter {}", describe(&adapter.get_info()));
+            return Some(adapter);
+        }
+        eprintln!("no graphics adapter called {name}, the ones there are:");
+        for adapter in instance.enumerate_adapters(backend.backends()) {
+            eprintln!("  {}", describe(&adapter.get_info()));
+        }
+    }
+    let adapter = instance
+        .request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: choice.power_preference(),
+            force_fallback_adapter: false,
+            compatible_surface: Some(surface),
+        })
+        .await?;
+    println!("graphics adapter {}", describe(&adapter.get_info()));
+    Some(adapter)
+}
+
+#[cfg(test)]
+mod tests {
+    use wgpu::{AdapterInfo, Backends, DeviceType};
+
+    use super::{adapter_from_args, backend_from_args, AdapterChoice, Backend};
+
+    #[test]
+    fn backends_and_adapters_parse() {
+        assert_eq!("Vulkan".parse(), Ok(Backend::Vulkan));
+        assert_eq!(
+            "opengl".parse::<Backend>().unwrap().backends(),
+            Backends::GL
+        );
+        assert!("glide".parse::<Backend>().is_err());
+        assert_eq!("low-power".parse(), Ok(AdapterChoice::LowPower));
+
+        let args = ["normalcraft", "--adapter", "GeForce", "--backend", "dx12"].map(String::from);
+        assert_eq!(
+            backend_from_args(args.clone().into_iter()),
+            Some(Backend::Dx12)
+        );
+        let choice = adapter_from_args(args.into_iter()).unwrap();
+        assert_eq!(choice, AdapterChoice::Named("GeForce".into()));
+
+        let info = |name: &str| AdapterInfo {
+            name: name.into(),
+            vendor: 0,
+            device: 0,
+            device_type: DeviceType::DiscreteGpu,
+            driver: String::new(),
+            driver_info: String::new(),
+            backend: wgpu::Backend::Vulkan,
+        };
+        assert!(choice.matches(&info("NVIDIA GeForce RTX 3060 Laptop GPU")));
+        assert!(!choice.matches(&info("Intel(R) UHD Graphics")));
+        assert!(!AdapterChoice::Default.matches(&info("GeForce")));
+    }
+}
@@ -0,0 +1,48 @@
+use glam::{ivec2, IVec2, IVec3};
+
+/// The highest block that stops light in every column of a world, x along its width and z
+/// along its height, so whether a position is open to the sky takes one lookup instead of a
+/// walk up its column.
+pub struct HeightMap {
+    width: i32,
+    height: i32,
+    tops: Vec<Option<i32>>,
+}
+
+impl HeightMap {
+    pub fn new(width: u32, height: u32, top: impl Fn(IVec2) -> Option<i32>) -> Self {
+        let (width, height) = (width as i32, height as i32);
+        let tops = (0..height)
+            .flat_map(|z| (0..width).map(move |x| ivec2(x, z)))
+            .map(top)
+            .collect();
+        Self {
+            width,
+            height,
+            tops,
+        }
+    }
+
+    fn index(&self, column: IVec2) -> Option<usize> {
+        let inside = (0..self.width).contains(&column.x) && (0..self.height).contains(&column.y);
+        inside.then_some((column.x + column.y * self.width) as usize)
+    }
+
+    /// The y of the highest block stopping light in a column, None if there isn't one or the
+    /// column is off the edge of the world.
+    pub fn top(&self, column: IVec2) -> Option<i32> {
+        self.index(column).and_then(|index| self.tops[index])
+    }
+
+    pub fn set(&mut self, column: IVec2, top: Option<i32>) {
+        if let Some(index) = self.index(column) {
+            self.tops[index] = top;
+        }
+    }
+
+    /// Whether nothing above `position` stops light from the sky.
+    pub fn is_exposed(&self, position: IVec3) -> bool {
+        self.top(ivec2(position.x, position.z))
+            .is_none_or(|top| position.y > top)
+    }
+}
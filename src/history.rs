@@ -0,0 +1,151 @@
+use std::collections::VecDeque;
+
+use glam::IVec3;
+
+use crate::world::{Snapshot, World};
+
+/// One position changed by an edit.
+pub struct Change {
+    pub position: IVec3,
+    pub before: Snapshot,
+    pub after: Snapshot,
+}
+
+impl Change {
+    fn size(&self) -> usize {
+        std::mem::size_of::<IVec3>() + self.before.size() + self.after.size()
+    }
+}
+
+/// Edits to the world that can be undone and redone, each made up of every block one command
+/// changed. The oldest edits are forgotten once they take up more memory than the limit.
+pub struct History {
+    undo: VecDeque<Vec<Change>>,
+    redo: Vec<Vec<Change>>,
+    /// In bytes, shared between both stacks.
+    limit: usize,
+    used: usize,
+}
+
+fn size(edit: &[Change]) -> usize {
+    edit.iter().map(Change::size).sum()
+}
+
+impl History {
+    pub fn new(limit: usize) -> Self {
+        Self {
+            undo: VecDeque::new(),
+            redo: vec![],
+            limit,
+            used: 0,
+        }
+    }
+
+    /// Adds an edit that's just been made, which can't be followed by redoing anything undone
+    /// before it.
+    pub fn record(&mut self, edit: Vec<Change>) {
+        if edit.is_empty() {
+            return;
+        }
+        for undone in self.redo.drain(..) {
+            self.used -= size(&undone);
+        }
+        self.used += size(&edit);
+        self.undo.push_back(edit);
+        while self.used > self.limit {
+            let Some(oldest) = self.undo.pop_front() else {
+                break;
+            };
+            self.used -= size(&oldest);
+        }
+    }
+
+    /// Puts back what the last edit changed, returning how many positions that was, or None
+    /// if there's nothing to undo.
+    pub fn undo(&mut self, world: &mut World) -> Option<usize> {
+        let edit = self.undo.pop_back()?;
+        for change in &edit {
+            world.restore(change.position, &change.before);
+        }
+        let count = edit.len();
+        self.redo.push(edit);
+        Some(count)
+    }
+
+    /// Makes the last undone edit again, returning how many positions it changed, or None if
+    /// there's nothing to redo.
+    pub fn redo(&mut self, world: &mut World) -> Option<usize> {
+        let edit = self.redo.pop()?;
+        for change in &edit {
+            world.restore(change.position, &change.after);
+        }
+        let count = edit.len();
+        self.undo.push_back(edit);
+        Some(count)
+    }
+
+    /// Forgets every edit, for when the world they were made in is left.
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.used = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{ivec3, IVec3};
+
+    use crate::{
+        generator::PerlinGenerator,
+        world::{Block, BlockType, World},
+    };
+
+    use super::{size, History};
+
+    const EMPTY: PerlinGenerator = PerlinGenerator::new(9999.0);
+
+    fn place(world: &mut World, history: &mut History, position: IVec3, block_type: BlockType) {
+        world.start_recording();
+        world.set_block(position, Some(Block::new(block_type)));
+        history.record(world.stop_recording());
+    }
+
+    #[test]
+    fn edits_undo_and_redo() {
+        let mut world = World::new(4, 4, 8, &EMPTY, 0);
+        let mut history = History::new(usize::MAX);
+        let position = ivec3(1, -6, 1);
+        place(&mut world, &mut history, position, BlockType::Stone);
+        place(&mut world, &mut history, position, BlockType::Dirt);
+
+        assert_eq!(history.undo(&mut world), Some(1));
+        assert_eq!(world.block_type(position), Some(BlockType::Stone));
+        assert_eq!(history.undo(&mut world), Some(1));
+        assert_eq!(world.block_type(position), None);
+        assert_eq!(history.undo(&mut world), None);
+        assert_eq!(history.redo(&mut world), Some(1));
+        assert_eq!(world.block_type(position), Some(BlockType::Stone));
+
+        // a new edit drops what was left to redo
+        place(&mut world, &mut history, position, BlockType::Sand);
+        assert_eq!(history.redo(&mut world), None);
+    }
+
+    #[test]
+    fn oldest_edits_are_forgotten_past_the_limit() {
+        let mut world = World::new(4, 4, 8, &EMPTY, 0);
+        world.start_recording();
+        world.set_block(ivec3(0, -6, 0), Some(Block::new(BlockType::Stone)));
+        let one_block = size(&world.stop_recording());
+
+        let mut history = History::new(2 * one_block);
+        for x in 1..4 {
+            place(&mut world, &mut history, ivec3(x, -6, 0), BlockType::Stone);
+        }
+        assert!(history.undo(&mut world).is_some());
+        assert!(history.undo(&mut world).is_some());
+        assert_eq!(history.undo(&mut world), None);
+        assert_eq!(world.block_type(ivec3(1, -6, 0)), Some(BlockType::Stone));
+    }
+}
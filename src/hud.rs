@@ -0,0 +1,49 @@
+use glam::{vec2, Vec2};
+
+use crate::{
+    player::Experience,
+    renderer::{FontHandle, Renderer, TextHandle},
+    world::World,
+};
+
+/// The experience bar's bottom left corner and size on the ui, centred along the bottom.
+const BAR_MIN: Vec2 = vec2(200.0, 44.0);
+const BAR_SIZE: Vec2 = vec2(400.0, 10.0);
+
+/// What's always drawn over the world while playing: the experience bar and level.
+pub struct Hud {
+    font_handle: FontHandle,
+    level_text: TextHandle,
+    /// The level the text shows, so it's only rebuilt when that changes.
+    shown_level: Option<u32>,
+}
+
+impl Hud {
+    pub fn new(renderer: &mut Renderer, font_handle: FontHandle) -> Self {
+        Self {
+            font_handle,
+            level_text: renderer.create_text("0", font_handle, 0.0, 0.0, 0.15),
+            shown_level: None,
+        }
+    }
+
+    pub fn draw(&mut self, renderer: &mut Renderer, world: &World, experience: Experience) {
+        let (level, progress) = experience.level();
+        renderer.queue_ui_quad(BAR_MIN, BAR_MIN + BAR_SIZE, world.get_texture("slot"));
+        if progress > 0.0 {
+            let filled = vec2(BAR_SIZE.x * progress, BAR_SIZE.y);
+            renderer.queue_ui_quad(BAR_MIN, BAR_MIN + filled, world.get_texture("bar"));
+        }
+        if level > 0 {
+            if self.shown_level != Some(level) {
+                let text = level.to_string();
+                // roughly centred over the bar
+                let x = BAR_MIN.x + BAR_SIZE.x / 2.0 - 5.0 * text.len() as f32;
+                let y = BAR_MIN.y + BAR_SIZE.y + 4.0;
+                renderer.set_text(self.level_text, &text, self.font_handle, x, y, 0.15);
+                self.shown_level = Some(level);
+            }
+            renderer.queue_draw_text(self.level_text);
+        }
+    }
+}
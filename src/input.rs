@@ -0,0 +1,185 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use glam::{vec2, Vec2};
+
+use crate::settings::{KeyMode, Settings};
+
+/// Radians of camera rotation per pixel of mouse motion at a sensitivity of 1.
+const RADIANS_PER_PIXEL: f32 = 0.01;
+/// The longest gap between two presses of jump that switches flying on or off.
+const DOUBLE_TAP: Duration = Duration::from_millis(300);
+
+/// Creates a Hashmap<String, bool> with value false, accepting a key array.
+macro_rules! kbd_map {
+    ($($a:expr),*) => {
+        {
+        let map: HashMap<String, bool> = HashMap::from([
+            $(
+                ($a.into(), false),
+            )*
+        ]);
+        map
+        }
+    }
+}
+
+pub struct InputState {
+    pub kbd_map: HashMap<String, bool>,
+    /// Raw mouse motion in pixels gathered since the last tick.
+    mouse_delta: Vec2,
+    /// The smoothed motion applied on the previous tick, in pixels.
+    smoothed_delta: Vec2,
+    /// Whether the sprint key is down, which with `KeyMode::Toggle` isn't whether the player is
+    /// sprinting.
+    sprint_key: bool,
+    /// When jump was last pressed, if that could be the first press of a double tap.
+    last_jump: Option<Instant>,
+}
+
+impl InputState {
+    pub fn new() -> Self {
+        Self {
+            kbd_map: kbd_map!("w", "s", "a", "d", "q", "e", "shift", "space", "ctrl"),
+            mouse_delta: Vec2::ZERO,
+            smoothed_delta: Vec2::ZERO,
+            sprint_key: false,
+            last_jump: None,
+        }
+    }
+
+    /// Records the sprint key going down or coming up, starting or stopping sprinting as `mode`
+    /// says.
+    pub fn press_sprint(&mut self, pressed: bool, mode: KeyMode) {
+        let sprinting = match mode {
+            KeyMode::Hold => pressed,
+            // held keys repeat, so only the first press of each switches it
+            KeyMode::Toggle => self.kbd_map["shift"] ^ (pressed && !self.sprint_key),
+        };
+        self.sprint_key = pressed;
+        self.kbd_map.insert("shift".into(), sprinting);
+    }
+
+    /// Records the jump key going down or coming up at `now`, returning whether it was just
+    /// pressed twice in quick succession, which switches flying.
+    pub fn press_jump(&mut self, pressed: bool, now: Instant) -> bool {
+        // held keys repeat, so only the first press of each counts
+        let tapped = pressed && !self.kbd_map["space"];
+        self.kbd_map.insert("space".into(), pressed);
+        if !tapped {
+            return false;
+        }
+        let double = self
+            .last_jump
+            .is_some_and(|last| now.duration_since(last) <= DOUBLE_TAP);
+        // a third tap starts a new pair rather than switching back straight away
+        self.last_jump = if double { None } else { Some(now) };
+        double
+    }
+
+    /// Records mouse motion. Nothing is applied until the next tick calls `take_look`.
+    pub fn add_mouse_motion(&mut self, delta: (f64, f64)) {
+        self.mouse_delta += vec2(delta.0 as f32, delta.1 as f32);
+    }
+
+    /// Consumes the motion gathered since the last tick and returns the yaw and pitch to add to
+    /// the camera, in radians, after applying the mouse settings.
+    pub fn take_look(&mut self, settings: &Settings) -> Vec2 {
+        let mut delta = std::mem::take(&mut self.mouse_delta);
+        delta *= 1.0 + settings.mouse_acceleration * delta.length() / 100.0;
+        // with smoothing the remaining motion keeps easing out over the following ticks
+        self.smoothed_delta = delta.lerp(self.smoothed_delta, settings.mouse_smoothing);
+        let y_sign = if settings.invert_y { 1.0 } else { -1.0 };
+        vec2(-self.smoothed_delta.x, y_sign * self.smoothed_delta.y)
+            * settings.mouse_sensitivity
+            * RADIANS_PER_PIXEL
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use glam::vec2;
+
+    use crate::settings::{KeyMode, Settings};
+
+    use super::InputState;
+
+    #[test]
+    fn look_applies_sensitivity_and_invert() {
+        let mut settings = Settings::default();
+        let mut input = InputState::new();
+        input.add_mouse_motion((10.0, 20.0));
+        input.add_mouse_motion((10.0, 0.0));
+        assert!(input
+            .take_look(&settings)
+            .abs_diff_eq(vec2(-0.2, -0.2), 1e-6));
+        // the motion was consumed
+        assert_eq!(input.take_look(&settings), vec2(0.0, 0.0));
+
+        settings.mouse_sensitivity = 2.0;
+        settings.invert_y = true;
+        input.add_mouse_motion((10.0, 10.0));
+        assert!(input
+            .take_look(&settings)
+            .abs_diff_eq(vec2(-0.2, 0.2), 1e-6));
+    }
+
+    #[test]
+    fn smoothing_spreads_motion_over_ticks() {
+        let settings = Settings {
+            mouse_smoothing: 0.5,
+            ..Settings::default()
+        };
+        let mut input = InputState::new();
+        input.add_mouse_motion((100.0, 0.0));
+        let mut total = input.take_look(&settings).x;
+        assert!((total + 0.5).abs() < 1e-5);
+        for _ in 0..32 {
+            total += input.take_look(&settings).x;
+        }
+        // the full turn arrives eventually
+        assert!((total + 1.0).abs() < 1e-3, "{total}");
+    }
+
+    #[test]
+    fn toggled_sprint_switches_on_each_press() {
+        let mut input = InputState::new();
+        let sprinting = |input: &InputState| input.kbd_map["shift"];
+        input.press_sprint(true, KeyMode::Hold);
+        assert!(sprinting(&input));
+        input.press_sprint(false, KeyMode::Hold);
+        assert!(!sprinting(&input));
+
+        // repeats while the key is held don't switch it back
+        for _ in 0..3 {
+            input.press_sprint(true, KeyMode::Toggle);
+        }
+        input.press_sprint(false, KeyMode::Toggle);
+        assert!(sprinting(&input));
+        input.press_sprint(true, KeyMode::Toggle);
+        input.press_sprint(false, KeyMode::Toggle);
+        assert!(!sprinting(&input));
+    }
+
+    #[test]
+    fn double_tapping_jump_switches_flying() {
+        let mut input = InputState::new();
+        let start = Instant::now();
+        let at = |millis| start + Duration::from_millis(millis);
+        assert!(!input.press_jump(true, at(0)));
+        // a held key repeating isn't a second tap
+        assert!(!input.press_jump(true, at(100)));
+        input.press_jump(false, at(150));
+        assert!(input.press_jump(true, at(250)));
+        input.press_jump(false, at(300));
+        // the third tap starts over
+        assert!(!input.press_jump(true, at(400)));
+        input.press_jump(false, at(450));
+        // and taps too far apart don't count
+        assert!(!input.press_jump(true, at(900)));
+    }
+}
@@ -1,23 +1,67 @@
 use glam::{Mat4, Quat, Vec3};
 
-use crate::texture::TextureHandle;
+use crate::{
+    interpolation::{Interpolated, Transform},
+    texture::TextureHandle,
+};
 
 pub struct Instance {
-    position: Vec3,
-    rotation: Quat,
+    motion: Interpolated,
+    scale: Vec3,
     pub texture: TextureHandle,
+    /// How strongly to tint it red, for a hit.
+    pub flash: f32,
+    /// Multiplies its colour.
+    pub tint: Vec3,
+    /// How opaque it is, from 0 to 1.
+    pub alpha: f32,
 }
 
 impl Instance {
     pub fn new(position: Vec3, rotation: Quat, texture: TextureHandle) -> Self {
+        Self::moving(
+            Interpolated::new(Transform::new(position, rotation)),
+            texture,
+        )
+    }
+
+    /// An instance of something that moves on the tick, drawn between where it was over the
+    /// last two.
+    pub fn moving(motion: Interpolated, texture: TextureHandle) -> Self {
         Self {
-            position,
-            rotation,
+            motion,
+            scale: Vec3::ONE,
             texture,
+            flash: 0.0,
+            tint: Vec3::ONE,
+            alpha: 1.0,
         }
     }
 
-    pub fn raw(&self) -> [f32; 16] {
-        Mat4::from_rotation_translation(self.rotation, self.position).to_cols_array()
+    pub fn with_scale(mut self, scale: Vec3) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    pub fn with_flash(mut self, flash: f32) -> Self {
+        self.flash = flash;
+        self
+    }
+
+    pub fn with_tint(mut self, tint: Vec3) -> Self {
+        self.tint = tint;
+        self
+    }
+
+    pub fn with_alpha(mut self, alpha: f32) -> Self {
+        self.alpha = alpha;
+        self
+    }
+
+    /// The model matrix for a frame `alpha` of the way from the last tick but one to the last.
+    pub fn raw(&self, alpha: f32) -> [f32; 16] {
+        let transform = self.motion.at(alpha);
+        Mat4::from_scale_rotation_translation(self.scale, transform.rotation, transform.position)
+            .to_cols_array()
     }
 }
@@ -0,0 +1,86 @@
+use glam::{Quat, Vec3};
+
+/// Where something is and which way it's turned.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Transform {
+    pub position: Vec3,
+    pub rotation: Quat,
+}
+
+impl Transform {
+    pub fn new(position: Vec3, rotation: Quat) -> Self {
+        Self { position, rotation }
+    }
+
+    /// Unturned, at `position`.
+    pub fn at(position: Vec3) -> Self {
+        Self::new(position, Quat::IDENTITY)
+    }
+
+    fn lerp(self, other: Self, alpha: f32) -> Self {
+        Self {
+            position: self.position.lerp(other.position, alpha),
+            rotation: self.rotation.slerp(other.rotation, alpha),
+        }
+    }
+}
+
+/// The transforms something had at the last two ticks, so frames drawn between ticks can show
+/// it part of the way from one to the other rather than jumping a tick at a time. Anything that
+/// moves on the tick keeps one, pushing its new transform in at the end of every tick.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Interpolated {
+    previous: Transform,
+    current: Transform,
+}
+
+impl Interpolated {
+    pub fn new(transform: Transform) -> Self {
+        Self {
+            previous: transform,
+            current: transform,
+        }
+    }
+
+    /// Moves on to the transform from the tick just run.
+    pub fn tick(&mut self, transform: Transform) {
+        self.previous = self.current;
+        self.current = transform;
+    }
+
+    /// Jumps straight to `transform`, for moves that shouldn't be shown sliding across.
+    pub fn teleport(&mut self, transform: Transform) {
+        *self = Self::new(transform);
+    }
+
+    /// The transform to draw with, `alpha` of the way from the last tick but one to the last,
+    /// where 0 is the former and 1 the latter.
+    pub fn at(&self, alpha: f32) -> Transform {
+        self.previous.lerp(self.current, alpha.clamp(0.0, 1.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{vec3, Quat, Vec3};
+
+    use super::{Interpolated, Transform};
+
+    #[test]
+    fn frames_between_ticks_are_drawn_part_way() {
+        let mut motion = Interpolated::new(Transform::at(Vec3::ZERO));
+        assert_eq!(motion.at(0.5).position, Vec3::ZERO);
+
+        motion.tick(Transform::new(
+            vec3(2.0, 0.0, 0.0),
+            Quat::from_rotation_y(1.0),
+        ));
+        let halfway = motion.at(0.5);
+        assert_eq!(halfway.position, vec3(1.0, 0.0, 0.0));
+        assert!(halfway.rotation.angle_between(Quat::from_rotation_y(0.5)) < 1e-4);
+        assert_eq!(motion.at(2.0).position, vec3(2.0, 0.0, 0.0));
+
+        motion.teleport(Transform::at(vec3(0.0, 10.0, 0.0)));
+        assert_eq!(motion.at(0.0).position, vec3(0.0, 10.0, 0.0));
+    }
+}
@@ -0,0 +1,445 @@
+use glam::{vec2, Vec2};
+
+use crate::{
+    renderer::{FontHandle, Renderer, TextHandle},
+    world::{BlockType, World},
+};
+
+/// Slots along the bottom of the inventory that stay at hand outside it.
+pub const HOTBAR_SLOTS: usize = 9;
+/// The hotbar followed by the main grid.
+pub const SLOTS: usize = HOTBAR_SLOTS * 4;
+pub const MAX_STACK: u32 = 64;
+
+/// Damage dealt with an empty hand.
+const FIST_DAMAGE: f32 = 1.0;
+
+/// Ui units along each side of a slot, and the space between slots.
+pub const SLOT_SIZE: f32 = 40.0;
+const SLOT_GAP: f32 = 4.0;
+/// Extra space between the main grid and the hotbar below it.
+const HOTBAR_GAP: f32 = 12.0;
+/// The bottom left corner of the first hotbar slot. The grid is centred on the 800 by 600 ui.
+const GRID_ORIGIN: Vec2 = vec2(
+    (800.0 - HOTBAR_SLOTS as f32 * (SLOT_SIZE + SLOT_GAP) + SLOT_GAP) / 2.0,
+    160.0,
+);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ItemStack {
+    pub block_type: BlockType,
+    pub count: u32,
+}
+
+impl ItemStack {
+    pub fn new(block_type: BlockType, count: u32) -> Self {
+        Self { block_type, count }
+    }
+
+    /// Damage dealt hitting something with this in hand. Hard blocks hurt more.
+    pub fn attack_damage(held: Option<ItemStack>) -> f32 {
+        match held.map(|stack| stack.block_type) {
+            None => FIST_DAMAGE,
+            Some(BlockType::Stone | BlockType::Cobble) => 4.0,
+            Some(
+                BlockType::Dirt
+                | BlockType::Sand
+                | BlockType::Bed
+                | BlockType::Sign
+                | BlockType::Placeholder,
+            ) => 2.0,
+            Some(BlockType::Water | BlockType::Torch | BlockType::Portal | BlockType::Glass) => {
+                FIST_DAMAGE
+            }
+        }
+    }
+}
+
+/// A stack picked up with the mouse, and the slot it came from so it can go back there.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Held {
+    stack: ItemStack,
+    from: usize,
+}
+
+/// The blocks the player carries. Slots 0 to 8 are the hotbar and the rest the main grid, top
+/// row first.
+pub struct Inventory {
+    slots: [Option<ItemStack>; SLOTS],
+    held: Option<Held>,
+    /// The hotbar slot in the player's hand.
+    selected: usize,
+}
+
+impl Default for Inventory {
+    fn default() -> Self {
+        Self {
+            slots: [None; SLOTS],
+            held: None,
+            selected: 0,
+        }
+    }
+}
+
+impl Inventory {
+    /// A full stack of every block, for a new player.
+    pub fn starting_kit() -> Self {
+        let mut inventory = Self::default();
+        for block_type in BlockType::ALL {
+            inventory.add(ItemStack::new(block_type, MAX_STACK));
+        }
+        inventory
+    }
+
+    pub fn get(&self, slot: usize) -> Option<ItemStack> {
+        self.slots[slot]
+    }
+
+    /// Puts hotbar slot `slot` in the player's hand.
+    pub fn select(&mut self, slot: usize) {
+        self.selected = slot.min(HOTBAR_SLOTS - 1);
+    }
+
+    /// The stack in the player's hand, if any.
+    pub fn in_hand(&self) -> Option<ItemStack> {
+        self.slots[self.selected]
+    }
+
+    /// The stack on the mouse, if any.
+    pub fn held(&self) -> Option<ItemStack> {
+        self.held.map(|held| held.stack)
+    }
+
+    /// Fills stacks of the same block first, then empty slots, hotbar first. Returns how many
+    /// didn't fit.
+    pub fn add(&mut self, stack: ItemStack) -> u32 {
+        self.add_to(stack, 0..SLOTS)
+    }
+
+    fn add_to(&mut self, stack: ItemStack, slots: std::ops::Range<usize>) -> u32 {
+        let mut count = stack.count;
+        for merging in [true, false] {
+            for slot in slots.clone() {
+                if count == 0 {
+                    return 0;
+                }
+                let space = match self.slots[slot] {
+                    Some(existing) if merging && existing.block_type == stack.block_type => {
+                        MAX_STACK - existing.count
+                    }
+                    None if !merging => MAX_STACK,
+                    _ => continue,
+                };
+                let moved = space.min(count);
+                let existing = self.slots[slot].get_or_insert(ItemStack::new(stack.block_type, 0));
+                existing.count += moved;
+                count -= moved;
+            }
+        }
+        count
+    }
+
+    /// Empties every slot and the mouse, handing back everything that was in them.
+    pub fn take_all(&mut self) -> Vec<ItemStack> {
+        self.release(None);
+        self.slots.iter_mut().filter_map(Option::take).collect()
+    }
+
+    /// Picks up the stack in `slot`, or half of it rounded up with `split`. Does nothing while a
+    /// stack is already held.
+    pub fn press(&mut self, slot: usize, split: bool) {
+        if self.held.is_some() {
+            return;
+        }
+        let Some(stack) = &mut self.slots[slot] else {
+            return;
+        };
+        let count = if split {
+            stack.count.div_ceil(2)
+        } else {
+            stack.count
+        };
+        let taken = ItemStack::new(stack.block_type, count);
+        stack.count -= count;
+        if stack.count == 0 {
+            self.slots[slot] = None;
+        }
+        self.held = Some(Held {
+            stack: taken,
+            from: slot,
+        });
+    }
+
+    /// Puts the held stack down in `slot`. It merges with a stack of the same block, anything
+    /// over a full stack going back where it came from, and swaps with a different block if it
+    /// left its own slot empty. Otherwise, or released over no slot, it goes back.
+    pub fn release(&mut self, slot: Option<usize>) {
+        let Some(Held { mut stack, from }) = self.held.take() else {
+            return;
+        };
+        if let Some(slot) = slot {
+            let from_empty = self.slots[from].is_none();
+            match &mut self.slots[slot] {
+                target @ None => {
+                    *target = Some(stack);
+                    return;
+                }
+                Some(existing) if existing.block_type == stack.block_type => {
+                    let moved = (MAX_STACK - existing.count).min(stack.count);
+                    existing.count += moved;
+                    stack.count -= moved;
+                    if stack.count == 0 {
+                        return;
+                    }
+                }
+                Some(existing) if from_empty => {
+                    let swapped = std::mem::replace(existing, stack);
+                    self.slots[from] = Some(swapped);
+                    return;
+                }
+                Some(_) => (),
+            }
+        }
+        // the source slot is either empty or holds the rest of a split of the same block
+        let source = self.slots[from].get_or_insert(ItemStack::new(stack.block_type, 0));
+        source.count += stack.count;
+    }
+
+    /// Moves the stack in `slot` between the hotbar and the main grid, as much of it as fits.
+    pub fn quick_transfer(&mut self, slot: usize) {
+        let Some(stack) = self.slots[slot].take() else {
+            return;
+        };
+        let other = if slot < HOTBAR_SLOTS {
+            HOTBAR_SLOTS..SLOTS
+        } else {
+            0..HOTBAR_SLOTS
+        };
+        let left = self.add_to(stack, other);
+        if left > 0 {
+            self.slots[slot] = Some(ItemStack::new(stack.block_type, left));
+        }
+    }
+}
+
+/// Space left around an item inside its slot.
+const ITEM_INSET: f32 = 6.0;
+const COUNT_SCALE: f32 = 0.12;
+const TOOLTIP_SCALE: f32 = 0.15;
+
+/// Draws an inventory over the world while it's open: a slot per stack with the block's texture
+/// and count, the stack held on the mouse, and the name of whatever the mouse is over.
+pub struct InventoryScreen {
+    pub open: bool,
+    font_handle: FontHandle,
+    /// The count text of each slot and then of the held stack, with what it says and where, so
+    /// it's only rebuilt when that changes.
+    counts: Vec<(TextHandle, Option<(String, Vec2)>)>,
+    tooltip: (TextHandle, Option<(String, Vec2)>),
+}
+
+impl InventoryScreen {
+    pub fn new(renderer: &mut Renderer, font_handle: FontHandle) -> Self {
+        let mut text = |scale| renderer.create_text("0", font_handle, 0.0, 0.0, scale);
+        Self {
+            open: false,
+            font_handle,
+            counts: (0..=SLOTS).map(|_| (text(COUNT_SCALE), None)).collect(),
+            tooltip: (text(TOOLTIP_SCALE), None),
+        }
+    }
+
+    /// Queues this frame's quads and text, with the mouse at `cursor` on the ui. Block textures
+    /// come from the world.
+    pub fn draw(
+        &mut self,
+        renderer: &mut Renderer,
+        inventory: &Inventory,
+        world: &World,
+        cursor: Vec2,
+    ) {
+        let slot_texture = world.get_texture("slot");
+        for slot in 0..SLOTS {
+            let min = slot_position(slot);
+            renderer.queue_ui_quad(min, min + SLOT_SIZE, slot_texture);
+            if let Some(stack) = inventory.get(slot) {
+                self.draw_stack(renderer, world, slot, min + ITEM_INSET, stack);
+            }
+        }
+        if let Some(stack) = inventory.held() {
+            let min = cursor - (SLOT_SIZE / 2.0 - ITEM_INSET);
+            self.draw_stack(renderer, world, SLOTS, min, stack);
+        }
+
+        // only over a full slot, and not while dragging
+        let hovered = slot_at(cursor)
+            .filter(|_| inventory.held().is_none())
+            .and_then(|slot| inventory.get(slot));
+        if let Some(stack) = hovered {
+            let name = <&str>::from(stack.block_type).to_string();
+            let shown = (name, cursor + 12.0);
+            let (text, drawn) = &mut self.tooltip;
+            Self::update_text(
+                renderer,
+                *text,
+                drawn,
+                shown,
+                self.font_handle,
+                TOOLTIP_SCALE,
+            );
+            renderer.queue_draw_text(*text);
+        }
+    }
+
+    /// Draws an item with its bottom left corner at `min`, along with its count using count
+    /// text `index`.
+    fn draw_stack(
+        &mut self,
+        renderer: &mut Renderer,
+        world: &World,
+        index: usize,
+        min: Vec2,
+        stack: ItemStack,
+    ) {
+        let size = SLOT_SIZE - 2.0 * ITEM_INSET;
+        let texture = world.get_texture(stack.block_type.into());
+        renderer.queue_ui_quad(min, min + size, texture);
+        if stack.count > 1 {
+            let shown = (stack.count.to_string(), min + vec2(size - 10.0, -4.0));
+            let (text, drawn) = &mut self.counts[index];
+            Self::update_text(renderer, *text, drawn, shown, self.font_handle, COUNT_SCALE);
+            renderer.queue_draw_text(*text);
+        }
+    }
+
+    /// Rebuilds `text` to show a value at a position if it doesn't already.
+    fn update_text(
+        renderer: &mut Renderer,
+        text: TextHandle,
+        drawn: &mut Option<(String, Vec2)>,
+        shown: (String, Vec2),
+        font_handle: FontHandle,
+        scale: f32,
+    ) {
+        if drawn.as_ref() != Some(&shown) {
+            let (value, position) = &shown;
+            renderer.set_text(text, value, font_handle, position.x, position.y, scale);
+            *drawn = Some(shown);
+        }
+    }
+}
+
+/// The bottom left corner of `slot` on the ui.
+pub fn slot_position(slot: usize) -> Vec2 {
+    let column = (slot % HOTBAR_SLOTS) as f32;
+    let y = if slot < HOTBAR_SLOTS {
+        GRID_ORIGIN.y
+    } else {
+        // the main grid's top row is furthest from the hotbar
+        let row = (SLOTS - 1 - slot) / HOTBAR_SLOTS;
+        GRID_ORIGIN.y + SLOT_SIZE + HOTBAR_GAP + row as f32 * (SLOT_SIZE + SLOT_GAP)
+    };
+    vec2(GRID_ORIGIN.x + column * (SLOT_SIZE + SLOT_GAP), y)
+}
+
+/// The slot under a point on the ui, if any.
+pub fn slot_at(point: Vec2) -> Option<usize> {
+    (0..SLOTS).find(|slot| {
+        let min = slot_position(*slot);
+        point.cmpge(min).all() && point.cmplt(min + SLOT_SIZE).all()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::vec2;
+
+    use crate::world::BlockType;
+
+    use super::{
+        slot_at, slot_position, Inventory, ItemStack, HOTBAR_SLOTS, MAX_STACK, SLOTS, SLOT_SIZE,
+    };
+
+    fn stack(block_type: BlockType, count: u32) -> Option<ItemStack> {
+        Some(ItemStack::new(block_type, count))
+    }
+
+    #[test]
+    fn stacks_move_split_and_merge() {
+        let mut inventory = Inventory::default();
+        inventory.add(ItemStack::new(BlockType::Dirt, 5));
+        inventory.add(ItemStack::new(BlockType::Sand, 60));
+
+        // half of five, rounded up, into an empty slot
+        inventory.press(0, true);
+        assert_eq!(inventory.held(), stack(BlockType::Dirt, 3));
+        inventory.release(Some(20));
+        assert_eq!(inventory.get(0), stack(BlockType::Dirt, 2));
+        assert_eq!(inventory.get(20), stack(BlockType::Dirt, 3));
+
+        // merging back
+        inventory.press(20, false);
+        inventory.release(Some(0));
+        assert_eq!(inventory.get(0), stack(BlockType::Dirt, 5));
+        assert_eq!(inventory.get(20), None);
+
+        // a split can't swap, so it goes back
+        inventory.press(1, true);
+        inventory.release(Some(0));
+        assert_eq!(inventory.get(1), stack(BlockType::Sand, 60));
+        // a whole stack can
+        inventory.press(1, false);
+        inventory.release(Some(0));
+        assert_eq!(inventory.get(0), stack(BlockType::Sand, 60));
+        assert_eq!(inventory.get(1), stack(BlockType::Dirt, 5));
+
+        // adding tops up the stack before taking a new slot
+        inventory.add(ItemStack::new(BlockType::Sand, 10));
+        assert_eq!(inventory.get(0), stack(BlockType::Sand, MAX_STACK));
+        assert_eq!(inventory.get(2), stack(BlockType::Sand, 6));
+        inventory.press(0, true);
+        inventory.release(Some(2));
+        assert_eq!(inventory.get(2), stack(BlockType::Sand, 38));
+        // only what fits merges, the rest goes back
+        inventory.press(2, false);
+        inventory.release(Some(0));
+        assert_eq!(inventory.get(0), stack(BlockType::Sand, MAX_STACK));
+        assert_eq!(inventory.get(2), stack(BlockType::Sand, 6));
+
+        // dropped outside the grid
+        inventory.press(1, false);
+        inventory.release(None);
+        assert_eq!(inventory.get(1), stack(BlockType::Dirt, 5));
+        assert_eq!(inventory.held(), None);
+    }
+
+    #[test]
+    fn quick_transfer_crosses_the_hotbar() {
+        let mut inventory = Inventory::starting_kit();
+        assert_eq!(inventory.get(0), stack(BlockType::Dirt, MAX_STACK));
+        inventory.quick_transfer(0);
+        assert_eq!(inventory.get(0), None);
+        // past whatever of the kit didn't fit in the hotbar
+        let first_free = BlockType::ALL.len().max(HOTBAR_SLOTS);
+        assert_eq!(inventory.get(first_free), stack(BlockType::Dirt, MAX_STACK));
+        inventory.quick_transfer(first_free);
+        assert_eq!(inventory.get(0), stack(BlockType::Dirt, MAX_STACK));
+
+        inventory.press(1, true);
+        assert_eq!(inventory.take_all().len(), BlockType::ALL.len());
+        assert!((0..SLOTS).all(|slot| inventory.get(slot).is_none()));
+        assert_eq!(inventory.held(), None);
+    }
+
+    #[test]
+    fn slots_are_found_under_the_cursor() {
+        for slot in 0..SLOTS {
+            let centre = slot_position(slot) + SLOT_SIZE / 2.0;
+            assert_eq!(slot_at(centre), Some(slot));
+        }
+        // the hotbar is below the main grid
+        assert!(slot_position(0).y < slot_position(SLOTS - 1).y);
+        assert!(slot_position(SLOTS - 1).y < slot_position(HOTBAR_SLOTS).y);
+        assert_eq!(slot_at(vec2(0.0, 0.0)), None);
+    }
+}
@@ -0,0 +1,193 @@
+use std::collections::VecDeque;
+
+use fxhash::{FxHashMap, FxHashSet};
+use glam::IVec3;
+
+use crate::chunk::{Face, MAX_LIGHT};
+
+/// Light given off by blocks, spreading from each source through transparent cells and dimming
+/// by one level per block. Only lit cells are stored, so an unlit world costs nothing.
+///
+/// Every change takes a `transparent` function saying which cells light can pass through, and
+/// returns the cells whose light changed so whatever was built from them can be rebuilt.
+#[derive(Default)]
+pub struct LightMap {
+    levels: FxHashMap<IVec3, u8>,
+}
+
+impl LightMap {
+    pub fn get(&self, position: IVec3) -> u8 {
+        self.levels.get(&position).copied().unwrap_or(0)
+    }
+
+    fn set(&mut self, position: IVec3, level: u8) {
+        if level == 0 {
+            self.levels.remove(&position);
+        } else {
+            self.levels.insert(position, level);
+        }
+    }
+
+    /// Lights `position` to `level` and spreads it outwards.
+    pub fn add_source(
+        &mut self,
+        position: IVec3,
+        level: u8,
+        transparent: impl Fn(IVec3) -> bool,
+    ) -> FxHashSet<IVec3> {
+        self.add_sources(std::iter::once(position), level, transparent)
+    }
+
+    /// Lights every one of `positions` to `level` and spreads it outwards, all at once.
+    pub fn add_sources(
+        &mut self,
+        positions: impl Iterator<Item = IVec3>,
+        level: u8,
+        transparent: impl Fn(IVec3) -> bool,
+    ) -> FxHashSet<IVec3> {
+        debug_assert!(level <= MAX_LIGHT);
+        let mut changed = FxHashSet::default();
+        let mut queue = VecDeque::new();
+        for position in positions {
+            if level > self.get(position) {
+                self.set(position, level);
+                changed.insert(position);
+                queue.push_back(position);
+            }
+        }
+        self.spread(queue, &transparent, &mut changed);
+        changed
+    }
+
+    /// Darkens `position` and everything lit through it, then lets any other light flow back
+    /// in. For removed sources and for cells that just became opaque.
+    pub fn remove(
+        &mut self,
+        position: IVec3,
+        transparent: impl Fn(IVec3) -> bool,
+    ) -> FxHashSet<IVec3> {
+        let mut changed = FxHashSet::default();
+        let level = self.get(position);
+        if level == 0 {
+            return changed;
+        }
+        self.set(position, 0);
+        changed.insert(position);
+
+        // anything dimmer than its neighbour was lit through it and goes dark too, anything
+        // at least as bright has its own source and relights the darkened area afterwards
+        let mut darken = VecDeque::from([(position, level)]);
+        let mut relight = VecDeque::new();
+        while let Some((position, level)) = darken.pop_front() {
+            for face in Face::ALL {
+                let neighbour = position + face.normal();
+                let neighbour_level = self.get(neighbour);
+                if neighbour_level == 0 {
+                    continue;
+                }
+                if neighbour_level < level {
+                    self.set(neighbour, 0);
+                    changed.insert(neighbour);
+                    darken.push_back((neighbour, neighbour_level));
+                } else {
+                    relight.push_back(neighbour);
+                }
+            }
+        }
+        self.spread(relight, &transparent, &mut changed);
+        changed
+    }
+
+    /// Lets the light around `position` into it, for cells that just became transparent.
+    pub fn open(
+        &mut self,
+        position: IVec3,
+        transparent: impl Fn(IVec3) -> bool,
+    ) -> FxHashSet<IVec3> {
+        let mut changed = FxHashSet::default();
+        let lit = Face::ALL
+            .iter()
+            .map(|face| position + face.normal())
+            .filter(|neighbour| self.get(*neighbour) > 0)
+            .collect();
+        self.spread(lit, &transparent, &mut changed);
+        changed
+    }
+
+    /// Floods light outwards from the queued cells.
+    fn spread(
+        &mut self,
+        mut queue: VecDeque<IVec3>,
+        transparent: &impl Fn(IVec3) -> bool,
+        changed: &mut FxHashSet<IVec3>,
+    ) {
+        while let Some(position) = queue.pop_front() {
+            let level = self.get(position);
+            for face in Face::ALL {
+                let neighbour = position + face.normal();
+                if level > 1 && self.get(neighbour) < level - 1 && transparent(neighbour) {
+                    self.set(neighbour, level - 1);
+                    changed.insert(neighbour);
+                    queue.push_back(neighbour);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{ivec3, IVec3};
+
+    use super::LightMap;
+
+    #[test]
+    fn light_dims_with_distance() {
+        let mut light = LightMap::default();
+        let changed = light.add_source(IVec3::ZERO, 3, |_| true);
+        // the source, its 6 neighbours and the 18 cells two steps away
+        assert_eq!(changed.len(), 25);
+        assert_eq!(light.get(IVec3::ZERO), 3);
+        assert_eq!(light.get(ivec3(0, -1, 0)), 2);
+        assert_eq!(light.get(ivec3(1, 1, 0)), 1);
+        assert_eq!(light.get(ivec3(3, 0, 0)), 0);
+    }
+
+    #[test]
+    fn opaque_cells_block_light() {
+        let mut light = LightMap::default();
+        // a wall at x = 1 with a gap at z = 2
+        let transparent = |p: IVec3| p.x != 1 || p.z == 2;
+        light.add_source(IVec3::ZERO, 8, transparent);
+        assert_eq!(light.get(ivec3(1, 0, 0)), 0);
+        // around through the gap rather than straight through the wall
+        assert_eq!(light.get(ivec3(2, 0, 0)), 2);
+
+        // filling the gap darkens everything behind it
+        let transparent = |p: IVec3| p.x != 1;
+        light.remove(ivec3(1, 0, 2), transparent);
+        assert_eq!(light.get(ivec3(2, 0, 0)), 0);
+        assert_eq!(light.get(ivec3(0, 0, 2)), 6);
+
+        // and opening it back up lets the light through again
+        let transparent = |p: IVec3| p.x != 1 || p.z == 2;
+        light.open(ivec3(1, 0, 2), transparent);
+        assert_eq!(light.get(ivec3(2, 0, 0)), 2);
+    }
+
+    #[test]
+    fn removing_a_source_keeps_other_light() {
+        let mut light = LightMap::default();
+        light.add_source(IVec3::ZERO, 5, |_| true);
+        light.add_source(ivec3(4, 0, 0), 5, |_| true);
+        assert_eq!(light.get(ivec3(2, 0, 0)), 3);
+
+        light.remove(IVec3::ZERO, |_| true);
+        assert_eq!(light.get(IVec3::ZERO), 1);
+        assert_eq!(light.get(ivec3(2, 0, 0)), 3);
+        assert_eq!(light.get(ivec3(-1, 0, 0)), 0);
+
+        light.remove(ivec3(4, 0, 0), |_| true);
+        assert!(light.levels.is_empty());
+    }
+}
@@ -1,24 +1,92 @@
-use std::{collections::HashMap, time::Instant};
+use std::time::{Duration, Instant};
 
+use ambience::{Ambience, Soundscape, Surroundings};
+use benchmark::Benchmark;
+use border::WorldBorder;
 use camera::Camera;
-use glam::{vec2, Vec3};
+use chunk::{Face, CHUNK_SIZE};
+use console::{BorderCommand, Command, Console, StructureCommand};
+use death::DeathScreen;
+use dimension::{Atmosphere, Dimension};
+use entity::{Entities, Entity, EntityKind, Hit};
+use fxhash::FxHashMap;
+use generator::WorldGenerator;
+use glam::{vec3, IVec3, Vec2, Vec3};
+use governor::{Governor, Levels, WindowActivity};
+use history::History;
+use hud::Hud;
 use image::DynamicImage;
+use input::InputState;
+use interpolation::{Interpolated, Transform};
+use inventory::{Inventory, InventoryScreen, ItemStack};
+use particle::Particles;
+use player::{Controls, Player, PlayerData};
+use post::Grading;
 use renderer::Renderer;
+use save::{Metadata, WorldSave};
+use settings::Settings;
+use sign::{SignEditor, SignScreen};
+use sky::DayCycle;
+use sound::Sounds;
+use spawning::Spawner;
+use structure::{Rotation, Structure};
 
 use text::Font;
+use texture::Animation;
+use util::{block_position, Frustum};
 use winit::{
-    event::{DeviceEvent, ElementState, Event, VirtualKeyCode, WindowEvent},
+    event::{
+        DeviceEvent, ElementState, Event, ModifiersState, MouseButton, VirtualKeyCode, WindowEvent,
+    },
     event_loop::EventLoop,
     window::WindowBuilder,
 };
-use world::World;
+use world::{Block, BlockType, World, WORLD_DEPTH, WORLD_TOP};
 
+mod ambience;
+mod anvil;
+mod bed;
+mod benchmark;
+mod biome;
+mod border;
 mod camera;
+mod chunk;
+mod console;
+mod death;
+mod dimension;
+mod entity;
+mod erosion;
+mod generator;
+mod governor;
+mod gpu;
+mod heightmap;
+mod history;
+mod hud;
+mod input;
 mod instance;
-mod mesh_instancer;
+mod interpolation;
+mod inventory;
+mod light;
+mod nbt;
+mod particle;
+mod pipeline;
+mod player;
+mod post;
 mod renderer;
+mod save;
+mod schematic;
+mod settings;
+mod sign;
+mod sky;
+mod sound;
+mod spawning;
+mod structure;
 mod text;
 mod texture;
+mod tick;
+mod torch;
+mod util;
+mod village;
 mod world;
 
 fn load_tex(name: &str) -> DynamicImage {
@@ -31,15 +99,35 @@ fn load_tex(name: &str) -> DynamicImage {
     .unwrap_or_else(|_| panic!("Couldn't load {path} into an image."))
 }
 
+/// How the texture called `name` animates, from the file beside it if it has one.
+fn load_animation(name: &str) -> Option<Animation> {
+    let path = format!("{}.anim", name);
+    let contents = std::fs::read_to_string(&path).ok()?;
+    contents
+        .parse()
+        .map_err(|err| eprintln!("{path}: {err}"))
+        .ok()
+}
+
+/// Puts the saved parts of a dimension into its freshly generated world.
+fn load_world(save: &mut WorldSave, dimension: Dimension, world: &mut World) {
+    match save.load(dimension, world) {
+        Ok(0) => {}
+        Ok(chunks) => println!("loaded {chunks} saved chunks of the {}", dimension.name()),
+        Err(e) => eprintln!("couldn't load the {}: {e}", dimension.name()),
+    }
+}
+
 fn main() {
     env_logger::init();
+    let settings = Settings::load("settings.cfg");
     let ev = EventLoop::new();
     let window = WindowBuilder::new()
         .with_title("normalcraft")
         .build(&ev)
         .unwrap();
 
-    let aspect_ratio = (window.inner_size().width as f32 / window.inner_size().height as f32);
+    let aspect_ratio = window.inner_size().width as f32 / window.inner_size().height as f32;
     println!(
         "{}, {}, {aspect_ratio}",
         window.inner_size().width,
@@ -50,14 +138,108 @@ fn main() {
 
     let mut input_state = InputState::new();
 
-    let mut state = State::new();
+    let benchmark_duration = benchmark::from_args(std::env::args());
+    let mut save = save::from_args(std::env::args()).map(|name| WorldSave::new(&name));
+    let generator_name =
+        generator::from_args(std::env::args()).unwrap_or_else(|| settings.world_generator.clone());
+    let metadata = if benchmark_duration.is_some() {
+        Metadata::current(benchmark::SEED, &generator_name)
+    } else {
+        let current = Metadata::current(rand::random(), &generator_name);
+        match save.as_mut().map(|save| save.open(current.clone())) {
+            Some(Ok(metadata)) => {
+                for difference in metadata.differences(&current) {
+                    eprintln!("warning: {difference}");
+                }
+                metadata
+            }
+            Some(Err(e)) => {
+                eprintln!("couldn't open the world metadata: {e}");
+                current
+            }
+            None => current,
+        }
+    };
+    let seed = metadata.seed;
+    println!("world seed {seed}");
+    let generator = generator::named(&metadata.generator).unwrap_or_else(|err| panic!("{err}"));
+    let generation_start = Instant::now();
+    let mut state = State::new(&camera, seed, generator.as_ref(), &settings, save);
+    let generation = generation_start.elapsed();
 
     let font = Font::new("Roboto/Roboto-Regular.ttf", 120);
 
-    let mut renderer = Renderer::new(&window, &camera);
+    let backend = gpu::backend_from_args(std::env::args()).unwrap_or(settings.graphics_backend);
+    let adapter =
+        gpu::adapter_from_args(std::env::args()).unwrap_or_else(|| settings.adapter.clone());
+    let mut renderer = Renderer::new(&window, &camera, backend, &adapter);
+    renderer.set_ui_resize_strategy(settings.ui_resize);
+    renderer.set_ui_scale(settings.ui_scale);
+    renderer.set_color_filter(settings.color_filter);
+    renderer.set_bloom(settings.bloom_threshold, settings.bloom_intensity);
+    renderer.set_grading(Grading {
+        brightness: settings.brightness,
+        contrast: settings.contrast,
+        gamma: settings.gamma,
+    });
+    if let Some(path) = &settings.color_lut {
+        let lut = image::open(path).map_err(|err| format!("couldn't open {path}: {err}"));
+        if let Err(err) = lut.and_then(|lut| renderer.set_color_lut(Some(&lut))) {
+            eprintln!("color lookup table: {err}");
+        }
+    }
     renderer.init_text_pipeline();
 
+    let target_fps = 60.0;
+    // benchmark runs measure everything at full quality
+    let mut governor = Governor::new(
+        1.0 / target_fps,
+        settings.adaptive_quality && benchmark_duration.is_none(),
+    );
+    apply_levels(
+        &mut renderer,
+        governor.levels(),
+        state.dimension.atmosphere(),
+    );
+
+    // the top centre of the world, in world space
+    let world_centre = vec3(
+        state.world.width as f32 / 2.0,
+        WORLD_TOP as f32,
+        state.world.height as f32 / 2.0,
+    );
+    let mut benchmark = benchmark_duration.map(|duration| {
+        Benchmark::new(duration, world_centre).with_generation(
+            &generator_name,
+            generation,
+            generator.erosion_time(),
+        )
+    });
+    if benchmark.is_some() {
+        // frames shouldn't wait on the display
+        renderer.set_vsync(false);
+    }
+
     let font_handle = renderer.register_font(font);
+    let overlay_text =
+        renderer.create_text(&governor.overlay_text(), font_handle, 10.0, 580.0, 0.15);
+    let mut overlay_updated = Instant::now();
+    let mut show_overlay = false;
+
+    let mut console = Console::default();
+    let console_text = renderer.create_text("/", font_handle, 10.0, 20.0, 0.2);
+    // the last command's reply stays up for a while after the console closes
+    let mut console_reply: Option<Instant> = None;
+
+    let mut inventory_screen = InventoryScreen::new(&mut renderer, font_handle);
+    let death_screen = DeathScreen::new(&mut renderer, font_handle);
+    let mut hud = Hud::new(&mut renderer, font_handle);
+    let mut sign_screen = SignScreen::new(&mut renderer, font_handle);
+    // the sign being written on, while its text is typed in
+    let mut sign_editor: Option<SignEditor> = None;
+    // the mouse position on the ui
+    let mut cursor = Vec2::ZERO;
+    let mut modifiers = ModifiersState::empty();
 
     // let text_mesh = renderer.create_text_mesh("MNOPQRSTUVWXYZ", font_handle, 0.0, 50.0, 0.5);
     // renderer.queue_draw_text_mesh(text_mesh);
@@ -68,57 +250,238 @@ fn main() {
     // let text_mesh = renderer.create_text_mesh("abcdefghijkl", font_handle, 0.0, 350.0, 0.5);
     // renderer.queue_draw_text_mesh(text_mesh);
 
-    let textures = vec![
-        ("dirt".into(), load_tex("dirt")),
-        ("stone".into(), load_tex("stone")),
-        ("cobble".into(), load_tex("cobble")),
-        ("water".into(), load_tex("water")),
-        ("sand".into(), load_tex("sand")),
-    ];
+    let textures = [
+        "dirt",
+        "stone",
+        "cobble",
+        "water",
+        "sand",
+        "torch",
+        "portal",
+        "slot",
+        "shade",
+        "bed",
+        "orb",
+        "bar",
+        "sign",
+        "glass",
+        "placeholder",
+    ]
+    .into_iter()
+    .map(|name| (name.into(), load_tex(name), load_animation(name)))
+    .collect();
 
     state.world.setup_textures(&mut renderer, textures);
 
+    let start = Instant::now();
     let mut now = Instant::now();
-    let target_fps = 60.0;
+    let mut accumulator = 0.0;
+    let mut activity = WindowActivity::default();
 
     #[allow(clippy::collapsible_match)]
     ev.run(move |event, _, cf| match event {
         Event::WindowEvent { event, .. } => match event {
-            WindowEvent::CloseRequested => cf.set_exit(),
-            WindowEvent::Resized(size) => println!("Resized {:?}", size),
+            WindowEvent::CloseRequested => {
+                state.save_player_data();
+                state.save_world();
+                cf.set_exit();
+            }
+            WindowEvent::Resized(size)
+            | WindowEvent::ScaleFactorChanged {
+                new_inner_size: &mut size,
+                ..
+            } => {
+                // minimising shrinks the window to nothing
+                activity.hidden = size.width == 0 || size.height == 0;
+                if !activity.hidden {
+                    camera.resize(size, settings.camera_resize);
+                    renderer.resize(size);
+                }
+                state.set_muted(settings.mute_in_background && activity.throttled());
+            }
+            WindowEvent::Focused(focused) => {
+                activity.focused = focused;
+                state.set_muted(settings.mute_in_background && activity.throttled());
+            }
+            WindowEvent::Occluded(occluded) => {
+                activity.hidden = occluded;
+                state.set_muted(settings.mute_in_background && activity.throttled());
+            }
+            WindowEvent::ReceivedCharacter(char) if sign_editor.is_some() => {
+                let editor = sign_editor.as_mut().unwrap();
+                if let Some(lines) = editor.input_char(char) {
+                    state.world.write_sign(editor.position, lines);
+                    sign_editor = None;
+                }
+            }
+            WindowEvent::ReceivedCharacter(char) => {
+                let was_open = console.is_open();
+                if let Some(line) = console.input_char(char) {
+                    let reply = match line.parse() {
+                        Ok(command) => state.run_command(command),
+                        Err(err) => err,
+                    };
+                    println!("{reply}");
+                    renderer.set_text(console_text, &reply, font_handle, 10.0, 20.0, 0.2);
+                    console_reply = Some(Instant::now());
+                    if let Some(sign) = state.placed_sign.take() {
+                        sign_editor = Some(SignEditor::new(sign));
+                    }
+                } else if let Some(prompt) = console.prompt() {
+                    renderer.set_text(console_text, &prompt, font_handle, 10.0, 20.0, 0.2);
+                    if !was_open {
+                        console_reply = None;
+                    }
+                }
+            }
             WindowEvent::KeyboardInput {
                 device_id: _,
                 input,
                 is_synthetic: _,
             } => {
+                // typing into the console or on a sign, using the inventory or being dead doesn't
+                // move the player, but letting go of a key still registers so nothing is left
+                // held down
+                let pressed = input.state == ElementState::Pressed
+                    && !console.is_open()
+                    && sign_editor.is_none()
+                    && !inventory_screen.open
+                    && !state.player.is_dead();
                 match input.virtual_keycode.unwrap() {
-                    VirtualKeyCode::W => input_state
-                        .kbd_map
-                        .insert("w".into(), input.state == ElementState::Pressed),
-                    VirtualKeyCode::S => input_state
-                        .kbd_map
-                        .insert("s".into(), input.state == ElementState::Pressed),
-                    VirtualKeyCode::A => input_state
-                        .kbd_map
-                        .insert("a".into(), input.state == ElementState::Pressed),
-                    VirtualKeyCode::D => input_state
-                        .kbd_map
-                        .insert("d".into(), input.state == ElementState::Pressed),
-                    VirtualKeyCode::Q => input_state
-                        .kbd_map
-                        .insert("q".into(), input.state == ElementState::Pressed),
-                    VirtualKeyCode::E => input_state
-                        .kbd_map
-                        .insert("e".into(), input.state == ElementState::Pressed),
-                    VirtualKeyCode::LShift => input_state
-                        .kbd_map
-                        .insert("shift".into(), input.state == ElementState::Pressed),
+                    VirtualKeyCode::W => input_state.kbd_map.insert("w".into(), pressed),
+                    VirtualKeyCode::S => input_state.kbd_map.insert("s".into(), pressed),
+                    VirtualKeyCode::A => input_state.kbd_map.insert("a".into(), pressed),
+                    VirtualKeyCode::D => input_state.kbd_map.insert("d".into(), pressed),
+                    VirtualKeyCode::Q => input_state.kbd_map.insert("q".into(), pressed),
+                    VirtualKeyCode::E => input_state.kbd_map.insert("e".into(), pressed),
+                    VirtualKeyCode::Space => {
+                        if input_state.press_jump(pressed, Instant::now()) {
+                            state.player.flying = !state.player.flying;
+                        }
+                        None
+                    }
+                    VirtualKeyCode::LControl => input_state.kbd_map.insert("ctrl".into(), pressed),
+                    VirtualKeyCode::LShift => {
+                        input_state.press_sprint(pressed, settings.sprint);
+                        None
+                    }
+                    key @ (VirtualKeyCode::Up | VirtualKeyCode::Down)
+                        if input.state == ElementState::Pressed && console.is_open() =>
+                    {
+                        if key == VirtualKeyCode::Up {
+                            console.history_back();
+                        } else {
+                            console.history_forward();
+                        }
+                        if let Some(prompt) = console.prompt() {
+                            renderer.set_text(console_text, &prompt, font_handle, 10.0, 20.0, 0.2);
+                        }
+                        None
+                    }
+                    VirtualKeyCode::F3 => {
+                        if pressed {
+                            show_overlay = !show_overlay;
+                        }
+                        None
+                    }
+                    key @ (VirtualKeyCode::Key1
+                    | VirtualKeyCode::Key2
+                    | VirtualKeyCode::Key3
+                    | VirtualKeyCode::Key4
+                    | VirtualKeyCode::Key5
+                    | VirtualKeyCode::Key6
+                    | VirtualKeyCode::Key7
+                    | VirtualKeyCode::Key8
+                    | VirtualKeyCode::Key9) => {
+                        if pressed {
+                            state
+                                .inventory
+                                .select(key as usize - VirtualKeyCode::Key1 as usize);
+                        }
+                        None
+                    }
+                    key @ (VirtualKeyCode::Z | VirtualKeyCode::Y)
+                        if pressed && modifiers.ctrl() =>
+                    {
+                        let reply = if key == VirtualKeyCode::Z {
+                            state.undo()
+                        } else {
+                            state.redo()
+                        };
+                        println!("{reply}");
+                        renderer.set_text(console_text, &reply, font_handle, 10.0, 20.0, 0.2);
+                        console_reply = Some(Instant::now());
+                        None
+                    }
+                    VirtualKeyCode::I => {
+                        if input.state == ElementState::Pressed
+                            && !console.is_open()
+                            && sign_editor.is_none()
+                            && !state.player.is_dead()
+                        {
+                            inventory_screen.open = !inventory_screen.open;
+                            // a stack still on the mouse goes back where it came from
+                            state.inventory.release(None);
+                        }
+                        None
+                    }
                     _ => {
                         // println!("{:?}", input);
                         None
                     }
                 };
             }
+            WindowEvent::ModifiersChanged(changed) => modifiers = changed,
+            WindowEvent::CursorMoved { position, .. } => {
+                cursor = renderer.screen_to_ui(position.x as f32, position.y as f32);
+            }
+            WindowEvent::MouseInput {
+                state: ElementState::Pressed,
+                button: MouseButton::Left,
+                ..
+            } if state.player.is_dead() => {
+                if DeathScreen::on_button(cursor) {
+                    state.respawn();
+                }
+            }
+            WindowEvent::MouseInput {
+                state: ElementState::Pressed,
+                button: MouseButton::Left,
+                ..
+            } if !inventory_screen.open => state.attack(&camera),
+            WindowEvent::MouseInput {
+                state: ElementState::Pressed,
+                button: MouseButton::Right,
+                ..
+            } if !inventory_screen.open => {
+                if let Some(reply) = state.interact(&camera) {
+                    println!("{reply}");
+                    renderer.set_text(console_text, &reply, font_handle, 10.0, 20.0, 0.2);
+                    console_reply = Some(Instant::now());
+                }
+            }
+            WindowEvent::MouseInput {
+                state: button_state,
+                button,
+                ..
+            } if inventory_screen.open => {
+                let slot = inventory::slot_at(cursor);
+                match (button_state, button, slot) {
+                    (ElementState::Pressed, MouseButton::Left, Some(slot)) if modifiers.shift() => {
+                        state.inventory.quick_transfer(slot)
+                    }
+                    (ElementState::Pressed, MouseButton::Left, Some(slot)) => {
+                        state.inventory.press(slot, false)
+                    }
+                    (ElementState::Pressed, MouseButton::Right, Some(slot)) => {
+                        state.inventory.press(slot, true)
+                    }
+                    (ElementState::Released, MouseButton::Left | MouseButton::Right, _) => {
+                        state.inventory.release(slot)
+                    }
+                    _ => (),
+                }
+            }
             _ => (),
         },
         #[allow(clippy::single_match)]
@@ -126,51 +489,194 @@ fn main() {
             device_id: _,
             event,
         } => match event {
-            DeviceEvent::MouseMotion { delta } => {
-                // println!("mousemove");
-                camera.look_add(vec2(-delta.0 as f32 / 100.0, -delta.1 as f32 / 100.0));
-                renderer.update_camera(&camera);
+            // the mouse points at the inventory or death screen rather than turning the camera
+            DeviceEvent::MouseMotion { delta }
+                if !inventory_screen.open && !state.player.is_dead() =>
+            {
+                input_state.add_mouse_motion(delta)
             }
             _ => (),
         },
         Event::MainEventsCleared => {
-            if now.elapsed().as_secs_f32() >= 1.0 / target_fps {
+            let frame_time = activity.frame_time(1.0 / target_fps, 1.0 / settings.background_fps);
+            let throttled = activity.throttled() && benchmark.is_none();
+            if !throttled {
+                cf.set_poll();
+            }
+            if benchmark.is_some() || now.elapsed().as_secs_f32() >= frame_time {
+                let interval = now.elapsed().as_secs_f32();
+                // run as many fixed ticks as the time since the last frame covers
+                // (capped so a long stall doesn't leave us simulating for seconds to catch up)
+                accumulator = (accumulator + interval).min(0.25);
                 now = Instant::now();
-                state.update(&input_state, &mut camera);
-                state.world.draw(&mut renderer);
+                if throttled {
+                    // sleep until the next frame is due rather than spinning
+                    cf.set_wait_until(now + Duration::from_secs_f32(frame_time));
+                }
+                while accumulator >= TICK {
+                    state.update(&mut input_state, &settings, &mut camera);
+                    accumulator -= TICK;
+                }
+                if !activity.draws() {
+                    return;
+                }
+                // draw everything that moves on the tick part of the way to where it is now
+                let alpha = accumulator / TICK;
+                renderer.set_alpha(alpha);
+                camera.set_position(state.eye.at(alpha).position);
+                if std::mem::take(&mut state.travelled) {
+                    // the new world's chunks replace the old ones as they're meshed
+                    renderer.clear_chunks();
+                    apply_levels(
+                        &mut renderer,
+                        governor.levels(),
+                        state.dimension.atmosphere(),
+                    );
+                }
+                if let Some(benchmark) = &benchmark {
+                    benchmark.place_camera(&mut camera);
+                }
+                let meshing = state.world.mesh_chunks(
+                    &mut renderer,
+                    governor.levels().meshing_budget,
+                    camera.position(),
+                    &Frustum::from_matrix(camera.compute()),
+                );
+                state
+                    .entities
+                    .draw(&mut renderer, &state.world, font_handle);
+                state.world.draw_torches(&mut renderer);
+                state.world.draw_beds(&mut renderer);
+                state.world.draw_signs(&mut renderer, font_handle);
+                state.particles.set_limit(governor.levels().max_particles);
+                state.particles.draw(&mut renderer, &state.world);
+                if state.player.is_dead() {
+                    inventory_screen.open = false;
+                    sign_editor = None;
+                    death_screen.draw(&mut renderer, &state.world);
+                } else {
+                    hud.draw(&mut renderer, &state.world, state.player_data.experience);
+                    if let Some(editor) = &sign_editor {
+                        sign_screen.draw(&mut renderer, editor, &state.world);
+                    } else if inventory_screen.open {
+                        inventory_screen.draw(
+                            &mut renderer,
+                            &state.inventory,
+                            &state.world,
+                            cursor,
+                        );
+                    }
+                }
+                if show_overlay {
+                    // rebuilding the text every frame would be wasteful and unreadable
+                    if overlay_updated.elapsed().as_secs_f32() > 0.25 {
+                        let text = format!(
+                            "{}  {}  {}  health {:.0} hunger {:.0}",
+                            governor.overlay_text(),
+                            state.soundscape.describe(),
+                            state.sounds.describe(),
+                            state.player.health,
+                            state.player.hunger
+                        );
+                        renderer.set_text(overlay_text, &text, font_handle, 10.0, 580.0, 0.15);
+                        overlay_updated = Instant::now();
+                    }
+                    renderer.queue_draw_text(overlay_text);
+                }
+                if console.is_open()
+                    || console_reply.is_some_and(|shown| shown.elapsed().as_secs_f32() < 4.0)
+                {
+                    renderer.queue_draw_text(console_text);
+                }
+                let border = state.world.border;
+                renderer.set_world_border(border.min(), border.max());
+                let atmosphere = state.dimension.atmosphere();
+                renderer.set_draw_sky(atmosphere.day_cycle);
+                if atmosphere.day_cycle {
+                    // the renderer wants the way sunlight travels, not where it comes from
+                    renderer.set_sun_direction(-state.day.sun_direction());
+                    renderer.set_sky_light(state.day.sky_light());
+                    renderer.set_fog_color(state.day.sky_colour());
+                } else {
+                    renderer.set_sky_light(atmosphere.sky_light);
+                    renderer.set_fog_color(atmosphere.fog_colour);
+                }
                 renderer.update_camera(&camera);
+                renderer.set_time(start.elapsed().as_secs_f32());
                 renderer.draw();
+
+                if let Some(benchmark) = &mut benchmark {
+                    if benchmark.record(interval, renderer.draw_calls(), &meshing) {
+                        let report = benchmark.report();
+                        match report.write("benchmark.json") {
+                            Ok(()) => println!("wrote benchmark.json"),
+                            Err(e) => eprintln!("couldn't write benchmark.json: {e}"),
+                        }
+                        println!("{}", report.summary());
+                        cf.set_exit();
+                    }
+                }
+
+                // judged on the frame's own work, not the time spent waiting for the next one,
+                // though changes are spaced out in real time
+                if governor.update(now.elapsed().as_secs_f32(), interval) {
+                    apply_levels(
+                        &mut renderer,
+                        governor.levels(),
+                        state.dimension.atmosphere(),
+                    );
+                }
             }
         }
         _ => (),
     });
 }
 
-/// Creates a Hashmap<String, bool> with value false, accepting a key array.
-macro_rules! kbd_map {
-    ($($a:expr),*) => {
-        {
-        let map: HashMap<String, bool> = HashMap::from([
-            $(
-                ($a.into(), false),
-            )*
-        ]);
-        map
-        }
-    }
+/// Applies the governor's render distance, pulling the fog in with it so chunks past the edge
+/// fade out instead of popping. Some dimensions pull it in further.
+fn apply_levels(renderer: &mut Renderer, levels: Levels, atmosphere: Atmosphere) {
+    renderer.set_render_distance(levels.render_distance);
+    let fog_end = (levels.render_distance * CHUNK_SIZE) as f32 * atmosphere.fog_distance;
+    renderer.set_fog(fog_end * 0.6, fog_end);
 }
 
-struct InputState {
-    pub kbd_map: HashMap<String, bool>,
-}
+/// Length of one simulation step in seconds.
+const TICK: f32 = 1.0 / 60.0;
 
-impl InputState {
-    pub fn new() -> Self {
-        Self {
-            kbd_map: kbd_map!("w", "s", "a", "d", "q", "e", "shift"),
-        }
-    }
-}
+/// Ticks the player has to stand in a portal before it takes them.
+const PORTAL_TICKS: u32 = 60;
+
+/// How far from where the player arrives in another dimension to look for a portal to come out
+/// of, before building one.
+const PORTAL_SEARCH: i32 = 16;
+
+/// How far from a portal the player can come out of it, if the blocks right next to it are
+/// taken.
+const ARRIVAL_SEARCH: i32 = 3;
+
+/// How far below the bottom of the world the player can fall before dying.
+const VOID_DEPTH: f32 = 16.0;
+
+/// How fast items scatter from where the player died, in blocks per second.
+const DROP_SPEED: f32 = 2.0;
+
+/// The experience in each orb a killed mob drops.
+const MOB_EXPERIENCE: [u32; 3] = [2, 2, 1];
+
+/// How far away the player can use blocks from.
+const REACH: f32 = 5.0;
+
+const PLAYER_DATA: &str = "player.cfg";
+
+/// Blocks walked between footsteps.
+const STEP_LENGTH: f32 = 1.7;
+
+/// Ticks between saves of the world, when it's being saved.
+const AUTOSAVE_TICKS: u32 = 60 * 60;
+
+/// Ticks between looks at what's around the player for the ambience, which crossfades slowly
+/// enough not to need a fresh look every tick.
+const SURROUNDINGS_TICKS: u32 = 10;
 
 fn bool_move(b: bool) -> f32 {
     if b {
@@ -182,16 +688,99 @@ fn bool_move(b: bool) -> f32 {
 
 struct State {
     world: World,
+    entities: Entities,
+    player: Player,
+    inventory: Inventory,
+    /// Where the player's feet go when they respawn in the overworld, unless they have a bed.
+    spawn: Vec3,
+    player_data: PlayerData,
+    /// Set once the player's inventory has been dropped for their death, until they respawn.
+    dead: bool,
+    day: DayCycle,
+    soundscape: Soundscape,
+    /// Sounds of blocks being broken and walked on.
+    sounds: Sounds,
+    particles: Particles,
+    /// Blocks walked since the last footstep.
+    walked: f32,
+    seed: u64,
+    /// The dimension `world` belongs to.
+    dimension: Dimension,
+    /// The worlds of the other dimensions the player has been to, and what was left in them.
+    away: FxHashMap<Dimension, (World, Entities)>,
+    /// How long the player has been standing in a portal.
+    portal_ticks: u32,
+    /// Set when the player changes dimension, until the renderer has been told.
+    travelled: bool,
+    /// Set when a sign is placed, until it's been opened for writing on.
+    placed_sign: Option<IVec3>,
+    /// Block edits made with commands, to undo and redo.
+    history: History,
+    /// Where the camera goes, following the player's eyes a tick behind.
+    eye: Interpolated,
+    /// Brings mobs out in the dark.
+    spawner: Spawner,
+    /// Where the world is kept between runs, if it is.
+    save: Option<WorldSave>,
+    /// Ticks since the world was last saved.
+    autosave_ticks: u32,
+    /// What was around the player when last looked, and how many ticks ago that was.
+    surroundings: Option<(Surroundings, u32)>,
 }
 
 impl State {
-    pub fn new() -> Self {
+    pub fn new(
+        camera: &Camera,
+        seed: u64,
+        generator: &dyn WorldGenerator,
+        settings: &Settings,
+        mut save: Option<WorldSave>,
+    ) -> Self {
+        let mut world = World::new(128, 128, WORLD_DEPTH, generator, seed);
+        if let Some(size) = settings.world_border {
+            world.border = WorldBorder::new(world.border.centre, size);
+        }
+        if let Some(save) = &mut save {
+            load_world(save, Dimension::Overworld, &mut world);
+        }
+        let player = Player::from_eye_position(camera.position());
         Self {
-            world: World::new(128, 128, 128, 0.0),
+            world,
+            entities: Entities::default(),
+            spawn: player.position,
+            player,
+            player_data: PlayerData::load(PLAYER_DATA),
+            inventory: Inventory::starting_kit(),
+            dead: false,
+            // mid morning
+            day: DayCycle::new(0.1),
+            soundscape: Soundscape::default(),
+            sounds: Sounds::default(),
+            particles: Particles::new(seed),
+            walked: 0.0,
+            seed,
+            dimension: Dimension::Overworld,
+            away: FxHashMap::default(),
+            portal_ticks: 0,
+            travelled: false,
+            placed_sign: None,
+            history: History::new(settings.undo_memory * 1024),
+            eye: Interpolated::new(Transform::at(camera.position())),
+            spawner: Spawner::new(seed),
+            save,
+            autosave_ticks: 0,
+            surroundings: None,
         }
     }
 
-    pub fn update(&mut self, input_state: &InputState, camera: &mut Camera) {
+    pub fn update(
+        &mut self,
+        input_state: &mut InputState,
+        settings: &Settings,
+        camera: &mut Camera,
+    ) {
+        camera.look_add(input_state.take_look(settings));
+
         let mut movement = Vec3::splat(0.0);
         movement.z = bool_move(*input_state.kbd_map.get("w").unwrap())
             - bool_move(*input_state.kbd_map.get("s").unwrap());
@@ -200,16 +789,479 @@ impl State {
         movement.y = bool_move(*input_state.kbd_map.get("q").unwrap())
             - bool_move(*input_state.kbd_map.get("e").unwrap());
 
-        let shift = *input_state.kbd_map.get("shift").unwrap();
-
         movement = movement.normalize_or_zero();
-        let speed = if !shift { 0.05 } else { 0.5 };
-        camera.translate(
-            (movement.x * camera.right()
-                + movement.y * camera.up()
-                + movement.z * camera.look_dir())
-            .normalize_or_zero()
-                * speed,
+        let direction = (movement.x * camera.right()
+            + movement.y * camera.up()
+            + movement.z * camera.look_dir())
+        .normalize_or_zero();
+        if self.entities.riding() {
+            // the ride carries the player along
+            self.entities.drive(direction, TICK, &self.world);
+        } else {
+            let controls = Controls {
+                direction,
+                sprint: input_state.kbd_map["shift"],
+                ascend: input_state.kbd_map["space"],
+                descend: input_state.kbd_map["ctrl"],
+            };
+            let before = self.player.position;
+            self.player.steer(
+                controls,
+                settings.fly_speed,
+                settings.fly_momentum,
+                TICK,
+                |position| self.world.is_collidable(position),
+            );
+            if !self.player.flying && self.player.is_on_ground() {
+                self.walked += ((self.player.position - before) * vec3(1.0, 0.0, 1.0)).length();
+                if self.walked >= STEP_LENGTH {
+                    self.walked -= STEP_LENGTH;
+                    self.step();
+                }
+            }
+        }
+
+        self.day.advance(TICK);
+        self.world.tick();
+        self.entities.update(TICK, &mut self.player);
+        self.spawner.tick(
+            &self.world,
+            &mut self.entities,
+            self.player.position,
+            self.day.sky_light(),
+        );
+        if !self.player.is_dead() {
+            self.player_data.experience.points += self.entities.collect_orbs(&self.player);
+        }
+        self.player.confine(&self.world.border);
+        let bottom = (WORLD_TOP - self.world.depth as i32) as f32;
+        if self.player.position.y < bottom - VOID_DEPTH {
+            self.player.damage(Player::MAX_HEALTH);
+        }
+        if self.player.is_dead() && !self.dead {
+            self.die();
+        }
+
+        let in_portal = [
+            self.player.position + Vec3::Y * 0.5,
+            self.player.eye_position(),
+        ]
+        .into_iter()
+        .any(|p| self.world.block_type(block_position(p)) == Some(BlockType::Portal));
+        self.portal_ticks = if in_portal { self.portal_ticks + 1 } else { 0 };
+        if self.portal_ticks >= PORTAL_TICKS {
+            self.portal_ticks = 0;
+            self.travel();
+        }
+        self.eye.tick(Transform::at(self.player.eye_position()));
+        self.autosave_ticks += 1;
+        if self.autosave_ticks >= AUTOSAVE_TICKS {
+            self.autosave_ticks = 0;
+            self.save_world();
+        }
+
+        let surroundings = match self.surroundings {
+            Some((surroundings, ticks)) if ticks < SURROUNDINGS_TICKS => {
+                self.surroundings = Some((surroundings, ticks + 1));
+                surroundings
+            }
+            _ => {
+                let surroundings = self.world.surroundings(self.player.eye_position());
+                self.surroundings = Some((surroundings, 1));
+                surroundings
+            }
+        };
+        self.soundscape.update(TICK, Ambience::pick(&surroundings));
+        self.sounds.update(TICK);
+        self.particles
+            .update(TICK, |position| self.world.is_solid(position));
+    }
+
+    /// Silences or unsilences every sound.
+    pub fn set_muted(&mut self, muted: bool) {
+        self.soundscape.muted = muted;
+        self.sounds.muted = muted;
+    }
+
+    /// A footstep on whatever the player is standing on, sounding like it and kicking a little
+    /// of it up.
+    fn step(&mut self) {
+        let feet = self.player.position;
+        let ground = block_position(feet - Vec3::Y * 0.25);
+        let Some(block_type) = self.world.block_type(ground) else {
+            return;
+        };
+        self.sounds
+            .play(block_type.sound_group().step_sound(), feet);
+        self.particles
+            .step(feet, self.world.particle_colour(block_type));
+    }
+
+    /// Sounds and debris for a block that's just been broken.
+    fn block_broken(&mut self, position: IVec3, block_type: BlockType) {
+        self.sounds
+            .play(block_type.sound_group().break_sound(), position.as_vec3());
+        self.particles.break_block(
+            position,
+            self.world.particle_colour(block_type),
+            block_type.hardness(),
+        );
+    }
+
+    /// Takes the player to the other dimension, coming out of the portal nearest to where they
+    /// land or a new one built there.
+    fn travel(&mut self) {
+        // the ride stays behind
+        self.entities.dismount();
+        let to = self.dimension.other();
+        let target = self
+            .dimension
+            .translate(self.player.position, to)
+            .round()
+            .as_ivec3();
+        self.enter(to);
+        // beside the portal rather than in it, so it doesn't send them straight back
+        let feet = self
+            .world
+            .find_portal(target, PORTAL_SEARCH)
+            .and_then(|portal| self.world.clear_spot(portal, ARRIVAL_SEARCH))
+            // a new portal has room on either side
+            .unwrap_or_else(|| self.world.build_portal(target) + IVec3::X);
+        self.player.position = feet.as_vec3() - Vec3::Y * 0.5;
+        self.eye.teleport(Transform::at(self.player.eye_position()));
+    }
+
+    /// Swaps in the world of another dimension and the entities in it, leaving the player where
+    /// they are.
+    fn enter(&mut self, to: Dimension) {
+        let (mut world, entities) = match self.away.remove(&to) {
+            Some(away) => away,
+            None => {
+                let mut world = to
+                    .generate(self.seed)
+                    .expect("the overworld exists from the start");
+                if let Some(save) = &mut self.save {
+                    load_world(save, to, &mut world);
+                }
+                (world, Entities::default())
+            }
+        };
+        world.textures = self.world.textures.clone();
+        world.queue_all_chunks();
+        // the edits were made to the world being left
+        self.history.clear();
+
+        let from = std::mem::replace(&mut self.world, world);
+        let left = std::mem::replace(&mut self.entities, entities);
+        self.away.insert(self.dimension, (from, left));
+        self.dimension = to;
+        self.travelled = true;
+    }
+
+    /// Scatters everything the player was carrying around where they died.
+    fn die(&mut self) {
+        self.dead = true;
+        self.entities.dismount();
+        let position = self.player.position + Vec3::Y * 0.5;
+        for (i, stack) in self.inventory.take_all().into_iter().enumerate() {
+            // spread evenly around a circle by the golden angle
+            let angle = i as f32 * 2.4;
+            let velocity = vec3(angle.cos(), 0.0, angle.sin()) * DROP_SPEED;
+            self.entities
+                .spawn(Entity::dropped(stack, position, velocity));
+        }
+    }
+
+    /// Brings the player back to life on their bed, or at the spawn point if it's gone, in the
+    /// overworld.
+    pub fn respawn(&mut self) {
+        if self.dimension != Dimension::Overworld {
+            self.enter(Dimension::Overworld);
+        }
+        let bed = self
+            .player_data
+            .bed
+            .filter(|bed| self.world.block_type(*bed) == Some(BlockType::Bed));
+        if bed.is_none() && self.player_data.bed.take().is_some() {
+            println!("your bed was missing");
+            self.save_player_data();
+        }
+        // on top of the mattress
+        self.player
+            .respawn(bed.map_or(self.spawn, |bed| bed.as_vec3()));
+        self.eye.teleport(Transform::at(self.player.eye_position()));
+        self.dead = false;
+    }
+
+    /// Gets out of whatever the player is riding, or else into the ride the camera is looking
+    /// at, or else uses the block it's looking at. Returns what to tell the player if there's
+    /// anything to.
+    pub fn interact(&mut self, camera: &Camera) -> Option<String> {
+        if self.player.is_dead() {
+            return None;
+        }
+        if let Some(position) = self.entities.dismount() {
+            self.player.position = position;
+            return None;
+        }
+        let (origin, direction) = (camera.position(), camera.look_dir());
+        let reach = self
+            .world
+            .raycast(origin, direction, REACH)
+            .map_or(REACH, |block| block.as_vec3().distance(origin));
+        match self.entities.raycast_rideable(origin, direction, reach) {
+            Some((id, _)) if self.entities.mount(id) => None,
+            _ => self.use_block(camera),
+        }
+    }
+
+    /// Uses the block the camera is looking at, returning what to tell the player if it did
+    /// anything.
+    fn use_block(&mut self, camera: &Camera) -> Option<String> {
+        if self.player.is_dead() {
+            return None;
+        }
+        let position = self
+            .world
+            .raycast(camera.position(), camera.look_dir(), REACH)?;
+        match self.world.block_type(position)? {
+            BlockType::Bed => Some(self.sleep(position)),
+            _ => None,
+        }
+    }
+
+    /// Hits the entity the camera is looking at with whatever is in hand, as long as no block
+    /// is in the way.
+    pub fn attack(&mut self, camera: &Camera) {
+        if self.player.is_dead() {
+            return;
+        }
+        let (origin, direction) = (camera.position(), camera.look_dir());
+        let reach = self
+            .world
+            .raycast(origin, direction, REACH)
+            .map_or(REACH, |block| block.as_vec3().distance(origin));
+        if let Some((id, _)) = self.entities.raycast(origin, direction, reach) {
+            let damage = ItemStack::attack_damage(self.inventory.in_hand());
+            if let Some(Hit::Killed(position)) = self.entities.attack(id, damage, direction) {
+                for (i, experience) in MOB_EXPERIENCE.into_iter().enumerate() {
+                    let angle = i as f32 * 2.4;
+                    let mut orb = Entity::orb(position, experience);
+                    orb.velocity = vec3(angle.cos(), 1.0, angle.sin()) * DROP_SPEED;
+                    self.entities.spawn(orb);
+                }
+            }
+        }
+    }
+
+    /// Sleeps through the night in a bed, which becomes where the player respawns.
+    fn sleep(&mut self, bed: IVec3) -> String {
+        if self.dimension != Dimension::Overworld {
+            return "you can't sleep here".into();
+        }
+        if !self.day.is_night() {
+            return "you can only sleep at night".into();
+        }
+        // sunrise
+        self.day.set_time(0.0);
+        self.player_data.bed = Some(bed);
+        self.save_player_data();
+        "respawn point set".into()
+    }
+
+    pub fn save_player_data(&self) {
+        if let Err(e) = self.player_data.save(PLAYER_DATA) {
+            eprintln!("couldn't write {PLAYER_DATA}: {e}");
+        }
+    }
+
+    /// Saves the edits to every dimension's world, if the world is being saved.
+    pub fn save_world(&mut self) {
+        let Some(save) = &self.save else {
+            return;
+        };
+        let worlds = std::iter::once((self.dimension, &mut self.world)).chain(
+            self.away
+                .iter_mut()
+                .map(|(dimension, (world, _))| (*dimension, world)),
         );
+        for (dimension, world) in worlds {
+            if let Err(e) = save.save(dimension, world) {
+                eprintln!("couldn't save the {}: {e}", dimension.name());
+            }
+        }
+    }
+
+    /// Runs a console command, returning what to tell the player. Whatever it changes in the
+    /// world is kept as one edit to undo.
+    pub fn run_command(&mut self, command: Command) -> String {
+        self.world.start_recording();
+        let reply = self.apply_command(command);
+        let edit = self.world.stop_recording();
+        self.history.record(edit);
+        reply
+    }
+
+    /// Undoes the last edit, returning what to tell the player.
+    pub fn undo(&mut self) -> String {
+        match self.history.undo(&mut self.world) {
+            Some(count) => format!("undid {count} blocks"),
+            None => "nothing to undo".into(),
+        }
+    }
+
+    /// Redoes the last undone edit, returning what to tell the player.
+    pub fn redo(&mut self) -> String {
+        match self.history.redo(&mut self.world) {
+            Some(count) => format!("redid {count} blocks"),
+            None => "nothing to redo".into(),
+        }
+    }
+
+    fn apply_command(&mut self, command: Command) -> String {
+        match command {
+            Command::Border(command) => {
+                let border = self.world.border;
+                let border = match command {
+                    BorderCommand::Show => {
+                        return format!(
+                            "border is {} by {} around {}, {}",
+                            border.size.x, border.size.y, border.centre.x, border.centre.y
+                        )
+                    }
+                    BorderCommand::Size(size) => WorldBorder::new(border.centre, size),
+                    BorderCommand::Centre(centre) => WorldBorder::new(centre, border.size),
+                    BorderCommand::Reset => self.world.default_border(),
+                };
+                self.world.set_border(border);
+                format!(
+                    "border set to {} by {} around {}, {}",
+                    border.size.x, border.size.y, border.centre.x, border.centre.y
+                )
+            }
+            Command::SetBlock(position, Some(BlockType::Torch)) => {
+                // stand it on the block below if there is one, otherwise hang it off a wall
+                let placed = [Face::PosY, Face::PosX, Face::NegX, Face::PosZ, Face::NegZ]
+                    .into_iter()
+                    .any(|face| self.world.place_torch(position - face.normal(), face));
+                if placed {
+                    format!("set {}, {}, {}", position.x, position.y, position.z)
+                } else {
+                    "nothing there to hold a torch".into()
+                }
+            }
+            Command::SetBlock(position, Some(BlockType::Bed)) => {
+                // the head goes whichever way there's room for it
+                let placed = [Face::PosX, Face::NegX, Face::PosZ, Face::NegZ]
+                    .into_iter()
+                    .any(|face| self.world.place_bed(position, face));
+                if placed {
+                    format!("set {}, {}, {}", position.x, position.y, position.z)
+                } else {
+                    "no room for a bed".into()
+                }
+            }
+            Command::SetBlock(position, Some(BlockType::Sign)) => {
+                if self.world.place_sign(position) {
+                    self.placed_sign = Some(position);
+                    format!("set {}, {}, {}", position.x, position.y, position.z)
+                } else {
+                    "nothing there to stand a sign on".into()
+                }
+            }
+            Command::SetBlock(position, block_type) => {
+                let old = self.world.block_type(position);
+                if self.world.set_block(position, block_type.map(Block::new)) {
+                    if let (Some(old), None) = (old, block_type) {
+                        self.block_broken(position, old);
+                    }
+                    format!("set {}, {}, {}", position.x, position.y, position.z)
+                } else {
+                    format!(
+                        "{}, {}, {} is outside the world",
+                        position.x, position.y, position.z
+                    )
+                }
+            }
+            Command::Kill => {
+                self.player.damage(Player::MAX_HEALTH);
+                "you died".into()
+            }
+            Command::Structure(StructureCommand::Save(name, a, b)) => {
+                let path = match structure::path(&name) {
+                    Ok(path) => path,
+                    Err(err) => return err,
+                };
+                let structure = self.world.copy_structure(a, b);
+                let count = structure.blocks(Rotation::None).count();
+                match structure.save(&path) {
+                    Ok(()) => format!("saved {count} blocks to {path}"),
+                    Err(e) => format!("couldn't write {path}: {e}"),
+                }
+            }
+            Command::Structure(StructureCommand::Place(name, origin, rotation)) => {
+                match structure::path(&name).and_then(Structure::load) {
+                    Ok(structure) => {
+                        let placed = self.world.place_structure(&structure, origin, rotation);
+                        format!("placed {placed} blocks of {name}")
+                    }
+                    Err(err) => err,
+                }
+            }
+            Command::Structure(StructureCommand::Export(name, a, b)) => {
+                let path = match structure::file_path(&name, "schem") {
+                    Ok(path) => path,
+                    Err(err) => return err,
+                };
+                let structure = self.world.copy_structure(a, b);
+                let count = structure.blocks(Rotation::None).count();
+                match schematic::save(&structure, &path) {
+                    Ok(()) => format!("exported {count} blocks to {path}"),
+                    Err(err) => err,
+                }
+            }
+            Command::Structure(StructureCommand::Import(name, origin, rotation)) => {
+                match structure::file_path(&name, "schem").and_then(schematic::load) {
+                    Ok(structure) => {
+                        let placed = self.world.place_structure(&structure, origin, rotation);
+                        format!("imported {placed} blocks of {name}")
+                    }
+                    Err(err) => err,
+                }
+            }
+            Command::Summon(kind, position, name) => {
+                // a block to the side of the player, at their middle
+                let position = position.unwrap_or(self.player.position + vec3(1.0, 0.9, 0.0));
+                let mut entity = match kind {
+                    EntityKind::Orb => Entity::orb(position, 1),
+                    _ => Entity::new(kind, position),
+                };
+                if let Some(name) = name {
+                    entity = entity.with_name(&name);
+                }
+                self.entities.spawn(entity);
+                format!(
+                    "summoned {} at {:.1}, {:.1}, {:.1}",
+                    kind.name(),
+                    position.x,
+                    position.y,
+                    position.z
+                )
+            }
+            Command::Invisibility(kind, seconds) => {
+                let count = self.entities.make_invisible(kind, seconds);
+                if seconds > 0.0 {
+                    format!("made {count} {} invisible for {seconds}s", kind.name())
+                } else {
+                    format!("made {count} {} visible", kind.name())
+                }
+            }
+            Command::Time(time) => {
+                if let Some(time) = time {
+                    self.day.set_time(time);
+                }
+                format!("time is {:.2} of the way through the day", self.day.time())
+            }
+        }
     }
 }
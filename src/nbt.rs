@@ -0,0 +1,201 @@
+/// How deeply lists and compounds can nest before a file is taken to be broken.
+const MAX_DEPTH: usize = 512;
+
+/// The tag at the start of uncompressed NBT, along with its name.
+pub fn read(bytes: &[u8]) -> Result<(String, Tag), String> {
+    Reader { bytes }
+        .named_tag()
+        .map_err(|e| format!("bad nbt: {e}"))
+}
+
+/// One value in Minecraft's Named Binary Tag format, which its worlds and the schematics other
+/// tools share are stored in. Compounds keep their entries in order so files are written the
+/// same way every time.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Tag {
+    Byte(i8),
+    Short(i16),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    ByteArray(Vec<u8>),
+    String(String),
+    List(Vec<Tag>),
+    Compound(Vec<(String, Tag)>),
+    IntArray(Vec<i32>),
+    LongArray(Vec<i64>),
+}
+
+impl Tag {
+    fn id(&self) -> u8 {
+        match self {
+            Tag::Byte(_) => 1,
+            Tag::Short(_) => 2,
+            Tag::Int(_) => 3,
+            Tag::Long(_) => 4,
+            Tag::Float(_) => 5,
+            Tag::Double(_) => 6,
+            Tag::ByteArray(_) => 7,
+            Tag::String(_) => 8,
+            Tag::List(_) => 9,
+            Tag::Compound(_) => 10,
+            Tag::IntArray(_) => 11,
+            Tag::LongArray(_) => 12,
+        }
+    }
+
+    /// The entry called `name`, if this is a compound with one.
+    pub fn get(&self, name: &str) -> Option<&Tag> {
+        match self {
+            Tag::Compound(entries) => entries.iter().find(|(n, _)| n == name).map(|(_, t)| t),
+            _ => None,
+        }
+    }
+
+    pub fn write_named(&self, name: &str, out: &mut Vec<u8>) {
+        out.push(self.id());
+        write_string(name, out);
+        self.write(out);
+    }
+
+    fn write(&self, out: &mut Vec<u8>) {
+        match self {
+            Tag::Byte(value) => out.push(*value as u8),
+            Tag::Short(value) => out.extend(value.to_be_bytes()),
+            Tag::Int(value) => out.extend(value.to_be_bytes()),
+            Tag::Long(value) => out.extend(value.to_be_bytes()),
+            Tag::Float(value) => out.extend(value.to_be_bytes()),
+            Tag::Double(value) => out.extend(value.to_be_bytes()),
+            Tag::ByteArray(bytes) => {
+                out.extend((bytes.len() as i32).to_be_bytes());
+                out.extend(bytes);
+            }
+            Tag::String(string) => write_string(string, out),
+            Tag::List(tags) => {
+                out.push(tags.first().map_or(0, Tag::id));
+                out.extend((tags.len() as i32).to_be_bytes());
+                for tag in tags {
+                    tag.write(out);
+                }
+            }
+            Tag::Compound(entries) => {
+                for (name, tag) in entries {
+                    tag.write_named(name, out);
+                }
+                out.push(0);
+            }
+            Tag::IntArray(values) => {
+                out.extend((values.len() as i32).to_be_bytes());
+                for value in values {
+                    out.extend(value.to_be_bytes());
+                }
+            }
+            Tag::LongArray(values) => {
+                out.extend((values.len() as i32).to_be_bytes());
+                for value in values {
+                    out.extend(value.to_be_bytes());
+                }
+            }
+        }
+    }
+}
+
+fn write_string(string: &str, out: &mut Vec<u8>) {
+    out.extend((string.len() as u16).to_be_bytes());
+    out.extend(string.as_bytes());
+}
+
+/// Reads tags off the front of an uncompressed NBT file.
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl Reader<'_> {
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], String> {
+        let (front, rest) = self.bytes.split_first_chunk().ok_or("ends early")?;
+        self.bytes = rest;
+        Ok(*front)
+    }
+
+    fn take_slice(&mut self, len: usize) -> Result<&[u8], String> {
+        if len > self.bytes.len() {
+            return Err("ends early".into());
+        }
+        let (front, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(front)
+    }
+
+    /// A length, which can't be negative.
+    fn len(&mut self) -> Result<usize, String> {
+        usize::try_from(i32::from_be_bytes(self.take()?))
+            .map_err(|_| "has a negative length".into())
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        let len = u16::from_be_bytes(self.take()?) as usize;
+        // other tools write java's modified utf-8, which only differs for unusual characters
+        Ok(String::from_utf8_lossy(self.take_slice(len)?).into_owned())
+    }
+
+    fn named_tag(&mut self) -> Result<(String, Tag), String> {
+        let [id] = self.take()?;
+        let name = self.string()?;
+        Ok((name, self.tag(id, 0)?))
+    }
+
+    fn tag(&mut self, id: u8, depth: usize) -> Result<Tag, String> {
+        if depth > MAX_DEPTH {
+            return Err("nested too deeply".into());
+        }
+        Ok(match id {
+            1 => Tag::Byte(i8::from_be_bytes(self.take()?)),
+            2 => Tag::Short(i16::from_be_bytes(self.take()?)),
+            3 => Tag::Int(i32::from_be_bytes(self.take()?)),
+            4 => Tag::Long(i64::from_be_bytes(self.take()?)),
+            5 => Tag::Float(f32::from_be_bytes(self.take()?)),
+            6 => Tag::Double(f64::from_be_bytes(self.take()?)),
+            7 => {
+                let len = self.len()?;
+                Tag::ByteArray(self.take_slice(len)?.to_vec())
+            }
+            8 => Tag::String(self.string()?),
+            9 => {
+                let [id] = self.take()?;
+                let len = self.len()?;
+                let tags = (0..len)
+                    .map(|_| self.tag(id, depth + 1))
+                    .collect::<Result<_, _>>()?;
+                Tag::List(tags)
+            }
+            10 => {
+                let mut entries = vec![];
+                loop {
+                    let [id] = self.take()?;
+                    if id == 0 {
+                        break;
+                    }
+                    let name = self.string()?;
+                    entries.push((name, self.tag(id, depth + 1)?));
+                }
+                Tag::Compound(entries)
+            }
+            11 => {
+                let len = self.len()?;
+                let values = (0..len)
+                    .map(|_| self.take().map(i32::from_be_bytes))
+                    .collect::<Result<_, _>>()?;
+                Tag::IntArray(values)
+            }
+            12 => {
+                let len = self.len()?;
+                let values = (0..len)
+                    .map(|_| self.take().map(i64::from_be_bytes))
+                    .collect::<Result<_, _>>()?;
+                Tag::LongArray(values)
+            }
+            _ => return Err(format!("unknown tag type {id}")),
+        })
+    }
+}
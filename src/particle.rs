@@ -0,0 +1,214 @@
+use glam::{vec3, IVec3, Vec3};
+use image::DynamicImage;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{
+    instance::Instance,
+    interpolation::{Interpolated, Transform},
+    renderer::{Drawable, Renderer, Vertex},
+    util::block_position,
+    world::{cube_indices, cube_vertices, World},
+};
+
+/// The renderer object particles are drawn with.
+const PARTICLE_OBJECT: u32 = 4;
+
+/// Seconds a particle lasts.
+const LIFETIME: f32 = 0.8;
+/// Edge length of a particle when it appears, shrinking away to nothing over its life.
+const SIZE: f32 = 0.12;
+/// Downwards acceleration, in blocks per second squared.
+const GRAVITY: f32 = 16.0;
+/// Debris a broken block scatters, plus this many more for each point of hardness.
+const BREAK_PARTICLES: f32 = 4.0;
+const PARTICLES_PER_HARDNESS: f32 = 6.0;
+/// Kicked up by each step.
+const STEP_PARTICLES: usize = 2;
+
+/// The average colour of the opaque texels of a texture, from 0 to 1, which is what a block's
+/// particles are tinted.
+pub fn average_colour(texture: &DynamicImage) -> Vec3 {
+    let (total, count) = texture
+        .to_rgba8()
+        .pixels()
+        .filter(|pixel| pixel[3] >= 128)
+        .fold((Vec3::ZERO, 0), |(total, count), pixel| {
+            let rgb = vec3(pixel[0] as f32, pixel[1] as f32, pixel[2] as f32);
+            (total + rgb / 255.0, count + 1)
+        });
+    if count == 0 {
+        return Vec3::ONE;
+    }
+    total / count as f32
+}
+
+pub struct Particle {
+    position: Vec3,
+    velocity: Vec3,
+    colour: Vec3,
+    /// Seconds since it appeared.
+    age: f32,
+    /// Where it's drawn, following its position a tick behind.
+    motion: Interpolated,
+}
+
+impl Drawable for Particle {
+    fn draw(&self, renderer: &mut Renderer, world: &World) {
+        renderer.queue_draw(PARTICLE_OBJECT, self, world);
+    }
+
+    fn vertices(&self) -> Vec<Vertex> {
+        cube_vertices()
+    }
+
+    fn indices(&self) -> Vec<u16> {
+        cube_indices()
+    }
+
+    fn instance(&self, world: &World) -> Instance {
+        let size = SIZE * (1.0 - self.age / LIFETIME);
+        Instance::moving(self.motion, world.get_texture("particle"))
+            .with_scale(Vec3::splat(size))
+            .with_tint(self.colour)
+    }
+}
+
+/// Small coloured cubes thrown off blocks as they're broken and walked on, which fall and
+/// settle on the ground before fading away.
+pub struct Particles {
+    particles: Vec<Particle>,
+    /// The most there can be at once, the oldest making way for new ones past it. There's no
+    /// limit until one is set.
+    limit: usize,
+    rng: StdRng,
+}
+
+impl Particles {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            particles: vec![],
+            limit: usize::MAX,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    pub fn set_limit(&mut self, limit: usize) {
+        self.limit = limit;
+        let over = self.particles.len().saturating_sub(limit);
+        self.particles.drain(..over);
+    }
+
+    fn spawn(&mut self, position: Vec3, velocity: Vec3, colour: Vec3) {
+        if self.limit == 0 {
+            return;
+        }
+        if self.particles.len() >= self.limit {
+            self.particles.remove(0);
+        }
+        self.particles.push(Particle {
+            position,
+            velocity,
+            colour,
+            age: 0.0,
+            motion: Interpolated::new(Transform::at(position)),
+        });
+    }
+
+    /// Scatters debris from all over a broken block, more the harder it was.
+    pub fn break_block(&mut self, block: IVec3, colour: Vec3, hardness: f32) {
+        let count = (BREAK_PARTICLES + hardness * PARTICLES_PER_HARDNESS).round() as usize;
+        for _ in 0..count {
+            let offset = vec3(
+                self.rng.gen_range(-0.4..0.4),
+                self.rng.gen_range(-0.4..0.4),
+                self.rng.gen_range(-0.4..0.4),
+            );
+            let velocity = offset * 5.0 + Vec3::Y * self.rng.gen_range(1.0..3.0);
+            self.spawn(block.as_vec3() + offset, velocity, colour);
+        }
+    }
+
+    /// Kicks up a little of the ground around the player's feet.
+    pub fn step(&mut self, feet: Vec3, colour: Vec3) {
+        for _ in 0..STEP_PARTICLES {
+            let sideways = vec3(
+                self.rng.gen_range(-1.0..1.0),
+                0.0,
+                self.rng.gen_range(-1.0..1.0),
+            );
+            let velocity = sideways + Vec3::Y * self.rng.gen_range(1.0..2.0);
+            self.spawn(feet + sideways * 0.2, velocity, colour);
+        }
+    }
+
+    /// Moves every particle `dt` seconds on, stopping any that would fall into a solid block,
+    /// and drops those that have had their time.
+    pub fn update(&mut self, dt: f32, solid: impl Fn(IVec3) -> bool) {
+        for particle in &mut self.particles {
+            particle.velocity.y -= GRAVITY * dt;
+            let next = particle.position + particle.velocity * dt;
+            if solid(block_position(next)) {
+                particle.velocity = Vec3::ZERO;
+            } else {
+                particle.position = next;
+            }
+            particle.age += dt;
+            particle.motion.tick(Transform::at(particle.position));
+        }
+        self.particles.retain(|particle| particle.age < LIFETIME);
+    }
+
+    pub fn draw(&self, renderer: &mut Renderer, world: &World) {
+        for particle in &self.particles {
+            particle.draw(renderer, world);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{ivec3, vec3, IVec3, Vec3};
+    use image::{DynamicImage, Rgba, RgbaImage};
+
+    use super::{average_colour, Particles, LIFETIME};
+
+    #[test]
+    fn particles_take_the_colour_of_the_opaque_texels() {
+        // half red, half see-through green
+        let image = RgbaImage::from_fn(4, 4, |x, _| {
+            if x < 2 {
+                Rgba([255, 0, 0, 255])
+            } else {
+                Rgba([0, 255, 0, 0])
+            }
+        });
+        let colour = average_colour(&DynamicImage::ImageRgba8(image));
+        assert_eq!(colour, vec3(1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn debris_settles_on_the_ground_and_fades() {
+        let mut particles = Particles::new(0);
+        // harder blocks throw off more
+        particles.break_block(IVec3::ZERO, Vec3::ONE, 0.5);
+        assert_eq!(particles.particles.len(), 7);
+        particles.break_block(ivec3(5, 0, 0), Vec3::ONE, 2.0);
+        assert_eq!(particles.particles.len(), 7 + 16);
+
+        // everything below y = -1.5 is solid ground
+        let ground = |position: IVec3| position.y <= -2;
+        for _ in 0..10 {
+            particles.update(LIFETIME / 20.0, ground);
+        }
+        assert!(particles.particles.iter().all(|p| p.position.y >= -1.5));
+        particles.update(LIFETIME, ground);
+        assert!(particles.particles.is_empty());
+
+        // past the limit the oldest go first
+        particles.set_limit(3);
+        particles.step(Vec3::ZERO, Vec3::ONE);
+        particles.step(Vec3::X, Vec3::ONE);
+        assert_eq!(particles.particles.len(), 3);
+        assert!(particles.particles[2].position.x > 0.5);
+    }
+}
@@ -0,0 +1,378 @@
+use fxhash::FxHashMap;
+use wgpu::{DepthBiasState, DepthStencilState, StencilState};
+
+use crate::texture;
+
+pub type PipelineHandle = u32;
+
+/// Every shader the renderer knows about, keyed by the name pipelines refer to them with.
+const SHADERS: &[(&str, &str)] = &[
+    ("shader", include_str!("shader.wgsl")),
+    ("chunk", include_str!("chunk.wgsl")),
+    ("border", include_str!("border.wgsl")),
+    ("sky", include_str!("sky.wgsl")),
+    ("text", include_str!("text.wgsl")),
+    ("post", include_str!("post.wgsl")),
+];
+
+/// Bind group layouts owned by the registry so that pipelines (and the bind groups created for
+/// them) all agree on the same layout objects.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub enum Layout {
+    /// The per-frame uniform block, visible to both stages.
+    Frame,
+    /// A filterable 2d texture at binding 0 and its sampler at binding 1.
+    Texture,
+    /// Per chunk data: the atlas rect and variation of every texture at binding 0, for both
+    /// stages, then for the vertex stage the chunk origin at binding 1 and, with vertex pulling,
+    /// the packed quads at binding 2.
+    Chunk,
+    /// For post-processing passes: the image being processed at binding 0, its sampler at
+    /// binding 1, a second image to combine it with at binding 2, the pass settings at binding 3
+    /// and a colour lookup table at binding 4, all for the fragment stage.
+    Post,
+}
+
+impl Layout {
+    fn create(self, device: &wgpu::Device) -> wgpu::BindGroupLayout {
+        match self {
+            Layout::Frame => device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Frame bind group layout"),
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                }],
+            }),
+            Layout::Texture => device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Texture bind group layout"),
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            multisampled: false,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                        count: None,
+                    },
+                ],
+            }),
+            Layout::Post => {
+                let texture = |binding| wgpu::BindGroupLayoutEntry {
+                    binding,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                };
+                device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                    label: Some("Post bind group layout"),
+                    entries: &[
+                        texture(0),
+                        wgpu::BindGroupLayoutEntry {
+                            binding: 1,
+                            visibility: wgpu::ShaderStages::FRAGMENT,
+                            ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                            count: None,
+                        },
+                        texture(2),
+                        wgpu::BindGroupLayoutEntry {
+                            binding: 3,
+                            visibility: wgpu::ShaderStages::FRAGMENT,
+                            ty: wgpu::BindingType::Buffer {
+                                ty: wgpu::BufferBindingType::Uniform,
+                                has_dynamic_offset: false,
+                                min_binding_size: None,
+                            },
+                            count: None,
+                        },
+                        texture(4),
+                    ],
+                })
+            }
+            Layout::Chunk => {
+                let storage = |binding, visibility| wgpu::BindGroupLayoutEntry {
+                    binding,
+                    visibility,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                };
+                let mut entries = vec![
+                    storage(0, wgpu::ShaderStages::VERTEX_FRAGMENT),
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::VERTEX,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                ];
+                if cfg!(feature = "vertex-pulling") {
+                    entries.push(storage(2, wgpu::ShaderStages::VERTEX));
+                }
+                device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                    label: Some("Chunk bind group layout"),
+                    entries: &entries,
+                })
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+struct VertexLayout {
+    array_stride: u64,
+    step_mode: wgpu::VertexStepMode,
+    attributes: Vec<wgpu::VertexAttribute>,
+}
+
+/// Everything that distinguishes one pipeline from another. Building the same descriptor twice
+/// hands back the pipeline created the first time.
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub struct PipelineDescriptor {
+    label: &'static str,
+    shader: &'static str,
+    vertex_entry: &'static str,
+    fragment_entry: &'static str,
+    vertex_layouts: Vec<VertexLayout>,
+    bind_group_layouts: Vec<Layout>,
+    cull_mode: Option<wgpu::Face>,
+    blend: Option<wgpu::BlendState>,
+    depth_compare: wgpu::CompareFunction,
+    depth_write_enabled: bool,
+    /// Post-processing passes draw without a depth buffer.
+    depth_buffer: bool,
+    /// What the pipeline draws to, or None for the surface.
+    color_format: Option<wgpu::TextureFormat>,
+}
+
+/// Describes a pipeline with sensible defaults for opaque world geometry: back-face culling,
+/// no blending and a reverse-Z depth test that writes.
+pub struct PipelineBuilder {
+    descriptor: PipelineDescriptor,
+}
+
+impl PipelineBuilder {
+    pub fn new(label: &'static str, shader: &'static str) -> Self {
+        Self {
+            descriptor: PipelineDescriptor {
+                label,
+                shader,
+                vertex_entry: "vertex",
+                fragment_entry: "fragment",
+                vertex_layouts: vec![],
+                bind_group_layouts: vec![],
+                cull_mode: Some(wgpu::Face::Back),
+                blend: Some(wgpu::BlendState::REPLACE),
+                depth_compare: wgpu::CompareFunction::Greater,
+                depth_write_enabled: true,
+                depth_buffer: true,
+                color_format: None,
+            },
+        }
+    }
+
+    pub fn entry_points(mut self, vertex: &'static str, fragment: &'static str) -> Self {
+        self.descriptor.vertex_entry = vertex;
+        self.descriptor.fragment_entry = fragment;
+        self
+    }
+
+    /// Appends a vertex buffer layout, the nth call describes the buffer in slot n.
+    pub fn vertex_buffer<T>(
+        mut self,
+        step_mode: wgpu::VertexStepMode,
+        attributes: &[wgpu::VertexAttribute],
+    ) -> Self {
+        self.descriptor.vertex_layouts.push(VertexLayout {
+            array_stride: std::mem::size_of::<T>() as u64,
+            step_mode,
+            attributes: attributes.to_vec(),
+        });
+        self
+    }
+
+    /// The bind group layouts in group order.
+    pub fn bind_groups(mut self, layouts: &[Layout]) -> Self {
+        self.descriptor.bind_group_layouts = layouts.to_vec();
+        self
+    }
+
+    pub fn cull_mode(mut self, cull_mode: Option<wgpu::Face>) -> Self {
+        self.descriptor.cull_mode = cull_mode;
+        self
+    }
+
+    pub fn blend(mut self, blend: wgpu::BlendState) -> Self {
+        self.descriptor.blend = Some(blend);
+        self
+    }
+
+    pub fn depth(mut self, compare: wgpu::CompareFunction, write_enabled: bool) -> Self {
+        self.descriptor.depth_compare = compare;
+        self.descriptor.depth_write_enabled = write_enabled;
+        self
+    }
+
+    /// Draws without a depth buffer, for passes over the whole screen.
+    pub fn without_depth(mut self) -> Self {
+        self.descriptor.depth_buffer = false;
+        self
+    }
+
+    /// Draws to a texture of the given format rather than the surface.
+    pub fn target(mut self, format: wgpu::TextureFormat) -> Self {
+        self.descriptor.color_format = Some(format);
+        self
+    }
+
+    pub fn build(self, registry: &mut PipelineRegistry, device: &wgpu::Device) -> PipelineHandle {
+        registry.get_or_create(device, self.descriptor)
+    }
+}
+
+/// Owns every render pipeline along with the shader modules and bind group layouts they share.
+pub struct PipelineRegistry {
+    color_format: wgpu::TextureFormat,
+    shaders: FxHashMap<&'static str, wgpu::ShaderModule>,
+    layouts: FxHashMap<Layout, wgpu::BindGroupLayout>,
+    handles: FxHashMap<PipelineDescriptor, PipelineHandle>,
+    pipelines: Vec<wgpu::RenderPipeline>,
+}
+
+impl PipelineRegistry {
+    pub fn new(device: &wgpu::Device, color_format: wgpu::TextureFormat) -> Self {
+        let layouts = [Layout::Frame, Layout::Texture, Layout::Chunk, Layout::Post]
+            .into_iter()
+            .map(|layout| (layout, layout.create(device)))
+            .collect();
+        Self {
+            color_format,
+            shaders: FxHashMap::default(),
+            layouts,
+            handles: FxHashMap::default(),
+            pipelines: vec![],
+        }
+    }
+
+    pub fn layout(&self, layout: Layout) -> &wgpu::BindGroupLayout {
+        self.layouts
+            .get(&layout)
+            .unwrap_or_else(|| panic!("Bind group layout {layout:?} was never created."))
+    }
+
+    pub fn get(&self, handle: PipelineHandle) -> &wgpu::RenderPipeline {
+        self.pipelines
+            .get(handle as usize)
+            .unwrap_or_else(|| panic!("No pipeline found for handle {handle}."))
+    }
+
+    fn load_shader(&mut self, device: &wgpu::Device, name: &'static str) {
+        self.shaders.entry(name).or_insert_with(|| {
+            let (_, source) = SHADERS
+                .iter()
+                .find(|(shader, _)| *shader == name)
+                .unwrap_or_else(|| panic!("No shader named {name} is registered."));
+            device.create_shader_module(wgpu::ShaderModuleDescriptor {
+                label: Some(name),
+                source: wgpu::ShaderSource::Wgsl((*source).into()),
+            })
+        });
+    }
+
+    fn get_or_create(
+        &mut self,
+        device: &wgpu::Device,
+        descriptor: PipelineDescriptor,
+    ) -> PipelineHandle {
+        if let Some(handle) = self.handles.get(&descriptor) {
+            return *handle;
+        }
+
+        self.load_shader(device, descriptor.shader);
+        let module = &self.shaders[descriptor.shader];
+
+        let bind_group_layouts: Vec<&wgpu::BindGroupLayout> = descriptor
+            .bind_group_layouts
+            .iter()
+            .map(|layout| self.layout(*layout))
+            .collect();
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some(descriptor.label),
+            bind_group_layouts: &bind_group_layouts,
+            push_constant_ranges: &[],
+        });
+
+        let buffers: Vec<wgpu::VertexBufferLayout> = descriptor
+            .vertex_layouts
+            .iter()
+            .map(|layout| wgpu::VertexBufferLayout {
+                array_stride: layout.array_stride,
+                step_mode: layout.step_mode,
+                attributes: &layout.attributes,
+            })
+            .collect();
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some(descriptor.label),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module,
+                entry_point: descriptor.vertex_entry,
+                buffers: &buffers,
+            },
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: descriptor.cull_mode,
+                ..Default::default()
+            },
+            depth_stencil: descriptor.depth_buffer.then(|| DepthStencilState {
+                format: texture::Texture::DEPTH_FORMAT,
+                depth_write_enabled: descriptor.depth_write_enabled,
+                depth_compare: descriptor.depth_compare,
+                stencil: StencilState::default(),
+                bias: DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState::default(),
+            fragment: Some(wgpu::FragmentState {
+                module,
+                entry_point: descriptor.fragment_entry,
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: descriptor.color_format.unwrap_or(self.color_format),
+                    blend: descriptor.blend,
+                    write_mask: wgpu::ColorWrites::all(),
+                })],
+            }),
+            multiview: None,
+        });
+
+        let handle = self.pipelines.len() as PipelineHandle;
+        self.pipelines.push(pipeline);
+        self.handles.insert(descriptor, handle);
+        handle
+    }
+}
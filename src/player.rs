@@ -0,0 +1,398 @@
+use std::{fmt, path::Path};
+
+use glam::{ivec3, vec3, IVec3, Vec3, Vec3Swizzles};
+
+use crate::{border::WorldBorder, save::write_atomic, util::Aabb};
+
+/// Falling speed gained per second while walking.
+const GRAVITY: f32 = 32.0;
+/// The fastest the player falls.
+const TERMINAL_SPEED: f32 = 60.0;
+/// Upwards speed of a jump, enough to clear a block.
+const JUMP_SPEED: f32 = 9.0;
+/// Blocks per second on foot.
+const WALK_SPEED: f32 = 4.3;
+/// How much faster sprinting is on foot.
+const WALK_SPRINT: f32 = 1.3;
+/// How much faster sprinting is in flight.
+const FLY_SPRINT: f32 = 10.0;
+/// Seconds walking takes to ease most of the way to a new speed.
+const WALK_MOMENTUM: f32 = 0.05;
+
+/// What the player's keys ask them to do this tick.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Controls {
+    /// Which way the movement keys point in the world, at most a unit long.
+    pub direction: Vec3,
+    pub sprint: bool,
+    /// Rise while flying, or jump from the ground.
+    pub ascend: bool,
+    /// Sink while flying.
+    pub descend: bool,
+}
+
+pub struct Player {
+    /// The centre of the player's feet.
+    pub position: Vec3,
+    /// Blocks per second.
+    pub velocity: Vec3,
+    /// Flying players ignore gravity and pass through blocks, everyone else walks.
+    pub flying: bool,
+    /// Whether the player was standing on something after their last move.
+    on_ground: bool,
+    /// Dead at 0.
+    pub health: f32,
+    pub hunger: f32,
+}
+
+impl Player {
+    const HALF_EXTENTS: Vec3 = vec3(0.3, 0.9, 0.3);
+    const EYE_HEIGHT: f32 = 1.62;
+    /// Matches a mob so the two push each other equally.
+    pub const MASS: f32 = 1.0;
+    pub const MAX_HEALTH: f32 = 20.0;
+    pub const MAX_HUNGER: f32 = 20.0;
+
+    pub fn new(position: Vec3) -> Self {
+        Self {
+            position,
+            velocity: Vec3::ZERO,
+            flying: true,
+            on_ground: false,
+            health: Self::MAX_HEALTH,
+            hunger: Self::MAX_HUNGER,
+        }
+    }
+
+    /// Whether the player was standing on something after their last move.
+    pub fn is_on_ground(&self) -> bool {
+        self.on_ground
+    }
+
+    /// Places the player so that their eyes are at `eye_position`.
+    pub fn from_eye_position(eye_position: Vec3) -> Self {
+        Self::new(eye_position - Vec3::Y * Self::EYE_HEIGHT)
+    }
+
+    pub fn eye_position(&self) -> Vec3 {
+        self.position + Vec3::Y * Self::EYE_HEIGHT
+    }
+
+    pub fn damage(&mut self, amount: f32) {
+        self.health = (self.health - amount).max(0.0);
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.health <= 0.0
+    }
+
+    /// Brings the player back at `position` with full health and hunger.
+    pub fn respawn(&mut self, position: Vec3) {
+        *self = Self::new(position);
+    }
+
+    /// Pushes the player back inside the border.
+    pub fn confine(&mut self, border: &WorldBorder) {
+        self.position = border.confine(self.position, Self::HALF_EXTENTS.xz());
+    }
+
+    pub fn aabb(&self) -> Aabb {
+        Self::aabb_at(self.position)
+    }
+
+    fn aabb_at(position: Vec3) -> Aabb {
+        Aabb::from_center(
+            position + Vec3::Y * Self::HALF_EXTENTS.y,
+            Self::HALF_EXTENTS,
+        )
+    }
+
+    /// Moves the player for `dt` seconds as `controls` ask. Their velocity eases towards the
+    /// speed asked for rather than jumping to it, taking about `momentum` seconds in flight, so
+    /// they speed up and slow down smoothly. `solid` says which blocks a walking player can't
+    /// pass through.
+    pub fn steer(
+        &mut self,
+        controls: Controls,
+        fly_speed: f32,
+        momentum: f32,
+        dt: f32,
+        solid: impl Fn(IVec3) -> bool,
+    ) {
+        // the fraction of the old velocity left after easing for dt
+        let keep = |momentum: f32| {
+            if momentum > 0.0 {
+                (-dt / momentum).exp()
+            } else {
+                0.0
+            }
+        };
+        if self.flying {
+            let vertical = controls.ascend as i32 - controls.descend as i32;
+            let direction = (controls.direction + Vec3::Y * vertical as f32).normalize_or_zero();
+            let sprint = if controls.sprint { FLY_SPRINT } else { 1.0 };
+            let target = direction * fly_speed * sprint;
+            self.velocity = target.lerp(self.velocity, keep(momentum));
+            self.position += self.velocity * dt;
+            self.on_ground = false;
+            return;
+        }
+
+        let direction = (controls.direction * vec3(1.0, 0.0, 1.0)).normalize_or_zero();
+        let sprint = if controls.sprint { WALK_SPRINT } else { 1.0 };
+        let target = direction * WALK_SPEED * sprint;
+        let horizontal = target.lerp(self.velocity * vec3(1.0, 0.0, 1.0), keep(WALK_MOMENTUM));
+        let mut fall = self.velocity.y;
+        if self.on_ground && controls.ascend {
+            fall = JUMP_SPEED;
+        }
+        fall = (fall - GRAVITY * dt).max(-TERMINAL_SPEED);
+        self.velocity = vec3(horizontal.x, fall, horizontal.z);
+        self.move_colliding(self.velocity * dt, &solid);
+    }
+
+    /// Moves the player by `delta` an axis at a time, stopping flush against solid blocks in
+    /// the way. Blocks the player was already inside don't stop them, so they can climb out.
+    fn move_colliding(&mut self, delta: Vec3, solid: &impl Fn(IVec3) -> bool) {
+        self.on_ground = false;
+        let offsets = Self::aabb_at(Vec3::ZERO);
+        for axis in 0..3 {
+            if delta[axis] == 0.0 {
+                continue;
+            }
+            let start = self.aabb();
+            let mut moved = self.position;
+            moved[axis] += delta[axis];
+            let mut step = Vec3::ZERO;
+            step[axis] = delta[axis];
+            // the block in the way that the player would touch first
+            let first = Self::aabb_at(moved)
+                .blocks()
+                .filter(|block| solid(*block) && !start.blocks().any(|inside| inside == *block))
+                .filter_map(|block| {
+                    let bounds = Aabb::from_center(block.as_vec3(), Vec3::splat(0.5));
+                    Some((start.sweep(step, &bounds)?, block))
+                })
+                .min_by(|(a, _), (b, _)| a.total_cmp(b));
+            match first {
+                Some((_, block)) => {
+                    // flush against its face
+                    self.position[axis] = if delta[axis] < 0.0 {
+                        block[axis] as f32 + 0.5 - offsets.min[axis]
+                    } else {
+                        block[axis] as f32 - 0.5 - offsets.max[axis]
+                    };
+                    self.velocity[axis] = 0.0;
+                    if axis == 1 && delta.y < 0.0 {
+                        self.on_ground = true;
+                    }
+                }
+                None => self.position = moved,
+            }
+        }
+    }
+}
+
+/// Experience points gathered from orbs, counted in levels that each take a few more points
+/// than the last.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Experience {
+    pub points: u32,
+}
+
+impl Experience {
+    /// Points needed to go from `level` to the next.
+    fn points_for(level: u32) -> u32 {
+        7 + 2 * level
+    }
+
+    /// Whole levels reached, and how far through the next one as a fraction.
+    pub fn level(self) -> (u32, f32) {
+        let mut level = 0;
+        let mut points = self.points;
+        while points >= Self::points_for(level) {
+            points -= Self::points_for(level);
+            level += 1;
+        }
+        (level, points as f32 / Self::points_for(level) as f32)
+    }
+}
+
+/// What's kept about the player between runs, in the same `key = value` form as the settings.
+#[derive(Debug, Default, PartialEq)]
+pub struct PlayerData {
+    /// The bed the player last slept in, which they respawn beside as long as it's there.
+    pub bed: Option<IVec3>,
+    pub experience: Experience,
+}
+
+impl PlayerData {
+    /// The saved data, or nothing saved yet if the file is missing.
+    pub fn load<P: AsRef<Path>>(path: P) -> Self {
+        match std::fs::read_to_string(path.as_ref()) {
+            Ok(contents) => Self::parse(&contents),
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub fn parse(contents: &str) -> Self {
+        let mut data = Self::default();
+        for line in contents.lines() {
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            match (key.trim(), value.trim()) {
+                ("bed", value) => data.bed = parse_position(value),
+                ("experience", value) => match value.parse() {
+                    Ok(points) => data.experience = Experience { points },
+                    Err(_) => eprintln!("couldn't parse experience {value}"),
+                },
+                (key, _) => eprintln!("unknown player data {key}"),
+            }
+        }
+        data
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> std::io::Result<()> {
+        write_atomic(path.as_ref(), self.to_string().as_bytes())
+    }
+}
+
+impl fmt::Display for PlayerData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(bed) = self.bed {
+            writeln!(f, "bed = {} {} {}", bed.x, bed.y, bed.z)?;
+        }
+        writeln!(f, "experience = {}", self.experience.points)
+    }
+}
+
+fn parse_position(value: &str) -> Option<IVec3> {
+    let coords: Vec<i32> = value
+        .split_whitespace()
+        .map(str::parse)
+        .collect::<Result<_, _>>()
+        .ok()?;
+    match coords[..] {
+        [x, y, z] => Some(ivec3(x, y, z)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{ivec3, vec3, IVec3, Vec3};
+
+    use super::{Controls, Experience, Player, PlayerData};
+
+    const DT: f32 = 1.0 / 60.0;
+
+    #[test]
+    fn respawning_heals() {
+        let mut player = Player::new(Vec3::ZERO);
+        player.hunger = 3.0;
+        player.damage(5.0);
+        assert!(!player.is_dead());
+        player.damage(Player::MAX_HEALTH);
+        assert!(player.is_dead());
+        assert_eq!(player.health, 0.0);
+
+        player.respawn(vec3(1.0, 2.0, 3.0));
+        assert!(!player.is_dead());
+        assert_eq!(player.position, vec3(1.0, 2.0, 3.0));
+        assert_eq!(player.hunger, Player::MAX_HUNGER);
+    }
+
+    #[test]
+    fn flight_eases_in_and_out() {
+        let mut player = Player::new(Vec3::ZERO);
+        let forwards = Controls {
+            direction: Vec3::Z,
+            ..Controls::default()
+        };
+        let nothing_solid = |_| false;
+        player.steer(forwards, 3.0, 0.15, DT, nothing_solid);
+        let first = player.velocity.z;
+        assert!(first > 0.0 && first < 0.5, "{first}");
+        for _ in 0..60 {
+            player.steer(forwards, 3.0, 0.15, DT, nothing_solid);
+        }
+        assert!((player.velocity.z - 3.0).abs() < 0.01);
+
+        // letting go drifts on a little way rather than stopping dead
+        let stopped_at = player.position.z;
+        for _ in 0..60 {
+            player.steer(Controls::default(), 3.0, 0.15, DT, nothing_solid);
+        }
+        let drift = player.position.z - stopped_at;
+        assert!(drift > 0.3 && drift < 0.5, "{drift}");
+        assert!(player.velocity.length() < 0.01);
+
+        // and without momentum it's the old start and stop
+        player.steer(forwards, 3.0, 0.0, DT, nothing_solid);
+        assert_eq!(player.velocity, Vec3::Z * 3.0);
+        player.steer(Controls::default(), 3.0, 0.0, DT, nothing_solid);
+        assert_eq!(player.velocity, Vec3::ZERO);
+    }
+
+    #[test]
+    fn walking_players_fall_and_bump_into_blocks() {
+        // a floor of blocks at y = -1, with a wall along x = 2
+        let solid = |block: IVec3| block.y == -1 || block.x == 2;
+        let mut player = Player::new(vec3(0.0, 3.0, 0.0));
+        player.flying = false;
+        for _ in 0..120 {
+            player.steer(Controls::default(), 3.0, 0.15, DT, solid);
+        }
+        // standing on top of the floor
+        assert_eq!(player.position.y, -0.5);
+        assert!(player.on_ground);
+
+        let towards_wall = Controls {
+            direction: Vec3::X,
+            ..Controls::default()
+        };
+        for _ in 0..120 {
+            player.steer(towards_wall, 3.0, 0.15, DT, solid);
+        }
+        assert!(
+            (player.position.x - 1.2).abs() < 1e-5,
+            "{}",
+            player.position.x
+        );
+
+        // jumping leaves the ground and comes back down
+        let jump = Controls {
+            ascend: true,
+            ..Controls::default()
+        };
+        player.steer(jump, 3.0, 0.15, DT, solid);
+        assert!(player.position.y > -0.5);
+        for _ in 0..120 {
+            player.steer(Controls::default(), 3.0, 0.15, DT, solid);
+        }
+        assert_eq!(player.position.y, -0.5);
+    }
+
+    #[test]
+    fn player_data_round_trips() {
+        let data = PlayerData {
+            bed: Some(ivec3(4, -12, 30)),
+            experience: Experience { points: 12 },
+        };
+        assert_eq!(PlayerData::parse(&data.to_string()), data);
+        assert_eq!(PlayerData::parse("bed = 1 2"), PlayerData::default());
+        assert_eq!(PlayerData::parse(""), PlayerData::default());
+    }
+
+    #[test]
+    fn levels_take_more_points_each_time() {
+        assert_eq!(Experience { points: 0 }.level(), (0, 0.0));
+        assert_eq!(Experience { points: 7 }.level(), (1, 0.0));
+        // 7 for the first level and 9 for the second
+        assert_eq!(Experience { points: 16 }.level(), (2, 0.0));
+        let (level, progress) = Experience { points: 21 }.level();
+        assert_eq!(level, 2);
+        assert!((progress - 5.0 / 11.0).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,386 @@
+use bytemuck::{Pod, Zeroable};
+use image::DynamicImage;
+use wgpu::util::DeviceExt;
+
+use crate::{
+    pipeline::{Layout, PipelineBuilder, PipelineHandle, PipelineRegistry},
+    texture::Texture,
+};
+
+/// The glow is blurred in images this format, at a fraction of the screen size.
+const GLOW_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+const GLOW_DIVISOR: u32 = 2;
+
+#[repr(C)]
+#[derive(Pod, Zeroable, Clone, Copy)]
+struct PostUniforms {
+    threshold: f32,
+    intensity: f32,
+    /// The size of one glow texel in uvs.
+    texel: [f32; 2],
+    brightness: f32,
+    contrast: f32,
+    gamma: f32,
+    /// Entries along each side of the colour lookup table, or 0 without one.
+    lut_size: f32,
+    /// A `ColorFilter` as a number.
+    color_filter: u32,
+    _padding: [u32; 3],
+}
+
+/// How the final image is adjusted before it's shown.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Grading {
+    /// Multiplies every colour, above 1 to see into dark caves.
+    pub brightness: f32,
+    /// Spreads colours away from mid grey, or below 1 pulls them towards it.
+    pub contrast: f32,
+    /// Above 1 lifts dark colours more than bright ones.
+    pub gamma: f32,
+}
+
+/// Shifts colours a kind of colour blindness confuses into ones that can be told apart.
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub enum ColorFilter {
+    #[default]
+    None = 0,
+    /// For red blindness.
+    Protanopia,
+    /// For green blindness, the most common kind.
+    Deuteranopia,
+    /// For blue blindness.
+    Tritanopia,
+}
+
+impl Default for Grading {
+    fn default() -> Self {
+        Self {
+            brightness: 1.0,
+            contrast: 1.0,
+            gamma: 1.0,
+        }
+    }
+}
+
+/// The offscreen images the passes draw through, which are remade whenever the window resizes.
+struct Targets {
+    scene: Texture,
+    /// The blur runs from the first image to the second and back.
+    glow: [Texture; 2],
+    /// One per pass, in the order they run.
+    bind_groups: [wgpu::BindGroup; 4],
+}
+
+/// Everything done to the image after the world is drawn and before the ui goes over it. The
+/// world is drawn to an offscreen image, whose bright parts are picked out and blurred at a
+/// smaller size for bloom, and the two are composited onto the surface with colour grading
+/// and an optional lookup table applied. With all of it off the world is drawn straight to the
+/// surface.
+pub struct PostProcess {
+    targets: Targets,
+    uniforms: PostUniforms,
+    uniform_buffer: wgpu::Buffer,
+    /// A lookup table the graded colours are mapped through, or a placeholder without one.
+    lut: Texture,
+    /// Picking out the bright parts, blurring across, blurring down and compositing.
+    pipelines: [PipelineHandle; 4],
+}
+
+impl PostProcess {
+    pub fn new(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        registry: &mut PipelineRegistry,
+        config: &wgpu::SurfaceConfiguration,
+    ) -> Self {
+        let mut pass = |label, fragment, format| {
+            PipelineBuilder::new(label, "post")
+                .entry_points("vertex", fragment)
+                .bind_groups(&[Layout::Post])
+                .cull_mode(None)
+                .without_depth()
+                .target(format)
+                .build(registry, device)
+        };
+        let pipelines = [
+            pass("Bloom bright pass", "bright", GLOW_FORMAT),
+            pass("Bloom blur across", "blur_across", GLOW_FORMAT),
+            pass("Bloom blur down", "blur_down", GLOW_FORMAT),
+            pass("Post composite", "composite", config.format),
+        ];
+        // everything off until told otherwise
+        let grading = Grading::default();
+        let uniforms = PostUniforms {
+            threshold: 1.0,
+            intensity: 0.0,
+            texel: glow_texel(config),
+            brightness: grading.brightness,
+            contrast: grading.contrast,
+            gamma: grading.gamma,
+            lut_size: 0.0,
+            color_filter: ColorFilter::None as u32,
+            _padding: [0; 3],
+        };
+        let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Post uniform buffer"),
+            contents: bytemuck::bytes_of(&uniforms),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let lut = create_lut(device, queue, 1, 1, &[255; 4]);
+        Self {
+            targets: Targets::new(device, registry, config, &uniform_buffer, &lut),
+            uniforms,
+            uniform_buffer,
+            lut,
+            pipelines,
+        }
+    }
+
+    /// Pixels brighter than `threshold`, from 0 to 1, glow, and `intensity` scales how much.
+    /// An intensity of 0 turns bloom off.
+    pub fn set_bloom(&mut self, queue: &wgpu::Queue, threshold: f32, intensity: f32) {
+        self.uniforms.threshold = threshold;
+        self.uniforms.intensity = intensity;
+        self.write_uniforms(queue);
+    }
+
+    pub fn set_grading(&mut self, queue: &wgpu::Queue, grading: Grading) {
+        self.uniforms.brightness = grading.brightness;
+        self.uniforms.contrast = grading.contrast;
+        self.uniforms.gamma = grading.gamma;
+        self.write_uniforms(queue);
+    }
+
+    pub fn set_color_filter(&mut self, queue: &wgpu::Queue, filter: ColorFilter) {
+        self.uniforms.color_filter = filter as u32;
+        self.write_uniforms(queue);
+    }
+
+    /// Maps the final colours through a lookup table: an image of `n` squares of `n` by `n`
+    /// side by side, red increasing across each square, green down it and blue from square
+    /// to square. None goes back to no table.
+    pub fn set_lut(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        registry: &PipelineRegistry,
+        config: &wgpu::SurfaceConfiguration,
+        lut: Option<&DynamicImage>,
+    ) -> Result<(), String> {
+        let (lut, size) = match lut {
+            Some(image) => {
+                let size = lut_size(image.width(), image.height())?;
+                let rgba = image.to_rgba8();
+                (
+                    create_lut(device, queue, image.width(), image.height(), &rgba),
+                    size,
+                )
+            }
+            None => (create_lut(device, queue, 1, 1, &[255; 4]), 0),
+        };
+        self.lut = lut;
+        self.uniforms.lut_size = size as f32;
+        self.write_uniforms(queue);
+        self.targets = Targets::new(device, registry, config, &self.uniform_buffer, &self.lut);
+        Ok(())
+    }
+
+    fn write_uniforms(&self, queue: &wgpu::Queue) {
+        queue.write_buffer(&self.uniform_buffer, 0, bytemuck::bytes_of(&self.uniforms));
+    }
+
+    fn bloom(&self) -> bool {
+        self.uniforms.intensity > 0.0
+    }
+
+    /// Whether anything is done to the image at all, so the world has to be drawn offscreen
+    /// first.
+    pub fn enabled(&self) -> bool {
+        let graded = self.uniforms.brightness != 1.0
+            || self.uniforms.contrast != 1.0
+            || self.uniforms.gamma != 1.0
+            || self.uniforms.lut_size > 0.0
+            || self.uniforms.color_filter != ColorFilter::None as u32;
+        self.bloom() || graded
+    }
+
+    /// Where the world should be drawn while post-processing is enabled.
+    pub fn scene(&self) -> &wgpu::TextureView {
+        &self.targets.scene.view
+    }
+
+    pub fn resize(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        registry: &PipelineRegistry,
+        config: &wgpu::SurfaceConfiguration,
+    ) {
+        self.targets = Targets::new(device, registry, config, &self.uniform_buffer, &self.lut);
+        self.uniforms.texel = glow_texel(config);
+        self.write_uniforms(queue);
+    }
+
+    /// Runs the passes over the drawn scene, ending with the finished image on `target`.
+    /// Returns the number of draw calls.
+    pub fn draw(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        registry: &PipelineRegistry,
+        target: &wgpu::TextureView,
+    ) -> u32 {
+        let [first, second] = &self.targets.glow;
+        let outputs = [&first.view, &second.view, &first.view, target];
+        // without bloom only the composite runs, adding nothing from the glow
+        let skip = if self.bloom() { 0 } else { 3 };
+        let passes = self
+            .pipelines
+            .iter()
+            .zip(&self.targets.bind_groups)
+            .zip(outputs)
+            .skip(skip);
+        let mut draw_calls = 0;
+        for ((pipeline, bind_group), output) in passes {
+            let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Post pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: output,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                        store: true,
+                    },
+                })],
+                depth_stencil_attachment: None,
+            });
+            rpass.set_pipeline(registry.get(*pipeline));
+            rpass.set_bind_group(0, bind_group, &[]);
+            // one triangle over the whole screen, generated in the shader
+            rpass.draw(0..3, 0..1);
+            draw_calls += 1;
+        }
+        draw_calls
+    }
+}
+
+impl Targets {
+    fn new(
+        device: &wgpu::Device,
+        registry: &PipelineRegistry,
+        config: &wgpu::SurfaceConfiguration,
+        uniform_buffer: &wgpu::Buffer,
+        lut: &Texture,
+    ) -> Self {
+        let (width, height) = (config.width, config.height);
+        let scene = Texture::create_render_target(device, "Scene", width, height, config.format);
+        let glow = |label| {
+            let (width, height) = (width / GLOW_DIVISOR, height / GLOW_DIVISOR);
+            Texture::create_render_target(device, label, width, height, GLOW_FORMAT)
+        };
+        let glow = [glow("Glow"), glow("Blurred glow")];
+
+        // only the composite reads the second image and the table, but every pass shares a
+        // layout
+        let bind_group = |source: &Texture, other: &Texture| {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Post bind group"),
+                layout: registry.layout(Layout::Post),
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(&source.view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::Sampler(&source.sampler),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: wgpu::BindingResource::TextureView(&other.view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 3,
+                        resource: uniform_buffer.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 4,
+                        resource: wgpu::BindingResource::TextureView(&lut.view),
+                    },
+                ],
+            })
+        };
+        let bind_groups = [
+            bind_group(&scene, &scene),
+            bind_group(&glow[0], &glow[0]),
+            bind_group(&glow[1], &glow[1]),
+            bind_group(&scene, &glow[0]),
+        ];
+        Self {
+            scene,
+            glow,
+            bind_groups,
+        }
+    }
+}
+
+fn glow_texel(config: &wgpu::SurfaceConfiguration) -> [f32; 2] {
+    [
+        GLOW_DIVISOR as f32 / config.width.max(1) as f32,
+        GLOW_DIVISOR as f32 / config.height.max(1) as f32,
+    ]
+}
+
+/// Entries along each side of a lookup table image `width` by `height`, which has to be that
+/// many squares of that size side by side.
+fn lut_size(width: u32, height: u32) -> Result<u32, String> {
+    if height < 2 || width != height * height {
+        return Err(format!(
+            "a {width}x{height} image isn't a colour lookup table, it should be n*n wide and n \
+             tall"
+        ));
+    }
+    Ok(height)
+}
+
+fn create_lut(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    width: u32,
+    height: u32,
+    rgba: &[u8],
+) -> Texture {
+    let texture = device.create_texture_with_data(
+        queue,
+        &wgpu::TextureDescriptor {
+            label: Some("Colour lookup table"),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            // the table's values are used as they're stored
+            format: wgpu::TextureFormat::Rgba8Unorm,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING,
+        },
+        rgba,
+    );
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+    let sampler = device.create_sampler(&wgpu::SamplerDescriptor::default());
+    Texture { view, sampler }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::lut_size;
+
+    #[test]
+    fn lookup_tables_are_squares_side_by_side() {
+        assert_eq!(lut_size(256, 16), Ok(16));
+        assert_eq!(lut_size(1024, 32), Ok(32));
+        assert!(lut_size(256, 256).is_err());
+        assert!(lut_size(1, 1).is_err());
+    }
+}
@@ -0,0 +1,142 @@
+struct Post {
+    // brightness a pixel needs before any of it glows
+    threshold: f32,
+    // how strongly the glow is added back over the scene, 0 for none
+    intensity: f32,
+    // the size of one texel of the image being read, for stepping the blur
+    texel: vec2<f32>,
+    // colour grading, all 1 to leave the image as it is
+    brightness: f32,
+    contrast: f32,
+    gamma: f32,
+    // entries along each side of the lookup table, 0 for none
+    lut_size: f32,
+    // 0 for none, then filters for protanopia, deuteranopia and tritanopia
+    color_filter: u32,
+}
+
+@group(0) @binding(0)
+var source: texture_2d<f32>;
+@group(0) @binding(1)
+var samp: sampler;
+@group(0) @binding(2)
+var glow: texture_2d<f32>;
+@group(0) @binding(3)
+var<uniform> post: Post;
+@group(0) @binding(4)
+var lut: texture_2d<f32>;
+
+// relative brightness of each channel as the eye sees it
+let LUMINANCE: vec3<f32> = vec3<f32>(0.2126, 0.7152, 0.0722);
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+// one triangle big enough to cover the screen, no vertex buffers needed
+@vertex
+fn vertex(@builtin(vertex_index) index: u32) -> VertexOutput {
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    var out: VertexOutput;
+    // uvs run down the screen, clip space runs up it
+    out.position = vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
+    out.uv = uv;
+    return out;
+}
+
+// keeps only the part of each pixel brighter than the threshold
+@fragment
+fn bright(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSample(source, samp, in.uv).rgb;
+    let brightness = dot(color, LUMINANCE);
+    let kept = max(brightness - post.threshold, 0.0) / max(brightness, 0.0001);
+    return vec4<f32>(color * kept, 1.0);
+}
+
+// a 9 tap gaussian along one axis, run once across and once down
+fn blur(uv: vec2<f32>, direction: vec2<f32>) -> vec4<f32> {
+    var weights = array<f32, 5>(0.227027, 0.1945946, 0.1216216, 0.054054, 0.016216);
+    var color = textureSample(source, samp, uv).rgb * weights[0];
+    for (var i = 1; i < 5; i++) {
+        let offset = direction * post.texel * f32(i);
+        color += textureSample(source, samp, uv + offset).rgb * weights[i];
+        color += textureSample(source, samp, uv - offset).rgb * weights[i];
+    }
+    return vec4<f32>(color, 1.0);
+}
+
+@fragment
+fn blur_across(in: VertexOutput) -> @location(0) vec4<f32> {
+    return blur(in.uv, vec2<f32>(1.0, 0.0));
+}
+
+@fragment
+fn blur_down(in: VertexOutput) -> @location(0) vec4<f32> {
+    return blur(in.uv, vec2<f32>(0.0, 1.0));
+}
+
+// where a colour's entry is in the lookup table for one blue slice, red running across the
+// slice and green down it
+fn lut_uv(color: vec3<f32>, slice: f32) -> vec2<f32> {
+    let size = post.lut_size;
+    let x = (slice * size + color.r * (size - 1.0) + 0.5) / (size * size);
+    let y = (color.g * (size - 1.0) + 0.5) / size;
+    return vec2<f32>(x, y);
+}
+
+// the colour looked up in the table, blending between the two nearest blue slices
+fn look_up(color: vec3<f32>) -> vec3<f32> {
+    let blue = color.b * (post.lut_size - 1.0);
+    let lower = floor(blue);
+    let upper = min(lower + 1.0, post.lut_size - 1.0);
+    let a = textureSample(lut, samp, lut_uv(color, lower)).rgb;
+    let b = textureSample(lut, samp, lut_uv(color, upper)).rgb;
+    return mix(a, b, blue - lower);
+}
+
+// from linear rgb to the response of the eye's long, medium and short wavelength cones and back,
+// each written as its rows so vectors multiply on the left
+let RGB_TO_LMS: mat3x3<f32> = mat3x3<f32>(
+    vec3<f32>(17.8824, 43.5161, 4.11935),
+    vec3<f32>(3.45565, 27.1554, 3.86714),
+    vec3<f32>(0.0299566, 0.184309, 1.46709),
+);
+let LMS_TO_RGB: mat3x3<f32> = mat3x3<f32>(
+    vec3<f32>(0.0809445, -0.130504, 0.116721),
+    vec3<f32>(-0.0102485, 0.0540193, -0.113615),
+    vec3<f32>(-0.000365297, -0.00412161, 0.693511),
+);
+
+// daltonizes: works out what someone missing one kind of cone sees, and moves the difference
+// into the channels they can still see
+fn filter_color(color: vec3<f32>) -> vec3<f32> {
+    var lms = color * RGB_TO_LMS;
+    if (post.color_filter == 1u) {
+        lms.x = 2.02344 * lms.y - 2.52581 * lms.z;
+    } else if (post.color_filter == 2u) {
+        lms.y = 0.494207 * lms.x + 1.24827 * lms.z;
+    } else {
+        lms.z = -0.395913 * lms.x + 0.801109 * lms.y;
+    }
+    let error = color - lms * LMS_TO_RGB;
+    let shifted = vec3<f32>(0.0, 0.7 * error.r + error.g, 0.7 * error.r + error.b);
+    return clamp(color + shifted, vec3<f32>(0.0), vec3<f32>(1.0));
+}
+
+// the scene with the blurred glow added over it, then graded
+@fragment
+fn composite(in: VertexOutput) -> @location(0) vec4<f32> {
+    let scene = textureSample(source, samp, in.uv).rgb;
+    let glowing = textureSample(glow, samp, in.uv).rgb;
+    var color = (scene + glowing * post.intensity) * post.brightness;
+    color = (color - 0.5) * post.contrast + 0.5;
+    color = pow(clamp(color, vec3<f32>(0.0), vec3<f32>(1.0)), vec3<f32>(1.0 / post.gamma));
+    if (post.lut_size > 0.0) {
+        color = look_up(color);
+    }
+    if (post.color_filter > 0u) {
+        color = filter_color(color);
+    }
+    return vec4<f32>(color, 1.0);
+}
@@ -1,22 +1,44 @@
 use bytemuck::{Pod, Zeroable};
 use fxhash::FxHashMap;
-use glam::vec3;
+use glam::{vec2, vec3, IVec3, Mat4, Vec2, Vec3};
 use image::{DynamicImage, GenericImage, GenericImageView, RgbaImage};
 use wgpu::{
     util::{BufferInitDescriptor, DeviceExt},
-    vertex_attr_array, Adapter, DepthBiasState, DepthStencilState, FragmentState, StencilState,
-    Surface, SurfaceConfiguration, VertexState,
+    vertex_attr_array, Adapter, Surface, SurfaceConfiguration,
 };
-use winit::window::Window;
+use winit::{dpi::PhysicalSize, window::Window};
 
 use crate::{
-    camera::Camera,
+    camera::{Camera, ResizeStrategy},
+    chunk::{self, Quad, CHUNK_SIZE, MAX_TEXTURES},
+    gpu::{self, AdapterChoice, Backend},
     instance,
-    text::Font,
-    texture::{self, Texture, TextureAtlas, TextureHandle},
+    pipeline::{Layout, PipelineBuilder, PipelineHandle, PipelineRegistry},
+    post::{ColorFilter, Grading, PostProcess},
+    text::{Font, Glyph},
+    texture::{self, Animation, Rect, Texture, TextureAtlas, TextureHandle, Variation},
+    util::{block_position, chunk_bounds, chunk_coord, Frustum},
     world::World,
 };
 
+/// What chunk shaders know of a texture, matching `TextureInfo` in chunk.wgsl.
+#[repr(C)]
+#[derive(Pod, Zeroable, Clone, Copy)]
+struct TextureInfo {
+    rect: [f32; 4],
+    variants: u32,
+    rotate: u32,
+    _padding: [u32; 2],
+}
+
+/// An animated texture's frames, each with its own place in the atlas.
+struct TextureAnimation {
+    animation: Animation,
+    frames: Vec<TextureHandle>,
+    /// Which of the frames is drawn.
+    shown: usize,
+}
+
 pub struct TextMesh {
     vertex_buffer: wgpu::Buffer,
     index_buffer: wgpu::Buffer,
@@ -73,94 +95,265 @@ pub struct RenderInstance {
     raw: [f32; 16],
     tex_offset: [f32; 2],
     tex_size: [f32; 2],
+    /// How strongly the object is tinted red, from 0 to 1.
+    flash: f32,
+    /// Multiplies the object's colour.
+    tint: [f32; 3],
+    /// How opaque the object is, anything under 1 is drawn blended after the world.
+    alpha: f32,
+}
+
+/// A chunk uploaded to the gpu. With vertex pulling the quads live in the bind group and the
+/// vertices are generated in the shader.
+struct ChunkMesh {
+    bind_group: wgpu::BindGroup,
+    #[cfg(not(feature = "vertex-pulling"))]
+    vertex_buffer: wgpu::Buffer,
+    #[cfg(not(feature = "vertex-pulling"))]
+    index_buffer: wgpu::Buffer,
+    #[cfg(feature = "vertex-pulling")]
+    quad_buffer: wgpu::Buffer,
+    num_quads: u32,
+}
+
+/// A chunk's translucent faces, kept on the cpu as well so they can be sorted again as the
+/// camera moves.
+struct TranslucentMesh {
+    mesh: ChunkMesh,
+    quads: Vec<Quad>,
+    /// The camera position the quads were last sorted for.
+    sorted_from: Vec3,
+}
+
+/// How far the camera moves before translucent chunks are sorted again, in blocks.
+const RESORT_DISTANCE: f32 = 1.0;
+
+/// A world position in the block space of the chunk at `coord`, where quads are built.
+fn chunk_space(coord: IVec3, position: Vec3) -> Vec3 {
+    position - (coord * CHUNK_SIZE).as_vec3() + 0.5
+}
+
+fn draw_chunk<'a>(rpass: &mut wgpu::RenderPass<'a>, chunk: &'a ChunkMesh) {
+    rpass.set_bind_group(2, &chunk.bind_group, &[]);
+    #[cfg(not(feature = "vertex-pulling"))]
+    {
+        rpass.set_vertex_buffer(0, chunk.vertex_buffer.slice(..));
+        rpass.set_index_buffer(chunk.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+        rpass.draw_indexed(0..chunk.num_quads * 6, 0, 0..1);
+    }
+    #[cfg(feature = "vertex-pulling")]
+    rpass.draw(0..chunk.num_quads * 6, 0..1);
+}
+
+/// Draws every object with instances queued, each of them once per instance, and clears the
+/// queues. Instances are written to `instance_buffer` from `instance_offset` on, which is left
+/// after the last of them. Returns the number of draw calls issued.
+fn draw_objects<'a>(
+    rpass: &mut wgpu::RenderPass<'a>,
+    queue: &wgpu::Queue,
+    instance_buffer: &'a wgpu::Buffer,
+    instance_offset: &mut u64,
+    objects: &'a [Object],
+    object_instances: &mut [Vec<RenderInstance>],
+) -> u32 {
+    let mut draw_calls = 0;
+    for (object, instances) in objects.iter().zip(object_instances) {
+        if instances.is_empty() {
+            continue;
+        }
+        rpass.set_vertex_buffer(0, object.vertex_buffer.as_ref().unwrap().slice(..));
+        rpass.set_index_buffer(
+            object.index_buffer.as_ref().unwrap().slice(..),
+            wgpu::IndexFormat::Uint16,
+        );
+
+        let instance_data: &[u8] = bytemuck::cast_slice(instances);
+        queue.write_buffer(instance_buffer, *instance_offset, instance_data);
+
+        let instance_end = *instance_offset + instance_data.len() as u64;
+        rpass.set_vertex_buffer(1, instance_buffer.slice(*instance_offset..instance_end));
+        *instance_offset = instance_end;
+
+        rpass.draw_indexed(
+            0..object.indices_length as u32,
+            0,
+            0..instances.len() as u32,
+        );
+        draw_calls += 1;
+        instances.clear();
+    }
+    draw_calls
+}
+
+/// Orders instances by how far their origin is from `camera`, furthest first, so blending
+/// them in order covers what's behind with what's in front.
+fn sort_back_to_front(instances: &mut [RenderInstance], camera: Vec3) {
+    let distance = |instance: &RenderInstance| {
+        Vec3::from_slice(&instance.raw[12..15]).distance_squared(camera)
+    };
+    instances.sort_by(|a, b| distance(b).total_cmp(&distance(a)));
+}
+
+/// Uniform data shared by every pipeline, rewritten once per frame. Field order follows the
+/// `Frame` struct in the shaders so the vec3s pack against the scalar that follows them.
+#[repr(C)]
+#[derive(Pod, Zeroable, Clone, Copy)]
+pub struct FrameUniforms {
+    view_proj: [f32; 16],
+    view: [f32; 16],
+    camera_position: [f32; 3],
+    time: f32,
+    sun_direction: [f32; 3],
+    fog_start: f32,
+    fog_color: [f32; 3],
+    fog_end: f32,
+    screen_size: [f32; 2],
+    sky_light: f32,
+    _padding: f32,
+    border: [f32; 4],
+}
+
+impl FrameUniforms {
+    fn new(camera: &Camera, screen_size: [f32; 2]) -> Self {
+        Self {
+            view_proj: camera.compute().to_cols_array(),
+            view: camera.view().to_cols_array(),
+            camera_position: camera.position().to_array(),
+            time: 0.0,
+            sun_direction: [0.0, -1.0, 0.0],
+            fog_start: 0.0,
+            fog_color: [0.1, 0.1, 0.5],
+            fog_end: f32::MAX,
+            screen_size,
+            sky_light: 1.0,
+            _padding: 0.0,
+            border: [0.0; 4],
+        }
+    }
 }
 
-type FontHandle = u32;
+pub type FontHandle = u32;
+pub type TextHandle = u32;
+pub type WorldTextHandle = u32;
 
-#[allow(dead_code)]
 pub struct RendererBase {
-    instance: wgpu::Instance,
     surface: wgpu::Surface,
     adapter: wgpu::Adapter,
     device: wgpu::Device,
     queue: wgpu::Queue,
 }
 
+/// A font and the texture its atlas is uploaded to.
+struct LoadedFont {
+    font: Font,
+    texture: wgpu::Texture,
+    size: wgpu::Extent3d,
+    bind_group: wgpu::BindGroup,
+}
+
 struct TextModule {
-    pipeline: wgpu::RenderPipeline,
-    bgl: wgpu::BindGroupLayout,
+    pipeline: PipelineHandle,
     text_meshes: FxHashMap<FontHandle, Vec<TextMesh>>,
-    camera_bg: wgpu::BindGroup,
+    /// Screen text that can be redrawn every frame without rebuilding it.
+    texts: Vec<TextMesh>,
+    text_queue: Vec<TextHandle>,
+    world_pipeline: PipelineHandle,
+    world_text_meshes: Vec<TextMesh>,
+    /// World text to draw this frame and where.
+    world_text_queue: Vec<(WorldTextHandle, [f32; 3])>,
+    world_text_instances: Option<wgpu::Buffer>,
+    /// Textured quads from the atlas to draw under this frame's screen text, six vertices each.
+    ui_quads: Vec<TextVertex>,
+    camera: Camera,
+    frame_buffer: wgpu::Buffer,
+    frame_bg: wgpu::BindGroup,
 }
 
-#[allow(dead_code)]
 pub struct Renderer {
-    num_objects: u32,
     base: RendererBase,
-    pipeline: wgpu::RenderPipeline,
-    vertices: wgpu::Buffer,
+    pipelines: PipelineRegistry,
+    pipeline: PipelineHandle,
     indices: wgpu::Buffer,
-    vertices_length: u32,
-    indices_length: u32,
-    camera_bg: wgpu::BindGroup,
-    camera_buffer: wgpu::Buffer,
+    frame: FrameUniforms,
+    frame_bg: wgpu::BindGroup,
+    frame_buffer: wgpu::Buffer,
+    surface_config: SurfaceConfiguration,
+    ui_resize_strategy: ResizeStrategy,
+    /// How much bigger than its designed size the ui is drawn.
+    ui_scale: f32,
     depth_texture: Texture,
     objects: Vec<Object>,
     object_instances: Vec<Vec<RenderInstance>>,
+    /// Like `object_instances`, for the instances that are see-through this frame.
+    translucent_instances: Vec<Vec<RenderInstance>>,
+    translucent_object_pipeline: PipelineHandle,
     texture_atlas: TextureAtlas,
     textures: FxHashMap<TextureHandle, DynamicImage>,
+    /// Animated textures by the handle they're drawn with, which shows whichever frame is
+    /// current.
+    animations: FxHashMap<TextureHandle, TextureAnimation>,
     texture_atlas_tex: wgpu::Texture,
     sampler: wgpu::Sampler,
     texture_atlas_bg: wgpu::BindGroup,
     texture_atlas_extend: wgpu::Extent3d,
-    texture_atlas_bgl: wgpu::BindGroupLayout,
     font_count: u32,
-    fonts: Vec<(Font, wgpu::BindGroup)>,
+    fonts: Vec<LoadedFont>,
     text_module: Option<TextModule>,
     instance_buffer: Option<wgpu::Buffer>,
+    chunk_pipeline: PipelineHandle,
+    /// The `TextureInfo` of every texture handle, for chunk shaders.
+    chunk_textures: wgpu::Buffer,
+    /// Textures that vary from block to block, by their first handle.
+    variations: FxHashMap<TextureHandle, Variation>,
+    chunks: FxHashMap<IVec3, ChunkMesh>,
+    translucent_pipeline: PipelineHandle,
+    translucent_chunks: FxHashMap<IVec3, TranslucentMesh>,
+    /// Chunks further than this from the camera, in chunks, aren't drawn.
+    render_distance: i32,
+    /// Issued by the last frame.
+    draw_calls: u32,
+    border_pipeline: PipelineHandle,
+    /// Whether a world border has been set.
+    draw_border: bool,
+    sky_pipeline: PipelineHandle,
+    /// Whether the sun, moon and stars are drawn.
+    draw_sky: bool,
+    post: PostProcess,
+    /// How far the frame is from the last tick to the next, from 0 to 1, for drawing moving
+    /// things part of the way between.
+    alpha: f32,
 }
 
 impl Renderer {
-    pub fn new(window: &winit::window::Window, camera: &Camera) -> Self {
-        let base = Self::init(window);
+    pub fn new(
+        window: &winit::window::Window,
+        camera: &Camera,
+        backend: Backend,
+        adapter: &AdapterChoice,
+    ) -> Self {
+        let base = Self::init(window, backend, adapter);
 
-        let module = base
-            .device
-            .create_shader_module(wgpu::ShaderModuleDescriptor {
-                label: None,
-                source: wgpu::ShaderSource::Wgsl(include_str!("shader.wgsl").into()),
-            });
+        let surface_format = base.surface.get_supported_formats(&base.adapter)[0];
+        let mut pipelines = PipelineRegistry::new(&base.device, surface_format);
+
+        let size = window.inner_size();
+        let frame = FrameUniforms::new(camera, [size.width as f32, size.height as f32]);
 
-        let camera_buffer = base
+        let frame_buffer = base
             .device
             .create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                label: Some("Camera buffer"),
-                contents: bytemuck::cast_slice(&camera.compute().to_cols_array()),
+                label: Some("Frame buffer"),
+                contents: bytemuck::bytes_of(&frame),
                 usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
             });
 
-        let camera_bgl = base
-            .device
-            .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                label: Some("Camera bind group layout"),
-                entries: &[wgpu::BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: wgpu::ShaderStages::VERTEX,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Uniform,
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
-                    },
-                    count: None,
-                }],
-            });
-        let camera_bg = base.device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("Camera bind group"),
-            layout: &camera_bgl,
+        let frame_bg = base.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Frame bind group"),
+            layout: pipelines.layout(Layout::Frame),
             entries: &[wgpu::BindGroupEntry {
                 binding: 0,
                 resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
-                    buffer: &camera_buffer,
+                    buffer: &frame_buffer,
                     offset: 0,
                     size: None,
                 }),
@@ -176,29 +369,6 @@ impl Renderer {
             ..Default::default()
         });
 
-        let texture_bgl = base
-            .device
-            .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                label: Some("Texture bind group layout"),
-                entries: &[
-                    wgpu::BindGroupLayoutEntry {
-                        binding: 0,
-                        visibility: wgpu::ShaderStages::FRAGMENT,
-                        ty: wgpu::BindingType::Texture {
-                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
-                            view_dimension: wgpu::TextureViewDimension::D2,
-                            multisampled: false,
-                        },
-                        count: None,
-                    },
-                    wgpu::BindGroupLayoutEntry {
-                        binding: 1,
-                        visibility: wgpu::ShaderStages::FRAGMENT,
-                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
-                        count: None,
-                    },
-                ],
-            });
         // let texture_bg = base.device.create_bind_group(&wgpu::BindGroupDescriptor {
         //     label: Some("Texture bind group"),
         //     layout: &texture_bgl,
@@ -241,7 +411,7 @@ impl Renderer {
 
         let texture_atlas_bg = base.device.create_bind_group(&wgpu::BindGroupDescriptor {
             label: Some("Texture bind group"),
-            layout: &texture_bgl,
+            layout: pipelines.layout(Layout::Texture),
             entries: &[
                 wgpu::BindGroupEntry {
                     binding: 0,
@@ -254,63 +424,71 @@ impl Renderer {
             ],
         });
 
-        let pipeline_layout = base
-            .device
-            .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-                label: None,
-                bind_group_layouts: &[&camera_bgl, &texture_bgl],
-                push_constant_ranges: &[],
-            });
-        let pipeline = base
-            .device
-            .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-                label: None,
-                layout: Some(&pipeline_layout),
-                vertex: wgpu::VertexState {
-                    module: &module,
-                    entry_point: "vertex",
-                    buffers: &[
-                        wgpu::VertexBufferLayout {
-                            array_stride: std::mem::size_of::<Vertex>() as u64,
-                            step_mode: wgpu::VertexStepMode::Vertex,
-                            attributes: &vertex_attr_array![0 => Float32x3, 1 => Float32x2],
-                        },
-                        wgpu::VertexBufferLayout {
-                            array_stride: std::mem::size_of::<RenderInstance>() as u64,
-                            step_mode: wgpu::VertexStepMode::Instance,
-                            attributes: &vertex_attr_array![2 => Float32x4, 3 => Float32x4, 4 => Float32x4, 5 => Float32x4, 6 => Float32x2, 7 => Float32x2],
-                        },
-                    ],
-                },
-                primitive: wgpu::PrimitiveState {
-                    topology: wgpu::PrimitiveTopology::TriangleList,
-                    front_face: wgpu::FrontFace::Ccw,
-                    cull_mode: Some(wgpu::Face::Back),
-                    ..Default::default()
-                },
-                depth_stencil: Some(DepthStencilState{ format: texture::Texture::DEPTH_FORMAT, depth_write_enabled: true, depth_compare: wgpu::CompareFunction::Less, stencil: StencilState::default(), bias: DepthBiasState::default() }),
-                multisample: wgpu::MultisampleState::default(),
-                fragment: Some(wgpu::FragmentState {
-                    module: &module,
-                    entry_point: "fragment",
-                    targets: &[Some(wgpu::ColorTargetState {
-                        format: base.surface.get_supported_formats(&base.adapter)[0],
-                        blend: Some(wgpu::BlendState::REPLACE),
-                        write_mask: wgpu::ColorWrites::all(),
-                    })],
-                }),
-                multiview: None,
-            });
-
-        let vertices_data = crate::world::cube_vertices();
-
-        let vertices = base
-            .device
-            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                label: Some("Vertex buffer"),
-                contents: bytemuck::cast_slice(&vertices_data),
-                usage: wgpu::BufferUsages::VERTEX,
-            });
+        let object_builder = |label| {
+            PipelineBuilder::new(label, "shader")
+                .vertex_buffer::<Vertex>(
+                    wgpu::VertexStepMode::Vertex,
+                    &vertex_attr_array![0 => Float32x3, 1 => Float32x2],
+                )
+                .vertex_buffer::<RenderInstance>(
+                    wgpu::VertexStepMode::Instance,
+                    &vertex_attr_array![2 => Float32x4, 3 => Float32x4, 4 => Float32x4, 5 => Float32x4, 6 => Float32x2, 7 => Float32x2, 8 => Float32, 9 => Float32x3, 10 => Float32],
+                )
+                .bind_groups(&[Layout::Frame, Layout::Texture])
+        };
+        let pipeline = object_builder("World pipeline").build(&mut pipelines, &base.device);
+        // fading and invisible entities, blended over everything solid without hiding each other
+        let translucent_object_pipeline = object_builder("Translucent object pipeline")
+            .blend(wgpu::BlendState::ALPHA_BLENDING)
+            .depth(wgpu::CompareFunction::Greater, false)
+            .build(&mut pipelines, &base.device);
+
+        #[cfg(not(feature = "vertex-pulling"))]
+        let chunk_builder = |label, fragment| {
+            PipelineBuilder::new(label, "chunk")
+                .entry_points("vertex", fragment)
+                .vertex_buffer::<[u32; 2]>(
+                    wgpu::VertexStepMode::Vertex,
+                    &vertex_attr_array![0 => Uint32, 1 => Uint32],
+                )
+        };
+        #[cfg(feature = "vertex-pulling")]
+        let chunk_builder = |label, fragment| {
+            PipelineBuilder::new(label, "chunk").entry_points("pulled_vertex", fragment)
+        };
+        let chunk_pipeline = chunk_builder("Chunk pipeline", "fragment")
+            .bind_groups(&[Layout::Frame, Layout::Texture, Layout::Chunk])
+            .build(&mut pipelines, &base.device);
+        // blended over the solid chunks without hiding each other, in sorted order
+        let translucent_pipeline =
+            chunk_builder("Translucent chunk pipeline", "translucent_fragment")
+                .bind_groups(&[Layout::Frame, Layout::Texture, Layout::Chunk])
+                .blend(wgpu::BlendState::ALPHA_BLENDING)
+                .depth(wgpu::CompareFunction::Greater, false)
+                .build(&mut pipelines, &base.device);
+
+        // seen from both sides and blended over whatever is behind it
+        let border_pipeline = PipelineBuilder::new("Border pipeline", "border")
+            .bind_groups(&[Layout::Frame])
+            .cull_mode(None)
+            .blend(wgpu::BlendState::ALPHA_BLENDING)
+            .depth(wgpu::CompareFunction::Greater, false)
+            .build(&mut pipelines, &base.device);
+
+        // drawn first on the far plane, everything else covers it
+        let sky_pipeline = PipelineBuilder::new("Sky pipeline", "sky")
+            .bind_groups(&[Layout::Frame])
+            .cull_mode(None)
+            .blend(wgpu::BlendState::ALPHA_BLENDING)
+            .depth(wgpu::CompareFunction::Always, false)
+            .build(&mut pipelines, &base.device);
+
+        let chunk_textures = base.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Chunk texture buffer"),
+            size: MAX_TEXTURES as u64 * std::mem::size_of::<TextureInfo>() as u64,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
 
         let indices_data = crate::world::cube_indices();
 
@@ -322,59 +500,81 @@ impl Renderer {
                 usage: wgpu::BufferUsages::INDEX,
             });
 
-        let depth_texture = texture::Texture::create_depth_texture(
-            &base.device,
-            &Self::get_surface_config(&base.adapter, window, &base.surface),
-        );
+        let surface_config = Self::get_surface_config(&base.adapter, window, &base.surface);
+        let depth_texture = texture::Texture::create_depth_texture(&base.device, &surface_config);
+        let post = PostProcess::new(&base.device, &base.queue, &mut pipelines, &surface_config);
 
         Self {
-            num_objects: 0,
             base,
+            pipelines,
             pipeline,
-            camera_bg,
-            vertices,
+            frame,
+            frame_bg,
             indices,
-            vertices_length: vertices_data.len() as u32,
-            indices_length: indices_data.len() as u32,
-            camera_buffer,
+            frame_buffer,
+            surface_config,
+            ui_resize_strategy: ResizeStrategy::KeepY,
+            ui_scale: 1.0,
             depth_texture,
             objects: vec![],
             object_instances: vec![],
+            translucent_instances: vec![],
+            translucent_object_pipeline,
             texture_atlas: TextureAtlas::new(),
             textures: FxHashMap::default(),
+            animations: FxHashMap::default(),
+            alpha: 1.0,
             texture_atlas_tex,
             sampler,
             texture_atlas_bg,
             texture_atlas_extend: texture_size,
-            texture_atlas_bgl: texture_bgl,
             font_count: 0,
             fonts: vec![],
             text_module: None,
             instance_buffer: None,
+            chunk_pipeline,
+            chunk_textures,
+            variations: FxHashMap::default(),
+            chunks: FxHashMap::default(),
+            translucent_pipeline,
+            translucent_chunks: FxHashMap::default(),
+            render_distance: i32::MAX,
+            draw_calls: 0,
+            border_pipeline,
+            draw_border: false,
+            sky_pipeline,
+            draw_sky: true,
+            post,
         }
     }
 
-    pub fn init(window: &winit::window::Window) -> RendererBase {
-        let instance = wgpu::Instance::new(wgpu::Backends::all());
-        let surface = unsafe { instance.create_surface(window) };
-        let (adapter, device, queue) = pollster::block_on(async {
-            let adapter = instance
-                .request_adapter(&wgpu::RequestAdapterOptionsBase::default())
-                .await
-                .unwrap();
-            let (device, queue) = adapter
-                .request_device(&wgpu::DeviceDescriptor::default(), None)
-                .await
+    pub fn init(
+        window: &winit::window::Window,
+        backend: Backend,
+        adapter: &AdapterChoice,
+    ) -> RendererBase {
+        let mut backend = backend;
+        let (surface, adapter) = loop {
+            let instance = wgpu::Instance::new(backend.backends());
+            let surface = unsafe { instance.create_surface(window) };
+            match pollster::block_on(gpu::request_adapter(&instance, &surface, backend, adapter)) {
+                Some(adapter) => break (surface, adapter),
+                None if backend != Backend::Auto => {
+                    eprintln!("no graphics adapter supports {backend:?}, trying every backend");
+                    backend = Backend::Auto;
+                }
+                None => panic!("no graphics adapter can draw to the window"),
+            }
+        };
+        let (device, queue) =
+            pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor::default(), None))
                 .unwrap();
-            (adapter, device, queue)
-        });
 
         let surface_config = Self::get_surface_config(&adapter, window, &surface);
 
         surface.configure(&device, &surface_config);
 
         RendererBase {
-            instance,
             surface,
             adapter,
             device,
@@ -398,149 +598,100 @@ impl Renderer {
     }
 
     pub fn init_text_pipeline(&mut self) {
-        let module = self
+        let mut camera =
+            Camera::new_orthographic(vec3(0.0, 0.0, 0.0), 0.0, 800.0, 0.0, 600.0, 0.0, 100.0);
+        camera.resize(self.screen_size(), self.ui_resize_strategy);
+        camera.set_scale(self.ui_scale);
+
+        let frame_buffer = self
             .base
             .device
-            .create_shader_module(wgpu::ShaderModuleDescriptor {
-                label: None,
-                source: wgpu::ShaderSource::Wgsl(include_str!("text.wgsl").into()),
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Text frame buffer"),
+                contents: bytemuck::bytes_of(&FrameUniforms {
+                    view_proj: camera.compute().to_cols_array(),
+                    ..self.frame
+                }),
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
             });
 
-        let camera =
-            Camera::new_orthographic(vec3(0.0, 0.0, 0.0), 0.0, 800.0, 0.0, 600.0, 0.0, 100.0);
-
-        let camera_buffer =
-            self.base
-                .device
-                .create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                    label: Some("Camera buffer"),
-                    contents: bytemuck::cast_slice(&camera.compute().to_cols_array()),
-                    usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-                });
-
-        let camera_bgl =
-            self.base
-                .device
-                .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                    label: Some("Camera bind group layout"),
-                    entries: &[wgpu::BindGroupLayoutEntry {
-                        binding: 0,
-                        visibility: wgpu::ShaderStages::VERTEX,
-                        ty: wgpu::BindingType::Buffer {
-                            ty: wgpu::BufferBindingType::Uniform,
-                            has_dynamic_offset: false,
-                            min_binding_size: None,
-                        },
-                        count: None,
-                    }],
-                });
-
-        let camera_bg = self
+        let frame_bg = self
             .base
             .device
             .create_bind_group(&wgpu::BindGroupDescriptor {
-                label: Some("Camera bind group"),
-                layout: &camera_bgl,
+                label: Some("Text frame bind group"),
+                layout: self.pipelines.layout(Layout::Frame),
                 entries: &[wgpu::BindGroupEntry {
                     binding: 0,
                     resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
-                        buffer: &camera_buffer,
+                        buffer: &frame_buffer,
                         offset: 0,
                         size: None,
                     }),
                 }],
             });
 
-        let font_texture_bgl =
-            self.base
-                .device
-                .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                    label: Some("font texture bind group layout"),
-                    entries: &[
-                        wgpu::BindGroupLayoutEntry {
-                            binding: 0,
-                            visibility: wgpu::ShaderStages::FRAGMENT,
-                            ty: wgpu::BindingType::Texture {
-                                sample_type: wgpu::TextureSampleType::Float { filterable: true },
-                                view_dimension: wgpu::TextureViewDimension::D2,
-                                multisampled: false,
-                            },
-                            count: None,
-                        },
-                        wgpu::BindGroupLayoutEntry {
-                            binding: 1,
-                            visibility: wgpu::ShaderStages::FRAGMENT,
-                            ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
-                            count: None,
-                        },
-                    ],
-                });
-
-        let text_pipeline_layout =
-            self.base
-                .device
-                .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-                    label: Some("Text pipeline layout"),
-                    bind_group_layouts: &[&camera_bgl, &font_texture_bgl],
-                    push_constant_ranges: &[],
-                });
-        let text_pipeline =
-            self.base
-                .device
-                .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-                    label: Some("Text pipeline"),
-                    layout: Some(&text_pipeline_layout),
-                    vertex: VertexState {
-                        module: &module,
-                        entry_point: "vertex",
-                        buffers: &[wgpu::VertexBufferLayout {
-                            array_stride: std::mem::size_of::<TextVertex>() as u64,
-                            step_mode: wgpu::VertexStepMode::Vertex,
-                            attributes: &vertex_attr_array![0 => Float32x2, 1 => Float32x2],
-                        }],
-                    },
-                    primitive: wgpu::PrimitiveState {
-                        topology: wgpu::PrimitiveTopology::TriangleList,
-                        front_face: wgpu::FrontFace::Ccw,
-                        cull_mode: Some(wgpu::Face::Back),
-                        ..Default::default()
-                    },
-                    depth_stencil: Some(DepthStencilState {
-                        format: texture::Texture::DEPTH_FORMAT,
-                        depth_write_enabled: true,
-                        depth_compare: wgpu::CompareFunction::Always,
-                        stencil: StencilState::default(),
-                        bias: DepthBiasState::default(),
-                    }),
-
-                    multisample: wgpu::MultisampleState::default(),
-                    fragment: Some(FragmentState {
-                        module: &module,
-                        entry_point: "fragment",
-                        targets: &[Some(wgpu::ColorTargetState {
-                            format: self.base.surface.get_supported_formats(&self.base.adapter)[0],
-                            blend: Some(wgpu::BlendState::ALPHA_BLENDING),
-                            write_mask: wgpu::ColorWrites::all(),
-                        })],
-                    }),
-                    multiview: None,
-                });
+        let text_pipeline = PipelineBuilder::new("Text pipeline", "text")
+            .vertex_buffer::<TextVertex>(
+                wgpu::VertexStepMode::Vertex,
+                &vertex_attr_array![0 => Float32x2, 1 => Float32x2],
+            )
+            .bind_groups(&[Layout::Frame, Layout::Texture])
+            .blend(wgpu::BlendState::ALPHA_BLENDING)
+            .depth(wgpu::CompareFunction::Always, true)
+            .build(&mut self.pipelines, &self.base.device);
+
+        // world text is depth tested against the scene but doesn't write depth, so overlapping
+        // labels blend rather than cut holes in each other
+        let world_text_pipeline = PipelineBuilder::new("World text pipeline", "text")
+            .entry_points("world_vertex", "fragment")
+            .vertex_buffer::<TextVertex>(
+                wgpu::VertexStepMode::Vertex,
+                &vertex_attr_array![0 => Float32x2, 1 => Float32x2],
+            )
+            .vertex_buffer::<[f32; 3]>(
+                wgpu::VertexStepMode::Instance,
+                &vertex_attr_array![2 => Float32x3],
+            )
+            .bind_groups(&[Layout::Frame, Layout::Texture])
+            .blend(wgpu::BlendState::ALPHA_BLENDING)
+            .depth(wgpu::CompareFunction::Greater, false)
+            .build(&mut self.pipelines, &self.base.device);
 
         self.text_module = Some(TextModule {
             pipeline: text_pipeline,
-            bgl: font_texture_bgl,
             text_meshes: FxHashMap::default(),
-            camera_bg,
+            texts: vec![],
+            text_queue: vec![],
+            world_pipeline: world_text_pipeline,
+            world_text_meshes: vec![],
+            world_text_queue: vec![],
+            world_text_instances: None,
+            ui_quads: vec![],
+            camera,
+            frame_buffer,
+            frame_bg,
         })
     }
 
-    pub fn register_font(&mut self, font: Font) -> FontHandle {
+    pub fn register_font(&mut self, mut font: Font) -> FontHandle {
         let handle = self.font_count;
         self.font_count += 1;
+        font.take_changed();
+        let (texture, size, bind_group) = self.create_font_texture(&font);
+        self.fonts.push(LoadedFont {
+            font,
+            texture,
+            size,
+            bind_group,
+        });
+        handle
+    }
 
+    fn create_font_texture(&self, font: &Font) -> (wgpu::Texture, wgpu::Extent3d, wgpu::BindGroup) {
         let texture_size = wgpu::Extent3d {
-            width: font.atlas.width as u32,
-            height: font.atlas.height as u32,
+            width: font.tex.width(),
+            height: font.tex.height(),
             depth_or_array_layers: 1,
         };
         let tex = self.base.device.create_texture_with_data(
@@ -554,7 +705,7 @@ impl Renderer {
                 format: wgpu::TextureFormat::Rgba8UnormSrgb,
                 usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
             },
-            font.tex.as_bytes(),
+            font.tex.as_raw(),
         );
 
         let texture_view = tex.create_view(&wgpu::TextureViewDescriptor::default());
@@ -564,11 +715,7 @@ impl Renderer {
             .device
             .create_bind_group(&wgpu::BindGroupDescriptor {
                 label: Some("Font texture bind group"),
-                layout: &self
-                    .text_module
-                    .as_ref()
-                    .expect("Expected text module to be initialised.")
-                    .bgl,
+                layout: self.pipelines.layout(Layout::Texture),
                 entries: &[
                     wgpu::BindGroupEntry {
                         binding: 0,
@@ -580,90 +727,135 @@ impl Renderer {
                     },
                 ],
             });
-        self.fonts.push((font, bind_group));
-        handle
+        (tex, texture_size, bind_group)
     }
 
-    pub fn create_text_mesh(
+    /// Uploads a font's atlas again if glyphs have been added to it, in place if it's still the
+    /// same size and to a new texture if it's grown. Meshes find the texture by font handle
+    /// when they're drawn, so ones already made carry on working.
+    fn upload_font(&mut self, font_handle: FontHandle) {
+        let loaded = &mut self.fonts[font_handle as usize];
+        if !loaded.font.take_changed() {
+            return;
+        }
+        let (width, height) = loaded.font.tex.dimensions();
+        let size = loaded.size;
+        if size.width != width || size.height != height {
+            let (texture, size, bind_group) =
+                self.create_font_texture(&self.fonts[font_handle as usize].font);
+            let loaded = &mut self.fonts[font_handle as usize];
+            loaded.texture = texture;
+            loaded.size = size;
+            loaded.bind_group = bind_group;
+            return;
+        }
+        self.base.queue.write_texture(
+            wgpu::ImageCopyTexture {
+                texture: &loaded.texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            loaded.font.tex.as_raw(),
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: std::num::NonZeroU32::new(4 * width),
+                rows_per_image: std::num::NonZeroU32::new(height),
+            },
+            size,
+        );
+    }
+
+    /// How many window pixels tall one ui unit is drawn.
+    fn pixels_per_ui_unit(&self) -> f32 {
+        1.0 / (self.screen_to_ui(0.0, 0.0).y - self.screen_to_ui(0.0, 1.0).y)
+    }
+
+    /// Lays out one line of text as a quad per glyph with its baseline starting at (x, y), and
+    /// returns the quads along with the horizontal advance of the whole line. Glyphs are the
+    /// font's rasterised at `px` and scaled to match, `scale` is relative to its own pixel size.
+    fn layout_text(
         &mut self,
         text: &str,
         font_handle: FontHandle,
+        px: u32,
         x: f32,
         y: f32,
         scale: f32,
-    ) -> TextMesh {
+    ) -> (Vec<TextVertex>, Vec<u16>, f32) {
+        let font = &mut self
+            .fonts
+            .get_mut(font_handle as usize)
+            .unwrap_or_else(|| panic!("Couldn't load font corresponding to handle {font_handle}."))
+            .font;
+        let scale = scale * font.pixel_size as f32 / px as f32;
         // technically we want grapheme clusters, not unicode chars but we can worry about it later
         let mut vertex_data: Vec<TextVertex> = vec![];
         let mut index_data: Vec<u16> = vec![];
         let mut current_width = -0.5;
         for char in text.chars() {
-            let (font, _) = self.fonts.get(font_handle as usize).unwrap_or_else(|| {
-                panic!("Couldn't load font corresponding to handle {font_handle}.")
-            });
-            let rect = font.get_char_rect(char);
+            let Glyph { rect, metric } = font.glyph(char, px);
             // v0----v1
             // | \   |
             // |  \  |
             // |   \ |
             // v2----v3
-            let metrics = font
-                .metrics
-                .get(&char)
-                .unwrap_or_else(|| panic!("Couldn't find metrics for character {char}."));
-            let xpos = x + current_width + metrics.bearing.x as f32 * scale;
-            let ypos = y - (metrics.size.y - metrics.bearing.y) as f32 * scale;
-            let w = metrics.size.x as f32 * scale;
-            let h = metrics.size.y as f32 * scale;
-            let uv_width = font.atlas.width as f32;
-            let uv_height = font.atlas.height as f32;
+            let xpos = x + current_width + metric.bearing.x as f32 * scale;
+            let ypos = y - (metric.size.y - metric.bearing.y) as f32 * scale;
+            let w = metric.size.x as f32 * scale;
+            let h = metric.size.y as f32 * scale;
+            // in atlas pixels, the shader divides by the texture's size so they still hold
+            // after the atlas grows
+            let (left, top) = (rect.x as f32, rect.y as f32);
+            let (right, bottom) = ((rect.x + rect.w) as f32, (rect.y + rect.h) as f32);
 
             let vertices = [
                 TextVertex {
                     position: [xpos, ypos + h],
-                    uv: [rect.x as f32 / uv_width, rect.y as f32 / uv_height],
+                    uv: [left, top],
                 }, // v0
                 TextVertex {
                     position: [xpos + w, ypos + h],
-                    uv: [
-                        (rect.x as f32 + rect.w as f32) / uv_width,
-                        rect.y as f32 / uv_height,
-                    ],
+                    uv: [right, top],
                 }, // v1
                 TextVertex {
                     position: [xpos, ypos],
-                    uv: [
-                        rect.x as f32 / uv_width,
-                        (rect.y as f32 + rect.h as f32) / uv_height,
-                    ],
+                    uv: [left, bottom],
                 }, // v2
                 TextVertex {
                     position: [xpos + w, ypos],
-                    uv: [
-                        (rect.x as f32 + rect.w as f32) / uv_width,
-                        (rect.y as f32 + rect.h as f32) / uv_height,
-                    ],
+                    uv: [right, bottom],
                 }, // v3
             ];
-            current_width += (metrics.advance >> 6) as f32 * scale;
+            current_width += (metric.advance >> 6) as f32 * scale;
 
             let start = vertex_data.len() as u16;
             let indices = [start, start + 2, start + 3, start, start + 3, start + 1];
-            // println!("char {char}, rect {rect:?},\nvertices: {vertices:?},\nindices: {indices:?}");
 
             vertex_data.extend(vertices);
             index_data.extend(indices);
         }
 
         assert!(vertex_data.len() / 4 == index_data.len() / 6);
+        self.upload_font(font_handle);
+
+        (vertex_data, index_data, current_width)
+    }
 
+    fn upload_text(
+        &self,
+        font_handle: FontHandle,
+        vertex_data: &[TextVertex],
+        index_data: &[u16],
+    ) -> TextMesh {
         let vertex_buffer = self.base.device.create_buffer_init(&BufferInitDescriptor {
             label: Some("Text vertex buffer"),
-            contents: bytemuck::cast_slice(&vertex_data),
+            contents: bytemuck::cast_slice(vertex_data),
             usage: wgpu::BufferUsages::VERTEX,
         });
         let index_buffer = self.base.device.create_buffer_init(&BufferInitDescriptor {
             label: Some("Text index buffer"),
-            contents: bytemuck::cast_slice(&index_data),
+            contents: bytemuck::cast_slice(index_data),
             usage: wgpu::BufferUsages::INDEX,
         });
 
@@ -675,12 +867,158 @@ impl Renderer {
         }
     }
 
-    pub fn queue_draw_text_mesh(&mut self, text_mesh: TextMesh) {
-        let map = &mut self
-            .text_module
+    pub fn create_text_mesh(
+        &mut self,
+        text: &str,
+        font_handle: FontHandle,
+        x: f32,
+        y: f32,
+        scale: f32,
+    ) -> TextMesh {
+        // sharp at the size it's drawn on screen right now
+        let font = &self.fonts[font_handle as usize].font;
+        let height = font.pixel_size as f32 * scale * self.pixels_per_ui_unit();
+        let px = font.size_for(height);
+        let (vertex_data, index_data, _) = self.layout_text(text, font_handle, px, x, y, scale);
+        self.upload_text(font_handle, &vertex_data, &index_data)
+    }
+
+    fn text_module_mut(&mut self) -> &mut TextModule {
+        self.text_module
             .as_mut()
             .expect("Text module not initialised.")
-            .text_meshes;
+    }
+
+    /// Creates screen text with its baseline starting at (x, y) in ui units.
+    pub fn create_text(
+        &mut self,
+        text: &str,
+        font_handle: FontHandle,
+        x: f32,
+        y: f32,
+        scale: f32,
+    ) -> TextHandle {
+        let mesh = self.create_text_mesh(text, font_handle, x, y, scale);
+        let texts = &mut self.text_module_mut().texts;
+        texts.push(mesh);
+        texts.len() as TextHandle - 1
+    }
+
+    /// Replaces the contents of screen text created with `create_text`.
+    pub fn set_text(
+        &mut self,
+        handle: TextHandle,
+        text: &str,
+        font_handle: FontHandle,
+        x: f32,
+        y: f32,
+        scale: f32,
+    ) {
+        let mesh = self.create_text_mesh(text, font_handle, x, y, scale);
+        self.text_module_mut().texts[handle as usize] = mesh;
+    }
+
+    /// Draws screen text this frame.
+    pub fn queue_draw_text(&mut self, handle: TextHandle) {
+        self.text_module_mut().text_queue.push(handle);
+    }
+
+    /// Creates text that can be drawn at any point in the world, always facing the camera. The
+    /// text is `height` world units tall and centred horizontally on the point it's drawn at,
+    /// with its baseline through it.
+    pub fn create_world_text(
+        &mut self,
+        text: &str,
+        font_handle: FontHandle,
+        height: f32,
+    ) -> WorldTextHandle {
+        let mesh = self.create_world_text_mesh(text, font_handle, height);
+        let world_text_meshes = &mut self.text_module_mut().world_text_meshes;
+        world_text_meshes.push(mesh);
+        world_text_meshes.len() as WorldTextHandle - 1
+    }
+
+    /// Replaces the contents of world text created with `create_world_text`.
+    pub fn set_world_text(
+        &mut self,
+        handle: WorldTextHandle,
+        text: &str,
+        font_handle: FontHandle,
+        height: f32,
+    ) {
+        let mesh = self.create_world_text_mesh(text, font_handle, height);
+        self.text_module_mut().world_text_meshes[handle as usize] = mesh;
+    }
+
+    fn create_world_text_mesh(
+        &mut self,
+        text: &str,
+        font_handle: FontHandle,
+        height: f32,
+    ) -> TextMesh {
+        // seen from any distance, so always at the font's full size
+        let px = self.fonts[font_handle as usize].font.pixel_size;
+        let scale = height / px as f32;
+        let (mut vertex_data, index_data, width) =
+            self.layout_text(text, font_handle, px, 0.0, 0.0, scale);
+        for vertex in vertex_data.iter_mut() {
+            vertex.position[0] -= width / 2.0;
+        }
+        self.upload_text(font_handle, &vertex_data, &index_data)
+    }
+
+    /// Draws world text at `position` this frame.
+    pub fn queue_draw_world_text(&mut self, handle: WorldTextHandle, position: Vec3) {
+        self.text_module_mut()
+            .world_text_queue
+            .push((handle, position.to_array()));
+    }
+
+    /// Draws a texture from the atlas stretched over a rectangle of the ui this frame, under
+    /// any screen text.
+    pub fn queue_ui_quad(&mut self, min: Vec2, max: Vec2, texture: TextureHandle) {
+        // in atlas pixels like text
+        let rect = self.shown_rect(texture);
+        let uv_min = vec2(rect.x as f32, rect.y as f32);
+        let uv_max = vec2((rect.x + rect.w) as f32, (rect.y + rect.h) as f32);
+        let vertex = |x: f32, y: f32, u: f32, v: f32| TextVertex {
+            position: [x, y],
+            uv: [u, v],
+        };
+        // the same winding as text, the image's top at max.y
+        let top_left = vertex(min.x, max.y, uv_min.x, uv_min.y);
+        let top_right = vertex(max.x, max.y, uv_max.x, uv_min.y);
+        let bottom_left = vertex(min.x, min.y, uv_min.x, uv_max.y);
+        let bottom_right = vertex(max.x, min.y, uv_max.x, uv_max.y);
+        self.text_module_mut().ui_quads.extend([
+            top_left,
+            bottom_left,
+            bottom_right,
+            top_left,
+            bottom_right,
+            top_right,
+        ]);
+    }
+
+    /// Where a point in window pixels, measured from the top left, lands on the ui.
+    pub fn screen_to_ui(&self, x: f32, y: f32) -> Vec2 {
+        let size = self.screen_size();
+        let ndc = vec3(
+            x / size.width as f32 * 2.0 - 1.0,
+            1.0 - y / size.height as f32 * 2.0,
+            0.0,
+        );
+        let camera = &self
+            .text_module
+            .as_ref()
+            .expect("Text module not initialised.")
+            .camera;
+        camera.compute().inverse().project_point3(ndc).truncate()
+    }
+
+    #[allow(dead_code)]
+    pub fn queue_draw_text_mesh(&mut self, text_mesh: TextMesh) {
+        let map = &mut self.text_module_mut().text_meshes;
         if let Some(value) = map.get_mut(&text_mesh.font_handle) {
             value.push(text_mesh)
         } else {
@@ -699,6 +1037,101 @@ impl Renderer {
         handle
     }
 
+    /// Registers a strip of frames, one above the other, that plays as `animation`. Every frame
+    /// goes in the atlas, and the returned handle draws whichever is current as `set_time` moves
+    /// on.
+    pub fn register_animated_texture(
+        &mut self,
+        strip: DynamicImage,
+        animation: Animation,
+    ) -> Result<TextureHandle, String> {
+        let frames = self.add_textures(animation.split(&strip)?);
+        let handle = frames[0];
+        self.animations.insert(
+            handle,
+            TextureAnimation {
+                animation,
+                frames,
+                shown: 0,
+            },
+        );
+        self.update_texture_buffer();
+        Ok(handle)
+    }
+
+    /// Registers each image of a sheet of `columns` by `rows`, returning the first handle. The
+    /// rest follow it in order, left to right then top to bottom.
+    pub fn register_texture_sheet(
+        &mut self,
+        sheet: DynamicImage,
+        columns: u32,
+        rows: u32,
+    ) -> Result<TextureHandle, String> {
+        let handles = self.add_textures(texture::split_sheet(&sheet, columns, rows)?);
+        self.update_texture_buffer();
+        Ok(handles[0])
+    }
+
+    /// Registers a texture that varies from block to block in chunks, its variants side by side
+    /// in `sheet`. Anything else drawing it shows the first variant.
+    pub fn register_varied_texture(
+        &mut self,
+        sheet: DynamicImage,
+        variation: Variation,
+    ) -> Result<TextureHandle, String> {
+        let handles = self.add_textures(texture::split_sheet(&sheet, variation.variants, 1)?);
+        self.variations.insert(handles[0], variation);
+        self.update_texture_buffer();
+        Ok(handles[0])
+    }
+
+    /// Adds `images` to the atlas under consecutive handles, without uploading it.
+    fn add_textures(&mut self, images: Vec<DynamicImage>) -> Vec<TextureHandle> {
+        let handles = images
+            .into_iter()
+            .map(|image| {
+                let handle = self
+                    .texture_atlas
+                    .add(image.width() as i32, image.height() as i32);
+                self.textures.insert(handle, image);
+                handle
+            })
+            .collect();
+        self.texture_atlas.pack();
+        handles
+    }
+
+    /// Where in the atlas the texture drawn with `handle` is right now.
+    fn shown_rect(&self, handle: TextureHandle) -> Rect {
+        let shown = self
+            .animations
+            .get(&handle)
+            .map_or(handle, |animation| animation.frames[animation.shown]);
+        self.texture_atlas
+            .get_rect(&shown)
+            .unwrap_or_else(|| panic!("No rect found for texture with handle {handle}"))
+            .0
+    }
+
+    /// Writes the atlas rect and variation of every texture for the chunk shaders, with
+    /// animated textures on their current frame.
+    fn write_texture_info(&self) {
+        let mut info = vec![TextureInfo::zeroed(); MAX_TEXTURES as usize];
+        for handle in self.textures.keys() {
+            let rect = self.shown_rect(*handle);
+            let variation = self.variations.get(handle);
+            info[*handle as usize] = TextureInfo {
+                rect: [rect.x as f32, rect.y as f32, rect.w as f32, rect.h as f32],
+                variants: variation.map_or(1, |variation| variation.variants),
+                rotate: variation.is_some_and(|variation| variation.rotate) as u32,
+                _padding: [0; 2],
+            };
+        }
+        self.base
+            .queue
+            .write_buffer(&self.chunk_textures, 0, bytemuck::cast_slice(&info));
+    }
+
     fn update_texture_buffer(&mut self) {
         // create texture from atlas and textures
         // how do we go from atlas to texture?
@@ -767,6 +1200,8 @@ impl Renderer {
             );
         }
 
+        self.write_texture_info();
+
         // recreate the view
         let texture_view = self
             .texture_atlas_tex
@@ -778,7 +1213,7 @@ impl Renderer {
             .device
             .create_bind_group(&wgpu::BindGroupDescriptor {
                 label: Some("Texture bind group"),
-                layout: &self.texture_atlas_bgl,
+                layout: self.pipelines.layout(Layout::Texture),
                 entries: &[
                     wgpu::BindGroupEntry {
                         binding: 0,
@@ -792,9 +1227,173 @@ impl Renderer {
             });
     }
 
-    fn create_object(&mut self, v: Vec<u8>, i: Vec<u8>, indices_length: usize) -> Object {
+    /// Replaces the mesh of the chunk at `coord` with `quads`, as built by `chunk::greedy_mesh`.
+    /// Returns the number of bytes uploaded for the mesh.
+    pub fn upload_chunk(&mut self, coord: IVec3, quads: &[Quad]) -> usize {
+        if quads.is_empty() {
+            self.chunks.remove(&coord);
+            return 0;
+        }
+        let (mesh, mesh_size) = self.create_chunk_mesh(coord, quads);
+        self.chunks.insert(coord, mesh);
+        mesh_size
+    }
+
+    /// Replaces the translucent mesh of the chunk at `coord` with `quads`, as built by
+    /// `chunk::translucent_mesh`. Returns the number of bytes uploaded for the mesh.
+    pub fn upload_translucent_chunk(&mut self, coord: IVec3, mut quads: Vec<Quad>) -> usize {
+        if quads.is_empty() {
+            self.translucent_chunks.remove(&coord);
+            return 0;
+        }
+        let camera = Vec3::from(self.frame.camera_position);
+        chunk::sort_back_to_front(&mut quads, chunk_space(coord, camera));
+        let (mesh, mesh_size) = self.create_chunk_mesh(coord, &quads);
+        self.translucent_chunks.insert(
+            coord,
+            TranslucentMesh {
+                mesh,
+                quads,
+                sorted_from: camera,
+            },
+        );
+        mesh_size
+    }
+
+    /// Drops every chunk mesh, for when the world they were built from is replaced.
+    pub fn clear_chunks(&mut self) {
+        self.chunks.clear();
+        self.translucent_chunks.clear();
+    }
+
+    /// Sorts the translucent chunks in view again if the camera has moved far enough since they
+    /// were last sorted.
+    fn sort_translucent_chunks(&mut self) {
+        let camera = Vec3::from(self.frame.camera_position);
+        let camera_chunk = chunk_coord(block_position(camera));
+        let stale: Vec<IVec3> = self
+            .translucent_chunks
+            .iter()
+            .filter(|(coord, translucent)| {
+                (**coord - camera_chunk).abs().max_element() <= self.render_distance
+                    && translucent.sorted_from.distance(camera) > RESORT_DISTANCE
+            })
+            .map(|(coord, _)| *coord)
+            .collect();
+        for coord in stale {
+            let mut translucent = self.translucent_chunks.remove(&coord).unwrap();
+            chunk::sort_back_to_front(&mut translucent.quads, chunk_space(coord, camera));
+            self.write_chunk_mesh(&translucent.mesh, &translucent.quads);
+            translucent.sorted_from = camera;
+            self.translucent_chunks.insert(coord, translucent);
+        }
+    }
+
+    /// Uploads `quads` as the mesh of the chunk at `coord`, along with its size in bytes.
+    fn create_chunk_mesh(&self, coord: IVec3, quads: &[Quad]) -> (ChunkMesh, usize) {
+        let origin = (coord * CHUNK_SIZE).as_vec3().extend(0.0);
+        let origin_buffer = self.base.device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("Chunk origin buffer"),
+            contents: bytemuck::bytes_of(&origin.to_array()),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+        #[cfg_attr(not(feature = "vertex-pulling"), allow(unused_mut))]
+        let mut entries = vec![
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: self.chunk_textures.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: origin_buffer.as_entire_binding(),
+            },
+        ];
+
+        #[cfg(feature = "vertex-pulling")]
+        let (mesh_size, quad_buffer) = {
+            let packed: Vec<[u32; 4]> = quads.iter().map(Quad::pack).collect();
+            let quad_buffer = self.base.device.create_buffer_init(&BufferInitDescriptor {
+                label: Some("Chunk quad buffer"),
+                contents: bytemuck::cast_slice(&packed),
+                usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            });
+            (std::mem::size_of_val(packed.as_slice()), quad_buffer)
+        };
+        #[cfg(feature = "vertex-pulling")]
+        entries.push(wgpu::BindGroupEntry {
+            binding: 2,
+            resource: quad_buffer.as_entire_binding(),
+        });
+
+        #[cfg(not(feature = "vertex-pulling"))]
+        let (mesh_size, vertex_buffer, index_buffer) = {
+            let mut vertices = Vec::with_capacity(quads.len() * 4);
+            let mut indices: Vec<u16> = Vec::with_capacity(quads.len() * 6);
+            for quad in quads {
+                let start = vertices.len() as u16;
+                vertices.extend(quad.vertices());
+                indices.extend([start, start + 1, start + 2, start, start + 2, start + 3]);
+            }
+            let vertex_buffer = self.base.device.create_buffer_init(&BufferInitDescriptor {
+                label: Some("Chunk vertex buffer"),
+                contents: bytemuck::cast_slice(&vertices),
+                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            });
+            let index_buffer = self.base.device.create_buffer_init(&BufferInitDescriptor {
+                label: Some("Chunk index buffer"),
+                contents: bytemuck::cast_slice(&indices),
+                usage: wgpu::BufferUsages::INDEX,
+            });
+            let mesh_size = std::mem::size_of_val(vertices.as_slice())
+                + std::mem::size_of_val(indices.as_slice());
+            (mesh_size, vertex_buffer, index_buffer)
+        };
+
+        let bind_group = self
+            .base
+            .device
+            .create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Chunk bind group"),
+                layout: self.pipelines.layout(Layout::Chunk),
+                entries: &entries,
+            });
+        let mesh = ChunkMesh {
+            bind_group,
+            #[cfg(not(feature = "vertex-pulling"))]
+            vertex_buffer,
+            #[cfg(not(feature = "vertex-pulling"))]
+            index_buffer,
+            #[cfg(feature = "vertex-pulling")]
+            quad_buffer,
+            num_quads: quads.len() as u32,
+        };
+        (mesh, mesh_size)
+    }
+
+    /// Overwrites `mesh` with `quads` in place, which must be as many quads as it was made with.
+    /// Each quad's indices only depend on its place in the list, so only the quads themselves
+    /// need writing.
+    fn write_chunk_mesh(&self, mesh: &ChunkMesh, quads: &[Quad]) {
+        debug_assert_eq!(mesh.num_quads as usize, quads.len());
+        #[cfg(feature = "vertex-pulling")]
+        {
+            let packed: Vec<[u32; 4]> = quads.iter().map(Quad::pack).collect();
+            self.base
+                .queue
+                .write_buffer(&mesh.quad_buffer, 0, bytemuck::cast_slice(&packed));
+        }
+        #[cfg(not(feature = "vertex-pulling"))]
+        {
+            let vertices: Vec<_> = quads.iter().flat_map(Quad::vertices).collect();
+            self.base
+                .queue
+                .write_buffer(&mesh.vertex_buffer, 0, bytemuck::cast_slice(&vertices));
+        }
+    }
+
+    fn create_object(&mut self, id: u32, v: Vec<u8>, i: Vec<u8>, indices_length: usize) -> Object {
         Object {
-            id: self.num_objects,
+            id,
             vertex_data: v,
             index_data: i,
             vertex_buffer: None,
@@ -822,17 +1421,12 @@ impl Renderer {
             });
         object.vertex_buffer = Some(vertices);
         object.index_buffer = Some(indices);
-        if object.id >= self.objects.len() as u32 {
-            // the object is new
-            self.objects.push(object);
-            self.object_instances.push(vec![instance.unwrap()]);
-        } else {
-            // the object already exists
-            self.object_instances
-                .get_mut(object.id as usize)
-                .unwrap()
-                .push(instance.unwrap());
-        }
+        self.objects.push(object);
+        let (translucent, opaque) = instance
+            .into_iter()
+            .partition(|instance| instance.alpha < 1.0);
+        self.object_instances.push(opaque);
+        self.translucent_instances.push(translucent);
         // self.objects.insert(
         //     object,
         //     if let Some(instance) = instance {
@@ -848,52 +1442,114 @@ impl Renderer {
         // if not existing, register it under a new bucket
 
         let instance = drawable.instance(world);
-        let rect = self
-            .texture_atlas
-            .get_rect(&instance.texture)
-            .unwrap_or_else(|| panic!("No rect found for texture with handle {}", instance.texture))
-            .0;
+        let rect = self.shown_rect(instance.texture);
         let render_instance = RenderInstance {
-            raw: instance.raw(),
+            raw: instance.raw(self.alpha),
             tex_offset: [rect.x as f32, rect.y as f32],
             tex_size: [rect.w as f32, rect.h as f32],
+            flash: instance.flash,
+            tint: instance.tint.into(),
+            alpha: instance.alpha,
         };
 
-        if object_id >= self.objects.len() as u32 {
-            // register this object
+        // objects are registered the first time they're drawn, in whatever order that happens
+        if let Some(index) = self
+            .objects
+            .iter()
+            .position(|object| object.id == object_id)
+        {
+            if render_instance.alpha < 1.0 {
+                self.translucent_instances[index].push(render_instance);
+            } else {
+                self.object_instances[index].push(render_instance);
+            }
+        } else {
             let v_data: Vec<u8> = bytemuck::cast_slice(&drawable.vertices()).to_vec();
             let i_data: Vec<u8> = bytemuck::cast_slice(&drawable.indices()).to_vec();
-            let object = self.create_object(v_data, i_data, drawable.indices().len());
+            let object = self.create_object(object_id, v_data, i_data, drawable.indices().len());
 
             self.register_object(object, Some(render_instance));
-        } else {
-            let v = self.object_instances.get_mut(object_id as usize);
-            if let Some(instances) = v {
-                instances.push(render_instance);
-            } else {
-                panic!("Expected to find Object in Renderer.")
-            }
         }
     }
 
     pub fn draw(&mut self) {
-        if self.instance_buffer.is_none() {
-            self.instance_buffer = Some(
-                self.base.device.create_buffer(&wgpu::BufferDescriptor {
-                    label: Some("Instance buffer"),
-                    size: std::mem::size_of::<RenderInstance>() as u64
-                        * self
-                            .object_instances
-                            .iter()
-                            .max_by(|a, b| a.len().cmp(&b.len()))
-                            .map_or(0, |x| x.len()) as u64,
-                    usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
-                    mapped_at_creation: false,
+        self.sort_translucent_chunks();
+        let mut draw_calls = 0;
+        // every object's instances go in one buffer, one after the other, since writes to the
+        // same range would all land before any of the draws run
+        let required_size = std::mem::size_of::<RenderInstance>() as u64
+            * self
+                .object_instances
+                .iter()
+                .chain(&self.translucent_instances)
+                .map(|instances| instances.len() as u64)
+                .sum::<u64>();
+        // (re)create the instance buffer whenever this frame's instances no longer fit
+        if self
+            .instance_buffer
+            .as_ref()
+            .is_none_or(|buffer| buffer.size() < required_size)
+        {
+            self.instance_buffer = Some(self.base.device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Instance buffer"),
+                size: required_size,
+                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            }));
+        }
+
+        let instance_buffer = self.instance_buffer.as_ref().unwrap();
+
+        self.base
+            .queue
+            .write_buffer(&self.frame_buffer, 0, bytemuck::bytes_of(&self.frame));
+        if let Some(text_module) = &mut self.text_module {
+            let required_size = std::mem::size_of_val(text_module.world_text_queue.as_slice());
+            if text_module
+                .world_text_instances
+                .as_ref()
+                .is_none_or(|buffer| buffer.size() < required_size as u64)
+            {
+                text_module.world_text_instances =
+                    Some(self.base.device.create_buffer(&wgpu::BufferDescriptor {
+                        label: Some("World text instance buffer"),
+                        size: required_size as u64,
+                        usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                        mapped_at_creation: false,
+                    }));
+            }
+            let anchors: Vec<[f32; 3]> = text_module
+                .world_text_queue
+                .iter()
+                .map(|(_, position)| *position)
+                .collect();
+            self.base.queue.write_buffer(
+                text_module.world_text_instances.as_ref().unwrap(),
+                0,
+                bytemuck::cast_slice(&anchors),
+            );
+            self.base.queue.write_buffer(
+                &text_module.frame_buffer,
+                0,
+                bytemuck::bytes_of(&FrameUniforms {
+                    view_proj: text_module.camera.compute().to_cols_array(),
+                    ..self.frame
                 }),
             );
         }
 
-        let instance_buffer = self.instance_buffer.as_ref().unwrap();
+        // rebuilt every frame, there are only ever a few dozen
+        let ui_quads = self
+            .text_module
+            .as_ref()
+            .filter(|text_module| !text_module.ui_quads.is_empty())
+            .map(|text_module| {
+                self.base.device.create_buffer_init(&BufferInitDescriptor {
+                    label: Some("Ui quad buffer"),
+                    contents: bytemuck::cast_slice(&text_module.ui_quads),
+                    usage: wgpu::BufferUsages::VERTEX,
+                })
+            });
 
         let frame = self.base.surface.get_current_texture().unwrap();
 
@@ -906,16 +1562,24 @@ impl Renderer {
             .device
             .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
 
+        // with post-processing the world goes offscreen first, to be composited onto the frame
+        // with its glow and grading before the ui is drawn over it
+        let scene = if self.post.enabled() {
+            self.post.scene()
+        } else {
+            view
+        };
         let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-            label: None,
+            label: Some("World pass"),
             color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                view,
+                view: scene,
                 resolve_target: None,
                 ops: wgpu::Operations {
+                    // clear to the fog colour so distant geometry fades into the background
                     load: wgpu::LoadOp::Clear(wgpu::Color {
-                        r: 0.1,
-                        g: 0.1,
-                        b: 0.5,
+                        r: self.frame.fog_color[0] as f64,
+                        g: self.frame.fog_color[1] as f64,
+                        b: self.frame.fog_color[2] as f64,
                         a: 1.0,
                     }),
                     store: true,
@@ -924,7 +1588,8 @@ impl Renderer {
             depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
                 view: &self.depth_texture.view,
                 depth_ops: Some(wgpu::Operations {
-                    load: wgpu::LoadOp::Clear(1.0),
+                    // reverse-Z: the far plane is at 0
+                    load: wgpu::LoadOp::Clear(0.0),
                     store: true,
                 }),
                 stencil_ops: None,
@@ -932,82 +1597,340 @@ impl Renderer {
         });
 
         // draw commands
-        rpass.set_pipeline(&self.pipeline);
-        // rpass.set_vertex_buffer(0, self.vertices.slice(..));
-        // rpass.set_vertex_buffer(1, self.instances.slice(..));
+        rpass.set_bind_group(0, &self.frame_bg, &[]);
+        if self.draw_sky {
+            rpass.set_pipeline(self.pipelines.get(self.sky_pipeline));
+            // a cube of stars with the sun and moon in front, generated in the shader
+            rpass.draw(0..48, 0..1);
+            draw_calls += 1;
+        }
+
+        rpass.set_pipeline(self.pipelines.get(self.pipeline));
         rpass.set_index_buffer(self.indices.slice(..), wgpu::IndexFormat::Uint16);
-        rpass.set_bind_group(0, &self.camera_bg, &[]);
         rpass.set_bind_group(1, &self.texture_atlas_bg, &[]);
-        // rpass.draw(0..self.vertices_length, 0..1);
-        // rpass.draw_indexed(0..self.indices_length, 0, 0..self.instances_length);
 
-        for (object, instances) in self
-            .objects
-            .iter_mut()
-            .zip(self.object_instances.iter_mut())
-        {
-            rpass.set_vertex_buffer(0, object.vertex_buffer.as_ref().unwrap().slice(..));
-            rpass.set_index_buffer(
-                object.index_buffer.as_ref().unwrap().slice(..),
-                wgpu::IndexFormat::Uint16,
-            );
+        let mut instance_offset = 0;
+        draw_calls += draw_objects(
+            &mut rpass,
+            &self.base.queue,
+            instance_buffer,
+            &mut instance_offset,
+            &self.objects,
+            &mut self.object_instances,
+        );
 
-            // instance_buffer =
-            //     self.base
-            //         .device
-            //         .create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            //             label: Some("Instance buffer"),
-            //             contents: bytemuck::cast_slice(&instances),
-            //             usage: wgpu::BufferUsages::VERTEX,
-            //         });
+        rpass.set_pipeline(self.pipelines.get(self.chunk_pipeline));
+        let camera = Vec3::from(self.frame.camera_position);
+        let camera_chunk = chunk_coord(block_position(camera));
+        let frustum = Frustum::from_matrix(Mat4::from_cols_array(&self.frame.view_proj));
+        let in_view = |coord: &IVec3| {
+            (*coord - camera_chunk).abs().max_element() <= self.render_distance
+                && frustum.intersects(&chunk_bounds(*coord))
+        };
+        for (_, chunk) in self.chunks.iter().filter(|(coord, _)| in_view(coord)) {
+            draw_chunk(&mut rpass, chunk);
+            draw_calls += 1;
+        }
 
-            self.base
-                .queue
-                .write_buffer(&instance_buffer, 0, bytemuck::cast_slice(instances));
+        // furthest chunk first, as within each chunk
+        rpass.set_pipeline(self.pipelines.get(self.translucent_pipeline));
+        let mut translucent: Vec<(&IVec3, &TranslucentMesh)> = self
+            .translucent_chunks
+            .iter()
+            .filter(|(coord, _)| in_view(coord))
+            .collect();
+        let distance = |coord: &IVec3| {
+            let centre = (*coord * CHUNK_SIZE).as_vec3() + CHUNK_SIZE as f32 / 2.0;
+            centre.distance_squared(camera)
+        };
+        translucent.sort_by(|(a, _), (b, _)| distance(b).total_cmp(&distance(a)));
+        for (_, translucent) in translucent {
+            draw_chunk(&mut rpass, &translucent.mesh);
+            draw_calls += 1;
+        }
 
-            rpass.set_vertex_buffer(1, instance_buffer.slice(..));
+        // after the translucent chunks, since entities are more often above water than in it
+        rpass.set_pipeline(self.pipelines.get(self.translucent_object_pipeline));
+        for instances in &mut self.translucent_instances {
+            sort_back_to_front(instances, camera);
+        }
+        draw_calls += draw_objects(
+            &mut rpass,
+            &self.base.queue,
+            instance_buffer,
+            &mut instance_offset,
+            &self.objects,
+            &mut self.translucent_instances,
+        );
 
-            rpass.draw_indexed(
-                0..object.indices_length as u32,
-                0,
-                0..instances.len() as u32,
-            );
-            instances.clear();
+        if self.draw_border {
+            rpass.set_pipeline(self.pipelines.get(self.border_pipeline));
+            // a quad per side, generated in the shader
+            rpass.draw(0..24, 0..1);
+            draw_calls += 1;
         }
 
         if let Some(text_module) = &mut self.text_module {
-            rpass.set_pipeline(&text_module.pipeline);
-            rpass.set_bind_group(0, &text_module.camera_bg, &[]);
+            rpass.set_pipeline(self.pipelines.get(text_module.world_pipeline));
+            rpass.set_bind_group(0, &self.frame_bg, &[]);
+            let instances = text_module.world_text_instances.as_ref().unwrap();
+            rpass.set_vertex_buffer(1, instances.slice(..));
+            // each keeps its place in the instance buffer, drawn a font at a time
+            let mut queue: Vec<_> = text_module
+                .world_text_queue
+                .iter()
+                .enumerate()
+                .map(|(i, (handle, _))| {
+                    (i as u32, &text_module.world_text_meshes[*handle as usize])
+                })
+                .collect();
+            queue.sort_by_key(|(_, mesh)| mesh.font_handle);
+            let mut bound = None;
+            for (instance, mesh) in queue {
+                if bound != Some(mesh.font_handle) {
+                    bound = Some(mesh.font_handle);
+                    rpass.set_bind_group(1, &self.fonts[mesh.font_handle as usize].bind_group, &[]);
+                }
+                rpass.set_vertex_buffer(0, mesh.vertex_buffer.slice(..));
+                rpass.set_index_buffer(mesh.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+                rpass.draw_indexed(0..mesh.num_indices, 0, instance..instance + 1);
+                draw_calls += 1;
+            }
+            text_module.world_text_queue.clear();
+        }
+        drop(rpass);
+
+        if self.post.enabled() {
+            draw_calls += self.post.draw(&mut encoder, &self.pipelines, view);
+        }
+
+        let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Ui pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: true,
+                },
+            })],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: &self.depth_texture.view,
+                depth_ops: Some(wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: true,
+                }),
+                stencil_ops: None,
+            }),
+        });
+
+        if let Some(text_module) = &mut self.text_module {
+            rpass.set_pipeline(self.pipelines.get(text_module.pipeline));
+            rpass.set_bind_group(0, &text_module.frame_bg, &[]);
 
             for (font_handle, meshes) in text_module.text_meshes.iter_mut() {
                 // bind the correct texture
-                let (_, bind_group) = self
+                let font = self
                     .fonts
                     .get(*font_handle as usize)
                     .expect("Couldn't find font.");
-                rpass.set_bind_group(1, bind_group, &[]);
+                rpass.set_bind_group(1, &font.bind_group, &[]);
                 for mesh in meshes.iter() {
                     rpass.set_vertex_buffer(0, mesh.vertex_buffer.slice(..));
                     rpass.set_index_buffer(mesh.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
                     rpass.draw_indexed(0..mesh.num_indices, 0, 0..1);
+                    draw_calls += 1;
                 }
             }
+            if let Some(ui_quads) = &ui_quads {
+                rpass.set_bind_group(1, &self.texture_atlas_bg, &[]);
+                rpass.set_vertex_buffer(0, ui_quads.slice(..));
+                rpass.draw(0..text_module.ui_quads.len() as u32, 0..1);
+                draw_calls += 1;
+                text_module.ui_quads.clear();
+            }
+            // a font at a time so its texture is only bound once, in the order queued within it
+            let mut queue: Vec<_> = text_module
+                .text_queue
+                .drain(..)
+                .map(|handle| &text_module.texts[handle as usize])
+                .collect();
+            queue.sort_by_key(|mesh| mesh.font_handle);
+            let mut bound = None;
+            for mesh in queue {
+                if bound != Some(mesh.font_handle) {
+                    bound = Some(mesh.font_handle);
+                    rpass.set_bind_group(1, &self.fonts[mesh.font_handle as usize].bind_group, &[]);
+                }
+                rpass.set_vertex_buffer(0, mesh.vertex_buffer.slice(..));
+                rpass.set_index_buffer(mesh.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+                rpass.draw_indexed(0..mesh.num_indices, 0, 0..1);
+                draw_calls += 1;
+            }
             // rpass.set_bind_group(index, bind_group, offsets);
             // for text_mesh in text_module.text_meshes.drain(..) {}
         }
 
         drop(rpass);
+        self.draw_calls = draw_calls;
 
         self.base.queue.submit(Some(encoder.finish()));
         frame.present();
     }
 
-    pub fn update_camera(&mut self, camera: &Camera) {
-        self.base.queue.write_buffer(
-            &self.camera_buffer,
-            0,
-            bytemuck::cast_slice(&camera.compute().to_cols_array()),
+    fn screen_size(&self) -> PhysicalSize<u32> {
+        PhysicalSize::new(self.surface_config.width, self.surface_config.height)
+    }
+
+    /// Reconfigures the surface and depth buffer for the new window size and refits the ui
+    /// camera. The world camera is owned by the caller, which should resize it as well.
+    pub fn resize(&mut self, size: PhysicalSize<u32>) {
+        // a minimised window reports a zero size, which the surface can't be configured with
+        if size.width == 0 || size.height == 0 {
+            return;
+        }
+        self.surface_config.width = size.width;
+        self.surface_config.height = size.height;
+        self.base
+            .surface
+            .configure(&self.base.device, &self.surface_config);
+        self.depth_texture =
+            texture::Texture::create_depth_texture(&self.base.device, &self.surface_config);
+        self.post.resize(
+            &self.base.device,
+            &self.base.queue,
+            &self.pipelines,
+            &self.surface_config,
         );
+        self.frame.screen_size = [size.width as f32, size.height as f32];
+        if let Some(text_module) = &mut self.text_module {
+            text_module.camera.resize(size, self.ui_resize_strategy);
+        }
+    }
+
+    pub fn set_ui_resize_strategy(&mut self, strategy: ResizeStrategy) {
+        self.ui_resize_strategy = strategy;
+        let size = self.screen_size();
+        if let Some(text_module) = &mut self.text_module {
+            text_module.camera.resize(size, strategy);
+        }
+    }
+
+    /// Draws the ui and its text `scale` times bigger, growing from the bottom left corner.
+    pub fn set_ui_scale(&mut self, scale: f32) {
+        self.ui_scale = scale;
+        if let Some(text_module) = &mut self.text_module {
+            text_module.camera.set_scale(scale);
+        }
+    }
+
+    /// How many draw calls the last frame took.
+    pub fn draw_calls(&self) -> u32 {
+        self.draw_calls
+    }
+
+    /// Makes pixels brighter than `threshold`, from 0 to 1, glow with the given intensity. An
+    /// intensity of 0 turns the glow off.
+    pub fn set_bloom(&mut self, threshold: f32, intensity: f32) {
+        self.post.set_bloom(&self.base.queue, threshold, intensity);
+    }
+
+    /// Adjusts the brightness, contrast and gamma of the world as it's shown.
+    pub fn set_grading(&mut self, grading: Grading) {
+        self.post.set_grading(&self.base.queue, grading);
+    }
+
+    /// Filters the world's colours for a kind of colour blindness.
+    pub fn set_color_filter(&mut self, filter: ColorFilter) {
+        self.post.set_color_filter(&self.base.queue, filter);
+    }
+
+    /// Maps the world's colours through a lookup table image, or stops with None.
+    pub fn set_color_lut(&mut self, lut: Option<&DynamicImage>) -> Result<(), String> {
+        self.post.set_lut(
+            &self.base.device,
+            &self.base.queue,
+            &self.pipelines,
+            &self.surface_config,
+            lut,
+        )
+    }
+
+    /// Without vsync frames are presented as soon as they're ready, tearing if need be.
+    pub fn set_vsync(&mut self, enabled: bool) {
+        self.surface_config.present_mode = if enabled {
+            wgpu::PresentMode::Fifo
+        } else {
+            wgpu::PresentMode::AutoNoVsync
+        };
+        self.base
+            .surface
+            .configure(&self.base.device, &self.surface_config);
+    }
+
+    /// Draws the world border as a wall between `min` and `max` on the x and z plane.
+    pub fn set_world_border(&mut self, min: Vec2, max: Vec2) {
+        self.frame.border = [min.x, min.y, max.x, max.y];
+        self.draw_border = true;
+    }
+
+    pub fn set_render_distance(&mut self, chunks: i32) {
+        self.render_distance = chunks;
+    }
+
+    pub fn update_camera(&mut self, camera: &Camera) {
+        self.frame.view_proj = camera.compute().to_cols_array();
+        self.frame.view = camera.view().to_cols_array();
+        self.frame.camera_position = camera.position().to_array();
+    }
+
+    pub fn set_alpha(&mut self, alpha: f32) {
+        self.alpha = alpha;
+    }
+
+    pub fn alpha(&self) -> f32 {
+        self.alpha
+    }
+
+    /// Seconds since startup, for shader and texture animation.
+    pub fn set_time(&mut self, time: f32) {
+        self.frame.time = time;
+        let mut changed = false;
+        for animation in self.animations.values_mut() {
+            let shown = animation.animation.frame(time);
+            changed |= shown != animation.shown;
+            animation.shown = shown;
+        }
+        // chunks look their rects up on the gpu, so only they need telling
+        if changed {
+            self.write_texture_info();
+        }
+    }
+
+    pub fn set_sun_direction(&mut self, direction: Vec3) {
+        self.frame.sun_direction = direction.normalize_or_zero().to_array();
+    }
+
+    /// Linear fog between `start` and `end` world units from the camera.
+    pub fn set_fog(&mut self, start: f32, end: f32) {
+        self.frame.fog_start = start;
+        self.frame.fog_end = end;
+    }
+
+    /// The colour distant geometry fades into. The clear colour follows it, so it's the colour
+    /// of the sky too.
+    pub fn set_fog_color(&mut self, color: Vec3) {
+        self.frame.fog_color = color.to_array();
+    }
+
+    /// Shows or hides the sun, moon and stars.
+    pub fn set_draw_sky(&mut self, draw_sky: bool) {
+        self.draw_sky = draw_sky;
+    }
+
+    /// How brightly the sky lights the world, from 0 to 1. Block light shows where it's brighter.
+    pub fn set_sky_light(&mut self, light: f32) {
+        self.frame.sky_light = light.clamp(0.0, 1.0);
     }
 }
 
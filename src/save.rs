@@ -0,0 +1,670 @@
+use std::{
+    fmt,
+    fs::{self, File},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use fxhash::{FxHashMap, FxHashSet};
+use glam::{ivec3, IVec3};
+
+use crate::{
+    chunk::CHUNK_SIZE,
+    dimension::Dimension,
+    nbt::{self, Tag},
+    util::chunk_coord,
+    world::{BlockType, Snapshot, World},
+};
+
+/// Where saved worlds are kept, a directory each.
+const SAVES: &str = "saves";
+/// What a world was made with, so it can be made the same way again.
+const METADATA: &str = "world.cfg";
+/// The edits of a save in progress.
+const JOURNAL: &str = "journal.nbt";
+const CHUNKS: &str = "chunks";
+const CHUNK_EXTENSION: &str = "nbt";
+/// What files are written as before they replace the real thing.
+const TEMP_EXTENSION: &str = "tmp";
+/// What chunk files that can't be read are renamed to, kept aside rather than saved over.
+const BAD_EXTENSION: &str = "bad";
+
+/// Reads `--world name` from the command line.
+pub fn from_args(args: impl Iterator<Item = String>) -> Option<String> {
+    let mut args = args.skip_while(|arg| arg != "--world");
+    args.next()?;
+    args.next()
+}
+
+/// Writes `bytes` to `path` so that however the game stops, the file holds either all of them
+/// or whatever it held before. They go to a file beside it first, which replaces it once
+/// they're on disk.
+pub fn write_atomic(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    let temp = path.with_extension(TEMP_EXTENSION);
+    let mut file = File::create(&temp)?;
+    file.write_all(bytes)?;
+    file.sync_all()?;
+    fs::rename(&temp, path)?;
+    // so the rename itself is on disk, where directories can be synced
+    if let Some(dir) = path.parent().and_then(|dir| File::open(dir).ok()) {
+        dir.sync_all().ok();
+    }
+    Ok(())
+}
+
+/// What a world was made with, so that reopening it always makes it the same way whatever
+/// the settings say now.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Metadata {
+    pub seed: u64,
+    /// The generator and its options, as `generator::named` takes them.
+    pub generator: String,
+    /// Every block type there was, by name.
+    pub blocks: Vec<String>,
+    /// The version of the game it was made with.
+    pub version: String,
+}
+
+impl Metadata {
+    /// A world made now, by this version of the game.
+    pub fn current(seed: u64, generator: &str) -> Self {
+        Self {
+            seed,
+            generator: generator.into(),
+            blocks: BlockType::ALL
+                .into_iter()
+                .map(|block_type| <&str>::from(block_type).into())
+                .collect(),
+            version: env!("CARGO_PKG_VERSION").into(),
+        }
+    }
+
+    /// Reads `key = value` lines, taking whatever's missing other than the seed from `current`.
+    fn parse(contents: &str, current: &Metadata) -> Option<Self> {
+        let values: FxHashMap<&str, &str> = contents
+            .lines()
+            .filter_map(|line| line.split_once('='))
+            .map(|(key, value)| (key.trim(), value.trim()))
+            .collect();
+        Some(Self {
+            seed: values.get("seed")?.parse().ok()?,
+            generator: values
+                .get("generator")
+                .map_or_else(|| current.generator.clone(), |value| value.to_string()),
+            blocks: values.get("blocks").map_or_else(
+                || current.blocks.clone(),
+                |value| value.split_whitespace().map(String::from).collect(),
+            ),
+            version: values
+                .get("version")
+                .map_or_else(|| current.version.clone(), |value| value.to_string()),
+        })
+    }
+
+    /// The blocks the world was made with that the game no longer has.
+    pub fn retired(&self) -> Vec<String> {
+        self.blocks
+            .iter()
+            .filter(|block| BlockType::from_name(block).is_none())
+            .cloned()
+            .collect()
+    }
+
+    /// How the world differs from one made now with `current`, one warning each.
+    pub fn differences(&self, current: &Metadata) -> Vec<String> {
+        let mut differences = vec![];
+        if self.generator != current.generator {
+            differences.push(format!(
+                "the world was made with generator {}, not {}, and still uses it",
+                self.generator, current.generator
+            ));
+        }
+        if self.version != current.version {
+            differences.push(format!(
+                "the world was made with version {}, new chunks may not match the old ones",
+                self.version
+            ));
+        }
+        let missing: Vec<&str> = self
+            .blocks
+            .iter()
+            .filter(|block| !current.blocks.contains(block))
+            .map(String::as_str)
+            .collect();
+        if !missing.is_empty() {
+            differences.push(format!(
+                "blocks {} no longer exist and load as placeholders",
+                missing.join(", ")
+            ));
+        }
+        differences
+    }
+}
+
+impl fmt::Display for Metadata {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "seed = {}", self.seed)?;
+        writeln!(f, "generator = {}", self.generator)?;
+        writeln!(f, "blocks = {}", self.blocks.join(" "))?;
+        writeln!(f, "version = {}", self.version)
+    }
+}
+
+/// A world kept on disk between runs. Only chunks with edits in them are saved, the rest are
+/// generated again from the seed.
+///
+/// A save first writes every edit since the last one to a journal, then rewrites the chunks
+/// they're in and finally deletes the journal. Each file is replaced atomically, so however a
+/// save is cut short, loading finds either the journal, whose edits it replays to finish the
+/// save, or no journal and the chunks of the last complete save.
+pub struct WorldSave {
+    dir: PathBuf,
+    /// Blocks the world was made with that the game no longer has, which load as placeholders.
+    retired: Vec<String>,
+    /// Dimensions that didn't load properly, which aren't saved so what's on disk is kept.
+    failed: FxHashSet<Dimension>,
+}
+
+impl WorldSave {
+    /// The world saved under `name`, or to be.
+    pub fn new(name: &str) -> Self {
+        Self::at(Path::new(SAVES).join(name))
+    }
+
+    pub fn at(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            retired: vec![],
+            failed: FxHashSet::default(),
+        }
+    }
+
+    /// Opens the world with what it was made with, or if it's new, makes it with `current`
+    /// and writes that down. Anything a world from an older version didn't write down is taken
+    /// from `current` and written down from then on.
+    pub fn open(&mut self, current: Metadata) -> io::Result<Metadata> {
+        let path = self.dir.join(METADATA);
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => Some(contents),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+            Err(e) => return Err(e),
+        };
+        let metadata = contents
+            .as_deref()
+            .and_then(|contents| Metadata::parse(contents, &current))
+            .unwrap_or(current);
+        self.retired = metadata.retired();
+        let written = metadata.to_string();
+        if contents.as_deref() != Some(written.as_str()) {
+            fs::create_dir_all(&self.dir)?;
+            write_atomic(&path, written.as_bytes())?;
+        }
+        Ok(metadata)
+    }
+
+    fn dimension_dir(&self, dimension: Dimension) -> PathBuf {
+        self.dir.join(dimension.name())
+    }
+
+    /// Saves the edits made to `world` since it was last saved, returning how many chunks it
+    /// wrote. Refuses to if the dimension didn't load properly.
+    pub fn save(&self, dimension: Dimension, world: &mut World) -> Result<usize, String> {
+        let dir = self.dimension_dir(dimension);
+        let edits: Vec<IVec3> = world.unsaved().collect();
+        if edits.is_empty() {
+            return Ok(0);
+        }
+        if self.failed.contains(&dimension) {
+            return Err(format!(
+                "it didn't load properly, so {} is left as it is",
+                dir.display()
+            ));
+        }
+        fs::create_dir_all(dir.join(CHUNKS)).map_err(|e| format!("{}: {e}", dir.display()))?;
+        write_journal(&dir, world, &edits)?;
+        let written = write_chunks(&dir, world, &edits)?;
+        remove(&dir.join(JOURNAL))?;
+        world.mark_saved();
+        Ok(written)
+    }
+
+    /// Puts the saved chunks into `world`, freshly generated, first finishing any save that was
+    /// cut short. Returns how many chunks it loaded.
+    ///
+    /// Chunk files that can't be read are renamed aside and left generated, and the rest are
+    /// loaded regardless. Anything else that goes wrong is returned once loading is done, and
+    /// the dimension isn't saved from then on.
+    pub fn load(&mut self, dimension: Dimension, world: &mut World) -> Result<usize, String> {
+        let mut errors = vec![];
+        let loaded = self.load_chunks(dimension, world, &mut errors);
+        if errors.is_empty() {
+            Ok(loaded)
+        } else {
+            self.failed.insert(dimension);
+            Err(errors.join(", "))
+        }
+    }
+
+    fn load_chunks(
+        &self,
+        dimension: Dimension,
+        world: &mut World,
+        errors: &mut Vec<String>,
+    ) -> usize {
+        let dir = self.dimension_dir(dimension);
+        // files a save was partway through writing, which never replaced anything
+        for dir in [dir.clone(), dir.join(CHUNKS)] {
+            for path in files_with_extension(&dir, TEMP_EXTENSION) {
+                errors.extend(remove(&path).err());
+            }
+        }
+
+        let mut loaded = 0;
+        for path in files_with_extension(&dir.join(CHUNKS), CHUNK_EXTENSION) {
+            let chunk = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(parse_coord)
+                .ok_or_else(|| format!("{} isn't named after a chunk", path.display()))
+                .and_then(|coord| Ok((coord, read_blocks(&path, "Blocks", &self.retired)?)));
+            match chunk {
+                Ok((coord, blocks)) => {
+                    load_chunk(world, coord, blocks);
+                    loaded += 1;
+                }
+                Err(e) => {
+                    let bad = path.with_extension(BAD_EXTENSION);
+                    match fs::rename(&path, &bad) {
+                        Ok(()) => eprintln!("{e}, moved to {}", bad.display()),
+                        Err(rename) => errors.push(format!("{e}, and couldn't move it: {rename}")),
+                    }
+                }
+            }
+        }
+
+        let journal = dir.join(JOURNAL);
+        if journal.exists() {
+            let finished = match read_blocks(&journal, "Edits", &self.retired) {
+                Ok(edits) => {
+                    let positions: Vec<IVec3> = edits.keys().copied().collect();
+                    for (position, snapshot) in edits {
+                        world.restore(position, &snapshot);
+                    }
+                    match write_chunks(&dir, world, &positions) {
+                        Ok(_) => {
+                            println!("finished an interrupted save of {}", dimension.name());
+                            true
+                        }
+                        // kept to try again next time
+                        Err(e) => {
+                            errors.push(e);
+                            false
+                        }
+                    }
+                }
+                Err(e) => {
+                    eprintln!("discarding an unfinished save of {}: {e}", dimension.name());
+                    true
+                }
+            };
+            if finished {
+                errors.extend(remove(&journal).err());
+            }
+        }
+        world.mark_saved();
+        loaded
+    }
+}
+
+fn chunk_path(dir: &Path, coord: IVec3) -> PathBuf {
+    dir.join(CHUNKS)
+        .join(format!("{}_{}_{}", coord.x, coord.y, coord.z))
+        .with_extension(CHUNK_EXTENSION)
+}
+
+fn parse_coord(stem: &str) -> Option<IVec3> {
+    let coords: Vec<i32> = stem
+        .split('_')
+        .map(|n| n.parse().ok())
+        .collect::<Option<_>>()?;
+    match coords[..] {
+        [x, y, z] => Some(ivec3(x, y, z)),
+        _ => None,
+    }
+}
+
+fn files_with_extension(dir: &Path, extension: &str) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return vec![];
+    };
+    entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_file() && path.extension().is_some_and(|e| e == extension))
+        .collect()
+}
+
+fn remove(path: &Path) -> Result<(), String> {
+    fs::remove_file(path).map_err(|e| format!("couldn't remove {}: {e}", path.display()))
+}
+
+/// A snapshot as NBT along with the position it was taken at.
+fn positioned(position: IVec3, snapshot: &Snapshot) -> Tag {
+    let mut tag = snapshot.to_nbt();
+    if let Tag::Compound(entries) = &mut tag {
+        entries.push(("Pos".into(), Tag::IntArray(position.to_array().to_vec())));
+    }
+    tag
+}
+
+/// What's at each of `positions`, as a compound holding a list of them under `name`. Empty
+/// positions are left out.
+fn blocks_nbt(world: &World, name: &str, positions: impl Iterator<Item = IVec3>) -> Vec<u8> {
+    let blocks = positions
+        .map(|position| (position, world.snapshot(position)))
+        .filter(|(_, snapshot)| !snapshot.is_empty())
+        .map(|(position, snapshot)| positioned(position, &snapshot))
+        .collect();
+    let mut bytes = vec![];
+    Tag::Compound(vec![(name.into(), Tag::List(blocks))]).write_named("", &mut bytes);
+    bytes
+}
+
+/// The positions and snapshots listed under `name` in the file at `path`.
+fn read_blocks(
+    path: &Path,
+    name: &str,
+    retired: &[String],
+) -> Result<FxHashMap<IVec3, Snapshot>, String> {
+    let error = |e| format!("{}: {e}", path.display());
+    let bytes = fs::read(path).map_err(|e| error(e.to_string()))?;
+    let (_, root) = nbt::read(&bytes).map_err(error)?;
+    let Some(Tag::List(blocks)) = root.get(name) else {
+        return Err(error(format!("no {name}")));
+    };
+    blocks
+        .iter()
+        .map(|tag| {
+            let position = match tag.get("Pos") {
+                Some(Tag::IntArray(p)) if p.len() == 3 => ivec3(p[0], p[1], p[2]),
+                _ => return Err(error("a block has no position".into())),
+            };
+            Ok((position, Snapshot::from_nbt(tag, retired).map_err(error)?))
+        })
+        .collect()
+}
+
+fn chunk_positions(coord: IVec3) -> impl Iterator<Item = IVec3> {
+    let origin = coord * CHUNK_SIZE;
+    (0..CHUNK_SIZE).flat_map(move |z| {
+        (0..CHUNK_SIZE).flat_map(move |y| (0..CHUNK_SIZE).map(move |x| origin + ivec3(x, y, z)))
+    })
+}
+
+/// Sets every position in the chunk at `coord` to what's in `blocks`, or empty if it's not in
+/// there. Positions already the same are left alone.
+fn load_chunk(world: &mut World, coord: IVec3, mut blocks: FxHashMap<IVec3, Snapshot>) {
+    for position in chunk_positions(coord) {
+        let saved = blocks.remove(&position).unwrap_or_default();
+        if world.snapshot(position).to_nbt() != saved.to_nbt() {
+            world.restore(position, &saved);
+        }
+    }
+}
+
+fn write_journal(dir: &Path, world: &World, edits: &[IVec3]) -> Result<(), String> {
+    // edits that emptied a position are kept too, so they can be replayed
+    let mut bytes = vec![];
+    let entries = edits
+        .iter()
+        .map(|position| positioned(*position, &world.snapshot(*position)))
+        .collect();
+    Tag::Compound(vec![("Edits".into(), Tag::List(entries))]).write_named("", &mut bytes);
+    let path = dir.join(JOURNAL);
+    write_atomic(&path, &bytes).map_err(|e| format!("{}: {e}", path.display()))
+}
+
+/// Rewrites every chunk with one of `positions` in it, returning how many there were.
+fn write_chunks(dir: &Path, world: &World, positions: &[IVec3]) -> Result<usize, String> {
+    let coords: FxHashSet<IVec3> = positions.iter().copied().map(chunk_coord).collect();
+    for coord in &coords {
+        let path = chunk_path(dir, *coord);
+        let bytes = blocks_nbt(world, "Blocks", chunk_positions(*coord));
+        write_atomic(&path, &bytes).map_err(|e| format!("{}: {e}", path.display()))?;
+    }
+    Ok(coords.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::PathBuf};
+
+    use glam::ivec3;
+
+    use crate::{
+        dimension::Dimension,
+        generator::FlatGenerator,
+        nbt::{self, Tag},
+        sign::SIGN_LINES,
+        world::{Block, BlockType, World},
+    };
+
+    use super::{
+        files_with_extension, write_journal, Metadata, WorldSave, BAD_EXTENSION, CHUNKS,
+        CHUNK_EXTENSION, JOURNAL, METADATA,
+    };
+
+    fn save_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("normalcraft-{name}-{}", std::process::id()));
+        fs::remove_dir_all(&dir).ok();
+        dir
+    }
+
+    fn new_world() -> World {
+        World::new(16, 16, 8, &FlatGenerator::default(), 0)
+    }
+
+    #[test]
+    fn edits_are_saved_and_loaded() {
+        let dir = save_dir("saves");
+        let mut save = WorldSave::at(&dir);
+
+        let mut world = new_world();
+        world.set_block(ivec3(1, -5, 1), None);
+        world.set_block(ivec3(2, -6, 2), Some(Block::new(BlockType::Glass)));
+        world.place_sign(ivec3(3, -4, 3));
+        let mut lines: [String; SIGN_LINES] = Default::default();
+        lines[0] = "hello".into();
+        world.write_sign(ivec3(3, -4, 3), lines);
+        assert_eq!(save.save(Dimension::Overworld, &mut world), Ok(1));
+        assert_eq!(save.save(Dimension::Overworld, &mut world), Ok(0));
+
+        let mut loaded = new_world();
+        assert_eq!(save.load(Dimension::Overworld, &mut loaded), Ok(1));
+        assert_eq!(loaded.block_type(ivec3(1, -5, 1)), None);
+        assert_eq!(loaded.block_type(ivec3(2, -6, 2)), Some(BlockType::Glass));
+        assert_eq!(loaded.block_type(ivec3(4, -5, 4)), Some(BlockType::Dirt));
+        let sign = ivec3(3, -4, 3);
+        assert_eq!(
+            loaded.snapshot(sign).to_nbt(),
+            world.snapshot(sign).to_nbt()
+        );
+        assert_eq!(loaded.unsaved().count(), 0);
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn worlds_keep_what_they_were_made_with() {
+        let dir = save_dir("metadata");
+        let mut save = WorldSave::at(&dir);
+        let made = Metadata::current(42, "hills:iterations=10");
+        assert_eq!(save.open(made.clone()).unwrap(), made);
+
+        // reopened with other settings, by a version with a block fewer
+        let mut current = Metadata::current(7, "perlin");
+        current.blocks.retain(|block| block != "glass");
+        current.version = "9.9.9".into();
+        let reopened = save.open(current.clone()).unwrap();
+        assert_eq!(reopened, made);
+        assert_eq!(reopened.differences(&current).len(), 3);
+        assert!(made.differences(&made).is_empty());
+
+        // worlds from before anything but the seed was written down get the rest now
+        fs::write(dir.join(METADATA), "seed = 5\n").unwrap();
+        assert_eq!(save.open(made.clone()).unwrap().seed, 5);
+        assert_eq!(
+            fs::read_to_string(dir.join(METADATA)).unwrap(),
+            Metadata { seed: 5, ..made }.to_string()
+        );
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn blocks_the_game_no_longer_has_load_as_placeholders() {
+        let dir = save_dir("retired");
+        let mut world = new_world();
+        world.set_block(ivec3(2, -6, 2), Some(Block::new(BlockType::Glass)));
+        WorldSave::at(&dir)
+            .save(Dimension::Overworld, &mut world)
+            .unwrap();
+
+        // as if the world was made by a version with marble, which this one doesn't have
+        fn rename(tag: &mut Tag) {
+            match tag {
+                Tag::String(name) if name == "glass" => *name = "marble".into(),
+                Tag::List(tags) => tags.iter_mut().for_each(rename),
+                Tag::Compound(entries) => entries.iter_mut().for_each(|(_, tag)| rename(tag)),
+                _ => {}
+            }
+        }
+        let chunks = WorldSave::at(&dir)
+            .dimension_dir(Dimension::Overworld)
+            .join(CHUNKS);
+        for path in files_with_extension(&chunks, CHUNK_EXTENSION) {
+            let (name, mut root) = nbt::read(&fs::read(&path).unwrap()).unwrap();
+            rename(&mut root);
+            let mut bytes = vec![];
+            root.write_named(&name, &mut bytes);
+            fs::write(path, bytes).unwrap();
+        }
+        let mut made = Metadata::current(0, "flat");
+        made.blocks.push("marble".into());
+        fs::write(dir.join(METADATA), made.to_string()).unwrap();
+
+        // a save that doesn't know the world's blocks can't make sense of it, and sets it aside
+        assert_eq!(
+            WorldSave::at(&dir).load(Dimension::Overworld, &mut new_world()),
+            Ok(0)
+        );
+        let bad = chunks.join("0_-1_0").with_extension(BAD_EXTENSION);
+        fs::rename(&bad, bad.with_extension(CHUNK_EXTENSION)).unwrap();
+        let mut save = WorldSave::at(&dir);
+        let current = Metadata::current(0, "flat");
+        let opened = save.open(current.clone()).unwrap();
+        assert_eq!(opened.retired(), ["marble"]);
+        assert_eq!(opened.differences(&current).len(), 1);
+        let mut loaded = new_world();
+        assert_eq!(save.load(Dimension::Overworld, &mut loaded), Ok(1));
+        assert_eq!(
+            loaded.block_type(ivec3(2, -6, 2)),
+            Some(BlockType::Placeholder)
+        );
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn interrupted_saves_are_finished_or_discarded() {
+        let dir = save_dir("journal");
+        let mut save = WorldSave::at(&dir);
+        let mut world = new_world();
+        world.set_block(ivec3(1, -5, 1), None);
+        save.save(Dimension::Overworld, &mut world).unwrap();
+
+        // stopped after the journal was written but before any chunks were
+        world.set_block(ivec3(5, -5, 5), None);
+        let edits: Vec<_> = world.unsaved().collect();
+        let overworld = dir.join("overworld");
+        write_journal(&overworld, &world, &edits).unwrap();
+        fs::write(overworld.join("chunks/0_-1_0.tmp"), "half a chunk").unwrap();
+
+        let mut loaded = new_world();
+        save.load(Dimension::Overworld, &mut loaded).unwrap();
+        assert_eq!(loaded.block_type(ivec3(1, -5, 1)), None);
+        assert_eq!(loaded.block_type(ivec3(5, -5, 5)), None);
+        assert!(!overworld.join(JOURNAL).exists());
+        assert!(!overworld.join("chunks/0_-1_0.tmp").exists());
+        // and the chunks were brought up to date
+        let mut reloaded = new_world();
+        save.load(Dimension::Overworld, &mut reloaded).unwrap();
+        assert_eq!(reloaded.block_type(ivec3(5, -5, 5)), None);
+
+        // a journal that can't be read is thrown away, leaving the last complete save
+        fs::write(overworld.join(JOURNAL), "not nbt").unwrap();
+        let mut loaded = new_world();
+        save.load(Dimension::Overworld, &mut loaded).unwrap();
+        assert_eq!(loaded.block_type(ivec3(5, -5, 5)), None);
+        assert!(!overworld.join(JOURNAL).exists());
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn unreadable_chunks_are_set_aside() {
+        let dir = save_dir("corrupt");
+        let mut save = WorldSave::at(&dir);
+        let mut world = new_world();
+        world.set_block(ivec3(1, -5, 1), None);
+        save.save(Dimension::Overworld, &mut world).unwrap();
+
+        // a chunk that got mangled, and a save cut short after its journal was written
+        let overworld = dir.join("overworld");
+        let chunk = overworld.join("chunks/0_-1_0.nbt");
+        fs::write(&chunk, "not nbt").unwrap();
+        world.set_block(ivec3(5, -5, 5), None);
+        let edits: Vec<_> = world.unsaved().collect();
+        write_journal(&overworld, &world, &edits).unwrap();
+
+        let mut loaded = new_world();
+        assert_eq!(save.load(Dimension::Overworld, &mut loaded), Ok(0));
+        // the journal's edits still made it in
+        assert_eq!(loaded.block_type(ivec3(5, -5, 5)), None);
+        assert_eq!(loaded.block_type(ivec3(1, -5, 1)), Some(BlockType::Dirt));
+        assert_eq!(loaded.unsaved().count(), 0);
+        assert!(!overworld.join(JOURNAL).exists());
+        assert_eq!(
+            fs::read(overworld.join("chunks/0_-1_0.bad")).unwrap(),
+            b"not nbt"
+        );
+        // and are on disk again
+        let mut reloaded = new_world();
+        assert_eq!(save.load(Dimension::Overworld, &mut reloaded), Ok(1));
+        assert_eq!(reloaded.block_type(ivec3(5, -5, 5)), None);
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn dimensions_that_fail_to_load_are_not_saved() {
+        let dir = save_dir("failed");
+        let mut save = WorldSave::at(&dir);
+        let mut world = new_world();
+        world.set_block(ivec3(1, -5, 1), None);
+        save.save(Dimension::Overworld, &mut world).unwrap();
+
+        // a journal that can't be removed
+        let overworld = dir.join("overworld");
+        fs::create_dir(overworld.join(JOURNAL)).unwrap();
+        let mut loaded = new_world();
+        assert!(save.load(Dimension::Overworld, &mut loaded).is_err());
+        assert_eq!(loaded.block_type(ivec3(1, -5, 1)), None);
+
+        loaded.set_block(ivec3(1, -5, 1), Some(Block::new(BlockType::Stone)));
+        assert!(save.save(Dimension::Overworld, &mut loaded).is_err());
+        assert_eq!(loaded.unsaved().count(), 1);
+        // what's on disk is as it was
+        fs::remove_dir(overworld.join(JOURNAL)).unwrap();
+        let mut reloaded = new_world();
+        WorldSave::at(&dir)
+            .load(Dimension::Overworld, &mut reloaded)
+            .unwrap();
+        assert_eq!(reloaded.block_type(ivec3(1, -5, 1)), None);
+        fs::remove_dir_all(dir).ok();
+    }
+}
@@ -0,0 +1,319 @@
+use std::{
+    io::{Read, Write},
+    path::Path,
+};
+
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use fxhash::FxHashMap;
+use glam::{ivec3, IVec3};
+
+use crate::{
+    nbt::{self, Tag},
+    structure::{Rotation, Structure},
+    world::BlockType,
+};
+
+/// The Sponge schematic version written, which most other voxel tools can read.
+const VERSION: i32 = 2;
+/// The Minecraft data version the block names are from.
+const DATA_VERSION: i32 = 3120;
+const AIR: &str = "minecraft:air";
+
+/// The name other tools know a block type by.
+fn block_name(block_type: BlockType) -> &'static str {
+    match block_type {
+        BlockType::Dirt => "minecraft:dirt",
+        BlockType::Cobble => "minecraft:cobblestone",
+        BlockType::Stone => "minecraft:stone",
+        BlockType::Water => "minecraft:water",
+        BlockType::Sand => "minecraft:sand",
+        BlockType::Torch => "minecraft:torch",
+        BlockType::Portal => "minecraft:nether_portal",
+        BlockType::Bed => "minecraft:red_bed",
+        BlockType::Sign => "minecraft:oak_sign",
+        BlockType::Glass => "minecraft:glass",
+        // nothing in minecraft is like it, so it's kept under this game's own name
+        BlockType::Placeholder => "normalcraft:placeholder",
+    }
+}
+
+/// The block type another tool's block name stands for, ignoring any block states after it.
+fn block_type(name: &str) -> Option<BlockType> {
+    let name = name.split_once('[').map_or(name, |(name, _)| name);
+    BlockType::ALL
+        .into_iter()
+        .find(|block_type| block_name(*block_type) == name)
+}
+
+/// A structure read from a Sponge schematic: a gzipped NBT file of a box of blocks, each an
+/// index into a palette of block names. Blocks without a type here and air are left out, and
+/// the box's offset is kept so it lands where it was copied from relative to the origin.
+pub fn read(bytes: &[u8]) -> Result<Structure, String> {
+    let mut nbt = vec![];
+    GzDecoder::new(bytes)
+        .read_to_end(&mut nbt)
+        .map_err(|e| format!("not a gzipped schematic: {e}"))?;
+    let (_, root) = nbt::read(&nbt)?;
+    // version 3 keeps everything inside a compound of its own, and the blocks inside another
+    let schematic = root.get("Schematic").unwrap_or(&root);
+    let blocks = schematic.get("Blocks").unwrap_or(schematic);
+
+    let dimension = |name| match schematic.get(name) {
+        Some(Tag::Short(size)) => Ok(*size as u16 as i32),
+        _ => Err(format!("schematic has no {name}")),
+    };
+    let size = ivec3(
+        dimension("Width")?,
+        dimension("Height")?,
+        dimension("Length")?,
+    );
+    let offset = match schematic.get("Offset") {
+        Some(Tag::IntArray(offset)) if offset.len() == 3 => ivec3(offset[0], offset[1], offset[2]),
+        _ => IVec3::ZERO,
+    };
+    let Some(Tag::Compound(palette)) = blocks.get("Palette") else {
+        return Err("schematic has no palette".into());
+    };
+    let palette: FxHashMap<i32, Option<BlockType>> = palette
+        .iter()
+        .filter_map(|(name, index)| match index {
+            Tag::Int(index) => Some((*index, block_type(name))),
+            _ => None,
+        })
+        .collect();
+    let data = match blocks.get("BlockData").or_else(|| blocks.get("Data")) {
+        Some(Tag::ByteArray(data)) => data,
+        _ => return Err("schematic has no block data".into()),
+    };
+
+    let indices = decode_varints(data)?;
+    let volume = size.x as usize * size.y as usize * size.z as usize;
+    if indices.len() != volume {
+        return Err(format!(
+            "schematic has {} blocks, expected {}x{}x{}",
+            indices.len(),
+            size.x,
+            size.y,
+            size.z
+        ));
+    }
+    let mut positions = (0..size.y)
+        .flat_map(|y| (0..size.z).flat_map(move |z| (0..size.x).map(move |x| ivec3(x, y, z))));
+    let mut structure_blocks = vec![];
+    for index in indices {
+        let position = positions.next().unwrap_or_default();
+        let block_type = palette
+            .get(&index)
+            .ok_or_else(|| format!("{index} isn't in the schematic's palette"))?;
+        if let Some(block_type) = block_type {
+            structure_blocks.push((position + offset, *block_type));
+        }
+    }
+    Ok(Structure::from_blocks(structure_blocks))
+}
+
+/// A structure as a Sponge schematic, with air wherever the structure leaves a position out.
+pub fn write(structure: &Structure) -> Result<Vec<u8>, String> {
+    let blocks: Vec<_> = structure.blocks(Rotation::None).collect();
+    let min = blocks
+        .iter()
+        .map(|(offset, _)| *offset)
+        .reduce(IVec3::min)
+        .unwrap_or_default();
+    let max = blocks
+        .iter()
+        .map(|(offset, _)| *offset)
+        .reduce(IVec3::max)
+        .unwrap_or(min - 1);
+    let size = max - min + 1;
+    let volume = size.x as usize * size.y as usize * size.z as usize;
+    // the block data is read back into an array with an i32 length
+    if size.max_element() > u16::MAX as i32 || volume > i32::MAX as usize {
+        return Err("structure is too big for a schematic".into());
+    }
+
+    let mut names = vec![AIR];
+    let mut indices = vec![0; volume];
+    for (offset, block_type) in blocks {
+        let name = block_name(block_type);
+        let index = match names.iter().position(|n| *n == name) {
+            Some(index) => index,
+            None => {
+                names.push(name);
+                names.len() - 1
+            }
+        };
+        let position = (offset - min).as_uvec3();
+        let (width, length) = (size.x as usize, size.z as usize);
+        indices[position.x as usize
+            + position.z as usize * width
+            + position.y as usize * width * length] = index as i32;
+    }
+    let palette = names
+        .iter()
+        .enumerate()
+        .map(|(index, name)| (name.to_string(), Tag::Int(index as i32)))
+        .collect();
+
+    let root = Tag::Compound(vec![
+        ("Version".into(), Tag::Int(VERSION)),
+        ("DataVersion".into(), Tag::Int(DATA_VERSION)),
+        ("Width".into(), Tag::Short(size.x as u16 as i16)),
+        ("Height".into(), Tag::Short(size.y as u16 as i16)),
+        ("Length".into(), Tag::Short(size.z as u16 as i16)),
+        ("Offset".into(), Tag::IntArray(min.to_array().to_vec())),
+        ("PaletteMax".into(), Tag::Int(names.len() as i32)),
+        ("Palette".into(), Tag::Compound(palette)),
+        ("BlockData".into(), Tag::ByteArray(encode_varints(&indices))),
+    ]);
+    let mut nbt = vec![];
+    root.write_named("Schematic", &mut nbt);
+    let mut encoder = GzEncoder::new(vec![], Compression::default());
+    encoder.write_all(&nbt).map_err(|e| e.to_string())?;
+    encoder.finish().map_err(|e| e.to_string())
+}
+
+pub fn load<P: AsRef<Path>>(path: P) -> Result<Structure, String> {
+    let path = path.as_ref();
+    let bytes =
+        std::fs::read(path).map_err(|e| format!("couldn't read {}: {e}", path.display()))?;
+    read(&bytes).map_err(|e| format!("couldn't read {}: {e}", path.display()))
+}
+
+pub fn save<P: AsRef<Path>>(structure: &Structure, path: P) -> Result<(), String> {
+    let path = path.as_ref();
+    let bytes = write(structure)?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    std::fs::write(path, bytes).map_err(|e| format!("couldn't write {}: {e}", path.display()))
+}
+
+/// Palette indices as the schematic stores them, seven bits to a byte with the top bit set on
+/// every byte but the last of each.
+fn encode_varints(values: &[i32]) -> Vec<u8> {
+    let mut bytes = vec![];
+    for value in values {
+        let mut value = *value as u32;
+        while value >= 0x80 {
+            bytes.push(value as u8 | 0x80);
+            value >>= 7;
+        }
+        bytes.push(value as u8);
+    }
+    bytes
+}
+
+fn decode_varints(bytes: &[u8]) -> Result<Vec<i32>, String> {
+    let mut values = vec![];
+    let (mut value, mut shift) = (0u32, 0);
+    for byte in bytes {
+        if shift > 28 {
+            return Err("block data has a palette index that's too long".into());
+        }
+        value |= ((byte & 0x7f) as u32) << shift;
+        shift += 7;
+        if byte & 0x80 == 0 {
+            values.push(value as i32);
+            (value, shift) = (0, 0);
+        }
+    }
+    if shift != 0 {
+        return Err("block data ends partway through a palette index".into());
+    }
+    Ok(values)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::{write::GzEncoder, Compression};
+    use glam::{ivec3, IVec3};
+
+    use crate::{
+        structure::{Rotation, Structure},
+        world::BlockType,
+    };
+
+    use crate::nbt::Tag;
+
+    use super::{decode_varints, encode_varints, read, write};
+
+    #[test]
+    fn schematics_round_trip() {
+        let structure = Structure::from_blocks([
+            (IVec3::ZERO, BlockType::Stone),
+            (ivec3(2, 0, 0), BlockType::Glass),
+            (ivec3(0, 3, 1), BlockType::Cobble),
+        ]);
+        let read = read(&write(&structure).unwrap()).unwrap();
+        let mut blocks: Vec<_> = read.blocks(Rotation::None).collect();
+        blocks.sort_by_key(|(offset, _)| offset.to_array());
+        assert_eq!(
+            blocks,
+            [
+                (IVec3::ZERO, BlockType::Stone),
+                (ivec3(0, 3, 1), BlockType::Cobble),
+                (ivec3(2, 0, 0), BlockType::Glass),
+            ]
+        );
+
+        let values = [0, 1, 127, 128, 300, 70000];
+        assert_eq!(
+            decode_varints(&encode_varints(&values)),
+            Ok(values.to_vec())
+        );
+        assert!(decode_varints(&[0x80]).is_err());
+
+        // blocks far enough apart that the space between them can't be written out
+        let spread = Structure::from_blocks([
+            (IVec3::ZERO, BlockType::Stone),
+            (IVec3::splat(2000), BlockType::Stone),
+        ]);
+        assert!(write(&spread).is_err());
+    }
+
+    #[test]
+    fn other_tools_blocks_are_read_by_name() {
+        // a version 3 schematic, two blocks long with an unknown block and block states
+        let palette = Tag::Compound(vec![
+            ("minecraft:glass".into(), Tag::Int(0)),
+            ("minecraft:oak_stairs[facing=north]".into(), Tag::Int(1)),
+            ("minecraft:sand[snowy=false]".into(), Tag::Int(2)),
+        ]);
+        let schematic = Tag::Compound(vec![(
+            "Schematic".into(),
+            Tag::Compound(vec![
+                ("Version".into(), Tag::Int(3)),
+                ("Width".into(), Tag::Short(3)),
+                ("Height".into(), Tag::Short(1)),
+                ("Length".into(), Tag::Short(1)),
+                ("Offset".into(), Tag::IntArray(vec![-1, 0, 0])),
+                (
+                    "Blocks".into(),
+                    Tag::Compound(vec![
+                        ("Palette".into(), palette),
+                        ("Data".into(), Tag::ByteArray(vec![0, 1, 2])),
+                        ("BlockEntities".into(), Tag::List(vec![])),
+                    ]),
+                ),
+            ]),
+        )]);
+        let mut nbt = vec![];
+        schematic.write_named("", &mut nbt);
+        let mut encoder = GzEncoder::new(vec![], Compression::default());
+        encoder.write_all(&nbt).unwrap();
+
+        let structure: Structure = read(&encoder.finish().unwrap()).unwrap();
+        let blocks: Vec<_> = structure.blocks(Rotation::None).collect();
+        assert_eq!(
+            blocks,
+            [
+                (ivec3(-1, 0, 0), BlockType::Glass),
+                (ivec3(1, 0, 0), BlockType::Sand),
+            ]
+        );
+        assert!(read(&nbt).is_err());
+    }
+}
@@ -0,0 +1,293 @@
+use std::{path::Path, str::FromStr};
+
+use glam::{vec2, Vec2};
+
+use crate::{
+    camera::ResizeStrategy,
+    generator,
+    gpu::{AdapterChoice, Backend},
+    post::ColorFilter,
+};
+
+/// User settings, read from a plain `key = value` file. Lines starting with `#` are comments,
+/// missing keys keep their defaults and a missing file gives the default settings.
+pub struct Settings {
+    /// How the world camera adapts to the window. Perspective cameras keep their vertical fov for
+    /// `keep-y`, their horizontal fov for `keep-x`, and `stretch` distorts the image.
+    pub camera_resize: ResizeStrategy,
+    /// How the orthographic ui camera adapts to the window.
+    pub ui_resize: ResizeStrategy,
+    /// Multiplier on mouse look speed.
+    pub mouse_sensitivity: f32,
+    /// Moving the mouse up looks down.
+    pub invert_y: bool,
+    /// How much of the previous tick's look motion carries into the next, from 0 (raw input) to
+    /// just under 1 (very floaty).
+    pub mouse_smoothing: f32,
+    /// Extra gain per 100 pixels of motion within a single tick, so fast flicks turn further.
+    pub mouse_acceleration: f32,
+    /// Lower render distance and other costs automatically when frames run long.
+    pub adaptive_quality: bool,
+    /// Size of the world border along x and z, either one size for both or `x z`. Without it
+    /// the border fits the whole world.
+    pub world_border: Option<Vec2>,
+    /// Name of the generator new worlds are made with, one of `generator::GENERATORS`. A
+    /// `--generator` on the command line takes its place.
+    pub world_generator: String,
+    /// How strongly bright parts of the world, like torches, glow. 0 turns the glow off.
+    pub bloom_intensity: f32,
+    /// Brightness from 0 to 1 a pixel needs before it glows.
+    pub bloom_threshold: f32,
+    /// Multiplier on the colour of the world, above 1 to see into dark caves.
+    pub brightness: f32,
+    /// Above 1 spreads colours away from mid grey, below 1 flattens them towards it.
+    pub contrast: f32,
+    /// Above 1 lifts dark colours more than bright ones.
+    pub gamma: f32,
+    /// Path to a colour lookup table image the world's colours are mapped through, `n` squares
+    /// of `n` by `n` side by side.
+    pub color_lut: Option<String>,
+    /// Memory in KiB kept for undoing block edits, past which the oldest are forgotten.
+    pub undo_memory: usize,
+    /// The graphics API to draw with: `auto`, `vulkan`, `metal`, `dx12` or `gl`. A `--backend`
+    /// on the command line takes its place.
+    pub graphics_backend: Backend,
+    /// The GPU to draw with: `default`, `high-performance`, `low-power` or part of its name. An
+    /// `--adapter` on the command line takes its place.
+    pub adapter: AdapterChoice,
+    /// Recolours the world for `protanopia`, `deuteranopia` or `tritanopia`, or `none`.
+    pub color_filter: ColorFilter,
+    /// How much bigger the ui and its text are drawn, from 0.5 to 2.
+    pub ui_scale: f32,
+    /// Whether sprinting lasts while the key is held or switches on and off with each press.
+    pub sprint: KeyMode,
+    /// Top flying speed in blocks per second, ten times faster while sprinting.
+    pub fly_speed: f32,
+    /// Seconds flying takes to ease most of the way to a new speed, 0 to start and stop dead.
+    pub fly_momentum: f32,
+    /// Frames per second while the window is in the background, from 4 to 60. Nothing is drawn
+    /// while it's minimised, but the world keeps running at this rate.
+    pub background_fps: f32,
+    /// Silences the game while the window is in the background.
+    pub mute_in_background: bool,
+}
+
+/// How a key that switches something on works.
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub enum KeyMode {
+    /// On while the key is held down.
+    #[default]
+    Hold,
+    /// Each press switches it on or off.
+    Toggle,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            camera_resize: ResizeStrategy::KeepY,
+            ui_resize: ResizeStrategy::KeepY,
+            mouse_sensitivity: 1.0,
+            invert_y: false,
+            mouse_smoothing: 0.0,
+            mouse_acceleration: 0.0,
+            adaptive_quality: true,
+            world_border: None,
+            world_generator: "perlin".into(),
+            bloom_intensity: 0.6,
+            bloom_threshold: 0.7,
+            brightness: 1.0,
+            contrast: 1.0,
+            gamma: 1.0,
+            color_lut: None,
+            undo_memory: 4096,
+            graphics_backend: Backend::Auto,
+            adapter: AdapterChoice::Default,
+            color_filter: ColorFilter::None,
+            ui_scale: 1.0,
+            sprint: KeyMode::Hold,
+            fly_speed: 3.0,
+            fly_momentum: 0.15,
+            background_fps: 10.0,
+            mute_in_background: true,
+        }
+    }
+}
+
+impl Settings {
+    pub fn load<P: AsRef<Path>>(path: P) -> Self {
+        match std::fs::read_to_string(path.as_ref()) {
+            Ok(contents) => Self::parse(&contents),
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub fn parse(contents: &str) -> Self {
+        let mut settings = Self::default();
+        for (number, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let result = match line.split_once('=') {
+                Some((key, value)) => settings.set(key.trim(), value.trim()),
+                None => Err("expected key = value".into()),
+            };
+            if let Err(err) = result {
+                eprintln!("settings line {}: {err}", number + 1);
+            }
+        }
+        settings
+    }
+
+    fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        match key {
+            "camera_resize" => self.camera_resize = value.parse()?,
+            "ui_resize" => self.ui_resize = value.parse()?,
+            "mouse_sensitivity" => self.mouse_sensitivity = parse(value)?,
+            "invert_y" => self.invert_y = parse(value)?,
+            "mouse_smoothing" => self.mouse_smoothing = parse::<f32>(value)?.clamp(0.0, 0.99),
+            "mouse_acceleration" => self.mouse_acceleration = parse(value)?,
+            "adaptive_quality" => self.adaptive_quality = parse(value)?,
+            "world_border" => {
+                let sizes: Vec<f32> = value
+                    .split_whitespace()
+                    .map(parse)
+                    .collect::<Result<_, _>>()?;
+                self.world_border = match sizes[..] {
+                    [size] => Some(Vec2::splat(size)),
+                    [x, z] => Some(vec2(x, z)),
+                    _ => return Err("expected a size or x z".into()),
+                };
+            }
+            "bloom_intensity" => self.bloom_intensity = parse::<f32>(value)?.max(0.0),
+            "bloom_threshold" => self.bloom_threshold = parse::<f32>(value)?.clamp(0.0, 1.0),
+            "brightness" => self.brightness = parse::<f32>(value)?.max(0.0),
+            "contrast" => self.contrast = parse::<f32>(value)?.max(0.0),
+            "gamma" => self.gamma = parse::<f32>(value)?.clamp(0.1, 10.0),
+            "color_lut" => self.color_lut = Some(value.into()),
+            "undo_memory" => self.undo_memory = parse(value)?,
+            "graphics_backend" => self.graphics_backend = value.parse()?,
+            "adapter" => self.adapter = value.parse()?,
+            "color_filter" => self.color_filter = value.parse()?,
+            "ui_scale" => self.ui_scale = parse::<f32>(value)?.clamp(0.5, 2.0),
+            "sprint" => self.sprint = value.parse()?,
+            "fly_speed" => self.fly_speed = parse::<f32>(value)?.max(0.0),
+            "fly_momentum" => self.fly_momentum = parse::<f32>(value)?.max(0.0),
+            "background_fps" => self.background_fps = parse::<f32>(value)?.clamp(4.0, 60.0),
+            "mute_in_background" => self.mute_in_background = parse(value)?,
+            "world_generator" => {
+                generator::named(value)?;
+                self.world_generator = value.into();
+            }
+            _ => return Err(format!("unknown setting {key}")),
+        }
+        Ok(())
+    }
+}
+
+fn parse<T: FromStr>(value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("couldn't parse {value} as {}", std::any::type_name::<T>()))
+}
+
+impl FromStr for ColorFilter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(ColorFilter::None),
+            "protanopia" => Ok(ColorFilter::Protanopia),
+            "deuteranopia" => Ok(ColorFilter::Deuteranopia),
+            "tritanopia" => Ok(ColorFilter::Tritanopia),
+            _ => Err(format!("unknown colour filter {s}")),
+        }
+    }
+}
+
+impl FromStr for KeyMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "hold" => Ok(KeyMode::Hold),
+            "toggle" => Ok(KeyMode::Toggle),
+            _ => Err(format!("expected hold or toggle, not {s}")),
+        }
+    }
+}
+
+impl FromStr for ResizeStrategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "keep-y" | "keep-height" => Ok(ResizeStrategy::KeepY),
+            "keep-x" | "keep-width" => Ok(ResizeStrategy::KeepX),
+            "letterbox" => Ok(ResizeStrategy::Letterbox),
+            "stretch" => Ok(ResizeStrategy::Stretch),
+            _ => Err(format!("unknown resize strategy {s}")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::vec2;
+
+    use crate::{
+        camera::ResizeStrategy,
+        gpu::{AdapterChoice, Backend},
+        post::ColorFilter,
+    };
+
+    use super::{KeyMode, Settings};
+
+    #[test]
+    fn parse_overrides_defaults() {
+        let settings = Settings::parse(
+            "# comment\n\
+             ui_resize = letterbox\n\
+             invert_y = true\n\
+             mouse_sensitivity = 0.5\n\
+             not a setting\n\
+             camera_resize = nonsense\n\
+             world_border = 64 32\n\
+             world_generator = nonsense\n\
+             bloom_intensity = -1\n\
+             brightness = 1.5\n\
+             gamma = 0\n\
+             undo_memory = 512\n\
+             graphics_backend = metal\n\
+             adapter = high-performance\n\
+             color_filter = deuteranopia\n\
+             ui_scale = 5\n\
+             sprint = toggle\n\
+             fly_speed = 8\n\
+             fly_momentum = -1\n\
+             background_fps = 1\n\
+             mute_in_background = false\n",
+        );
+        assert_eq!(settings.ui_resize, ResizeStrategy::Letterbox);
+        assert!(settings.invert_y);
+        assert_eq!(settings.mouse_sensitivity, 0.5);
+        assert_eq!(settings.world_border, Some(vec2(64.0, 32.0)));
+        assert_eq!(settings.bloom_intensity, 0.0);
+        assert_eq!(settings.brightness, 1.5);
+        assert_eq!(settings.gamma, 0.1);
+        assert_eq!(settings.undo_memory, 512);
+        assert_eq!(settings.graphics_backend, Backend::Metal);
+        assert_eq!(settings.adapter, AdapterChoice::HighPerformance);
+        assert_eq!(settings.color_filter, ColorFilter::Deuteranopia);
+        assert_eq!(settings.ui_scale, 2.0);
+        assert_eq!(settings.sprint, KeyMode::Toggle);
+        assert_eq!(settings.fly_speed, 8.0);
+        assert_eq!(settings.fly_momentum, 0.0);
+        assert_eq!(settings.background_fps, 4.0);
+        assert!(!settings.mute_in_background);
+        // bad lines are reported and skipped
+        assert_eq!(settings.camera_resize, ResizeStrategy::KeepY);
+        assert_eq!(settings.world_generator, "perlin");
+    }
+}
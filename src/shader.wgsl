@@ -1,5 +1,21 @@
+struct Frame {
+    view_proj: mat4x4<f32>,
+    view: mat4x4<f32>,
+    camera_position: vec3<f32>,
+    time: f32,
+    sun_direction: vec3<f32>,
+    fog_start: f32,
+    fog_color: vec3<f32>,
+    fog_end: f32,
+    screen_size: vec2<f32>,
+    // how bright the sky lights the world, from 0 to 1
+    sky_light: f32,
+    // world border corners on the x z plane, min in xy and max in zw
+    border: vec4<f32>,
+}
+
 @group(0) @binding(0)
-var<uniform> camera: mat4x4<f32>;
+var<uniform> frame: Frame;
 @group(1) @binding(0)
 var texture: texture_2d<f32>;
 @group(1) @binding(1)
@@ -18,13 +34,23 @@ struct InstanceInput {
     @location(5) model_matrix_3: vec4<f32>,
     @location(6) uv_offset: vec2<f32>,
     @location(7) uv_size: vec2<f32>,
+    @location(8) flash: f32,
+    @location(9) tint: vec3<f32>,
+    @location(10) alpha: f32,
 }
 
+// what a freshly hit object is tinted towards
+let FLASH_COLOUR = vec3<f32>(1.0, 0.1, 0.1);
+
 struct VertexOutput {
     @builtin(position) position: vec4<f32>,
     @location(0) tex: vec2<f32>,
     @location(1) uv_offset: vec2<f32>,
     @location(2) uv_size: vec2<f32>,
+    @location(3) world_position: vec3<f32>,
+    @location(4) flash: f32,
+    @location(5) tint: vec3<f32>,
+    @location(6) alpha: f32,
 }
 
 @vertex
@@ -37,10 +63,15 @@ fn vertex(vertex: VertexInput, instance: InstanceInput) -> VertexOutput {
     );
 
     var out: VertexOutput;
-    out.position = camera * model_matrix * vec4<f32>(vertex.position, 1.0);
+    let world_position = model_matrix * vec4<f32>(vertex.position, 1.0);
+    out.position = frame.view_proj * world_position;
+    out.world_position = world_position.xyz;
     out.tex = vertex.tex;
     out.uv_offset = instance.uv_offset;
     out.uv_size = instance.uv_size;
+    out.flash = instance.flash;
+    out.tint = instance.tint;
+    out.alpha = instance.alpha;
     return out;
 }
 
@@ -48,6 +79,10 @@ struct FragmentInput {
     @location(0) tex: vec2<f32>,
     @location(1) uv_offset: vec2<f32>,
     @location(2) uv_size: vec2<f32>,
+    @location(3) world_position: vec3<f32>,
+    @location(4) flash: f32,
+    @location(5) tint: vec3<f32>,
+    @location(6) alpha: f32,
 }
 
 @fragment
@@ -59,5 +94,14 @@ fn fragment(in: FragmentInput) -> @location(0) vec4<f32> {
     // what fraction of the image does this form?
     var dimensions: vec2<i32> = textureDimensions(texture);
     var adjustedTex: vec2<f32> = vec2(in.uv_offset.x / f32(dimensions.x) + in.tex.x  * in.uv_size.x / f32(dimensions.x), in.uv_offset.y / f32(dimensions.y) + in.tex.y * in.uv_size.y / f32(dimensions.y));
-    return textureSample(texture, samp, adjustedTex);
+    let color = textureSample(texture, samp, adjustedTex);
+    // cutout textures like torches have see-through texels
+    if (color.a < 0.5) {
+        discard;
+    }
+    let tinted = mix(color.rgb * in.tint, FLASH_COLOUR, in.flash * 0.6);
+    let distance = length(in.world_position - frame.camera_position);
+    let fog = clamp((distance - frame.fog_start) / (frame.fog_end - frame.fog_start), 0.0, 1.0);
+    // only blended by the translucent pipeline, the solid one writes over what's behind
+    return vec4<f32>(mix(tinted, frame.fog_color, fog), in.alpha);
 }
\ No newline at end of file
@@ -0,0 +1,175 @@
+use glam::{vec2, vec3, IVec3, Quat, Vec2, Vec3};
+
+use crate::{
+    console::{edit_line, LineEnd},
+    instance::Instance,
+    renderer::{Drawable, FontHandle, Renderer, TextHandle, Vertex},
+    world::{cube_indices, cube_vertices, World},
+};
+
+/// The renderer object signs are drawn with.
+const SIGN_OBJECT: u32 = 3;
+
+/// Lines of text a sign holds.
+pub const SIGN_LINES: usize = 4;
+
+/// Height of each line of a sign's text in the world, and the distance between their
+/// baselines.
+pub const SIGN_TEXT_HEIGHT: f32 = 0.15;
+pub const SIGN_LINE_SPACING: f32 = 0.18;
+/// Height of the bottom line's baseline above the centre of the sign's block, clear of the
+/// board so it isn't hidden when seen from behind.
+pub const SIGN_TEXT_BOTTOM: f32 = 0.45;
+
+/// The board's size, and how far its centre is raised from the centre of the block.
+const BOARD_SIZE: Vec3 = vec3(0.9, 0.5, 0.1);
+const BOARD_RAISE: f32 = 0.1;
+
+/// The editing board on the ui, its bottom left corner and size, and the first line's
+/// baseline.
+const PANEL_MIN: Vec2 = vec2(220.0, 200.0);
+const PANEL_SIZE: Vec2 = vec2(360.0, 200.0);
+const PANEL_TEXT: Vec2 = vec2(240.0, 350.0);
+const PANEL_LINE_SPACING: f32 = 40.0;
+const PANEL_SCALE: f32 = 0.2;
+
+/// A sign as it's drawn: a flat board standing on the block below.
+pub struct Sign {
+    position: IVec3,
+}
+
+impl Sign {
+    pub fn new(position: IVec3) -> Self {
+        Self { position }
+    }
+}
+
+impl Drawable for Sign {
+    fn draw(&self, renderer: &mut Renderer, world: &World) {
+        renderer.queue_draw(SIGN_OBJECT, self, world);
+    }
+
+    fn vertices(&self) -> Vec<Vertex> {
+        cube_vertices()
+    }
+
+    fn indices(&self) -> Vec<u16> {
+        cube_indices()
+    }
+
+    fn instance(&self, world: &World) -> Instance {
+        let centre = self.position.as_vec3() + Vec3::Y * BOARD_RAISE;
+        Instance::new(centre, Quat::IDENTITY, world.get_texture("sign")).with_scale(BOARD_SIZE)
+    }
+}
+
+/// The text of a sign being written, a line at a time. Typing goes through the same line
+/// editing as the console: enter moves on to the next line, or finishes after the last, and
+/// escape finishes early keeping what's been written.
+pub struct SignEditor {
+    pub position: IVec3,
+    lines: [String; SIGN_LINES],
+    /// The line being typed into.
+    line: usize,
+}
+
+impl SignEditor {
+    pub fn new(position: IVec3) -> Self {
+        Self {
+            position,
+            lines: Default::default(),
+            line: 0,
+        }
+    }
+
+    /// Feeds in a typed character, returning the lines once the sign is finished.
+    pub fn input_char(&mut self, char: char) -> Option<[String; SIGN_LINES]> {
+        match edit_line(&mut self.lines[self.line], char) {
+            Some(LineEnd::Submitted) if self.line + 1 < SIGN_LINES => {
+                self.line += 1;
+                None
+            }
+            Some(_) => Some(std::mem::take(&mut self.lines)),
+            None => None,
+        }
+    }
+
+    /// The lines as they're shown while editing, with a cursor on the current one.
+    pub fn shown_lines(&self) -> [String; SIGN_LINES] {
+        std::array::from_fn(|i| {
+            if i == self.line {
+                format!("{}_", self.lines[i])
+            } else {
+                self.lines[i].clone()
+            }
+        })
+    }
+}
+
+/// Shows the sign being written over the world.
+pub struct SignScreen {
+    font_handle: FontHandle,
+    /// Each line's text, along with what it currently says.
+    texts: [(TextHandle, String); SIGN_LINES],
+}
+
+impl SignScreen {
+    pub fn new(renderer: &mut Renderer, font_handle: FontHandle) -> Self {
+        Self {
+            font_handle,
+            texts: std::array::from_fn(|i| {
+                let (x, y) = Self::line_position(i);
+                let text = renderer.create_text(" ", font_handle, x, y, PANEL_SCALE);
+                (text, String::new())
+            }),
+        }
+    }
+
+    fn line_position(i: usize) -> (f32, f32) {
+        (PANEL_TEXT.x, PANEL_TEXT.y - i as f32 * PANEL_LINE_SPACING)
+    }
+
+    pub fn draw(&mut self, renderer: &mut Renderer, editor: &SignEditor, world: &World) {
+        renderer.queue_ui_quad(PANEL_MIN, PANEL_MIN + PANEL_SIZE, world.get_texture("sign"));
+        for (i, ((text, shown), line)) in
+            self.texts.iter_mut().zip(editor.shown_lines()).enumerate()
+        {
+            if line.is_empty() {
+                continue;
+            }
+            // only rebuilt when the line changes
+            if *shown != line {
+                let (x, y) = Self::line_position(i);
+                renderer.set_text(*text, &line, self.font_handle, x, y, PANEL_SCALE);
+                *shown = line;
+            }
+            renderer.queue_draw_text(*text);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::IVec3;
+
+    use super::{SignEditor, SIGN_LINES};
+
+    fn type_text(editor: &mut SignEditor, text: &str) -> Option<[String; SIGN_LINES]> {
+        text.chars().find_map(|char| editor.input_char(char))
+    }
+
+    #[test]
+    fn enter_moves_down_a_line() {
+        let mut editor = SignEditor::new(IVec3::ZERO);
+        assert_eq!(type_text(&mut editor, "north\rto the mimes\u{8}\r"), None);
+        assert_eq!(editor.shown_lines()[1], "to the mime");
+        assert_eq!(editor.shown_lines()[2], "_");
+        let lines = type_text(&mut editor, "\r\r").unwrap();
+        assert_eq!(lines, ["north", "to the mime", "", ""].map(String::from));
+
+        // escape keeps what's been written
+        let mut editor = SignEditor::new(IVec3::ZERO);
+        let lines = type_text(&mut editor, "keep out\u{1b}").unwrap();
+        assert_eq!(lines[0], "keep out");
+    }
+}
@@ -0,0 +1,102 @@
+use std::f32::consts::TAU;
+
+use glam::{vec3, Vec3};
+
+/// Seconds in a full day.
+const DAY_LENGTH: f32 = 20.0 * 60.0;
+
+/// Sky light at night, so the world never goes completely black.
+const NIGHT_LIGHT: f32 = 0.15;
+
+const DAY_COLOUR: Vec3 = vec3(0.1, 0.1, 0.5);
+const NIGHT_COLOUR: Vec3 = vec3(0.01, 0.01, 0.04);
+
+/// How far below and above the horizon the sun is, as the y of its direction, while the light
+/// fades between night and day.
+const TWILIGHT: f32 = 0.1;
+
+/// The time of day and everything that follows from it: where the sun and moon are, how bright
+/// the sky is and what colour.
+pub struct DayCycle {
+    /// Fraction of the day gone, 0 at sunrise, 0.25 at noon, 0.5 at sunset and 0.75 at
+    /// midnight.
+    time: f32,
+}
+
+impl DayCycle {
+    pub fn new(time: f32) -> Self {
+        Self {
+            time: time.rem_euclid(1.0),
+        }
+    }
+
+    pub fn advance(&mut self, dt: f32) {
+        self.time = (self.time + dt / DAY_LENGTH).rem_euclid(1.0);
+    }
+
+    pub fn time(&self) -> f32 {
+        self.time
+    }
+
+    pub fn set_time(&mut self, time: f32) {
+        self.time = time.rem_euclid(1.0);
+    }
+
+    /// Points at the sun, the moon is opposite. The sun rises in +x, passes straight overhead
+    /// and sets in -x.
+    pub fn sun_direction(&self) -> Vec3 {
+        let angle = self.time * TAU;
+        vec3(angle.cos(), angle.sin(), 0.0)
+    }
+
+    /// Whether the sun has set, when beds can be slept in.
+    pub fn is_night(&self) -> bool {
+        self.sun_direction().y < 0.0
+    }
+
+    /// From 0 at night to 1 during the day.
+    pub fn daylight(&self) -> f32 {
+        ((self.sun_direction().y + TWILIGHT) / (2.0 * TWILIGHT)).clamp(0.0, 1.0)
+    }
+
+    /// How brightly the sky lights the world, from `NIGHT_LIGHT` to 1.
+    pub fn sky_light(&self) -> f32 {
+        NIGHT_LIGHT + (1.0 - NIGHT_LIGHT) * self.daylight()
+    }
+
+    pub fn sky_colour(&self) -> Vec3 {
+        NIGHT_COLOUR.lerp(DAY_COLOUR, self.daylight())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::vec3;
+
+    use super::{DayCycle, DAY_COLOUR, DAY_LENGTH, NIGHT_LIGHT};
+
+    #[test]
+    fn sun_goes_round_once_a_day() {
+        let mut day = DayCycle::new(0.0);
+        assert!(day.sun_direction().abs_diff_eq(vec3(1.0, 0.0, 0.0), 1e-6));
+        day.advance(DAY_LENGTH / 4.0);
+        assert!(day.sun_direction().abs_diff_eq(vec3(0.0, 1.0, 0.0), 1e-6));
+        day.advance(DAY_LENGTH);
+        assert!((day.time() - 0.25).abs() < 1e-4);
+        day.set_time(-0.25);
+        assert!(day.sun_direction().abs_diff_eq(vec3(0.0, -1.0, 0.0), 1e-6));
+    }
+
+    #[test]
+    fn night_is_dark_but_not_black() {
+        let noon = DayCycle::new(0.25);
+        assert_eq!(noon.sky_light(), 1.0);
+        assert_eq!(noon.sky_colour(), DAY_COLOUR);
+        let midnight = DayCycle::new(0.75);
+        assert_eq!(midnight.sky_light(), NIGHT_LIGHT);
+        // halfway through twilight at sunrise
+        let sunrise = DayCycle::new(0.0);
+        assert!((sunrise.daylight() - 0.5).abs() < 1e-6);
+        assert!(midnight.is_night() && !noon.is_night() && !sunrise.is_night());
+    }
+}
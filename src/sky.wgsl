@@ -0,0 +1,147 @@
+struct Frame {
+    view_proj: mat4x4<f32>,
+    view: mat4x4<f32>,
+    camera_position: vec3<f32>,
+    time: f32,
+    sun_direction: vec3<f32>,
+    fog_start: f32,
+    fog_color: vec3<f32>,
+    fog_end: f32,
+    screen_size: vec2<f32>,
+    // how bright the sky lights the world, from 0 to 1
+    sky_light: f32,
+    // world border corners on the x z plane, min in xy and max in zw
+    border: vec4<f32>,
+}
+
+@group(0) @binding(0)
+var<uniform> frame: Frame;
+
+// how far the sky is drawn from the camera, it's pushed to the far plane either way
+let SKY_DISTANCE: f32 = 100.0;
+// half the width of the sun and moon quads at SKY_DISTANCE
+let SUN_SIZE: f32 = 8.0;
+let MOON_SIZE: f32 = 6.0;
+// stars per side of each face of the sky cube, and the fraction of those cells holding one
+let STAR_GRID: f32 = 48.0;
+let STAR_CHANCE: f32 = 0.015;
+
+let KIND_STARS: u32 = 0u;
+let KIND_SUN: u32 = 1u;
+let KIND_MOON: u32 = 2u;
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    // direction from the camera for the stars, position on the quad from -1 to 1 otherwise
+    @location(0) local: vec3<f32>,
+    @location(1) @interpolate(flat) kind: u32,
+}
+
+// the star cube, 6 faces of two triangles, followed by the sun and moon quads
+@vertex
+fn vertex(@builtin(vertex_index) index: u32) -> VertexOutput {
+    let quad = index / 6u;
+    let vertex = index % 6u;
+    // corners 0 1 2 and 0 2 3 of the quad, one bit per vertex as in chunk.wgsl
+    let u = f32((0x16u >> vertex) & 1u) * 2.0 - 1.0;
+    let v = f32((0x34u >> vertex) & 1u) * 2.0 - 1.0;
+
+    let to_sun = -frame.sun_direction;
+    var out: VertexOutput;
+    var offset: vec3<f32>;
+    if (quad < 6u) {
+        let axis = quad / 2u;
+        let side = f32(quad % 2u) * -2.0 + 1.0;
+        if (axis == 0u) {
+            offset = vec3<f32>(side, u, v);
+        } else if (axis == 1u) {
+            offset = vec3<f32>(u, side, v);
+        } else {
+            offset = vec3<f32>(u, v, side);
+        }
+        out.local = offset;
+        out.kind = KIND_STARS;
+        offset = offset * SKY_DISTANCE;
+    } else {
+        // the sun travels around the z axis, so z is always across its path
+        var centre = to_sun;
+        var size = SUN_SIZE;
+        out.kind = KIND_SUN;
+        if (quad == 7u) {
+            centre = -to_sun;
+            size = MOON_SIZE;
+            out.kind = KIND_MOON;
+        }
+        let right = vec3<f32>(0.0, 0.0, 1.0);
+        let up = cross(right, centre);
+        offset = centre * SKY_DISTANCE + (right * u + up * v) * size;
+        out.local = vec3<f32>(u, v, 0.0);
+    }
+
+    out.position = frame.view_proj * vec4<f32>(frame.camera_position + offset, 1.0);
+    // on the far plane, so it's never clipped and sits behind everything
+    out.position.z = 0.0;
+    return out;
+}
+
+fn hash(p: vec3<f32>) -> f32 {
+    return fract(sin(dot(p, vec3<f32>(12.9898, 78.233, 37.719))) * 43758.5453);
+}
+
+fn stars(direction: vec3<f32>) -> vec4<f32> {
+    // turn the sky with the sun, so the stars rise and set too
+    let to_sun = -frame.sun_direction;
+    let angle = atan2(to_sun.y, to_sun.x);
+    let c = cos(angle);
+    let s = sin(angle);
+    let d = vec3<f32>(c * direction.x + s * direction.y, c * direction.y - s * direction.x, direction.z);
+
+    // project onto the face of a cube so the grid doesn't bunch up at the poles
+    let a = abs(d);
+    var uv: vec2<f32>;
+    var face: f32;
+    if (a.x >= a.y && a.x >= a.z) {
+        uv = d.yz / a.x;
+        face = sign(d.x);
+    } else if (a.y >= a.z) {
+        uv = d.xz / a.y;
+        face = 2.0 + sign(d.y);
+    } else {
+        uv = d.xy / a.z;
+        face = 4.0 + sign(d.z);
+    }
+
+    let grid = (uv * 0.5 + 0.5) * STAR_GRID;
+    let cell = floor(grid);
+    let seed = vec3<f32>(cell, face);
+    if (hash(seed) > STAR_CHANCE) {
+        discard;
+    }
+    // somewhere inside the cell, away from its edges so it isn't cut off
+    let centre = cell + 0.3 + 0.4 * vec2<f32>(hash(seed + 1.0), hash(seed + 2.0));
+    let disc = 1.0 - smoothstep(0.0, 0.25, length(grid - centre));
+    let brightness = 0.5 + 0.5 * hash(seed + 3.0);
+    // only out at night, fading in as the sun goes down
+    let night = 1.0 - smoothstep(-0.2, 0.1, to_sun.y);
+    return vec4<f32>(vec3<f32>(brightness), disc * night);
+}
+
+struct FragmentInput {
+    @location(0) local: vec3<f32>,
+    @location(1) @interpolate(flat) kind: u32,
+}
+
+@fragment
+fn fragment(in: FragmentInput) -> @location(0) vec4<f32> {
+    if (in.kind == KIND_STARS) {
+        return stars(normalize(in.local));
+    }
+    // round discs with a soft edge
+    let distance = length(in.local.xy);
+    if (in.kind == KIND_SUN) {
+        let alpha = 1.0 - smoothstep(0.6, 1.0, distance);
+        return vec4<f32>(1.0, 0.9, 0.6, alpha);
+    }
+    let alpha = 1.0 - smoothstep(0.7, 0.8, distance);
+    return vec4<f32>(0.85, 0.88, 0.95, alpha);
+}
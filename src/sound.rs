@@ -0,0 +1,94 @@
+use glam::Vec3;
+
+/// Seconds a one-shot sound counts as playing for.
+const SOUND_LENGTH: f32 = 0.5;
+
+/// The family of sounds a block makes, each with its own sound for being broken and walked on,
+/// so a new block only has to say which family it belongs to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SoundGroup {
+    Stone,
+    Grass,
+    Sand,
+    Wood,
+    Glass,
+    Cloth,
+    Water,
+}
+
+impl SoundGroup {
+    fn name(self) -> &'static str {
+        match self {
+            SoundGroup::Stone => "stone",
+            SoundGroup::Grass => "grass",
+            SoundGroup::Sand => "sand",
+            SoundGroup::Wood => "wood",
+            SoundGroup::Glass => "glass",
+            SoundGroup::Cloth => "cloth",
+            SoundGroup::Water => "water",
+        }
+    }
+
+    /// The id of the sound played when a block of the group breaks.
+    pub fn break_sound(self) -> String {
+        format!("block.{}.break", self.name())
+    }
+
+    /// The id of the sound played for each step taken on a block of the group.
+    pub fn step_sound(self) -> String {
+        format!("block.{}.step", self.name())
+    }
+}
+
+/// The one-shot sounds playing at the moment, by id and where they come from. An audio
+/// backend starts each one as it's added here.
+#[derive(Default)]
+pub struct Sounds {
+    playing: Vec<(String, Vec3, f32)>,
+    /// Nothing plays while muted.
+    pub muted: bool,
+}
+
+impl Sounds {
+    pub fn play(&mut self, id: String, position: Vec3) {
+        if !self.muted {
+            self.playing.push((id, position, SOUND_LENGTH));
+        }
+    }
+
+    /// Lets `dt` seconds of every sound play, dropping those that have finished.
+    pub fn update(&mut self, dt: f32) {
+        for (_, _, left) in &mut self.playing {
+            *left -= dt;
+        }
+        self.playing.retain(|(_, _, left)| *left > 0.0);
+    }
+
+    /// The sounds that can be heard at the moment, for the debug overlay.
+    pub fn describe(&self) -> String {
+        match self.playing.last() {
+            Some((id, _, _)) => format!("sound {id}"),
+            None => "sound none".into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec3;
+
+    use super::{SoundGroup, Sounds, SOUND_LENGTH};
+
+    #[test]
+    fn sounds_play_for_a_moment() {
+        let mut sounds = Sounds::default();
+        sounds.play(SoundGroup::Stone.break_sound(), Vec3::ZERO);
+        sounds.update(SOUND_LENGTH / 2.0);
+        sounds.play(SoundGroup::Grass.step_sound(), Vec3::ONE);
+        assert_eq!(sounds.describe(), "sound block.grass.step");
+        sounds.update(SOUND_LENGTH / 2.0);
+        assert_eq!(sounds.playing.len(), 1);
+        sounds.update(SOUND_LENGTH);
+        assert_eq!(sounds.describe(), "sound none");
+    }
+}
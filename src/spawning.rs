@@ -0,0 +1,196 @@
+use fxhash::FxHashMap;
+use glam::{ivec3, IVec3, Vec3};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{
+    chunk::{CHUNK_SIZE, MAX_LIGHT},
+    entity::{Entities, Entity, EntityKind},
+    util::{block_position, chunk_coord},
+    world::World,
+};
+
+/// Mobs stop spawning once this many are around...
+pub const MOB_CAP: usize = 24;
+/// ...or this many are in the chunk they'd spawn in.
+pub const CHUNK_MOB_CAP: usize = 4;
+
+/// Random positions tried each tick.
+const ATTEMPTS: usize = 4;
+
+/// Mobs spawn no closer to the player than this...
+const MIN_DISTANCE: f32 = 16.0;
+/// ...and no further.
+const MAX_DISTANCE: f32 = 64.0;
+
+/// Mobs this far from the player despawn straight away...
+const DESPAWN_DISTANCE: f32 = 2.0 * MAX_DISTANCE;
+/// ...and those this far once they've been so for `IDLE_TICKS` ticks in a row, making room for
+/// mobs nearer the player.
+const IDLE_DISTANCE: f32 = 2.0 * MIN_DISTANCE;
+const IDLE_TICKS: u32 = 30 * 60;
+
+/// Mobs only spawn where the light is this level or darker.
+const MAX_SPAWN_LIGHT: u8 = 7;
+
+/// Height of a mob's centre above the centre of the block its feet are in, half a block down to
+/// the floor then half the mob's height back up.
+const MOB_CENTRE: f32 = -0.5 + 0.9;
+
+/// Whether a mob can spawn with its feet in the block at `feet`: standing on something solid
+/// that can't be seen through, with room for its body, somewhere dark enough and at the right
+/// distance from the player. `sky_light` is how brightly the sky lights the world, from 0 to 1.
+pub fn can_spawn(world: &World, feet: IVec3, player: Vec3, sky_light: f32) -> bool {
+    let distance = feet.as_vec3().distance(player);
+    let sky = (sky_light * MAX_LIGHT as f32).round() as u8;
+    (MIN_DISTANCE..=MAX_DISTANCE).contains(&distance)
+        && world
+            .block_type(feet - IVec3::Y)
+            .is_some_and(|ground| ground.is_collidable() && ground.is_opaque())
+        && [feet, feet + IVec3::Y]
+            .into_iter()
+            .all(|position| world.block_type(position).is_none())
+        && world.light_level(feet, sky) <= MAX_SPAWN_LIGHT
+}
+
+/// Brings mobs into the world in the dark, a few random positions a tick, until there are
+/// enough of them.
+pub struct Spawner {
+    rng: StdRng,
+}
+
+impl Spawner {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    /// Despawns mobs that have wandered far from the player, then tries spawning mobs at a few
+    /// random positions in the world's chunks.
+    pub fn tick(&mut self, world: &World, entities: &mut Entities, player: Vec3, sky_light: f32) {
+        entities.despawn_mobs(player, DESPAWN_DISTANCE, IDLE_DISTANCE, IDLE_TICKS);
+        let mut per_chunk: FxHashMap<IVec3, usize> = FxHashMap::default();
+        for position in entities.positions(EntityKind::Mob) {
+            *per_chunk
+                .entry(chunk_coord(block_position(position)))
+                .or_default() += 1;
+        }
+        let mut total: usize = per_chunk.values().sum();
+        let (min, max) = world.chunk_range();
+        for _ in 0..ATTEMPTS {
+            if total >= MOB_CAP {
+                break;
+            }
+            let coord = ivec3(
+                self.rng.gen_range(min.x..=max.x),
+                self.rng.gen_range(min.y..=max.y),
+                self.rng.gen_range(min.z..=max.z),
+            );
+            let local = ivec3(
+                self.rng.gen_range(0..CHUNK_SIZE),
+                self.rng.gen_range(0..CHUNK_SIZE),
+                self.rng.gen_range(0..CHUNK_SIZE),
+            );
+            let feet = coord * CHUNK_SIZE + local;
+            let in_chunk = per_chunk.entry(coord).or_default();
+            if *in_chunk >= CHUNK_MOB_CAP || !can_spawn(world, feet, player, sky_light) {
+                continue;
+            }
+            let centre = feet.as_vec3() + Vec3::Y * MOB_CENTRE;
+            entities.spawn(Entity::new(EntityKind::Mob, centre));
+            *in_chunk += 1;
+            total += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fxhash::FxHashMap;
+    use glam::{ivec3, vec3, IVec3, Vec3};
+
+    use crate::{
+        chunk::Face,
+        entity::{Entities, Entity, EntityKind},
+        generator::FlatGenerator,
+        util::{block_position, chunk_coord},
+        world::{Block, BlockType, World},
+    };
+
+    use super::{
+        can_spawn, Spawner, CHUNK_MOB_CAP, DESPAWN_DISTANCE, IDLE_DISTANCE, IDLE_TICKS, MOB_CAP,
+        MOB_CENTRE,
+    };
+
+    #[test]
+    fn mobs_spawn_only_in_the_dark() {
+        let generator = FlatGenerator::parse("dirt,6*stone").unwrap();
+        let mut world = World::new(48, 48, 8, &generator, 0);
+        let player = vec3(40.0, -4.0, 40.0);
+        // on top of the ground, only at night
+        let surface = ivec3(10, -4, 10);
+        assert!(can_spawn(&world, surface, player, 0.15));
+        assert!(!can_spawn(&world, surface, player, 1.0));
+        assert!(!can_spawn(&world, surface, surface.as_vec3(), 0.15));
+        // in the air or inside the ground, never
+        assert!(!can_spawn(&world, ivec3(10, -3, 10), player, 0.15));
+        assert!(!can_spawn(&world, ivec3(10, -6, 10), player, 0.15));
+        // nor on anything that isn't proper ground
+        for block_type in [BlockType::Water, BlockType::Glass, BlockType::Portal] {
+            world.set_block(surface - IVec3::Y, Some(Block::new(block_type)));
+            assert!(!can_spawn(&world, surface, player, 0.15));
+        }
+        world.set_block(surface - IVec3::Y, Some(Block::new(BlockType::Stone)));
+        assert!(can_spawn(&world, surface, player, 0.15));
+
+        // a cave is dark even during the day, unless there's a torch in it
+        let cave = ivec3(10, -7, 10);
+        for position in [cave, cave + IVec3::Y, cave + IVec3::X] {
+            world.set_block(position, None);
+        }
+        assert!(can_spawn(&world, cave, player, 1.0));
+        assert!(world.place_torch(cave + ivec3(1, -1, 0), Face::PosY));
+        assert!(!can_spawn(&world, cave, player, 1.0));
+    }
+
+    #[test]
+    fn spawning_stops_at_the_caps() {
+        let world = World::new(64, 64, 4, &FlatGenerator::default(), 0);
+        let mut entities = Entities::default();
+        let mut spawner = Spawner::new(0);
+        for _ in 0..5000 {
+            spawner.tick(&world, &mut entities, vec3(32.0, -4.0, 32.0), 0.15);
+        }
+        let mut per_chunk: FxHashMap<_, usize> = FxHashMap::default();
+        for position in entities.positions(EntityKind::Mob) {
+            *per_chunk
+                .entry(chunk_coord(block_position(position)))
+                .or_default() += 1;
+            // standing on the ground
+            assert_eq!(position.y, -4.0 + MOB_CENTRE);
+        }
+        assert_eq!(per_chunk.values().sum::<usize>(), MOB_CAP);
+        assert!(per_chunk.values().all(|count| *count <= CHUNK_MOB_CAP));
+    }
+
+    #[test]
+    fn mobs_far_from_the_player_despawn() {
+        let world = World::new(16, 16, 4, &FlatGenerator::default(), 0);
+        let mut entities = Entities::default();
+        let mut spawner = Spawner::new(0);
+        let player = Vec3::ZERO;
+        let mob = |x: f32| Entity::new(EntityKind::Mob, vec3(x, 0.0, 0.0));
+        entities.spawn(mob(DESPAWN_DISTANCE));
+        entities.spawn(mob(IDLE_DISTANCE));
+        entities.spawn(mob(DESPAWN_DISTANCE).with_name("Steve"));
+        entities.spawn(mob(1.0));
+        // daylight, so nothing new spawns
+        let count = |entities: &Entities| entities.positions(EntityKind::Mob).count();
+        spawner.tick(&world, &mut entities, player, 1.0);
+        assert_eq!(count(&entities), 3);
+        for _ in 1..IDLE_TICKS {
+            spawner.tick(&world, &mut entities, player, 1.0);
+        }
+        assert_eq!(count(&entities), 2);
+    }
+}
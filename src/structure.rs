@@ -0,0 +1,214 @@
+use std::{fmt, path::Path, str::FromStr};
+
+use glam::{ivec3, IVec3};
+
+use crate::world::BlockType;
+
+/// Where saved structures are kept, each in a file named after it.
+pub const STRUCTURE_DIR: &str = "structures";
+
+/// How far a structure is turned about the vertical axis through its origin when placed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Rotation {
+    #[default]
+    None,
+    Quarter,
+    Half,
+    ThreeQuarters,
+}
+
+impl Rotation {
+    pub fn rotate(self, offset: IVec3) -> IVec3 {
+        match self {
+            Rotation::None => offset,
+            Rotation::Quarter => ivec3(-offset.z, offset.y, offset.x),
+            Rotation::Half => ivec3(-offset.x, offset.y, -offset.z),
+            Rotation::ThreeQuarters => ivec3(offset.z, offset.y, -offset.x),
+        }
+    }
+}
+
+impl FromStr for Rotation {
+    type Err = String;
+
+    /// Reads a rotation in degrees, a multiple of 90.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "0" => Ok(Rotation::None),
+            "90" => Ok(Rotation::Quarter),
+            "180" => Ok(Rotation::Half),
+            "270" => Ok(Rotation::ThreeQuarters),
+            _ => Err(format!("{s} isn't a rotation, expected 0, 90, 180 or 270")),
+        }
+    }
+}
+
+/// A piece of the world kept to be placed again, for anything built from the same blocks in
+/// more than one spot. Only full blocks are kept, and positions it leaves out are left alone
+/// when it's placed.
+///
+/// Saved as text: a palette line naming each block type once, then a line for every block
+/// giving its offset from the origin and its index in the palette.
+///
+/// ```text
+/// palette = stone cobble
+/// 0 0 0 0
+/// 1 0 0 1
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Structure {
+    palette: Vec<BlockType>,
+    blocks: Vec<(IVec3, usize)>,
+}
+
+impl Structure {
+    /// A structure of `blocks`, each at its offset from the origin. Anything that isn't a full
+    /// block is left out.
+    pub fn from_blocks(blocks: impl IntoIterator<Item = (IVec3, BlockType)>) -> Self {
+        let mut structure = Self::default();
+        for (offset, block_type) in blocks {
+            if !block_type.is_cube() {
+                continue;
+            }
+            let index = match structure.palette.iter().position(|b| *b == block_type) {
+                Some(index) => index,
+                None => {
+                    structure.palette.push(block_type);
+                    structure.palette.len() - 1
+                }
+            };
+            structure.blocks.push((offset, index));
+        }
+        structure
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        let path = path.as_ref();
+        std::fs::read_to_string(path)
+            .map_err(|e| format!("couldn't read {}: {e}", path.display()))?
+            .parse()
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> std::io::Result<()> {
+        if let Some(dir) = path.as_ref().parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, self.to_string())
+    }
+
+    /// Every block turned by `rotation`, at its offset from the origin.
+    pub fn blocks(&self, rotation: Rotation) -> impl Iterator<Item = (IVec3, BlockType)> + '_ {
+        self.blocks
+            .iter()
+            .map(move |(offset, index)| (rotation.rotate(*offset), self.palette[*index]))
+    }
+}
+
+impl FromStr for Structure {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut structure = Self::default();
+        for line in s.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            if let Some(("palette", names)) = line.split_once('=').map(|(k, v)| (k.trim(), v)) {
+                for name in names.split_whitespace() {
+                    let block_type = BlockType::from_name(name)
+                        .filter(|block_type| block_type.is_cube())
+                        .ok_or_else(|| format!("{name} can't be in a structure"))?;
+                    structure.palette.push(block_type);
+                }
+                continue;
+            }
+            let numbers = line
+                .split_whitespace()
+                .map(str::parse)
+                .collect::<Result<Vec<i32>, _>>()
+                .map_err(|_| format!("couldn't read block {line}"))?;
+            let [x, y, z, index] = numbers[..] else {
+                return Err(format!("expected x y z and a palette index, got {line}"));
+            };
+            let index = usize::try_from(index)
+                .ok()
+                .filter(|index| *index < structure.palette.len())
+                .ok_or_else(|| format!("{index} isn't in the palette"))?;
+            structure.blocks.push((ivec3(x, y, z), index));
+        }
+        Ok(structure)
+    }
+}
+
+impl fmt::Display for Structure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names: Vec<&str> = self.palette.iter().map(|b| (*b).into()).collect();
+        writeln!(f, "palette = {}", names.join(" "))?;
+        for (offset, index) in &self.blocks {
+            writeln!(f, "{} {} {} {index}", offset.x, offset.y, offset.z)?;
+        }
+        Ok(())
+    }
+}
+
+/// Where the structure called `name` is saved, or why it can't be. Names are kept to letters,
+/// digits, `-` and `_` so they can't reach outside the structure directory.
+pub fn path(name: &str) -> Result<String, String> {
+    file_path(name, "structure")
+}
+
+/// Like `path`, for a file of the structure in another format.
+pub fn file_path(name: &str, extension: &str) -> Result<String, String> {
+    let allowed = |c: char| c.is_ascii_alphanumeric() || c == '-' || c == '_';
+    if name.is_empty() || !name.chars().all(allowed) {
+        return Err(format!("{name} can't be a structure name"));
+    }
+    Ok(format!("{STRUCTURE_DIR}/{name}.{extension}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{ivec3, IVec3};
+
+    use crate::world::BlockType;
+
+    use super::{file_path, path, Rotation, Structure};
+
+    #[test]
+    fn structures_round_trip() {
+        let structure = Structure::from_blocks([
+            (IVec3::ZERO, BlockType::Stone),
+            (ivec3(1, 0, 0), BlockType::Cobble),
+            (ivec3(2, 0, 0), BlockType::Stone),
+            (ivec3(0, 1, 0), BlockType::Torch),
+        ]);
+        // the torch is left out and stone is only in the palette once
+        assert_eq!(structure.blocks.len(), 3);
+        let text = structure.to_string();
+        assert_eq!(text, "palette = stone cobble\n0 0 0 0\n1 0 0 1\n2 0 0 0\n");
+        assert_eq!(text.parse(), Ok(structure));
+
+        assert!("palette = stone\n0 0 0 1".parse::<Structure>().is_err());
+        assert!("palette = bed".parse::<Structure>().is_err());
+        assert!("palette = stone\n0 0 0".parse::<Structure>().is_err());
+    }
+
+    #[test]
+    fn rotations_turn_about_the_origin() {
+        let offset = ivec3(2, 1, 0);
+        assert_eq!(Rotation::Quarter.rotate(offset), ivec3(0, 1, 2));
+        assert_eq!(Rotation::Half.rotate(offset), ivec3(-2, 1, 0));
+        let turned = Rotation::ThreeQuarters.rotate(Rotation::Quarter.rotate(offset));
+        assert_eq!(turned, offset);
+        assert_eq!("270".parse(), Ok(Rotation::ThreeQuarters));
+        assert!("45".parse::<Rotation>().is_err());
+    }
+
+    #[test]
+    fn names_stay_in_the_structure_directory() {
+        assert_eq!(path("ruin_2").as_deref(), Ok("structures/ruin_2.structure"));
+        assert!(path("../settings").is_err());
+        assert!(path("").is_err());
+        assert_eq!(
+            file_path("ruin_2", "schem").as_deref(),
+            Ok("structures/ruin_2.schem")
+        );
+    }
+}
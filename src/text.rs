@@ -1,125 +1,176 @@
 use std::ffi::OsStr;
 
-use freetype::{bitmap::PixelMode, face::LoadFlag, Library};
+use freetype::{bitmap::PixelMode, face::LoadFlag, Face, Library};
 use fxhash::FxHashMap;
 use glam::{ivec2, IVec2};
-use image::{DynamicImage, GenericImage, Rgba, RgbaImage};
+use image::{GenericImage, Rgba, RgbaImage};
 
-use crate::texture::{Rect, TextureAtlas, TextureHandle};
+use crate::texture::{Rect, TextureAtlas};
 
-const CHARS: [char; 26 * 2 + 1] = [
+/// Every character fonts rasterise up front, anything else is rasterised the first time it's
+/// drawn.
+pub const CHARS: [char; 26 * 2 + 10 + 13] = [
     'a', 'b', 'c', 'd', 'e', 'f', 'g', 'h', 'i', 'j', 'k', 'l', 'm', 'n', 'o', 'p', 'q', 'r', 's',
     't', 'u', 'v', 'w', 'x', 'y', 'z', 'A', 'B', 'C', 'D', 'E', 'F', 'G', 'H', 'I', 'J', 'K', 'L',
-    'M', 'N', 'O', 'P', 'Q', 'R', 'S', 'T', 'U', 'V', 'W', 'X', 'Y', 'Z', ' ',
+    'M', 'N', 'O', 'P', 'Q', 'R', 'S', 'T', 'U', 'V', 'W', 'X', 'Y', 'Z', ' ', '0', '1', '2', '3',
+    '4', '5', '6', '7', '8', '9', '.', ',', ':', '-', '+', '!', '?', '/', '(', ')', '%', '_',
 ];
 
+/// The pixel sizes glyphs are rasterised at below a font's own, text picks the smallest that's
+/// at least as tall as it's drawn so it stays sharp without filling the atlas with every size.
+const SIZES: [u32; 7] = [12, 16, 24, 32, 48, 64, 96];
+
+/// The smallest of `SIZES` at least `height` pixels tall, or `largest` if that's smaller.
+fn glyph_size(height: f32, largest: u32) -> u32 {
+    SIZES
+        .into_iter()
+        .find(|size| *size as f32 >= height && *size < largest)
+        .unwrap_or(largest)
+}
+
 pub struct CharacterMetric {
     pub size: IVec2,
     pub bearing: IVec2,
     pub advance: i32,
 }
 
+/// A rasterised character, where it is in the atlas and how to place it.
+pub struct Glyph {
+    pub rect: Rect,
+    pub metric: CharacterMetric,
+}
+
+/// A font face with its glyphs rasterised at any number of pixel sizes into one atlas, which
+/// grows as new characters and sizes are drawn without moving the glyphs already in it.
 pub struct Font {
-    pub atlas: TextureAtlas,
-    glyph_map: FxHashMap<char, TextureHandle>,
-    pub tex: DynamicImage,
-    pub metrics: FxHashMap<char, CharacterMetric>,
+    face: Face,
+    atlas: TextureAtlas,
+    glyphs: FxHashMap<(char, u32), Glyph>,
+    /// The atlas image, as tall as the atlas rounded up to a power of two.
+    pub tex: RgbaImage,
+    /// The pixel height text is measured in, and the largest glyphs are rasterised at.
+    pub pixel_size: u32,
+    /// Whether glyphs have been added since the atlas was last uploaded.
+    changed: bool,
 }
 
 impl Font {
     pub fn new<S: AsRef<OsStr>>(path: S, px: u32) -> Self {
         let lib = Library::init().unwrap();
-        // load the ttf font at the specified path
+        // load the ttf font at the specified path, the face keeps the library alive
         let face = lib.new_face(path, 0).unwrap();
-        face.set_pixel_sizes(0, px)
-            .unwrap_or_else(|err| panic!("{err}"));
-        // initialise an atlas for all glyphs, store an index of char -> TextureHandle
-        let mut atlas = TextureAtlas::new();
-        let mut bitmaps = vec![];
-        let mut glyph_map = FxHashMap::default();
-        let mut metrics = FxHashMap::default();
+        let mut font = Self {
+            face,
+            atlas: TextureAtlas::new(),
+            glyphs: FxHashMap::default(),
+            tex: RgbaImage::new(0, 0),
+            pixel_size: px,
+            changed: false,
+        };
         for char in CHARS {
-            face.load_char(char as usize, LoadFlag::RENDER)
-                .unwrap_or_else(|err| panic!("Face failed to load char: {char}, err: {err}"));
-            let glyph = face.glyph();
-            let bitmap = glyph.bitmap();
-
-            let bearing = ivec2(glyph.bitmap_left(), glyph.bitmap_top());
-            let size = ivec2(bitmap.width(), bitmap.rows());
-            let advance = glyph.advance().x as i32;
-
-            let handle = atlas.add(bitmap.width(), bitmap.rows());
-            // println!("{}, {}", bitmap.width(), bitmap.rows());
-            glyph_map.insert(char, handle);
-            let buffer = bitmap.buffer().to_vec();
-            bitmaps.push((buffer, handle, bitmap.pixel_mode().unwrap()));
-            metrics.insert(
-                char,
-                CharacterMetric {
-                    size,
-                    bearing,
-                    advance,
-                },
-            );
+            font.glyph(char, px);
+        }
+
+        font.tex
+            .save("font-bitmap.png")
+            .unwrap_or_else(|err| panic!("{err}"));
+        font
+    }
+
+    /// The pixel size to rasterise text drawn `height` pixels tall at.
+    pub fn size_for(&self, height: f32) -> u32 {
+        glyph_size(height, self.pixel_size)
+    }
+
+    /// `char` rasterised at `px`, added to the atlas if it isn't already.
+    pub fn glyph(&mut self, char: char, px: u32) -> &Glyph {
+        if !self.glyphs.contains_key(&(char, px)) {
+            let glyph = self.rasterise(char, px);
+            self.glyphs.insert((char, px), glyph);
         }
+        &self.glyphs[&(char, px)]
+    }
 
-        atlas.pack();
-
-        let mut tex =
-            DynamicImage::ImageRgba8(RgbaImage::new(atlas.width as u32, atlas.height as u32));
-        for (bitmap, handle, pixel_mode) in bitmaps {
-            let (rect, _) = atlas
-                .get_rect(&handle)
-                .unwrap_or_else(|| panic!("Expected rect for handle {handle}."));
-            // what does each u8 of our bitmap buffer represent? that will depend on the pixel mode
-            // for now let's assume it's PixelMode::Gray (each u8 is a pixel) and panic otherwise
-            assert!(
-                pixel_mode == PixelMode::Gray,
-                "pixel mode was {pixel_mode:?}",
-            );
-            let mut row = 0;
-            let mut col = 0;
-            // println!("rect: {rect:?}");
-            for pixel in bitmap {
-                if col >= rect.w {
-                    col = 0;
-                    row += 1
-                }
+    fn rasterise(&mut self, char: char, px: u32) -> Glyph {
+        self.face
+            .set_pixel_sizes(0, px)
+            .unwrap_or_else(|err| panic!("{err}"));
+        self.face
+            .load_char(char as usize, LoadFlag::RENDER)
+            .unwrap_or_else(|err| panic!("Face failed to load char: {char}, err: {err}"));
+        let glyph = self.face.glyph();
+        let bitmap = glyph.bitmap();
+        let metric = CharacterMetric {
+            size: ivec2(bitmap.width(), bitmap.rows()),
+            bearing: ivec2(glyph.bitmap_left(), glyph.bitmap_top()),
+            advance: glyph.advance().x as i32,
+        };
+        let handle = self.atlas.insert(bitmap.width(), bitmap.rows());
+        let (rect, _) = self
+            .atlas
+            .get_rect(&handle)
+            .unwrap_or_else(|| panic!("Expected rect for handle {handle}."));
+        self.grow();
+
+        // what does each u8 of our bitmap buffer represent? that will depend on the pixel mode
+        // for now let's assume it's PixelMode::Gray (each u8 is a pixel) and panic otherwise
+        let pixel_mode = bitmap.pixel_mode().unwrap();
+        assert!(
+            pixel_mode == PixelMode::Gray,
+            "pixel mode was {pixel_mode:?}",
+        );
+        // blank glyphs like spaces have no buffer at all
+        if rect.w == 0 || rect.h == 0 {
+            self.changed = true;
+            return Glyph { rect, metric };
+        }
+        // rows can be padded out past the glyph's width
+        let pitch = bitmap.pitch().unsigned_abs() as usize;
+        for (row, line) in bitmap.buffer().chunks(pitch).enumerate() {
+            for (col, pixel) in line.iter().copied().take(rect.w as usize).enumerate() {
                 // to convert grayscale to rgb we assume gray = (R+G+B)/3 so R = G = B = gray
-                tex.put_pixel(
+                self.tex.put_pixel(
                     rect.x as u32 + col as u32,
                     rect.y as u32 + row as u32,
-                    Rgba([
-                        pixel,
-                        pixel,
-                        pixel,
-                        if pixel == 0_u8 { 0_u8 } else { 255_u8 },
-                    ]),
+                    Rgba([pixel, pixel, pixel, if pixel == 0 { 0 } else { 255 }]),
                 );
-                col += 1;
             }
         }
+        self.changed = true;
+        Glyph { rect, metric }
+    }
 
-        tex.save("font-bitmap.png")
-            .unwrap_or_else(|err| panic!("{err}"));
-
-        Self {
-            glyph_map,
-            atlas,
-            tex,
-            metrics,
+    /// Makes the image big enough for the atlas, doubling its height so it isn't reallocated
+    /// for every glyph.
+    fn grow(&mut self) {
+        let (width, height) = (self.atlas.width as u32, self.atlas.height as u32);
+        if width == self.tex.width() && height <= self.tex.height() {
+            return;
         }
+        let mut tex = RgbaImage::new(width, height.next_power_of_two());
+        tex.copy_from(&self.tex, 0, 0)
+            .expect("The atlas only grows.");
+        self.tex = tex;
     }
 
-    pub fn get_char_rect(&self, char: char) -> Rect {
-        let handle = self
-            .glyph_map
-            .get(&char)
-            .expect("Couldn't find glyph in glyph map.");
-        let (rect, _) = self
-            .atlas
-            .get_rect(handle)
-            .expect("Couldn't find rect in atlas.");
-        rect
+    /// Whether glyphs have been added since this was last asked, and so the atlas needs
+    /// uploading again.
+    pub fn take_changed(&mut self) -> bool {
+        std::mem::take(&mut self.changed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::glyph_size;
+
+    #[test]
+    fn text_picks_the_smallest_size_that_stays_sharp() {
+        assert_eq!(glyph_size(5.0, 120), 12);
+        assert_eq!(glyph_size(16.0, 120), 16);
+        assert_eq!(glyph_size(16.5, 120), 24);
+        // never past the font's own size, bigger text is scaled up from that
+        assert_eq!(glyph_size(100.0, 120), 120);
+        assert_eq!(glyph_size(40.0, 32), 32);
     }
 }
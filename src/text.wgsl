@@ -1,5 +1,21 @@
+struct Frame {
+    view_proj: mat4x4<f32>,
+    view: mat4x4<f32>,
+    camera_position: vec3<f32>,
+    time: f32,
+    sun_direction: vec3<f32>,
+    fog_start: f32,
+    fog_color: vec3<f32>,
+    fog_end: f32,
+    screen_size: vec2<f32>,
+    // how bright the sky lights the world, from 0 to 1
+    sky_light: f32,
+    // world border corners on the x z plane, min in xy and max in zw
+    border: vec4<f32>,
+}
+
 @group(0) @binding(0)
-var<uniform> camera: mat4x4<f32>;
+var<uniform> frame: Frame;
 @group(1) @binding(0)
 var texture: texture_2d<f32>;
 @group(1) @binding(1)
@@ -19,7 +35,26 @@ struct VertexOutput {
 @vertex
 fn vertex(vertex: VertexInput) -> VertexOutput {
     var out: VertexOutput;
-    out.position = camera * vec4<f32>(vertex.position, 0.0, 1.0);
+    out.position = frame.view_proj * vec4<f32>(vertex.position, 0.0, 1.0);
+    out.tex = vertex.tex;
+    return out;
+}
+
+struct WorldTextInstance {
+    @location(2) anchor: vec3<f32>,
+}
+
+// positions are offsets from the anchor in world units along the camera's right and up axes, so
+// the text always faces the camera
+@vertex
+fn world_vertex(vertex: VertexInput, instance: WorldTextInstance) -> VertexOutput {
+    // the camera's right and up axes are the first two rows of the view matrix
+    let right = vec3<f32>(frame.view[0][0], frame.view[1][0], frame.view[2][0]);
+    let up = vec3<f32>(frame.view[0][1], frame.view[1][1], frame.view[2][1]);
+    let world_position = instance.anchor + right * vertex.position.x + up * vertex.position.y;
+
+    var out: VertexOutput;
+    out.position = frame.view_proj * vec4<f32>(world_position, 1.0);
     out.tex = vertex.tex;
     return out;
 }
@@ -30,5 +65,7 @@ struct FragmentInput {
 
 @fragment
 fn fragment(in: FragmentInput) -> @location(0) vec4<f32> {
-    return textureSample(texture, samp, in.tex);
+    // in texels, so text stays put when the atlas it's drawn from grows
+    let size = vec2<f32>(textureDimensions(texture));
+    return textureSample(texture, samp, in.tex / size);
 }
\ No newline at end of file
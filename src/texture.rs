@@ -1,3 +1,5 @@
+use std::str::FromStr;
+
 use image::DynamicImage;
 
 impl From<&DynamicImage> for Rect {
@@ -27,15 +29,116 @@ impl Ord for Rect {
 
 impl PartialOrd for Rect {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        self.h.partial_cmp(&other.h)
+        Some(self.cmp(other))
     }
 }
 
 pub type TextureHandle = u32;
 
+/// How a block's texture varies from one block to the next, so wide areas of it don't show the
+/// same tile repeating.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Variation {
+    /// Each block picks one of this many variants, laid side by side in the texture's image.
+    pub variants: u32,
+    /// Each block also turns its variant a random number of quarter turns.
+    pub rotate: bool,
+}
+
+/// How an animated texture plays. Its image is a strip of equally sized frames one above the
+/// other, shown top to bottom on a loop, and this is read from a `key = value` file beside it.
+///
+/// ```text
+/// frames = 4
+/// # seconds each frame is shown
+/// frame_time = 0.25
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Animation {
+    pub frames: u32,
+    pub frame_time: f32,
+}
+
+impl FromStr for Animation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut frames = None;
+        let mut frame_time = None;
+        for line in s.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            match line.split_once('=').map(|(k, v)| (k.trim(), v.trim())) {
+                Some(("frames", value)) => {
+                    frames = value.parse::<u32>().ok().filter(|frames| *frames > 0);
+                    if frames.is_none() {
+                        return Err(format!("{value} isn't a number of frames"));
+                    }
+                }
+                Some(("frame_time", value)) => {
+                    frame_time = value.parse::<f32>().ok().filter(|time| *time > 0.0);
+                    if frame_time.is_none() {
+                        return Err(format!("{value} isn't a frame time in seconds"));
+                    }
+                }
+                _ => return Err(format!("unknown animation line {line}")),
+            }
+        }
+        Ok(Self {
+            frames: frames.ok_or("missing frames")?,
+            frame_time: frame_time.ok_or("missing frame_time")?,
+        })
+    }
+}
+
+impl Animation {
+    /// The frame shown `time` seconds in.
+    pub fn frame(&self, time: f32) -> usize {
+        (time / self.frame_time) as usize % self.frames as usize
+    }
+
+    /// Splits a strip, one frame above the other, into the frames.
+    pub fn split(&self, strip: &DynamicImage) -> Result<Vec<DynamicImage>, String> {
+        split_sheet(strip, 1, self.frames)
+    }
+}
+
+/// Splits a sheet of `columns` by `rows` equally sized images into the images, left to right
+/// then top to bottom.
+pub fn split_sheet(
+    sheet: &DynamicImage,
+    columns: u32,
+    rows: u32,
+) -> Result<Vec<DynamicImage>, String> {
+    let (width, height) = (sheet.width(), sheet.height());
+    if columns == 0 || rows == 0 || width % columns != 0 || height % rows != 0 {
+        return Err(format!(
+            "a {width}x{height} image doesn't split into {columns} by {rows}"
+        ));
+    }
+    let (width, height) = (width / columns, height / rows);
+    Ok((0..rows)
+        .flat_map(|row| (0..columns).map(move |column| (column, row)))
+        .map(|(column, row)| sheet.crop_imm(column * width, row * height, width, height))
+        .collect())
+}
+
+/// The width atlases are packed to, they grow downwards.
+const ATLAS_WIDTH: i32 = 512;
+
+/// A row of an atlas that rects are placed along left to right, as tall as the first one.
+struct Shelf {
+    y: i32,
+    height: i32,
+    used: i32,
+}
+
 pub struct TextureAtlas {
     counter: u32,
     rects: Vec<(Rect, TextureHandle)>,
+    /// The rows rects have been placed along by `insert`.
+    shelves: Vec<Shelf>,
     pub width: i32,
     pub height: i32,
 }
@@ -46,11 +149,51 @@ impl TextureAtlas {
             counter: 0,
             // entries: FxHashMap::default(),
             rects: vec![],
+            shelves: vec![],
             width: 0,
             height: 0,
         }
     }
 
+    /// Adds a rect and places it straight away, leaving every rect already placed where it is
+    /// so textures can be added to a live atlas. It goes on the shortest shelf it fits on, or a
+    /// new one along the bottom, which grows the atlas.
+    pub fn insert(&mut self, w: i32, h: i32) -> TextureHandle {
+        assert!(w <= ATLAS_WIDTH, "a {w} wide rect doesn't fit in the atlas");
+        self.width = ATLAS_WIDTH;
+        let index = match self
+            .shelves
+            .iter()
+            .enumerate()
+            .filter(|(_, shelf)| h <= shelf.height && shelf.used + w <= self.width)
+            .min_by_key(|(_, shelf)| shelf.height)
+        {
+            Some((index, _)) => index,
+            None => {
+                self.shelves.push(Shelf {
+                    y: self.height,
+                    height: h,
+                    used: 0,
+                });
+                self.height += h;
+                self.shelves.len() - 1
+            }
+        };
+        let shelf = &mut self.shelves[index];
+        let rect = Rect {
+            x: shelf.used,
+            y: shelf.y,
+            w,
+            h,
+        };
+        shelf.used += w;
+
+        let handle = self.counter;
+        self.counter += 1;
+        self.rects.push((rect, handle));
+        handle
+    }
+
     pub fn add(&mut self, w: i32, h: i32) -> TextureHandle {
         let handle = self.counter;
         self.counter += 1;
@@ -63,7 +206,7 @@ impl TextureAtlas {
         // let's go for a fixed width to break on
         let mut x = 0;
         let mut y = 0;
-        self.width = 512;
+        self.width = ATLAS_WIDTH;
         // sort s.t. the tallest rect is first
         // decreasing rect height means we can place anything
         self.rects.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap());
@@ -91,8 +234,8 @@ impl TextureAtlas {
     }
 }
 
+/// A view of a texture and how it's sampled. The view keeps the texture itself alive.
 pub struct Texture {
-    pub texture: wgpu::Texture,
     pub view: wgpu::TextureView,
     pub sampler: wgpu::Sampler,
 }
@@ -133,14 +276,107 @@ impl Texture {
             mipmap_filter: wgpu::FilterMode::Nearest,
             lod_min_clamp: -100.0,
             lod_max_clamp: 100.0,
-            compare: Some(wgpu::CompareFunction::LessEqual),
+            compare: Some(wgpu::CompareFunction::GreaterEqual),
             ..Default::default()
         });
 
-        Self {
-            texture,
-            view,
-            sampler,
-        }
+        Self { view, sampler }
+    }
+
+    /// A texture to draw into and then sample from, as post-processing passes do, with a
+    /// linear sampler that clamps at the edges.
+    pub fn create_render_target(
+        device: &wgpu::Device,
+        label: &str,
+        width: u32,
+        height: u32,
+        format: wgpu::TextureFormat,
+    ) -> Self {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some(label),
+            size: wgpu::Extent3d {
+                width: width.max(1),
+                height: height.max(1),
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        Self { view, sampler }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{DynamicImage, GenericImageView, Rgba, RgbaImage};
+
+    use super::{split_sheet, Animation, TextureAtlas};
+
+    #[test]
+    fn strips_split_into_frames() {
+        let animation = Animation {
+            frames: 3,
+            frame_time: 1.0,
+        };
+        let strip = RgbaImage::from_fn(6, 12, |_, y| Rgba([(y / 4) as u8, 0, 0, 255]));
+        let frames = animation.split(&DynamicImage::ImageRgba8(strip)).unwrap();
+        assert_eq!(frames.len(), 3);
+        assert_eq!(frames[2].dimensions(), (6, 4));
+        assert_eq!(frames[2].get_pixel(1, 1), Rgba([2, 0, 0, 255]));
+        assert!(animation.split(&DynamicImage::new_rgba8(6, 10)).is_err());
+    }
+
+    #[test]
+    fn sheets_split_left_to_right_then_down() {
+        let sheet = RgbaImage::from_fn(8, 4, |x, y| Rgba([(x / 4 + 2 * (y / 2)) as u8, 0, 0, 255]));
+        let images = split_sheet(&DynamicImage::ImageRgba8(sheet), 2, 2).unwrap();
+        let order: Vec<u8> = images
+            .iter()
+            .map(|image| image.get_pixel(0, 0)[0])
+            .collect();
+        assert_eq!(order, [0, 1, 2, 3]);
+        assert_eq!(images[3].dimensions(), (4, 2));
+    }
+
+    #[test]
+    fn animations_loop_through_their_frames() {
+        let animation: Animation = "# water\nframes = 4\nframe_time = 0.5\n".parse().unwrap();
+        assert_eq!(animation.frame(0.4), 0);
+        assert_eq!(animation.frame(1.2), 2);
+        assert_eq!(animation.frame(2.1), 0);
+        assert!("frames = 4\nframe_time = 0".parse::<Animation>().is_err());
+        assert!("frame_time = 1".parse::<Animation>().is_err());
+    }
+
+    #[test]
+    fn inserted_rects_stay_put_as_the_atlas_grows() {
+        let mut atlas = TextureAtlas::new();
+        let tall = atlas.insert(300, 40);
+        let short = atlas.insert(100, 20);
+        assert_eq!(atlas.height, 40);
+        // too wide for the first shelf's space, so it starts a shelf of its own
+        let wide = atlas.insert(250, 20);
+        assert_eq!(atlas.height, 60);
+        // and the shorter shelf is the one smaller rects fill first
+        let small = atlas.insert(50, 10);
+
+        let rect = |handle| atlas.get_rect(&handle).unwrap().0;
+        assert_eq!((rect(tall).x, rect(tall).y), (0, 0));
+        assert_eq!((rect(short).x, rect(short).y), (300, 0));
+        assert_eq!((rect(wide).x, rect(wide).y), (0, 40));
+        assert_eq!((rect(small).x, rect(small).y), (250, 40));
     }
 }
@@ -0,0 +1,74 @@
+use std::{collections::BTreeMap, hash::Hash};
+
+use fxhash::FxHashSet;
+
+/// Work deferred to a later tick. Anything scheduled while a tick's work is being handled runs
+/// on a later tick, so chains of updates spread out over time instead of recursing within one
+/// frame.
+pub struct TickScheduler<T> {
+    tick: u64,
+    pending: BTreeMap<u64, Vec<T>>,
+    /// Everything in `pending`, each item can only be waiting once.
+    queued: FxHashSet<T>,
+}
+
+impl<T> Default for TickScheduler<T> {
+    fn default() -> Self {
+        Self {
+            tick: 0,
+            pending: BTreeMap::new(),
+            queued: FxHashSet::default(),
+        }
+    }
+}
+
+impl<T: Copy + Eq + Hash> TickScheduler<T> {
+    /// Runs `item` `delay` ticks from now, at least one. Does nothing if it's already waiting.
+    pub fn schedule(&mut self, item: T, delay: u64) {
+        if self.queued.insert(item) {
+            let tick = self.tick + delay.max(1);
+            self.pending.entry(tick).or_default().push(item);
+        }
+    }
+
+    /// Moves on to the next tick and returns what's due on it, in the order it was scheduled.
+    pub fn advance(&mut self) -> Vec<T> {
+        self.tick += 1;
+        let due = self.pending.remove(&self.tick).unwrap_or_default();
+        for item in &due {
+            self.queued.remove(item);
+        }
+        due
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TickScheduler;
+
+    #[test]
+    fn items_run_after_their_delay() {
+        let mut scheduler = TickScheduler::default();
+        scheduler.schedule('a', 2);
+        scheduler.schedule('b', 1);
+        // a delay of zero still waits for the next tick
+        scheduler.schedule('c', 0);
+        assert_eq!(scheduler.advance(), vec!['b', 'c']);
+        // scheduled during a tick, so it can't run on that same tick
+        scheduler.schedule('d', 1);
+        assert_eq!(scheduler.advance(), vec!['a', 'd']);
+        assert!(scheduler.advance().is_empty());
+    }
+
+    #[test]
+    fn waiting_items_are_not_queued_twice() {
+        let mut scheduler = TickScheduler::default();
+        scheduler.schedule(1, 1);
+        scheduler.schedule(1, 3);
+        assert_eq!(scheduler.advance(), vec![1]);
+        // once it has run it can be scheduled again
+        scheduler.schedule(1, 1);
+        assert_eq!(scheduler.advance(), vec![1]);
+        assert!(scheduler.advance().is_empty());
+    }
+}
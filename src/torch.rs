@@ -0,0 +1,104 @@
+use glam::{IVec3, Quat, Vec3};
+
+use crate::{
+    chunk::Face,
+    instance::Instance,
+    renderer::{v, Drawable, Renderer, Vertex},
+    world::World,
+};
+
+/// The renderer object torches are drawn with.
+const TORCH_OBJECT: u32 = 1;
+
+/// How far a wall torch leans away from the wall, in radians.
+const WALL_TILT: f32 = 0.4;
+/// How far a wall torch sits from the centre of its block towards the wall...
+const WALL_OFFSET: f32 = 0.3;
+/// ...and how far up, so its base rests against the wall.
+const WALL_RAISE: f32 = 0.2;
+
+/// A torch as it's drawn: two crossed quads showing the torch texture from every side.
+pub struct Torch {
+    position: IVec3,
+    /// The face of the block it's attached to.
+    face: Face,
+}
+
+impl Torch {
+    pub fn new(position: IVec3, face: Face) -> Self {
+        Self { position, face }
+    }
+}
+
+impl Drawable for Torch {
+    fn draw(&self, renderer: &mut Renderer, world: &World) {
+        renderer.queue_draw(TORCH_OBJECT, self, world);
+    }
+
+    // a block sized X seen from above, each quad twice so it shows from both sides
+    fn vertices(&self) -> Vec<Vertex> {
+        let mut vertices = vec![];
+        for (x, z) in [(0.5, 0.5), (0.5, -0.5)] {
+            vertices.extend([
+                v(-x, 0.5, -z, 0.0, 0.0),
+                v(x, 0.5, z, 1.0, 0.0),
+                v(-x, -0.5, -z, 0.0, 1.0),
+                v(x, -0.5, z, 1.0, 1.0),
+            ]);
+        }
+        vertices
+    }
+
+    fn indices(&self) -> Vec<u16> {
+        let mut indices = vec![];
+        for start in [0, 4] {
+            // v0----v1
+            // |      |
+            // v2----v3
+            indices.extend([0, 2, 3, 0, 3, 1].map(|i| start + i));
+            indices.extend([0, 3, 2, 0, 1, 3].map(|i| start + i));
+        }
+        indices
+    }
+
+    fn instance(&self, world: &World) -> Instance {
+        let centre = self.position.as_vec3();
+        let (position, rotation) = if self.face == Face::PosY {
+            (centre, Quat::IDENTITY)
+        } else {
+            // lean the top away from the wall, with the base against it
+            let normal = self.face.normal().as_vec3();
+            let tilt = Quat::from_axis_angle(Vec3::Y.cross(normal), WALL_TILT);
+            (centre - normal * WALL_OFFSET + Vec3::Y * WALL_RAISE, tilt)
+        };
+        Instance::new(position, rotation, world.get_texture("torch"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::ivec3;
+
+    use crate::{chunk::Face, renderer::Drawable};
+
+    use super::Torch;
+
+    #[test]
+    fn quads_show_from_both_sides() {
+        let torch = Torch::new(ivec3(0, 0, 0), Face::PosY);
+        let indices = torch.indices();
+        let triangles: Vec<&[u16]> = indices.chunks(3).collect();
+        assert_eq!(triangles.len(), 8);
+        // every triangle has a twin over the same corners wound the other way
+        for triangle in &triangles {
+            let [a, b, c] = [triangle[0], triangle[1], triangle[2]];
+            let reversed = [[a, c, b], [c, b, a], [b, a, c]];
+            assert!(
+                triangles
+                    .iter()
+                    .any(|other| reversed.contains(&[other[0], other[1], other[2]])),
+                "{triangle:?}"
+            );
+        }
+    }
+}
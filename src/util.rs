@@ -0,0 +1,337 @@
+use glam::{IVec3, Mat4, Vec3, Vec4};
+
+use crate::chunk::CHUNK_SIZE;
+
+/// Overlaps up to this depth count as touching, so boxes that were just pushed apart aren't
+/// flagged again over float rounding.
+const CONTACT_EPSILON: f32 = 1e-4;
+
+/// The block a world position is in. Blocks are centred on whole coordinates.
+pub fn block_position(position: Vec3) -> IVec3 {
+    position.round().as_ivec3()
+}
+
+/// The chunk containing the block at `position`.
+pub fn chunk_coord(position: IVec3) -> IVec3 {
+    IVec3::new(
+        position.x.div_euclid(CHUNK_SIZE),
+        position.y.div_euclid(CHUNK_SIZE),
+        position.z.div_euclid(CHUNK_SIZE),
+    )
+}
+
+/// The chunk containing the block at `position`, and where the block is relative to the chunk
+/// origin.
+pub fn split(position: IVec3) -> (IVec3, IVec3) {
+    let coord = chunk_coord(position);
+    (coord, position - coord * CHUNK_SIZE)
+}
+
+/// The space the blocks of the chunk at `coord` fill.
+pub fn chunk_bounds(coord: IVec3) -> Aabb {
+    let min = (coord * CHUNK_SIZE).as_vec3() - 0.5;
+    Aabb {
+        min,
+        max: min + CHUNK_SIZE as f32,
+    }
+}
+
+/// Every block a ray from `origin` passes through within `reach` of it, nearest first. A ray
+/// with no direction passes through nothing.
+pub fn voxel_ray(origin: Vec3, direction: Vec3, reach: f32) -> impl Iterator<Item = IVec3> {
+    // shifted so blocks span whole units, then stepped from one block boundary to the next
+    let direction = direction.normalize_or_zero();
+    let start = origin + 0.5;
+    let mut block = start.floor().as_ivec3();
+    let step = direction.signum().as_ivec3();
+    // distances along the ray between boundaries on each axis, and to the next one
+    let delta = direction.recip().abs();
+    let boundary = block.as_vec3() + step.max(IVec3::ZERO).as_vec3();
+    let mut next = (boundary - start) / direction;
+    // with no direction every distance is NaN
+    let mut distance = (direction != Vec3::ZERO).then_some(0.0);
+    std::iter::from_fn(move || {
+        distance.filter(|distance| *distance <= reach)?;
+        let current = block;
+        let axis = if next.x < next.y && next.x < next.z {
+            0
+        } else if next.y < next.z {
+            1
+        } else {
+            2
+        };
+        distance = Some(next[axis]);
+        next[axis] += delta[axis];
+        block[axis] += step[axis];
+        Some(current)
+    })
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Aabb {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Aabb {
+    pub fn from_center(center: Vec3, half_extents: Vec3) -> Self {
+        Self {
+            min: center - half_extents,
+            max: center + half_extents,
+        }
+    }
+
+    /// The smallest translation that moves `self` out of `other`, or None if they don't overlap.
+    /// Boxes that only touch don't overlap.
+    pub fn penetration(&self, other: &Aabb) -> Option<Vec3> {
+        let push_positive = other.max - self.min;
+        let push_negative = self.max - other.min;
+        if push_positive.min_element() <= CONTACT_EPSILON
+            || push_negative.min_element() <= CONTACT_EPSILON
+        {
+            return None;
+        }
+        let mut best = Vec3::ZERO;
+        let mut best_distance = f32::MAX;
+        for axis in 0..3 {
+            for (distance, sign) in [(push_positive[axis], 1.0), (push_negative[axis], -1.0)] {
+                if distance < best_distance {
+                    best_distance = distance;
+                    best = Vec3::ZERO;
+                    best[axis] = sign * distance;
+                }
+            }
+        }
+        Some(best)
+    }
+
+    /// How far along a ray from `origin` it first enters the box, in lengths of `direction`, or
+    /// None if it misses. Rays starting inside hit at 0.
+    pub fn ray_distance(&self, origin: Vec3, direction: Vec3) -> Option<f32> {
+        // the slab method, with infinities standing in for axes the ray runs parallel to
+        let inverse = direction.recip();
+        let a = (self.min - origin) * inverse;
+        let b = (self.max - origin) * inverse;
+        let near = a.min(b).max_element().max(0.0);
+        let far = a.max(b).min_element();
+        (near <= far).then_some(near)
+    }
+
+    /// How long `self` takes to first touch `other` moving at `velocity`, or None if it never
+    /// does. Boxes that already overlap touch at 0.
+    pub fn sweep(&self, velocity: Vec3, other: &Aabb) -> Option<f32> {
+        // the same as a ray from the centre of self into other grown by self's half extents
+        let half_extents = (self.max - self.min) / 2.0;
+        let grown = Aabb {
+            min: other.min - half_extents,
+            max: other.max + half_extents,
+        };
+        grown.ray_distance(self.min + half_extents, velocity)
+    }
+
+    /// Every block the box overlaps, not counting ones it only touches.
+    pub fn blocks(&self) -> impl Iterator<Item = IVec3> {
+        // blocks are centred on whole coordinates, so block b spans b - 0.5 to b + 0.5
+        let min = (self.min + 0.5).floor().as_ivec3();
+        let max = (self.max + 0.5).ceil().as_ivec3() - 1;
+        (min.x..=max.x).flat_map(move |x| {
+            (min.y..=max.y).flat_map(move |y| (min.z..=max.z).map(move |z| IVec3::new(x, y, z)))
+        })
+    }
+
+    /// Every cell of a grid of `size` cubes, the first with its corner at the origin, that the
+    /// box overlaps.
+    pub fn cells(&self, size: f32) -> impl Iterator<Item = IVec3> {
+        let min = (self.min / size).floor().as_ivec3();
+        let max = (self.max / size).floor().as_ivec3();
+        (min.x..=max.x).flat_map(move |x| {
+            (min.y..=max.y).flat_map(move |y| (min.z..=max.z).map(move |z| IVec3::new(x, y, z)))
+        })
+    }
+}
+
+/// The space a camera sees, bounded by six planes facing inwards.
+pub struct Frustum {
+    /// Each normal in xyz and distance in w, so points p inside have `plane.dot(p, 1) >= 0`.
+    planes: [Vec4; 6],
+}
+
+impl Frustum {
+    /// The frustum of a view projection matrix with depth from 0 to 1, the wgpu convention.
+    /// Reversed depth works the same, with the near and far planes swapped over.
+    pub fn from_matrix(view_proj: Mat4) -> Self {
+        let [x, y, z, w] = [0, 1, 2, 3].map(|i| view_proj.row(i));
+        Self {
+            planes: [w + x, w - x, w + y, w - y, z, w - z],
+        }
+    }
+
+    /// Whether any of `aabb` might be in view. Boxes just outside a corner of the frustum can
+    /// count as in view, but boxes in view never count as outside it.
+    pub fn intersects(&self, aabb: &Aabb) -> bool {
+        self.planes.iter().all(|plane| {
+            // the corner furthest along the plane's normal
+            let normal = plane.truncate();
+            let corner = Vec3::select(normal.cmpge(Vec3::ZERO), aabb.max, aabb.min);
+            normal.dot(corner) + plane.w >= 0.0
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{ivec3, vec3, BVec3, IVec3, Mat4, Vec3};
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use crate::chunk::CHUNK_SIZE;
+
+    use super::{block_position, chunk_coord, split, voxel_ray, Aabb, Frustum};
+
+    /// How many random cases each property is checked against.
+    const CASES: usize = 1000;
+
+    fn random_vec3(rng: &mut StdRng, range: f32) -> Vec3 {
+        vec3(
+            rng.gen_range(-range..range),
+            rng.gen_range(-range..range),
+            rng.gen_range(-range..range),
+        )
+    }
+
+    fn random_aabb(rng: &mut StdRng) -> Aabb {
+        let half_extents = vec3(
+            rng.gen_range(0.1..2.0),
+            rng.gen_range(0.1..2.0),
+            rng.gen_range(0.1..2.0),
+        );
+        Aabb::from_center(random_vec3(rng, 10.0), half_extents)
+    }
+
+    #[test]
+    fn chunk_coords_round_towards_negative_infinity() {
+        assert_eq!(chunk_coord(ivec3(15, 16, -1)), ivec3(0, 1, -1));
+        assert_eq!(chunk_coord(ivec3(-16, -17, 0)), ivec3(-1, -2, 0));
+    }
+
+    #[test]
+    fn positions_split_into_chunk_and_local() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..CASES {
+            let point = random_vec3(&mut rng, 1000.0);
+            let block = block_position(point);
+            assert!((point - block.as_vec3()).abs().max_element() <= 0.5);
+
+            let (coord, local) = split(block);
+            assert_eq!(coord * CHUNK_SIZE + local, block);
+            assert!(local.cmpge(IVec3::ZERO).all() && local.cmplt(IVec3::splat(CHUNK_SIZE)).all());
+            assert_eq!(coord, chunk_coord(block));
+        }
+    }
+
+    #[test]
+    fn penetration_picks_the_shallowest_axis() {
+        let a = Aabb::from_center(Vec3::ZERO, Vec3::splat(1.0));
+        let b = Aabb::from_center(vec3(1.5, 0.2, 0.0), Vec3::splat(1.0));
+        assert_eq!(a.penetration(&b), Some(vec3(-0.5, 0.0, 0.0)));
+        let touching = Aabb::from_center(vec3(2.0, 0.0, 0.0), Vec3::splat(1.0));
+        assert_eq!(a.penetration(&touching), None);
+
+        // and always separates the boxes
+        let mut rng = StdRng::seed_from_u64(1);
+        for _ in 0..CASES {
+            let (a, b) = (random_aabb(&mut rng), random_aabb(&mut rng));
+            if let Some(push) = a.penetration(&b) {
+                let moved = Aabb {
+                    min: a.min + push * 1.01,
+                    max: a.max + push * 1.01,
+                };
+                assert_eq!(moved.penetration(&b), None);
+            }
+        }
+    }
+
+    #[test]
+    fn rays_and_sweeps_find_the_first_touch() {
+        let unit = Aabb::from_center(Vec3::ZERO, Vec3::splat(0.5));
+        assert_eq!(unit.ray_distance(vec3(-3.0, 0.0, 0.0), Vec3::X), Some(2.5));
+        assert_eq!(unit.ray_distance(vec3(-3.0, 0.0, 0.0), Vec3::NEG_X), None);
+        assert_eq!(unit.ray_distance(Vec3::ZERO, Vec3::Y), Some(0.0));
+
+        let mut rng = StdRng::seed_from_u64(2);
+        for _ in 0..CASES {
+            let (a, b) = (random_aabb(&mut rng), random_aabb(&mut rng));
+            let velocity = random_vec3(&mut rng, 5.0);
+            let at = |time: f32| Aabb {
+                min: a.min + velocity * time,
+                max: a.max + velocity * time,
+            };
+            let overlaps = |time: f32| at(time).penetration(&b).is_some();
+            match a.sweep(velocity, &b) {
+                Some(time) => {
+                    assert!(overlaps(time + 0.01));
+                    assert!(time == 0.0 || !overlaps(time - 0.01));
+                }
+                None => assert!((0..=100).all(|i| !overlaps(i as f32 * 0.1))),
+            }
+        }
+    }
+
+    #[test]
+    fn voxel_rays_visit_every_block_in_order() {
+        let blocks: Vec<_> = voxel_ray(vec3(0.0, 0.2, 0.0), Vec3::X, 2.0).collect();
+        assert_eq!(blocks, [IVec3::ZERO, ivec3(1, 0, 0), ivec3(2, 0, 0)]);
+        assert_eq!(voxel_ray(Vec3::ZERO, Vec3::ZERO, 2.0).count(), 0);
+
+        let mut rng = StdRng::seed_from_u64(3);
+        for _ in 0..CASES {
+            let origin = random_vec3(&mut rng, 50.0);
+            let direction = random_vec3(&mut rng, 1.0).normalize_or_zero();
+            let reach = rng.gen_range(0.0..10.0);
+            let blocks: Vec<_> = voxel_ray(origin, direction, reach).collect();
+            assert_eq!(blocks[0], block_position(origin));
+            // each a face away from the last
+            for pair in blocks.windows(2) {
+                assert_eq!((pair[1] - pair[0]).abs().to_array().iter().sum::<i32>(), 1);
+            }
+            // and none missed
+            for i in 0..=100 {
+                let point = origin + direction * reach * i as f32 / 100.0;
+                assert!(blocks.contains(&block_position(point)));
+            }
+        }
+    }
+
+    #[test]
+    fn frustums_never_cull_boxes_in_view() {
+        let view_proj = Mat4::perspective_rh(1.2, 1.5, 0.1, 100.0)
+            * Mat4::look_to_rh(Vec3::ZERO, Vec3::NEG_Z, Vec3::Y);
+        let frustum = Frustum::from_matrix(view_proj);
+        let in_front = Aabb::from_center(vec3(0.0, 0.0, -10.0), Vec3::ONE);
+        let behind = Aabb::from_center(vec3(0.0, 0.0, 10.0), Vec3::ONE);
+        let beyond = Aabb::from_center(vec3(0.0, 0.0, -200.0), Vec3::ONE);
+        assert!(frustum.intersects(&in_front));
+        assert!(!frustum.intersects(&behind));
+        assert!(!frustum.intersects(&beyond));
+
+        let mut rng = StdRng::seed_from_u64(4);
+        for _ in 0..CASES {
+            let aabb = random_aabb(&mut rng);
+            let visible = (0..8).any(|i| {
+                let corner = Vec3::select(
+                    BVec3::new(i & 1 != 0, i & 2 != 0, i & 4 != 0),
+                    aabb.max,
+                    aabb.min,
+                );
+                let clip = view_proj * corner.extend(1.0);
+                let ndc = clip.truncate() / clip.w;
+                clip.w > 0.0
+                    && ndc.x.abs() <= 1.0
+                    && ndc.y.abs() <= 1.0
+                    && (0.0..=1.0).contains(&ndc.z)
+            });
+            if visible {
+                assert!(frustum.intersects(&aabb));
+            }
+        }
+    }
+}
@@ -0,0 +1,262 @@
+use glam::{ivec3, IVec3};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{
+    structure::{Rotation, Structure},
+    world::{Block, BlockType, World, WORLD_TOP},
+};
+
+/// The world is split into square regions this many blocks across, each with at most one
+/// village. Regions span several chunks, so a village is planned across chunk borders once
+/// they've all been generated.
+const REGION: i32 = 64;
+
+/// Chance of a region having a village, if it has somewhere to put one.
+const VILLAGE_CHANCE: f32 = 0.5;
+
+/// Houses a village tries to put up, and how far from its centre they go.
+const HOUSE_ATTEMPTS: usize = 8;
+const MIN_HOUSE_DISTANCE: f32 = 7.0;
+const MAX_HOUSE_DISTANCE: f32 = 13.0;
+
+/// Houses are 5 blocks square around their origin and 4 tall including the roof, and need
+/// this much space between their centres.
+const HOUSE_RADIUS: i32 = 2;
+const HOUSE_HEIGHT: i32 = 4;
+const HOUSE_SPACING: i32 = 6;
+
+/// How far a house's floor can be above or below the village centre.
+const MAX_STEP: i32 = 2;
+
+/// Mixed into the seed so villages don't line up with anything else seeded per region.
+const VILLAGE_SALT: u64 = 0x7611_a9e5;
+
+/// A small house: stone walls with a doorway on the negative z side and a cobble roof,
+/// centred on its origin so turning it keeps its footprint.
+fn house() -> Structure {
+    let mut blocks = vec![];
+    for x in -HOUSE_RADIUS..=HOUSE_RADIUS {
+        for z in -HOUSE_RADIUS..=HOUSE_RADIUS {
+            let wall = x.abs() == HOUSE_RADIUS || z.abs() == HOUSE_RADIUS;
+            for y in 0..HOUSE_HEIGHT - 1 {
+                let doorway = x == 0 && z == -HOUSE_RADIUS && y < 2;
+                if wall && !doorway {
+                    blocks.push((ivec3(x, y, z), BlockType::Stone));
+                }
+            }
+            blocks.push((ivec3(x, HOUSE_HEIGHT - 1, z), BlockType::Cobble));
+        }
+    }
+    Structure::from_blocks(blocks)
+}
+
+/// The height of the highest full block in a column, the ground anything there would stand
+/// on.
+fn surface(world: &World, x: i32, z: i32) -> Option<i32> {
+    (0..world.depth as i32)
+        .map(|depth| WORLD_TOP - depth)
+        .find(|y| {
+            world
+                .block_type(ivec3(x, *y, z))
+                .is_some_and(|block_type| block_type.is_cube())
+        })
+}
+
+/// The height of the floor a house at `site` would stand on, if the ground under all of it is
+/// level, dry and has room above it for the house.
+fn house_floor(world: &World, site: IVec3) -> Option<i32> {
+    let ground = surface(world, site.x, site.z)?;
+    for x in -HOUSE_RADIUS..=HOUSE_RADIUS {
+        for z in -HOUSE_RADIUS..=HOUSE_RADIUS {
+            let column = site + ivec3(x, 0, z);
+            if surface(world, column.x, column.z) != Some(ground)
+                || world.block_type(ivec3(column.x, ground, column.z)) == Some(BlockType::Water)
+            {
+                return None;
+            }
+        }
+    }
+    (ground + HOUSE_HEIGHT <= WORLD_TOP).then_some(ground + 1)
+}
+
+/// The rotation that turns a house's doorway to face from `site` towards `centre`.
+fn facing(site: IVec3, centre: IVec3) -> Rotation {
+    let towards = centre - site;
+    if towards.x.abs() > towards.z.abs() {
+        if towards.x > 0 {
+            Rotation::Quarter
+        } else {
+            Rotation::ThreeQuarters
+        }
+    } else if towards.z > 0 {
+        Rotation::Half
+    } else {
+        Rotation::None
+    }
+}
+
+/// Lays a cobble path over the ground from `from` to `to`, along x then along z. Ground
+/// that's much higher or lower than `level`, like another house's roof, is left alone.
+fn lay_path(world: &mut World, from: IVec3, to: IVec3, level: i32) {
+    let step = (to - from).signum();
+    let along_x = (0..=(to.x - from.x).abs()).map(|i| ivec3(from.x + i * step.x, 0, from.z));
+    let along_z = (0..=(to.z - from.z).abs()).map(|i| ivec3(to.x, 0, from.z + i * step.z));
+    for column in along_x.chain(along_z) {
+        let Some(ground) = surface(world, column.x, column.z) else {
+            continue;
+        };
+        let position = ivec3(column.x, ground, column.z);
+        if (ground - level).abs() <= MAX_STEP
+            && world.block_type(position) != Some(BlockType::Water)
+        {
+            world.set_block(position, Some(Block::new(BlockType::Cobble)));
+        }
+    }
+}
+
+/// Plans and builds the village of one region, if it gets one, returning how many houses it
+/// has.
+fn build_village(world: &mut World, seed: u64, region: IVec3, house: &Structure) -> usize {
+    let region_seed = seed
+        ^ VILLAGE_SALT
+        ^ (region.x as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15)
+        ^ (region.z as u64).wrapping_mul(0xc2b2_ae3d_27d4_eb4f);
+    let mut rng = StdRng::seed_from_u64(region_seed);
+    if rng.gen::<f32>() >= VILLAGE_CHANCE {
+        return 0;
+    }
+    // far enough in that the houses stay in the region
+    let margin = MAX_HOUSE_DISTANCE as i32 + HOUSE_RADIUS;
+    let centre = region * REGION
+        + ivec3(
+            rng.gen_range(margin..REGION - margin),
+            0,
+            rng.gen_range(margin..REGION - margin),
+        );
+    let Some(level) = surface(world, centre.x, centre.z) else {
+        return 0;
+    };
+
+    let mut sites: Vec<IVec3> = vec![];
+    for _ in 0..HOUSE_ATTEMPTS {
+        let angle = rng.gen::<f32>() * std::f32::consts::TAU;
+        let distance = rng.gen_range(MIN_HOUSE_DISTANCE..MAX_HOUSE_DISTANCE);
+        let site = centre
+            + ivec3(
+                (angle.cos() * distance).round() as i32,
+                0,
+                (angle.sin() * distance).round() as i32,
+            );
+        let crowded = sites.iter().any(|other| {
+            let apart = (*other - site).abs();
+            apart.x.max(apart.z) < HOUSE_SPACING
+        });
+        if crowded {
+            continue;
+        }
+        let Some(floor) = house_floor(world, site) else {
+            continue;
+        };
+        if (floor - 1 - level).abs() > MAX_STEP {
+            continue;
+        }
+        sites.push(site);
+        let rotation = facing(site, centre);
+        world.place_structure(house, ivec3(site.x, floor, site.z), rotation);
+        let door = site + rotation.rotate(ivec3(0, 0, -HOUSE_RADIUS - 1));
+        lay_path(world, door, centre, level);
+    }
+    sites.len()
+}
+
+/// Builds villages of a few houses joined by paths to a shared centre, wherever there's flat
+/// open ground for them. The same seed always gives the same villages. Returns how many
+/// houses were built.
+pub fn place_villages(seed: u64, world: &mut World) -> usize {
+    let house = house();
+    let regions = ivec3(world.width as i32, 0, world.height as i32) / REGION;
+    let mut houses = 0;
+    for x in 0..regions.x {
+        for z in 0..regions.z {
+            houses += build_village(world, seed, ivec3(x, 0, z), &house);
+        }
+    }
+    houses
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{ivec3, IVec3};
+
+    use crate::{
+        chunk::CHUNK_SIZE,
+        generator::{ChunkData, WorldGenerator},
+        structure::Rotation,
+        world::{BlockType, World},
+    };
+
+    use super::{facing, place_villages};
+
+    /// Solid dirt up to y = -20, with plenty of room above it.
+    struct Lowland;
+
+    impl WorldGenerator for Lowland {
+        fn generate_chunk(&self, _seed: u64, coord: IVec3) -> ChunkData {
+            let mut chunk = ChunkData::default();
+            for z in 0..CHUNK_SIZE {
+                for y in 0..CHUNK_SIZE {
+                    for x in 0..CHUNK_SIZE {
+                        if coord.y * CHUNK_SIZE + y <= -20 {
+                            chunk.set(ivec3(x, y, z), Some(BlockType::Dirt));
+                        }
+                    }
+                }
+            }
+            chunk
+        }
+    }
+
+    fn village_blocks(world: &World) -> Vec<(IVec3, BlockType)> {
+        let mut blocks = vec![];
+        for x in 0..world.width as i32 {
+            for z in 0..world.height as i32 {
+                for y in -19..=-16 {
+                    let position = ivec3(x, y, z);
+                    if let Some(block_type) = world.block_type(position) {
+                        blocks.push((position, block_type));
+                    }
+                }
+            }
+        }
+        blocks
+    }
+
+    #[test]
+    fn villages_are_the_same_for_the_same_seed() {
+        let mut a = World::new(128, 128, 24, &Lowland, 0);
+        let mut b = World::new(128, 128, 24, &Lowland, 0);
+        let houses = (0..4)
+            .map(|seed| place_villages(seed, &mut a))
+            .sum::<usize>();
+        assert!(houses > 0);
+        assert_eq!(
+            (0..4)
+                .map(|seed| place_villages(seed, &mut b))
+                .sum::<usize>(),
+            houses
+        );
+        assert_eq!(village_blocks(&a), village_blocks(&b));
+    }
+
+    #[test]
+    fn houses_face_the_centre() {
+        let centre = IVec3::ZERO;
+        assert_eq!(facing(ivec3(0, 0, 10), centre), Rotation::None);
+        assert_eq!(facing(ivec3(0, 0, -10), centre), Rotation::Half);
+        for site in [ivec3(10, 0, 1), ivec3(-10, 0, 1)] {
+            let door = facing(site, centre).rotate(ivec3(0, 0, -3));
+            // the doorway points back towards the centre
+            assert!((site + door).x.abs() < site.x.abs());
+        }
+    }
+}
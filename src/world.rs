@@ -1,30 +1,211 @@
-use std::error::Error;
+use std::{
+    error::Error,
+    time::{Duration, Instant},
+};
 
-use fxhash::FxHashMap;
-use glam::{vec3, Quat, Vec3};
-use image::DynamicImage;
-use noise::{NoiseFn, Perlin};
+use fxhash::{FxHashMap, FxHashSet};
+use glam::{ivec2, ivec3, IVec2, IVec3, Vec3};
+use image::{DynamicImage, Rgba, RgbaImage};
+use rand::Rng;
 
 use crate::{
-    instance::Instance,
-    renderer::{v, Drawable, Renderer, Vertex},
-    texture::TextureHandle,
+    ambience::Surroundings,
+    bed::Bed,
+    biome::{BiomeMap, Tint},
+    border::WorldBorder,
+    chunk::{self, Face, FaceTexture, CHUNK_SIZE, MAX_LIGHT, WHITE},
+    generator::WorldGenerator,
+    heightmap::HeightMap,
+    history::Change,
+    light::LightMap,
+    nbt::Tag,
+    particle,
+    renderer::{v, Drawable, FontHandle, Renderer, Vertex, WorldTextHandle},
+    sign::{Sign, SIGN_LINES, SIGN_LINE_SPACING, SIGN_TEXT_BOTTOM, SIGN_TEXT_HEIGHT},
+    sound::SoundGroup,
+    structure::{Rotation, Structure},
+    texture::{Animation, TextureHandle, Variation},
+    tick::TickScheduler,
+    torch::Torch,
+    util::{block_position, chunk_bounds, chunk_coord, voxel_ray, Frustum},
 };
 
-#[derive(Clone, Copy, Default)]
-enum BlockType {
+/// The six directly adjacent positions.
+const NEIGHBOURS: [IVec3; 6] = [
+    IVec3::X,
+    IVec3::NEG_X,
+    IVec3::Y,
+    IVec3::NEG_Y,
+    IVec3::Z,
+    IVec3::NEG_Z,
+];
+
+/// The four horizontal neighbours.
+const SIDES: [IVec3; 4] = [IVec3::X, IVec3::NEG_X, IVec3::Z, IVec3::NEG_Z];
+
+/// World y of the top layer of blocks, which worlds are built down from.
+pub const WORLD_TOP: i32 = -5;
+
+/// Layers of blocks in the deepest world, the overworld.
+pub const WORLD_DEPTH: u32 = 128;
+
+/// How many blocks water spreads sideways from a source.
+const WATER_REACH: u8 = 7;
+
+/// Light level given off by a torch.
+const TORCH_LIGHT: u8 = 14;
+
+/// Light level given off by a portal.
+const PORTAL_LIGHT: u8 = 11;
+
+/// How many blocks away water can be heard.
+const WATER_EARSHOT: i32 = 6;
+
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub enum BlockType {
     #[default]
     Dirt,
     Cobble,
     Stone,
     Water,
     Sand,
+    Torch,
+    /// Standing in it takes the player to the other dimension.
+    Portal,
+    /// Two blocks long, sleeping in it skips the night.
+    Bed,
+    /// Stands on a block and shows a few lines of text.
+    Sign,
+    /// See-through, and walls of it join up into one pane.
+    Glass,
+    /// Stands in for blocks of worlds made elsewhere that there's no type for here.
+    Placeholder,
 }
 
 impl BlockType {
-    pub fn random() -> Self {
-        let r = rand::random::<f32>();
-        r.into()
+    pub const ALL: [BlockType; 11] = [
+        BlockType::Dirt,
+        BlockType::Cobble,
+        BlockType::Stone,
+        BlockType::Water,
+        BlockType::Sand,
+        BlockType::Torch,
+        BlockType::Portal,
+        BlockType::Bed,
+        BlockType::Sign,
+        BlockType::Glass,
+        BlockType::Placeholder,
+    ];
+
+    /// The block type called `name`. Unlike converting from a str, unknown names give None.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|block_type| <&str>::from(*block_type) == name)
+    }
+
+    pub fn random(rng: &mut impl Rng) -> Self {
+        rng.gen::<f32>().into()
+    }
+
+    /// Ticks between a neighbour changing and this block reacting, or None for blocks that
+    /// never react.
+    fn update_delay(self) -> Option<u64> {
+        match self {
+            BlockType::Sand => Some(2),
+            BlockType::Water => Some(5),
+            BlockType::Torch | BlockType::Bed | BlockType::Sign => Some(1),
+            _ => None,
+        }
+    }
+
+    /// Full blocks are meshed into chunks, hide the faces next to them and stop light. Anything
+    /// else has its own model.
+    pub fn is_cube(self) -> bool {
+        !matches!(self, BlockType::Torch | BlockType::Bed | BlockType::Sign)
+    }
+
+    /// Stops whatever walks into it. Water and portals are full blocks but can be walked into,
+    /// and portals have to be to travel through them.
+    pub fn is_collidable(self) -> bool {
+        self.is_cube() && !matches!(self, BlockType::Water | BlockType::Portal)
+    }
+
+    /// A full block that can't be seen through.
+    pub fn is_opaque(self) -> bool {
+        self.is_cube() && !self.is_translucent()
+    }
+
+    /// Drawn blended over whatever is behind it, and meshed separately from solid blocks.
+    fn is_translucent(self) -> bool {
+        matches!(
+            self,
+            BlockType::Water | BlockType::Portal | BlockType::Glass
+        )
+    }
+
+    /// For blocks whose neighbours of the same type join up without borders between them, the
+    /// columns and rows of the sheet their texture's `chunk::CONNECTED_VARIANTS` variants are
+    /// laid out in, left to right then top to bottom.
+    pub fn connected_sheet(self) -> Option<(u32, u32)> {
+        match self {
+            BlockType::Glass => Some((4, 4)),
+            _ => None,
+        }
+    }
+
+    /// For natural blocks, how their texture varies from block to block so wide areas of them
+    /// don't tile visibly.
+    pub fn variation(self) -> Option<Variation> {
+        let (variants, rotate) = match self {
+            BlockType::Stone => (2, true),
+            BlockType::Dirt | BlockType::Sand => (1, true),
+            _ => return None,
+        };
+        Some(Variation { variants, rotate })
+    }
+
+    /// What the biome tints `face` of the block as, if anything.
+    fn tint(self, face: Face) -> Option<Tint> {
+        match (self, face) {
+            (BlockType::Dirt, Face::PosY) => Some(Tint::Grass),
+            (BlockType::Water, _) => Some(Tint::Water),
+            _ => None,
+        }
+    }
+
+    fn light(self) -> u8 {
+        match self {
+            BlockType::Torch => TORCH_LIGHT,
+            BlockType::Portal => PORTAL_LIGHT,
+            _ => 0,
+        }
+    }
+
+    /// What it sounds like to break and to walk on.
+    pub fn sound_group(self) -> SoundGroup {
+        match self {
+            BlockType::Stone | BlockType::Cobble | BlockType::Placeholder => SoundGroup::Stone,
+            BlockType::Dirt => SoundGroup::Grass,
+            BlockType::Sand => SoundGroup::Sand,
+            BlockType::Torch | BlockType::Sign => SoundGroup::Wood,
+            BlockType::Glass | BlockType::Portal => SoundGroup::Glass,
+            BlockType::Bed => SoundGroup::Cloth,
+            BlockType::Water => SoundGroup::Water,
+        }
+    }
+
+    /// How much effort it takes to break, which decides how much debris it scatters.
+    pub fn hardness(self) -> f32 {
+        match self {
+            BlockType::Stone | BlockType::Placeholder => 1.5,
+            BlockType::Cobble => 2.0,
+            BlockType::Sign => 1.0,
+            BlockType::Dirt | BlockType::Sand => 0.5,
+            BlockType::Glass => 0.3,
+            BlockType::Bed => 0.2,
+            BlockType::Torch | BlockType::Portal | BlockType::Water => 0.0,
+        }
     }
 }
 
@@ -60,6 +241,13 @@ impl From<&str> for BlockType {
             "cobble" => BlockType::Cobble,
             "stone" => BlockType::Stone,
             "sand" => BlockType::Sand,
+            "water" => BlockType::Water,
+            "torch" => BlockType::Torch,
+            "portal" => BlockType::Portal,
+            "bed" => BlockType::Bed,
+            "sign" => BlockType::Sign,
+            "glass" => BlockType::Glass,
+            "placeholder" => BlockType::Placeholder,
             _ => BlockType::Dirt,
         }
     }
@@ -73,6 +261,12 @@ impl<'a> From<BlockType> for &'a str {
             BlockType::Stone => "stone",
             BlockType::Sand => "sand",
             BlockType::Water => "water",
+            BlockType::Torch => "torch",
+            BlockType::Portal => "portal",
+            BlockType::Bed => "bed",
+            BlockType::Sign => "sign",
+            BlockType::Glass => "glass",
+            BlockType::Placeholder => "placeholder",
         }
     }
 }
@@ -140,41 +334,201 @@ pub fn cube_indices() -> Vec<u16> {
 
 #[derive(Default, Clone, Copy)]
 pub struct Block {
-    position: Vec3,
-    rotation: Quat,
     block_type: BlockType,
     visible: bool,
+    /// For water, how many blocks it is from its source, 0 being a source.
+    level: u8,
+    /// For torches, the face of the block they're stuck to.
+    attached_to: Option<Face>,
+    /// For beds, the face towards the bed's other half.
+    towards: Option<Face>,
 }
 
-// drawing one individual instance makes little sense...
-// the renderer could batch instances
-// allowing us to bind buffers once and do only 1 draw call
-impl Drawable for Block {
-    fn draw(&self, renderer: &mut Renderer, world: &World) {
-        renderer.queue_draw(0, self, world);
-    }
-    fn vertices(&self) -> Vec<Vertex> {
-        cube_vertices()
+impl Block {
+    pub fn new(block_type: BlockType) -> Self {
+        Self {
+            block_type,
+            visible: true,
+            level: 0,
+            attached_to: None,
+            towards: None,
+        }
     }
 
-    fn indices(&self) -> Vec<u16> {
-        cube_indices()
+    fn water(level: u8) -> Self {
+        Self {
+            level,
+            ..Self::new(BlockType::Water)
+        }
     }
+}
 
-    fn instance(&self, world: &World) -> Instance {
-        let tex_name = std::convert::Into::<&str>::into(self.block_type);
-        let texture = world.get_texture(tex_name);
-        Instance::new(self.position, self.rotation, texture)
-    }
+/// The text on a sign.
+#[derive(Default)]
+struct SignText {
+    lines: [String; SIGN_LINES],
+    /// The world text each line is drawn with, made when the line is first drawn with
+    /// anything on it.
+    meshes: [Option<WorldTextHandle>; SIGN_LINES],
+    /// Whether `meshes` show what's in `lines`.
+    drawn: bool,
 }
 
 // the world will consist of blocks and entities
 pub struct World {
     pub blocks: Vec<Option<Block>>,
     pub textures: FxHashMap<String, TextureHandle>,
+    /// The average colour of each texture, by name, which its block's particles are tinted.
+    particle_colours: FxHashMap<String, Vec3>,
     pub width: u32,
     pub height: u32,
     pub depth: u32,
+    /// Blocks outside it are left out of chunk meshes. Change it with `set_border` so the
+    /// chunks get remeshed.
+    pub border: WorldBorder,
+    /// The biome of every column, which tints grass and water.
+    biomes: BiomeMap,
+    /// Chunks that need (re)meshing.
+    pending_chunks: Vec<IVec3>,
+    /// Since the queue last emptied, reported when it empties again.
+    meshing_stats: MeshingStats,
+    /// Blocks due to react to a neighbour changing.
+    block_updates: TickScheduler<IVec3>,
+    light: LightMap,
+    /// The highest block stopping light in every column, below which it's dark but for sky
+    /// light spreading in from the sides.
+    heights: HeightMap,
+    /// Sky light under overhangs and in caves. Everything above `heights` gets the full sky,
+    /// which is left out of the map, bar the edges it spreads in from.
+    sky: LightMap,
+    /// Whether sky light comes from above, rather than lighting the world evenly as it does
+    /// under the nether's roof.
+    open_sky: bool,
+    /// Every torch along with the face it's attached to, to draw them without a search.
+    torches: FxHashMap<IVec3, Face>,
+    /// The foot of every bed along with the face its head is against.
+    beds: FxHashMap<IVec3, Face>,
+    /// What's written on every sign.
+    signs: FxHashMap<IVec3, SignText>,
+    /// World text from signs that are gone, to draw new signs' lines with.
+    spare_text: Vec<WorldTextHandle>,
+    /// While an edit is being recorded, every position it's changed along with what was there
+    /// before.
+    recording: Option<FxHashMap<IVec3, Snapshot>>,
+    /// Every position changed since the world was last saved.
+    unsaved: FxHashSet<IVec3>,
+}
+
+/// Everything kept at one position, to put it back as it was.
+#[derive(Clone, Default)]
+pub struct Snapshot {
+    block: Option<Block>,
+    /// For the foot of a bed, the way to its head.
+    bed: Option<Face>,
+    sign: Option<[String; SIGN_LINES]>,
+}
+
+impl Snapshot {
+    /// Roughly how much memory this takes up.
+    pub fn size(&self) -> usize {
+        let text = self.sign.iter().flatten().map(String::len).sum::<usize>();
+        std::mem::size_of::<Self>() + text
+    }
+
+    /// Whether there's nothing at the position at all.
+    pub fn is_empty(&self) -> bool {
+        self.block.is_none() && self.bed.is_none() && self.sign.is_none()
+    }
+
+    /// The snapshot as an NBT compound, for saving. Blocks are kept by name so saves survive
+    /// block types being added.
+    pub fn to_nbt(&self) -> Tag {
+        let face = |face: Face| Tag::Byte(face as i8);
+        let mut entries = vec![];
+        if let Some(block) = self.block {
+            entries.push((
+                "Type".into(),
+                Tag::String(<&str>::from(block.block_type).into()),
+            ));
+            entries.push(("Level".into(), Tag::Byte(block.level as i8)));
+            entries.extend(block.attached_to.map(|f| ("Attached".into(), face(f))));
+            entries.extend(block.towards.map(|f| ("Towards".into(), face(f))));
+        }
+        entries.extend(self.bed.map(|f| ("Bed".into(), face(f))));
+        if let Some(lines) = &self.sign {
+            let lines = lines.iter().map(|line| Tag::String(line.clone())).collect();
+            entries.push(("Sign".into(), Tag::List(lines)));
+        }
+        Tag::Compound(entries)
+    }
+
+    /// Reads a snapshot back, blocks in `retired` becoming placeholders. Any other block type
+    /// that doesn't exist is an error.
+    pub fn from_nbt(tag: &Tag, retired: &[String]) -> Result<Self, String> {
+        let face = |name| match tag.get(name) {
+            Some(Tag::Byte(face)) => Face::ALL
+                .get(*face as usize)
+                .copied()
+                .map(Some)
+                .ok_or_else(|| format!("{face} isn't a face")),
+            Some(_) => Err(format!("{name} isn't a face")),
+            None => Ok(None),
+        };
+        let block = match tag.get("Type") {
+            Some(Tag::String(name)) => Some(Block {
+                level: match tag.get("Level") {
+                    Some(Tag::Byte(level)) => *level as u8,
+                    _ => 0,
+                },
+                attached_to: face("Attached")?,
+                towards: face("Towards")?,
+                ..Block::new(match BlockType::from_name(name) {
+                    Some(block_type) => block_type,
+                    None if retired.contains(name) => BlockType::Placeholder,
+                    None => return Err(format!("unknown block {name}")),
+                })
+            }),
+            Some(_) => return Err("block type isn't a name".into()),
+            None => None,
+        };
+        let sign = match tag.get("Sign") {
+            Some(Tag::List(lines)) => {
+                let mut sign: [String; SIGN_LINES] = Default::default();
+                for (line, tag) in sign.iter_mut().zip(lines) {
+                    if let Tag::String(text) = tag {
+                        line.clone_from(text);
+                    }
+                }
+                Some(sign)
+            }
+            Some(_) => return Err("sign text isn't a list".into()),
+            None => None,
+        };
+        Ok(Self {
+            block,
+            bed: face("Bed")?,
+            sign,
+        })
+    }
+}
+
+/// Totals over some amount of chunk meshing.
+#[derive(Clone, Copy, Default)]
+pub struct MeshingStats {
+    pub chunks: usize,
+    pub quads: usize,
+    /// Uploaded to the gpu.
+    pub bytes: usize,
+    pub time: Duration,
+}
+
+impl MeshingStats {
+    pub fn add(&mut self, other: &MeshingStats) {
+        self.chunks += other.chunks;
+        self.quads += other.quads;
+        self.bytes += other.bytes;
+        self.time += other.time;
+    }
 }
 
 impl World {
@@ -201,41 +555,229 @@ impl World {
         Err("".into())
     }
 
-    pub fn new(width: u32, height: u32, depth: u32, perlin_threshold: f32) -> Self {
-        let p = Perlin::new(1);
-        let mut blocks = vec![];
-        for x in 0..width {
-            for y in 0..height {
-                for z in 0..depth {
-                    let val = p.get([x as f64 / 16.0, y as f64 / 16.0, z as f64 / 16.0]);
-                    #[allow(clippy::overly_complex_bool_expr)]
-                    if val > perlin_threshold as f64 {
-                        blocks.push(Some(Block {
-                            position: vec3(x as f32, -5. - z as f32, y as f32),
-                            rotation: Quat::default(),
-                            block_type: BlockType::random(),
-                            visible: true,
-                        }));
-                    } else {
-                        blocks.push(None);
-                    }
-                }
-            }
-        }
-
+    /// Generates a world from `seed` with `generator`, the same seed always giving the same
+    /// world.
+    pub fn new(
+        width: u32,
+        height: u32,
+        depth: u32,
+        generator: &dyn WorldGenerator,
+        seed: u64,
+    ) -> Self {
+        let blocks = vec![None; (width * height * depth) as usize];
         let mut this = Self {
             blocks,
             textures: FxHashMap::default(),
+            particle_colours: FxHashMap::default(),
             width,
             height,
             depth,
+            border: WorldBorder::around_blocks(width, height),
+            biomes: BiomeMap::new(width, height, |column| generator.biome(seed, column)),
+            pending_chunks: vec![],
+            meshing_stats: MeshingStats::default(),
+            block_updates: TickScheduler::default(),
+            light: LightMap::default(),
+            heights: HeightMap::new(0, 0, |_| None),
+            sky: LightMap::default(),
+            open_sky: true,
+            torches: FxHashMap::default(),
+            beds: FxHashMap::default(),
+            signs: FxHashMap::default(),
+            spare_text: vec![],
+            recording: None,
+            unsaved: FxHashSet::default(),
         };
+        for coord in this.chunks() {
+            let chunk = generator.generate_chunk(seed, coord);
+            for z in 0..CHUNK_SIZE {
+                for y in 0..CHUNK_SIZE {
+                    for x in 0..CHUNK_SIZE {
+                        let local = ivec3(x, y, z);
+                        if let Some(index) = this.index_at(coord * CHUNK_SIZE + local) {
+                            this.blocks[index] = chunk.get(local).map(Block::new);
+                        }
+                    }
+                }
+            }
+        }
+        generator.decorate(seed, &mut this);
+        // what was generated can be generated again, only later edits need saving
+        this.unsaved.clear();
+        this.heights = HeightMap::new(width, height, |column| this.column_top(column));
+        this.light_sky();
+        this.queue_all_chunks();
 
-        this.block_visibility();
+        this.block_visibility()
+            .expect("Failed to compute block visibility.");
 
         this
     }
 
+    /// Lights the world evenly with the sky light, however covered it is, for dimensions under a
+    /// roof.
+    pub fn without_open_sky(mut self) -> Self {
+        self.open_sky = false;
+        self.sky = LightMap::default();
+        self
+    }
+
+    /// World y of the bottom layer of blocks.
+    fn bottom(&self) -> i32 {
+        WORLD_TOP - (self.depth as i32 - 1)
+    }
+
+    /// The y of the highest block stopping light in a column, found by walking down it.
+    fn column_top(&self, column: IVec2) -> Option<i32> {
+        (self.bottom()..=WORLD_TOP).rev().find(|y| {
+            let position = ivec3(column.x, *y, column.y);
+            self.index_at(position)
+                .and_then(|index| self.blocks[index])
+                .is_some_and(|block| block.block_type.is_cube())
+        })
+    }
+
+    /// Whether sky light can spread into `position`: open but covered, since everything open to
+    /// the sky is fully lit already.
+    fn is_shaded(&self, position: IVec3) -> bool {
+        self.is_transparent(position) && !self.heights.is_exposed(position)
+    }
+
+    /// Spreads sky light in under every overhang, from the open positions beside each covered
+    /// one.
+    fn light_sky(&mut self) {
+        if !self.open_sky {
+            return;
+        }
+        let mut sky = std::mem::take(&mut self.sky);
+        let edges: Vec<_> = self
+            .shaded_positions()
+            .flat_map(|position| self.sky_edges(position))
+            .collect();
+        sky.add_sources(edges.into_iter(), MAX_LIGHT, |position| {
+            self.is_shaded(position)
+        });
+        self.sky = sky;
+    }
+
+    /// Every covered open position in the world.
+    fn shaded_positions(&self) -> impl Iterator<Item = IVec3> + '_ {
+        let bottom = self.bottom();
+        (0..self.height as i32)
+            .flat_map(move |z| (0..self.width as i32).map(move |x| ivec2(x, z)))
+            .flat_map(move |column| {
+                let top = self.heights.top(column).unwrap_or(bottom - 1);
+                (bottom..top).map(move |y| ivec3(column.x, y, column.y))
+            })
+            .filter(|position| self.is_transparent(*position))
+    }
+
+    /// The positions beside `position` sky light comes in from, open to the sky and not
+    /// blocked.
+    fn sky_edges(&self, position: IVec3) -> impl Iterator<Item = IVec3> + '_ {
+        SIDES
+            .into_iter()
+            .map(move |side| position + side)
+            .filter(|side| self.heights.is_exposed(*side) && self.is_transparent(*side))
+    }
+
+    /// Keeps the height map and sky light up to date with a change at `position` from `old` to
+    /// `new`, returning the positions whose sky light changed.
+    fn update_sky(&mut self, position: IVec3, old: bool, new: bool) -> FxHashSet<IVec3> {
+        let mut changed = FxHashSet::default();
+        let column = ivec2(position.x, position.z);
+        let old_top = self.heights.top(column);
+        let new_top = if new {
+            old_top.max(Some(position.y))
+        } else if old_top == Some(position.y) {
+            self.column_top(column)
+        } else {
+            old_top
+        };
+        self.heights.set(column, new_top);
+        if !self.open_sky || old == new {
+            return changed;
+        }
+
+        let bottom = self.bottom();
+        let between = |low: Option<i32>, high: Option<i32>| {
+            let low = low.map_or(bottom, |low| low + 1);
+            (low..high.unwrap_or(bottom)).map(|y| ivec3(position.x, y, position.z))
+        };
+        let mut sky = std::mem::take(&mut self.sky);
+        let shaded = |p| self.is_shaded(p);
+        // positions under a new top lose the sky above them, and light from the side takes over
+        let covered: Vec<_> = if new {
+            changed.extend(sky.remove(position, shaded));
+            between(old_top, new_top).collect()
+        } else if new_top < old_top {
+            // and positions under an old one get it back
+            let uncovered = between(new_top, old_top).chain([position]);
+            changed.extend(sky.add_sources(uncovered, MAX_LIGHT, shaded));
+            vec![]
+        } else {
+            vec![position]
+        };
+        for position in &covered {
+            changed.extend(sky.remove(*position, shaded));
+        }
+        let edges: Vec<_> = covered
+            .iter()
+            .flat_map(|position| self.sky_edges(*position))
+            .collect();
+        changed.extend(sky.add_sources(edges.into_iter(), MAX_LIGHT, shaded));
+        for position in &covered {
+            changed.extend(sky.open(*position, shaded));
+        }
+        self.sky = sky;
+        changed
+    }
+
+    /// How much sky light reaches `position`, from 0 to `MAX_LIGHT`.
+    fn sky_light(&self, position: IVec3) -> u8 {
+        if !self.open_sky || self.heights.is_exposed(position) {
+            MAX_LIGHT
+        } else {
+            self.sky.get(position)
+        }
+    }
+
+    /// Moves the border, remeshing the world to match.
+    pub fn set_border(&mut self, border: WorldBorder) {
+        self.border = border;
+        self.queue_all_chunks();
+    }
+
+    /// The border around the whole world.
+    pub fn default_border(&self) -> WorldBorder {
+        WorldBorder::around_blocks(self.width, self.height)
+    }
+
+    /// Queues every chunk the world's blocks fall in for meshing. Chunks left with nothing
+    /// inside the border mesh to nothing, which removes them.
+    pub fn queue_all_chunks(&mut self) {
+        self.pending_chunks = self.chunks().collect();
+    }
+
+    /// The lowest and highest coordinates of the chunks the world's blocks fall in.
+    pub fn chunk_range(&self) -> (IVec3, IVec3) {
+        let min = chunk_coord(ivec3(0, self.bottom(), 0));
+        let max = chunk_coord(ivec3(
+            self.width as i32 - 1,
+            WORLD_TOP,
+            self.height as i32 - 1,
+        ));
+        (min, max)
+    }
+
+    /// Every chunk the world's blocks fall in.
+    pub fn chunks(&self) -> impl Iterator<Item = IVec3> {
+        let (min, max) = self.chunk_range();
+        (min.x..=max.x)
+            .flat_map(move |x| (min.y..=max.y).map(move |y| (x, y)))
+            .flat_map(move |(x, y)| (min.z..=max.z).map(move |z| ivec3(x, y, z)))
+    }
+
     fn block_visibility(&mut self) -> Result<(), Box<dyn Error>> {
         // determine which blocks are visible
         for x in 1..self.width - 1 {
@@ -275,60 +817,56 @@ impl World {
         Ok(())
     }
 
-    // for each block, check if there's any air blocks next to it
-    // if so mark it as visible
-    fn visibility_floodfill(&self) {
-        let sz = 32;
-        for x in 0..sz {
-            for y in 0..sz {
-                for z in 0..sz {}
-            }
-        }
-    }
-
-    // method as described here: https://tomcc.github.io/2014/08/31/visibility-1.html
-    fn chunk_floodfill(&self, x: i32, y: i32, z: i32, chunk_size: i32) -> u16 {
-        // compute which pairs of faces are visible within the chunk using a 3d floodfill
-        // we can later use this information to determine whether we can see from one chunk into another
-        // we represent this using 15 bits
-        // there are 6 faces in a chunk so 6*5/2=15 pairs (avoiding duplicates)
-        let faces = 0_u16;
-
-        faces
-    }
-
-    // perform an occlusion check on loaded blocks to determine which blocks are visible.
-    // method as described here: https://tomcc.github.io/2014/08/31/visibility-2.html
-    pub fn occlusion(&mut self) {
-        let chunk_size = 16;
-        let mut visibility_grid: [[[u16; 10]; 10]; 10] = [[[0_u16; 10]; 10]; 10];
-        // let's do a 10x10x10 area of chunks around the player for now
-        // we don't need to recompute this unless the blocks change
-        for x in -5..5 {
-            for y in -5..5 {
-                for z in -5..5 {
-                    visibility_grid[x as usize][y as usize][z as usize] =
-                        self.chunk_floodfill(x, y, z, chunk_size);
-                }
-            }
-        }
-    }
-
     pub fn setup_textures(
         &mut self,
         renderer: &mut Renderer,
-        textures: Vec<(String, DynamicImage)>,
+        textures: Vec<(String, DynamicImage, Option<Animation>)>,
     ) {
         // how do we even identify these images?
         // at some point we read the files (./assets/dirt.png)
         // do we assign a string label and then create a mapping of String <-> BlockType ?
+        self.particle_colours = textures
+            .iter()
+            .map(|(label, tex, _)| (label.clone(), particle::average_colour(tex)))
+            .collect();
+        // particles are drawn white and tinted their block's colour
+        let white = RgbaImage::from_pixel(4, 4, Rgba([255; 4]));
         let handles: FxHashMap<String, TextureHandle> = textures
             .into_iter()
-            .map(|(label, tex)| (label, renderer.register_texture(tex)))
+            .chain([("particle".into(), DynamicImage::ImageRgba8(white), None)])
+            .map(|(label, tex, animation)| {
+                let block_type = BlockType::from_name(&label);
+                let sheet = block_type.and_then(BlockType::connected_sheet);
+                let variation = block_type.and_then(BlockType::variation);
+                let handle = match (animation, sheet, variation) {
+                    (Some(animation), _, _) => renderer
+                        .register_animated_texture(tex, animation)
+                        .unwrap_or_else(|err| panic!("Couldn't animate {label}: {err}")),
+                    (None, Some((columns, rows)), _) => {
+                        debug_assert_eq!(columns * rows, chunk::CONNECTED_VARIANTS);
+                        renderer
+                            .register_texture_sheet(tex, columns, rows)
+                            .unwrap_or_else(|err| panic!("Couldn't split up {label}: {err}"))
+                    }
+                    (None, None, Some(variation)) => renderer
+                        .register_varied_texture(tex, variation)
+                        .unwrap_or_else(|err| panic!("Couldn't split up {label}: {err}")),
+                    (None, None, None) => renderer.register_texture(tex),
+                };
+                (label, handle)
+            })
             .collect();
         self.textures = handles;
     }
 
+    /// The colour particles of `block_type` are tinted.
+    pub fn particle_colour(&self, block_type: BlockType) -> Vec3 {
+        self.particle_colours
+            .get(<&str>::from(block_type))
+            .copied()
+            .unwrap_or(Vec3::ONE)
+    }
+
     pub fn get_texture(&self, tex_name: &str) -> TextureHandle {
         *self
             .textures
@@ -336,22 +874,634 @@ impl World {
             .unwrap_or_else(|| panic!("No texture found for {tex_name} in {:?}", self.textures))
     }
 
-    pub fn draw(&self, renderer: &mut Renderer) {
-        self.blocks
-            .iter()
-            .flatten()
-            .filter(|block| block.visible)
-            .for_each(|block| block.draw(renderer, self));
+    /// Where the block at a world position is stored, if it's in the world. Blocks are stored
+    /// with their indices mapped onto the world as (x, WORLD_TOP - z, y).
+    fn index_at(&self, position: IVec3) -> Option<usize> {
+        let (x, y, z) = (position.x, position.z, WORLD_TOP - position.y);
+        if x < 0 || y < 0 || z < 0 {
+            return None;
+        }
+        let (x, y, z) = (x as u32, y as u32, z as u32);
+        if x >= self.width || y >= self.height || z >= self.depth {
+            return None;
+        }
+        Some(self.flatten_coords(x as usize, y as usize, z as usize))
+    }
+
+    /// The block at a world position, if it's inside the border.
+    fn block_at(&self, position: IVec3) -> Option<Block> {
+        if !self.border.contains_block(position) {
+            return None;
+        }
+        self.blocks[self.index_at(position)?]
+    }
+
+    /// Places or (with None) removes the block at a world position, returning false if it's
+    /// outside the world or border. The block and its neighbours get a chance to react on a later
+    /// tick and the chunks it shows in are remeshed.
+    pub fn set_block(&mut self, position: IVec3, block: Option<Block>) -> bool {
+        if !self.border.contains_block(position) {
+            return false;
+        }
+        let Some(index) = self.index_at(position) else {
+            return false;
+        };
+        if let Some(mut recording) = self.recording.take() {
+            recording
+                .entry(position)
+                .or_insert_with(|| self.snapshot(position));
+            self.recording = Some(recording);
+        }
+        self.unsaved.insert(position);
+        let old = std::mem::replace(&mut self.blocks[index], block);
+
+        match block.and_then(|block| block.attached_to) {
+            Some(face) => self.torches.insert(position, face),
+            None => self.torches.remove(&position),
+        };
+        // beds are added by `place_bed` once both halves are in
+        self.beds.remove(&position);
+        if block.map(|block| block.block_type) == Some(BlockType::Sign) {
+            self.signs.entry(position).or_default();
+        } else if let Some(sign) = self.signs.remove(&position) {
+            self.spare_text.extend(sign.meshes.into_iter().flatten());
+        }
+
+        // take the old block's light away before adding the new block's
+        let is_cube = |block: Option<Block>| block.is_some_and(|block| block.block_type.is_cube());
+        let mut light = std::mem::take(&mut self.light);
+        let transparent = |position| self.is_transparent(position);
+        let mut lit = FxHashSet::default();
+        if old.is_some_and(|old| old.block_type.light() > 0) || is_cube(block) {
+            lit.extend(light.remove(position, transparent));
+        }
+        if is_cube(old) && !is_cube(block) {
+            lit.extend(light.open(position, transparent));
+        }
+        if let Some(level) = block.map(|block| block.block_type.light()) {
+            if level > 0 {
+                lit.extend(light.add_source(position, level, transparent));
+            }
+        }
+        self.light = light;
+        lit.extend(self.update_sky(position, is_cube(old), is_cube(block)));
+        self.remesh_around(lit.into_iter().chain([position]));
+
+        for offset in NEIGHBOURS.into_iter().chain([IVec3::ZERO]) {
+            let neighbour = position + offset;
+            if let Some(delay) = self
+                .block_at(neighbour)
+                .and_then(|block| block.block_type.update_delay())
+            {
+                self.block_updates.schedule(neighbour, delay);
+            }
+        }
+        true
+    }
+
+    /// The type of the block at a world position, None for air or outside the world.
+    pub fn block_type(&self, position: IVec3) -> Option<BlockType> {
+        self.block_at(position).map(|block| block.block_type)
+    }
+
+    /// The portal block closest to `around` within `radius` blocks along each axis.
+    pub fn find_portal(&self, around: IVec3, radius: i32) -> Option<IVec3> {
+        let reach = -radius..=radius;
+        reach
+            .clone()
+            .flat_map(|x| reach.clone().map(move |y| (x, y)))
+            .flat_map(|(x, y)| reach.clone().map(move |z| ivec3(x, y, z)))
+            .filter(|offset| self.block_type(around + *offset) == Some(BlockType::Portal))
+            .min_by_key(|offset| offset.dot(*offset))
+            .map(|offset| around + offset)
+    }
+
+    /// The nearest block within `radius` blocks of `near` along each axis that the player fits
+    /// with their feet in: empty, with an empty block above and something to stand on below.
+    pub fn clear_spot(&self, near: IVec3, radius: i32) -> Option<IVec3> {
+        let reach = -radius..=radius;
+        let empty = |position: IVec3| {
+            self.border.contains_block(position)
+                && self.index_at(position).is_some()
+                && self.block_at(position).is_none()
+        };
+        reach
+            .clone()
+            .flat_map(|x| reach.clone().map(move |y| (x, y)))
+            .flat_map(|(x, y)| reach.clone().map(move |z| near + ivec3(x, y, z)))
+            .filter(|feet| {
+                self.is_collidable(*feet - IVec3::Y) && empty(*feet) && empty(*feet + IVec3::Y)
+            })
+            .min_by_key(|feet| (*feet - near).dot(*feet - near))
+    }
+
+    /// Builds a portal as close to `near` as fits in the world: two portal blocks one above the
+    /// other in a stone frame, with room to stand on either side. Returns the lower portal
+    /// block.
+    pub fn build_portal(&mut self, near: IVec3) -> IVec3 {
+        // the frame and the space around it reach 2 blocks out and 3 up
+        let min = ivec3(2, self.bottom() + 2, 2);
+        let max = ivec3(self.width as i32 - 3, WORLD_TOP - 3, self.height as i32 - 3);
+        let portal = near.clamp(min, max.max(min));
+        for x in -1..=1 {
+            for y in -1..=2 {
+                for z in -1..=1 {
+                    let offset = ivec3(x, y, z);
+                    let frame = y == -1 || y == 2 || (x == 0 && z != 0);
+                    let block = if frame {
+                        Some(Block::new(BlockType::Stone))
+                    } else if x == 0 {
+                        Some(Block::new(BlockType::Portal))
+                    } else {
+                        None
+                    };
+                    self.set_block(portal + offset, block);
+                }
+            }
+        }
+        portal
+    }
+
+    /// Places a torch against the given face of the block at `support`, returning false if that
+    /// block can't hold one or the space in front of it is taken. Torches stand on top of blocks
+    /// or hang off their sides, but can't go underneath.
+    pub fn place_torch(&mut self, support: IVec3, face: Face) -> bool {
+        let position = support + face.normal();
+        if face == Face::NegY || !self.is_solid(support) || self.block_at(position).is_some() {
+            return false;
+        }
+        let torch = Block {
+            attached_to: Some(face),
+            ..Block::new(BlockType::Torch)
+        };
+        self.set_block(position, Some(torch))
+    }
+
+    /// Places a bed with its foot at `foot` and its head against the given side, returning
+    /// false if either half has no floor under it or its space is taken.
+    pub fn place_bed(&mut self, foot: IVec3, towards: Face) -> bool {
+        let head = foot + towards.normal();
+        let fits = |position: IVec3| {
+            self.border.contains_block(position)
+                && self.index_at(position).is_some()
+                && self.block_at(position).is_none()
+                && self.is_solid(position - IVec3::Y)
+        };
+        if matches!(towards, Face::PosY | Face::NegY) || !fits(foot) || !fits(head) {
+            return false;
+        }
+        let half = |towards| Block {
+            towards: Some(towards),
+            ..Block::new(BlockType::Bed)
+        };
+        self.set_block(foot, Some(half(towards)));
+        self.set_block(head, Some(half(towards.opposite())));
+        self.beds.insert(foot, towards);
+        true
+    }
+
+    /// Stands a blank sign on the block below `position`, returning false if there's nothing
+    /// to stand it on or the space is taken.
+    pub fn place_sign(&mut self, position: IVec3) -> bool {
+        if !self.is_solid(position - IVec3::Y) || self.block_at(position).is_some() {
+            return false;
+        }
+        self.set_block(position, Some(Block::new(BlockType::Sign)))
+    }
+
+    /// Writes on the sign at `position`, returning false if there isn't one.
+    pub fn write_sign(&mut self, position: IVec3, lines: [String; SIGN_LINES]) -> bool {
+        let Some(sign) = self.signs.get_mut(&position) else {
+            return false;
+        };
+        self.unsaved.insert(position);
+        sign.lines = lines;
+        sign.drawn = false;
+        true
+    }
+
+    /// What's at `position`, including anything kept alongside the block.
+    pub fn snapshot(&self, position: IVec3) -> Snapshot {
+        Snapshot {
+            block: self.block_at(position),
+            bed: self.beds.get(&position).copied(),
+            sign: self.signs.get(&position).map(|sign| sign.lines.clone()),
+        }
+    }
+
+    /// Puts `position` back as it was when `snapshot` was taken.
+    pub fn restore(&mut self, position: IVec3, snapshot: &Snapshot) {
+        self.set_block(position, snapshot.block);
+        if let Some(towards) = snapshot.bed {
+            self.beds.insert(position, towards);
+        }
+        if let Some(lines) = &snapshot.sign {
+            self.write_sign(position, lines.clone());
+        }
+    }
+
+    /// Every position changed since `mark_saved` was last called, or since the world was
+    /// generated.
+    pub fn unsaved(&self) -> impl Iterator<Item = IVec3> + '_ {
+        self.unsaved.iter().copied()
+    }
+
+    pub fn mark_saved(&mut self) {
+        self.unsaved.clear();
+    }
+
+    /// Starts keeping track of the blocks changed, until `stop_recording`.
+    pub fn start_recording(&mut self) {
+        self.recording = Some(FxHashMap::default());
+    }
+
+    /// Every position changed since `start_recording`, as it was before and as it is now.
+    pub fn stop_recording(&mut self) -> Vec<Change> {
+        let recording = self.recording.take().unwrap_or_default();
+        recording
+            .into_iter()
+            .map(|(position, before)| Change {
+                position,
+                before,
+                after: self.snapshot(position),
+            })
+            .collect()
+    }
+
+    /// Copies the full blocks in the box between two corners into a structure, its origin at
+    /// the lowest corner.
+    pub fn copy_structure(&self, a: IVec3, b: IVec3) -> Structure {
+        let (min, max) = (a.min(b), a.max(b));
+        let mut blocks = vec![];
+        for z in min.z..=max.z {
+            for y in min.y..=max.y {
+                for x in min.x..=max.x {
+                    let position = ivec3(x, y, z);
+                    if let Some(block) = self.block_at(position) {
+                        blocks.push((position - min, block.block_type));
+                    }
+                }
+            }
+        }
+        Structure::from_blocks(blocks)
+    }
+
+    /// Places a structure with its origin at `origin`, turned by `rotation`, returning how many
+    /// of its blocks fit inside the world.
+    pub fn place_structure(
+        &mut self,
+        structure: &Structure,
+        origin: IVec3,
+        rotation: Rotation,
+    ) -> usize {
+        structure
+            .blocks(rotation)
+            .filter(|(offset, block_type)| {
+                self.set_block(origin + *offset, Some(Block::new(*block_type)))
+            })
+            .count()
+    }
+
+    /// The first block along a ray, ignoring water, within `reach` blocks of `origin`.
+    pub fn raycast(&self, origin: Vec3, direction: Vec3, reach: f32) -> Option<IVec3> {
+        voxel_ray(origin, direction, reach).find(|position| {
+            self.block_type(*position)
+                .is_some_and(|block_type| block_type != BlockType::Water)
+        })
+    }
+
+    /// Whether there's a full block at `position`.
+    pub fn is_solid(&self, position: IVec3) -> bool {
+        self.block_at(position)
+            .is_some_and(|block| block.block_type.is_cube())
+    }
+
+    /// Whether there's a block at `position` that can't be walked through.
+    pub fn is_collidable(&self, position: IVec3) -> bool {
+        self.block_at(position)
+            .is_some_and(|block| block.block_type.is_collidable())
+    }
+
+    /// Whether light passes through `position`. Nothing outside the world is lit.
+    fn is_transparent(&self, position: IVec3) -> bool {
+        self.index_at(position).is_some() && !self.is_solid(position)
+    }
+
+    /// Queues the chunks showing any of `positions` for remeshing. Faces and ambient occlusion
+    /// reach into neighbouring chunks, so those are included.
+    fn remesh_around(&mut self, positions: impl Iterator<Item = IVec3>) {
+        let mut coords = FxHashSet::default();
+        for position in positions {
+            for x in -1..=1 {
+                for y in -1..=1 {
+                    for z in -1..=1 {
+                        coords.insert(chunk_coord(position + ivec3(x, y, z)));
+                    }
+                }
+            }
+        }
+        for coord in coords {
+            if !self.pending_chunks.contains(&coord) {
+                self.pending_chunks.push(coord);
+            }
+        }
+    }
+
+    /// Queues a draw of every torch.
+    pub fn draw_torches(&self, renderer: &mut Renderer) {
+        for (position, face) in &self.torches {
+            Torch::new(*position, *face).draw(renderer, self);
+        }
+    }
+
+    /// Queues a draw of every bed.
+    pub fn draw_beds(&self, renderer: &mut Renderer) {
+        for (position, towards) in &self.beds {
+            Bed::new(*position, *towards).draw(renderer, self);
+        }
+    }
+
+    /// Queues a draw of every sign along with its text, which is made into world text the
+    /// first time it's drawn after being written. Each line keeps its world text from then on,
+    /// and text from signs that are gone is reused before any more is made.
+    pub fn draw_signs(&mut self, renderer: &mut Renderer, font_handle: FontHandle) {
+        let mut signs = std::mem::take(&mut self.signs);
+        for (position, sign) in &mut signs {
+            Sign::new(*position).draw(renderer, self);
+            if !sign.drawn {
+                for (line, mesh) in sign.lines.iter().zip(&mut sign.meshes) {
+                    if line.is_empty() {
+                        continue;
+                    }
+                    *mesh = Some(match mesh.or_else(|| self.spare_text.pop()) {
+                        Some(handle) => {
+                            renderer.set_world_text(handle, line, font_handle, SIGN_TEXT_HEIGHT);
+                            handle
+                        }
+                        None => renderer.create_world_text(line, font_handle, SIGN_TEXT_HEIGHT),
+                    });
+                }
+                sign.drawn = true;
+            }
+            for (i, (line, mesh)) in sign.lines.iter().zip(sign.meshes).enumerate() {
+                // emptied lines keep their text for when they're written on again
+                let (false, Some(mesh)) = (line.is_empty(), mesh) else {
+                    continue;
+                };
+                let top = position.as_vec3() + Vec3::Y * SIGN_TEXT_BOTTOM;
+                let row = (SIGN_LINES - 1 - i) as f32;
+                renderer.queue_draw_world_text(mesh, top + Vec3::Y * row * SIGN_LINE_SPACING);
+            }
+        }
+        self.signs = signs;
+    }
+
+    /// Whether nothing solid is above `position`.
+    fn sees_sky(&self, position: IVec3) -> bool {
+        self.heights.is_exposed(position)
+    }
+
+    /// How brightly lit `position` is, from 0 to `MAX_LIGHT`: the light of nearby blocks, or the
+    /// sky light reaching it dimmed to `sky` if that's brighter.
+    pub fn light_level(&self, position: IVec3, sky: u8) -> u8 {
+        let sky = self.sky_light(position) as u32 * sky as u32 / MAX_LIGHT as u32;
+        self.light.get(position).max(sky as u8)
+    }
+
+    /// What's around a position, for picking ambient sound.
+    pub fn surroundings(&self, position: Vec3) -> Surroundings {
+        let block = block_position(position);
+        let sky_access = self.sees_sky(block);
+        let reach = -WATER_EARSHOT..=WATER_EARSHOT;
+        let near_water = reach.clone().any(|x| {
+            reach.clone().any(|y| {
+                reach.clone().any(|z| {
+                    self.block_at(block + ivec3(x, y, z))
+                        .is_some_and(|block| block.block_type == BlockType::Water)
+                })
+            })
+        });
+        Surroundings {
+            sky_access,
+            altitude: position.y - WORLD_TOP as f32,
+            near_water,
+        }
+    }
+
+    /// Runs the block updates due this tick.
+    pub fn tick(&mut self) {
+        for position in self.block_updates.advance() {
+            self.update_block(position);
+        }
+    }
+
+    /// A block reacting to something next to it changing. Any changes it makes notify its own
+    /// neighbours in turn, which react on later ticks.
+    fn update_block(&mut self, position: IVec3) {
+        let Some(block) = self.block_at(position) else {
+            return;
+        };
+        let below = position - IVec3::Y;
+        let is_air = |world: &Self, position| {
+            world.index_at(position).is_some() && world.block_at(position).is_none()
+        };
+        match block.block_type {
+            BlockType::Sand if is_air(self, below) => {
+                self.set_block(position, None);
+                self.set_block(below, Some(block));
+            }
+            BlockType::Torch => {
+                // pops off once the block holding it goes
+                let face = block.attached_to.unwrap_or(Face::PosY);
+                if !self.is_solid(position - face.normal()) {
+                    self.set_block(position, None);
+                }
+            }
+            // falls over once the block it stands on goes
+            BlockType::Sign if !self.is_solid(below) => {
+                self.set_block(position, None);
+            }
+            BlockType::Bed => {
+                // goes once either half loses its floor or the other half is broken
+                let other = block.towards.map(|face| position + face.normal());
+                if !self.is_solid(below)
+                    || other.and_then(|other| self.block_type(other)) != Some(BlockType::Bed)
+                {
+                    self.set_block(position, None);
+                }
+            }
+            BlockType::Water => {
+                // flowing water dries up once nothing feeds it
+                let feeds = |offset: IVec3| {
+                    self.block_at(position + offset).is_some_and(|other| {
+                        other.block_type == BlockType::Water
+                            && (offset == IVec3::Y || other.level < block.level)
+                    })
+                };
+                if block.level > 0 && !SIDES.into_iter().chain([IVec3::Y]).any(feeds) {
+                    self.set_block(position, None);
+                } else if is_air(self, below) {
+                    self.set_block(below, Some(Block::water(1)));
+                } else if block.level < WATER_REACH {
+                    for offset in SIDES {
+                        if is_air(self, position + offset) {
+                            self.set_block(position + offset, Some(Block::water(block.level + 1)));
+                        }
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+
+    /// The colour of the face of the block at `position` at one of its corners, for faces that
+    /// take their colour from the biome. It's blended over the columns around the corner so
+    /// there's no seam where one biome meets the next.
+    fn face_tint(&self, position: IVec3, face: Face, corner: IVec3) -> [u8; 3] {
+        let Some(tint) = self
+            .block_at(position)
+            .and_then(|block| block.block_type.tint(face))
+        else {
+            return WHITE;
+        };
+        // a corner at x sits between the blocks at x - 1 and x
+        self.biomes.blend(tint, ivec2(corner.x, corner.z))
+    }
+
+    /// Orders the pending chunks so the ones in `view` come first, closest to `around` first
+    /// within that, and the ones out of view after them. Done before every batch, so turning
+    /// around moves what's now in front to the head of the queue.
+    fn prioritise_pending(&mut self, around: Vec3, view: &Frustum) {
+        let priority = |coord: &IVec3| {
+            let centre = (*coord * CHUNK_SIZE).as_vec3() + CHUNK_SIZE as f32 / 2.0;
+            (
+                !view.intersects(&chunk_bounds(*coord)),
+                centre.distance_squared(around),
+            )
+        };
+        // lowest priority first so the highest are popped off the end
+        self.pending_chunks.sort_by(|a, b| {
+            let ((a_hidden, a_distance), (b_hidden, b_distance)) = (priority(a), priority(b));
+            b_hidden
+                .cmp(&a_hidden)
+                .then(b_distance.total_cmp(&a_distance))
+        });
+    }
+
+    /// Meshes up to `budget` of the pending chunks, those in `view` and closest to `around`
+    /// first, and uploads them to the renderer, returning what it did. Textures must have been
+    /// set up first.
+    pub fn mesh_chunks(
+        &mut self,
+        renderer: &mut Renderer,
+        budget: usize,
+        around: Vec3,
+        view: &Frustum,
+    ) -> MeshingStats {
+        let mut stats = MeshingStats::default();
+        if self.pending_chunks.is_empty() {
+            return stats;
+        }
+        let start = Instant::now();
+        self.prioritise_pending(around, view);
+        for _ in 0..budget {
+            let Some(coord) = self.pending_chunks.pop() else {
+                break;
+            };
+            let texture = |block: Block| {
+                let texture = self.get_texture(block.block_type.into());
+                if block.block_type.connected_sheet().is_some() {
+                    FaceTexture::Connected(texture)
+                } else {
+                    FaceTexture::Single(texture)
+                }
+            };
+            let tint = |position, face, corner| self.face_tint(position, face, corner);
+            let quads = chunk::greedy_mesh(
+                coord,
+                |position| {
+                    self.block_at(position)
+                        .filter(|block| block.block_type.is_opaque())
+                        .map(texture)
+                },
+                |position| (self.light.get(position), self.sky_light(position)),
+                tint,
+            );
+            let translucent = chunk::translucent_mesh(
+                coord,
+                |position| {
+                    self.block_at(position)
+                        .filter(|block| block.block_type.is_translucent())
+                        .map(texture)
+                },
+                |position| {
+                    self.block_at(position)
+                        .is_some_and(|block| block.block_type.is_opaque())
+                },
+                |position| (self.light.get(position), self.sky_light(position)),
+                tint,
+            );
+            stats.chunks += 1;
+            stats.quads += quads.len() + translucent.len();
+            stats.bytes += renderer.upload_chunk(coord, &quads);
+            stats.bytes += renderer.upload_translucent_chunk(coord, translucent);
+        }
+        stats.time = start.elapsed();
+        self.meshing_stats.add(&stats);
+
+        if self.pending_chunks.is_empty() {
+            let total = std::mem::take(&mut self.meshing_stats);
+            println!(
+                "meshed {} chunks into {} quads ({} KiB) in {:?}",
+                total.chunks,
+                total.quads,
+                total.bytes / 1024,
+                total.time
+            );
+        }
+        stats
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::World;
+    use glam::{ivec2, ivec3, vec3, IVec3, Mat4, Vec3};
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use crate::{
+        chunk::{Face, MAX_LIGHT},
+        generator::PerlinGenerator,
+        heightmap::HeightMap,
+        light::LightMap,
+        player::{Controls, Player},
+        structure::Rotation,
+        util::{block_position, Frustum},
+    };
+
+    use super::{Block, BlockType, World};
+
+    /// Thresholds past the range of the noise fill the world completely or not at all.
+    const SOLID: PerlinGenerator = PerlinGenerator::new(-9999.0);
+    const EMPTY: PerlinGenerator = PerlinGenerator::new(9999.0);
+
+    fn run_ticks(world: &mut World, ticks: usize) {
+        for _ in 0..ticks {
+            world.tick();
+        }
+    }
+
+    fn count(world: &World, block_type: BlockType) -> usize {
+        world
+            .blocks
+            .iter()
+            .flatten()
+            .filter(|block| block.block_type == block_type)
+            .count()
+    }
 
     #[test]
     fn flat_index_test() {
-        let world = World::new(3, 3, 3, -9999.0); // a solid cube
+        let world = World::new(3, 3, 3, &SOLID, 0); // a solid cube
         let mut counter = 0;
         for z in 0..3 {
             for y in 0..3 {
@@ -369,18 +1519,18 @@ mod tests {
 
     #[test]
     fn interior_blocks_are_invisible() {
-        let world = World::new(3, 3, 3, -9999.0); // a solid cube
+        let world = World::new(3, 3, 3, &SOLID, 0); // a solid cube
 
         // in a 3x3x3 world we would expect that the middle block is invisible and the rest are visible
         for (idx, block) in world.blocks.iter().enumerate() {
             if idx == 13 {
                 assert!(
-                    block.unwrap().visible == false,
+                    !block.unwrap().visible,
                     "{idx}th block should've been visible"
                 );
             } else {
                 assert!(
-                    block.unwrap().visible == true,
+                    block.unwrap().visible,
                     "{idx}th block should've been invisible"
                 );
             }
@@ -389,14 +1539,11 @@ mod tests {
 
     #[test]
     fn interior_blocks_are_invisible_bigger() {
-        let world = World::new(4, 4, 4, -9999.0); // a solid cube
+        let world = World::new(4, 4, 4, &SOLID, 0); // a solid cube
 
         // in a 3x3x3 world we would expect that the middle block is invisible and the rest are visible
         for (idx, block) in world.blocks.iter().enumerate() {
-            if vec![21, 22, 25, 26, 37, 38, 41, 42]
-                .iter()
-                .any(|x| *x == idx)
-            {
+            if [21, 22, 25, 26, 37, 38, 41, 42].contains(&idx) {
                 assert!(
                     !block.unwrap().visible,
                     "{idx}th block should've been visible"
@@ -409,4 +1556,297 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn players_walk_into_water_and_portals() {
+        // a stone floor at y = -12 with water and then a portal standing on it
+        let mut world = World::new(8, 8, 8, &EMPTY, 0);
+        for x in 0..8 {
+            world.set_block(ivec3(x, -12, 1), Some(Block::new(BlockType::Stone)));
+        }
+        for y in [-11, -10] {
+            world.set_block(ivec3(3, y, 1), Some(Block::new(BlockType::Water)));
+            world.set_block(ivec3(5, y, 1), Some(Block::new(BlockType::Portal)));
+        }
+
+        let mut player = Player::new(vec3(1.0, -11.5, 1.0));
+        player.flying = false;
+        let forwards = Controls {
+            direction: Vec3::X,
+            ..Controls::default()
+        };
+        for _ in 0..60 {
+            player.steer(forwards, 3.0, 0.15, 1.0 / 60.0, |position| {
+                world.is_collidable(position)
+            });
+            if world.block_type(block_position(player.position + Vec3::Y * 0.5))
+                == Some(BlockType::Portal)
+            {
+                break;
+            }
+        }
+        assert!(player.position.x > 4.5, "{}", player.position.x);
+        // still standing on the floor
+        assert_eq!(player.position.y, -11.5);
+    }
+
+    #[test]
+    fn sand_falls_when_its_support_goes() {
+        // an empty world 8 blocks deep, its floor at y = -12
+        let mut world = World::new(4, 4, 8, &EMPTY, 0);
+        world.set_block(ivec3(1, -10, 1), Some(Block::new(BlockType::Stone)));
+        world.set_block(ivec3(1, -9, 1), Some(Block::new(BlockType::Sand)));
+        world.set_block(ivec3(1, -8, 1), Some(Block::new(BlockType::Sand)));
+        run_ticks(&mut world, 20);
+        assert!(world.block_at(ivec3(1, -8, 1)).is_some());
+
+        world.set_block(ivec3(1, -10, 1), None);
+        // nothing moves on the same tick, the sand waits for its update
+        assert!(world.block_at(ivec3(1, -9, 1)).is_some());
+        run_ticks(&mut world, 40);
+        for y in [-12, -11] {
+            let block = world.block_at(ivec3(1, y, 1)).unwrap();
+            assert_eq!(block.block_type, BlockType::Sand);
+        }
+        assert_eq!(count(&world, BlockType::Sand), 2);
+    }
+
+    #[test]
+    fn beds_need_room_and_break_together() {
+        let mut world = World::new(4, 4, 8, &EMPTY, 0);
+        let foot = ivec3(1, -11, 1);
+        // nothing to stand on
+        assert!(!world.place_bed(foot, Face::PosX));
+        for x in 0..4 {
+            world.set_block(ivec3(x, -12, 1), Some(Block::new(BlockType::Stone)));
+        }
+        assert!(!world.place_bed(foot, Face::PosY));
+        assert!(world.place_bed(foot, Face::PosX));
+        assert_eq!(world.block_type(foot + IVec3::X), Some(BlockType::Bed));
+        assert_eq!(world.beds.get(&foot), Some(&Face::PosX));
+        // the space is taken now
+        assert!(!world.place_bed(foot - IVec3::X, Face::PosX));
+
+        let eye = vec3(3.0, -10.5, 1.0);
+        assert_eq!(
+            world.raycast(eye, vec3(-1.0, -0.5, 0.0), 5.0),
+            Some(foot + IVec3::X)
+        );
+
+        world.set_block(foot + IVec3::X, None);
+        run_ticks(&mut world, 5);
+        assert_eq!(count(&world, BlockType::Bed), 0);
+        assert!(world.beds.is_empty());
+    }
+
+    #[test]
+    fn signs_keep_their_text_until_they_fall() {
+        let mut world = World::new(4, 4, 8, &EMPTY, 0);
+        let position = ivec3(1, -11, 1);
+        assert!(!world.place_sign(position));
+        world.set_block(position - IVec3::Y, Some(Block::new(BlockType::Stone)));
+        assert!(world.place_sign(position));
+        let lines = ["welcome", "", "", ""].map(String::from);
+        assert!(world.write_sign(position, lines.clone()));
+        assert_eq!(world.signs[&position].lines, lines);
+        assert!(!world.write_sign(position + IVec3::X, lines));
+
+        // as if drawn, the text stays for the sign to be drawn with once rewritten
+        let sign = world.signs.get_mut(&position).unwrap();
+        (sign.meshes[0], sign.drawn) = (Some(7), true);
+        assert!(world.write_sign(position, ["hello", "", "", ""].map(String::from)));
+        let sign = &world.signs[&position];
+        assert_eq!((sign.meshes[0], sign.drawn), (Some(7), false));
+
+        world.set_block(position - IVec3::Y, None);
+        run_ticks(&mut world, 5);
+        assert_eq!(count(&world, BlockType::Sign), 0);
+        assert!(world.signs.is_empty());
+        // and is left over for other signs once it's gone
+        assert_eq!(world.spare_text, [7]);
+    }
+
+    #[test]
+    fn structures_copy_and_place_turned() {
+        let mut world = World::new(8, 8, 8, &EMPTY, 0);
+        world.set_block(ivec3(1, -11, 1), Some(Block::new(BlockType::Stone)));
+        world.set_block(ivec3(2, -11, 1), Some(Block::new(BlockType::Sand)));
+        world.set_block(ivec3(1, -10, 1), Some(Block::new(BlockType::Cobble)));
+        let structure = world.copy_structure(ivec3(2, -10, 1), ivec3(1, -11, 2));
+
+        let origin = ivec3(5, -11, 5);
+        assert_eq!(
+            world.place_structure(&structure, origin, Rotation::Quarter),
+            3
+        );
+        assert_eq!(world.block_type(origin), Some(BlockType::Stone));
+        assert_eq!(world.block_type(origin + IVec3::Z), Some(BlockType::Sand));
+        assert_eq!(world.block_type(origin + IVec3::Y), Some(BlockType::Cobble));
+        // hanging over the edge, only the blocks inside are placed
+        let edge = ivec3(0, -11, 0);
+        assert_eq!(world.place_structure(&structure, edge, Rotation::Half), 2);
+    }
+
+    #[test]
+    fn water_spreads_and_dries_up() {
+        let mut world = World::new(4, 4, 8, &EMPTY, 0);
+        let source = ivec3(0, -9, 0);
+        world.set_block(source, Some(Block::new(BlockType::Water)));
+        run_ticks(&mut world, 200);
+        // falls to the floor and covers all of it
+        for x in 0..4 {
+            for z in 0..4 {
+                let block = world.block_at(ivec3(x, -12, z)).unwrap();
+                assert_eq!(block.block_type, BlockType::Water);
+            }
+        }
+        assert!(world.block_at(source - IVec3::Y).is_some());
+
+        world.set_block(source, None);
+        run_ticks(&mut world, 1000);
+        assert_eq!(count(&world, BlockType::Water), 0);
+    }
+
+    #[test]
+    fn torches_light_up_and_pop_off_without_support() {
+        let mut world = World::new(8, 8, 8, &EMPTY, 0);
+        let support = ivec3(2, -10, 2);
+        world.set_block(support, Some(Block::new(BlockType::Stone)));
+        // nothing underneath to hang from
+        assert!(!world.place_torch(support, Face::NegY));
+        assert!(!world.place_torch(ivec3(5, -10, 5), Face::PosX));
+
+        assert!(world.place_torch(support, Face::PosX));
+        let torch = support + IVec3::X;
+        assert_eq!(world.light.get(torch), super::TORCH_LIGHT);
+        assert_eq!(
+            world.light.get(torch + IVec3::Z * 2),
+            super::TORCH_LIGHT - 2
+        );
+        // the stone holding it stays dark
+        assert_eq!(world.light.get(support), 0);
+        assert_eq!(world.torches.get(&torch), Some(&Face::PosX));
+
+        world.set_block(support, None);
+        run_ticks(&mut world, 2);
+        assert!(world.block_at(torch).is_none());
+        assert!(world.torches.is_empty());
+        assert!(world.light.get(torch) == 0 && world.light.get(support) == 0);
+    }
+
+    #[test]
+    fn surroundings_see_sky_and_water() {
+        let mut world = World::new(4, 4, 8, &EMPTY, 0);
+        let position = vec3(1.0, -11.0, 1.0);
+        let open = world.surroundings(position);
+        assert!(open.sky_access && !open.near_water);
+        assert_eq!(open.altitude, -6.0);
+
+        world.set_block(ivec3(1, -6, 1), Some(Block::new(BlockType::Stone)));
+        world.set_block(ivec3(3, -12, 3), Some(Block::new(BlockType::Water)));
+        let covered = world.surroundings(position);
+        assert!(!covered.sky_access && covered.near_water);
+    }
+
+    #[test]
+    fn sky_light_spreads_in_under_overhangs() {
+        let mut world = World::new(16, 16, 8, &EMPTY, 0);
+        // a roof over the half of the world towards -x
+        for x in 0..8 {
+            for z in 0..16 {
+                world.set_block(ivec3(x, -6, z), Some(Block::new(BlockType::Stone)));
+            }
+        }
+        assert_eq!(world.heights.top(ivec2(3, 5)), Some(-6));
+        assert_eq!(world.heights.top(ivec2(8, 5)), None);
+        // dimming a level a block in from the edge
+        assert_eq!(world.sky_light(ivec3(8, -8, 5)), MAX_LIGHT);
+        assert_eq!(world.sky_light(ivec3(7, -8, 5)), MAX_LIGHT - 1);
+        assert_eq!(world.sky_light(ivec3(3, -8, 5)), MAX_LIGHT - 5);
+        assert_eq!(world.light_level(ivec3(3, -8, 5), 5), 3);
+
+        // a hole in the roof lets the sky straight in, until it's patched
+        world.set_block(ivec3(3, -6, 5), None);
+        assert_eq!(world.heights.top(ivec2(3, 5)), None);
+        assert_eq!(world.sky_light(ivec3(3, -8, 5)), MAX_LIGHT);
+        assert_eq!(world.sky_light(ivec3(2, -8, 5)), MAX_LIGHT - 1);
+        world.set_block(ivec3(3, -6, 5), Some(Block::new(BlockType::Stone)));
+        assert_eq!(world.sky_light(ivec3(2, -8, 5)), MAX_LIGHT - 6);
+
+        // however the world is edited, the light ends up as if it had been lit from scratch
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..200 {
+            let position = ivec3(
+                rng.gen_range(0..16),
+                rng.gen_range(-12..=-5),
+                rng.gen_range(0..16),
+            );
+            let block = rng.gen_bool(0.5).then(|| Block::new(BlockType::Stone));
+            world.set_block(position, block);
+        }
+        let positions: Vec<_> = (0..16)
+            .flat_map(|x| (-12..=-5).flat_map(move |y| (0..16).map(move |z| ivec3(x, y, z))))
+            .collect();
+        let edited: Vec<_> = positions.iter().map(|p| world.sky_light(*p)).collect();
+        world.heights = HeightMap::new(16, 16, |column| world.column_top(column));
+        world.sky = LightMap::default();
+        world.light_sky();
+        let relit: Vec<_> = positions.iter().map(|p| world.sky_light(*p)).collect();
+        assert_eq!(edited, relit);
+    }
+
+    #[test]
+    fn built_portals_can_be_found() {
+        let mut world = World::new(16, 16, 16, &EMPTY, 0);
+        assert_eq!(world.find_portal(ivec3(8, -10, 8), 4), None);
+        // clamped inside the world with room for the frame
+        let portal = world.build_portal(ivec3(-20, -10, 8));
+        assert_eq!(portal, ivec3(2, -10, 8));
+        assert_eq!(world.block_type(portal + IVec3::Y), Some(BlockType::Portal));
+        assert_eq!(world.block_type(portal - IVec3::Y), Some(BlockType::Stone));
+        assert_eq!(world.block_type(portal + IVec3::X), None);
+        assert_eq!(
+            world.find_portal(ivec3(4, -9, 8), 4),
+            Some(portal + IVec3::Y)
+        );
+    }
+
+    #[test]
+    fn players_come_out_of_portals_where_they_fit() {
+        let mut world = World::new(16, 16, 16, &EMPTY, 0);
+        let portal = world.build_portal(ivec3(8, -10, 8));
+        // walled up on one side
+        for y in 0..2 {
+            let wall = Block::new(BlockType::Stone);
+            world.set_block(portal + ivec3(1, y, 0), Some(wall));
+        }
+        assert_eq!(
+            world.clear_spot(portal + IVec3::Y, 3),
+            Some(portal - IVec3::X)
+        );
+        // nothing to stand on in the air over the portal
+        assert_eq!(world.clear_spot(portal + ivec3(0, 6, 0), 1), None);
+    }
+
+    #[test]
+    fn chunks_in_view_are_meshed_first() {
+        let mut world = World::new(4, 4, 4, &EMPTY, 0);
+        let eye = Vec3::splat(7.5);
+        let view = |direction: Vec3| {
+            let projection = Mat4::perspective_rh(1.2, 1.0, 0.1, 500.0);
+            Frustum::from_matrix(projection * Mat4::look_to_rh(eye, direction, Vec3::Y))
+        };
+        let coords = [-4, -1, 1, 3].map(|x| ivec3(x, 0, 0));
+        let order = |world: &mut World, direction: Vec3| {
+            world.pending_chunks = coords.to_vec();
+            world.prioritise_pending(eye, &view(direction));
+            let mut order = world.pending_chunks.clone();
+            order.reverse();
+            order.iter().map(|coord| coord.x).collect::<Vec<_>>()
+        };
+        // the closest in front, then the rest in front, then behind
+        assert_eq!(order(&mut world, Vec3::X), [1, 3, -1, -4]);
+        // and turning around swaps them over
+        assert_eq!(order(&mut world, Vec3::NEG_X), [-1, -4, 1, 3]);
+    }
 }